@@ -0,0 +1,324 @@
+use std::fmt::Write;
+
+use crate::{
+    lexing::{Position, Spanned},
+    parsing::{ast::*, visit::*, Arena, NodeId},
+    syntax::SyntaxKind,
+    tokenize,
+};
+
+// Debug dumps of what the front end made of a module, for when a program
+// misbehaves: the tokens the lexer read, one per line, and the tree the
+// parser built, as an indented S-expression. Positions are shown as
+// line:column, both counted from 1.
+
+pub fn dump_tokens(input: &str) -> String {
+    let lines = Lines::new(input);
+    let mut output = String::new();
+
+    for token in tokenize(input) {
+        let kind = format!("{:?}", token.kind());
+        let location = lines.locate(token.position().start);
+
+        let _ = writeln!(output, "{:<8}{:<20}{:?}", location, kind, token.value());
+    }
+
+    output
+}
+
+// Every node is dumped as `(Kind start-end atoms... children...)`, where
+// the atoms are what the node holds besides its children, e.g. a function's
+// name or a binary operator. Patterns and types are leaves, printed as
+// source.
+pub fn dump_ast(input: &str, file: &SourceFile<'_>) -> String {
+    let mut dump = Dump {
+        lines: Lines::new(input),
+        output: String::new(),
+        depth: 0,
+    };
+
+    dump.open("Module", None, vec![]);
+    dump.visit_source_file(file);
+    dump.close();
+    dump.output.push('\n');
+
+    dump.output
+}
+
+// The offset each line starts at, to turn offsets into line:column.
+struct Lines<'a> {
+    input: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(input: &'a str) -> Lines<'a> {
+        let breaks = input.match_indices('\n').map(|(index, _)| index + 1);
+
+        Lines {
+            input,
+            starts: std::iter::once(0).chain(breaks).collect(),
+        }
+    }
+
+    fn locate(&self, offset: usize) -> String {
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let start = self.starts[line - 1];
+        let column = self.input[start..offset].chars().count() + 1;
+
+        format!("{}:{}", line, column)
+    }
+
+    fn span(&self, position: Position) -> String {
+        format!(
+            "{}-{}",
+            self.locate(position.start),
+            self.locate(position.end)
+        )
+    }
+}
+
+struct Dump<'i> {
+    lines: Lines<'i>,
+    output: String,
+    depth: usize,
+}
+
+impl<'i> Dump<'i> {
+    fn open(&mut self, kind: &str, position: Option<Position>, atoms: Vec<String>) {
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        self.output.push_str(&"  ".repeat(self.depth));
+        self.output.push('(');
+        self.output.push_str(kind);
+
+        for atom in position
+            .map(|position| self.lines.span(position))
+            .into_iter()
+            .chain(atoms)
+        {
+            self.output.push(' ');
+            self.output.push_str(&atom);
+        }
+
+        self.depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.output.push(')');
+        self.depth -= 1;
+    }
+
+    fn leaf(&mut self, kind: SyntaxKind, position: Position, atom: String) {
+        self.open(&format!("{:?}", kind), Some(position), vec![atom]);
+        self.close();
+    }
+}
+
+fn atoms(arena: &Arena<'_>, node: &AST<'_>) -> Vec<String> {
+    match node {
+        AST::BooleanLiteral(literal) => vec![literal.value.to_string()],
+        AST::IntegerLiteral(literal) => vec![literal.value.to_string()],
+        AST::NumberLiteral(literal) => vec![literal.value.to_string()],
+        AST::RegexpLiteral(literal) => vec![format!("{:?}", literal.value)],
+        AST::StringLiteral(literal) => vec![format!("{:?}", literal.value)],
+        AST::TemplateLiteral(literal) => literal
+            .template
+            .iter()
+            .map(|text| format!("{:?}", text))
+            .collect(),
+        AST::Identifier(identifier) => vec![identifier.value.to_string()],
+        AST::MemberAccess(member) => vec![member.property.value.to_string()],
+        AST::Binary(binary) => vec![format!("{:?}", binary.operator)],
+        AST::Unary(unary) => vec![format!("{:?}", unary.operator)],
+        AST::LetBinding(binding) if binding.mutable => vec!["mutable".to_string()],
+        AST::Assignment(assignment) => vec![assignment.target.value.to_string()],
+        AST::ImportDeclaration(_) => vec![node.to_source(arena)],
+        AST::ExportDeclaration(export) => match export.kind {
+            ExportKind::Declaration(_) => vec![],
+            _ => vec![node.to_source(arena)],
+        },
+        AST::FunctionDeclaration(function) => vec![function.name.to_string()],
+        AST::TypeDeclaration(declaration) => vec![declaration.name.to_string()],
+        AST::TypeAlias(alias) => vec![alias.name.to_string()],
+        AST::StructDeclaration(declaration) => vec![declaration.name.to_string()],
+        AST::EffectDeclaration(effect) => vec![effect.name.to_string()],
+        AST::ImplBlock(block) => vec![block.effect.to_string()],
+        AST::TypeclassDeclaration(typeclass) => vec![typeclass.name.to_string()],
+        AST::InstanceDeclaration(instance) => vec![instance.typeclass.to_string()],
+        AST::HandlerDeclaration(handler) => {
+            handler.name.iter().map(|name| name.to_string()).collect()
+        }
+        AST::MacroDeclaration(declaration) => {
+            vec![
+                declaration.name.to_string(),
+                format!("{:?}", declaration.body.text),
+            ]
+        }
+        AST::MacroInvocation(invocation) => {
+            vec![
+                invocation.name.to_string(),
+                format!("{:?}", invocation.group.text),
+            ]
+        }
+        _ => vec![],
+    }
+}
+
+impl<'i, 'a> Visitor<'a> for Dump<'i> {
+    fn visit_node(&mut self, arena: &Arena<'a>, id: NodeId) {
+        let node = &arena[id];
+
+        self.open(
+            &format!("{:?}", SyntaxKind::of(node)),
+            Some(node.position()),
+            atoms(arena, node),
+        );
+        walk_node(self, arena, id);
+        self.close();
+    }
+
+    fn visit_record_member(&mut self, arena: &Arena<'a>, member: &RecordMember<'a>) {
+        match member {
+            RecordMember::Field(name, value) => {
+                self.open("Field", Some(name.position), vec![name.value.to_string()]);
+                self.visit_node(arena, *value);
+                self.close();
+            }
+            RecordMember::Shorthand(name) => {
+                self.open(
+                    "Shorthand",
+                    Some(name.position),
+                    vec![name.value.to_string()],
+                );
+                self.close();
+            }
+            RecordMember::Spread(value) => {
+                self.open("Spread", None, vec![]);
+                self.visit_node(arena, *value);
+                self.close();
+            }
+        }
+    }
+
+    fn visit_element(&mut self, arena: &Arena<'a>, element: &Element) {
+        match element {
+            Element::Member(value) => self.visit_node(arena, *value),
+            Element::Spread(value) => {
+                self.open("Spread", None, vec![]);
+                self.visit_node(arena, *value);
+                self.close();
+            }
+        }
+    }
+
+    fn visit_match_case(&mut self, arena: &Arena<'a>, case: &MatchCase<'a>) {
+        self.open("MatchCase", Some(case.position), vec![]);
+        walk_match_case(self, arena, case);
+        self.close();
+    }
+
+    fn visit_pattern(&mut self, _arena: &Arena<'a>, pattern: &Pattern<'a>) {
+        self.leaf(SyntaxKind::Pattern, pattern.position(), pattern.to_source());
+    }
+
+    fn visit_function_parameter(&mut self, arena: &Arena<'a>, parameter: &FunctionParameter<'a>) {
+        self.open(
+            "FunctionParameter",
+            Some(parameter.position),
+            vec![parameter.name.to_string()],
+        );
+        walk_function_parameter(self, arena, parameter);
+        self.close();
+    }
+
+    fn visit_type_parameter(&mut self, _arena: &Arena<'a>, parameter: &TypeParameter<'a>) {
+        let source = match &parameter.extends {
+            Some(extends) => format!("{}: {}", parameter.name, extends.to_source()),
+            None => parameter.name.to_string(),
+        };
+
+        self.leaf(SyntaxKind::TypeParameter, parameter.position, source);
+    }
+
+    fn visit_type_annotation(&mut self, _arena: &Arena<'a>, annotation: &TypeAnnotation<'a>) {
+        self.leaf(
+            SyntaxKind::TypeAnnotation,
+            annotation.position,
+            annotation.annotated.to_source(),
+        );
+    }
+
+    fn visit_labeled_type_annotation(
+        &mut self,
+        arena: &Arena<'a>,
+        annotation: &LabeledTypeAnnotation<'a>,
+    ) {
+        match annotation.name {
+            Some(name) => self.leaf(
+                SyntaxKind::TypeAnnotation,
+                annotation.position,
+                format!("{}: {}", name, annotation.annotated.annotated.to_source()),
+            ),
+            None => self.visit_type_annotation(arena, &annotation.annotated),
+        }
+    }
+
+    fn visit_type_constructor(&mut self, arena: &Arena<'a>, constructor: &TypeConstructor<'a>) {
+        self.open(
+            "TypeConstructor",
+            Some(constructor.position),
+            vec![constructor.name.to_string()],
+        );
+        walk_type_constructor(self, arena, constructor);
+        self.close();
+    }
+
+    fn visit_effect_operation(&mut self, arena: &Arena<'a>, operation: &EffectOperation<'a>) {
+        self.open(
+            "EffectOperation",
+            Some(operation.position),
+            operation.name.iter().map(|name| name.to_string()).collect(),
+        );
+        walk_effect_operation(self, arena, operation);
+        self.close();
+    }
+
+    fn visit_typeclass_method(&mut self, arena: &Arena<'a>, method: &TypeclassMethod<'a>) {
+        self.open(
+            "TypeclassMethod",
+            Some(method.position),
+            vec![method.name.to_string()],
+        );
+        walk_typeclass_method(self, arena, method);
+        self.close();
+    }
+
+    fn visit_associated_type(&mut self, arena: &Arena<'a>, associated: &AssociatedType<'a>) {
+        self.open(
+            "AssociatedType",
+            Some(associated.position),
+            vec![associated.name.to_string()],
+        );
+        walk_associated_type(self, arena, associated);
+        self.close();
+    }
+
+    fn visit_operation_clause(&mut self, arena: &Arena<'a>, clause: &OperationClause<'a>) {
+        self.open(
+            "OperationClause",
+            Some(clause.position),
+            vec![clause.name.to_string()],
+        );
+        walk_operation_clause(self, arena, clause);
+        self.close();
+    }
+
+    fn visit_return_clause(&mut self, arena: &Arena<'a>, clause: &ReturnClause<'a>) {
+        self.open("ReturnClause", Some(clause.position), vec![]);
+        walk_return_clause(self, arena, clause);
+        self.close();
+    }
+}
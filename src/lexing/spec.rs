@@ -11,10 +11,10 @@ pub type SpecFn<'a> = fn(&'a str, usize) -> Option<Token<'a>>;
 
 lazy_static! {
     // Literals
-    pub static ref BOOLEAN_LITERAL_REGEX: Regex = Regex::new(r"^(true|false)").unwrap();
+    pub static ref BOOLEAN_LITERAL_REGEX: Regex = Regex::new(r"^(true|false)\b").unwrap();
     pub static ref NUMBER_LITERAL_REGEX: Regex = Regex::new(r"^(\d+(\.\d+)?)").unwrap();
-    pub static ref REGEXP_LITERAL_REGEX: Regex = Regex::new(r"^\/.+\/").unwrap();
-    pub static ref STRING_LITERAL_REGEX: Regex = Regex::new(r#"^["'](.+)['"]"#).unwrap();
+    pub static ref REGEXP_LITERAL_REGEX: Regex = Regex::new(r"^/([^/\\\r\n]|\\.)+/").unwrap();
+    pub static ref STRING_LITERAL_REGEX: Regex = Regex::new(r#"^("([^"\\]|\\.)*"|'([^'\\]|\\.)*')"#).unwrap();
     pub static ref TEMPLATE_LITERAL_REGEX: Regex = Regex::new(r#"^`([^`\\]|\\.)*`"#).unwrap();
 
     // Identifiers
@@ -22,29 +22,29 @@ lazy_static! {
     pub static ref MACRO_IDENTIFIER_REGEX: Regex = Regex::new(r"^@[a-zA-Z_][a-zA-Z0-9_]*!").unwrap();
 
     // Keywords
-    pub static ref ALIAS_REGEX: Regex = Regex::new(r"^(alias)").unwrap();
-    pub static ref BRAND_REGEX: Regex = Regex::new(r"^(brand)").unwrap();
-    pub static ref EFFECT_REGEX: Regex = Regex::new(r"^(effect)").unwrap();
-    pub static ref EXPORT_REGEX: Regex = Regex::new(r"^(export)").unwrap();
-    pub static ref ELSE_REGEX: Regex = Regex::new(r"^(else)").unwrap();
-    pub static ref ELSE_IF_REGEX: Regex = Regex::new(r"^(else if)").unwrap();
-    pub static ref FOR_REGEX: Regex = Regex::new(r"^(for)").unwrap();
-    pub static ref FROM_REGEX: Regex = Regex::new(r"^(from)").unwrap();
-    pub static ref FUNCTION_REGEX: Regex = Regex::new(r"^(function)").unwrap();
-    pub static ref HANDLER_REGEX: Regex = Regex::new(r"^(handler)").unwrap();
-    pub static ref IF_REGEX: Regex = Regex::new(r"^(if)").unwrap();
-    pub static ref IMPORT_REGEX: Regex = Regex::new(r"^(import)").unwrap();
-    pub static ref IMPLEMENTATION_REGEX: Regex = Regex::new(r"^(impl)").unwrap();
-    pub static ref MACRO_REGEX: Regex = Regex::new(r"^(macro)").unwrap();
-    pub static ref MATCH_REGEX: Regex = Regex::new(r"^(match)").unwrap();
-    pub static ref OF_REGEX: Regex = Regex::new(r"^(of)").unwrap();
-    pub static ref RETURN_REGEX: Regex = Regex::new(r"^(return)").unwrap();
-    pub static ref STRUCT_REGEX: Regex = Regex::new(r"^(struct)").unwrap();
-    pub static ref TYPE_REGEX: Regex = Regex::new(r"^(type)").unwrap();
-    pub static ref TYPECLASS_REGEX: Regex = Regex::new(r"^(typeclass)").unwrap();
-    pub static ref WHILE_REGEX: Regex = Regex::new(r"^(while)").unwrap();
-    pub static ref WITH_REGEX: Regex = Regex::new(r"^(with)").unwrap();
-    pub static ref YIELD_REGEX: Regex = Regex::new(r"^(yield)").unwrap();
+    pub static ref ALIAS_REGEX: Regex = Regex::new(r"^(alias)\b").unwrap();
+    pub static ref BRAND_REGEX: Regex = Regex::new(r"^(brand)\b").unwrap();
+    pub static ref EFFECT_REGEX: Regex = Regex::new(r"^(effect)\b").unwrap();
+    pub static ref EXPORT_REGEX: Regex = Regex::new(r"^(export)\b").unwrap();
+    pub static ref ELSE_REGEX: Regex = Regex::new(r"^(else)\b").unwrap();
+    pub static ref ELSE_IF_REGEX: Regex = Regex::new(r"^(else if)\b").unwrap();
+    pub static ref FOR_REGEX: Regex = Regex::new(r"^(for)\b").unwrap();
+    pub static ref FROM_REGEX: Regex = Regex::new(r"^(from)\b").unwrap();
+    pub static ref FUNCTION_REGEX: Regex = Regex::new(r"^(function|fun)\b").unwrap();
+    pub static ref HANDLER_REGEX: Regex = Regex::new(r"^(handler)\b").unwrap();
+    pub static ref IF_REGEX: Regex = Regex::new(r"^(if)\b").unwrap();
+    pub static ref IMPORT_REGEX: Regex = Regex::new(r"^(import)\b").unwrap();
+    pub static ref IMPLEMENTATION_REGEX: Regex = Regex::new(r"^(impl)\b").unwrap();
+    pub static ref MACRO_REGEX: Regex = Regex::new(r"^(macro)\b").unwrap();
+    pub static ref MATCH_REGEX: Regex = Regex::new(r"^(match)\b").unwrap();
+    pub static ref OF_REGEX: Regex = Regex::new(r"^(of)\b").unwrap();
+    pub static ref RETURN_REGEX: Regex = Regex::new(r"^(return)\b").unwrap();
+    pub static ref STRUCT_REGEX: Regex = Regex::new(r"^(struct)\b").unwrap();
+    pub static ref TYPE_REGEX: Regex = Regex::new(r"^(type)\b").unwrap();
+    pub static ref TYPECLASS_REGEX: Regex = Regex::new(r"^(typeclass)\b").unwrap();
+    pub static ref WHILE_REGEX: Regex = Regex::new(r"^(while)\b").unwrap();
+    pub static ref WITH_REGEX: Regex = Regex::new(r"^(with)\b").unwrap();
+    pub static ref YIELD_REGEX: Regex = Regex::new(r"^(yield)\b").unwrap();
 
     // Symbols + Operators
 
@@ -97,14 +97,14 @@ lazy_static! {
     pub static ref RIGHT_BRACKET_REGEX: Regex = Regex::new(r"^\]").unwrap();
     pub static ref RIGHT_PAREN_REGEX: Regex = Regex::new(r"^\)").unwrap();
     pub static ref SEMICOLON_REGEX: Regex = Regex::new(r"^;").unwrap();
-    pub static ref UNDERSCORE_REGEX: Regex = Regex::new(r"^_").unwrap();
+    pub static ref UNDERSCORE_REGEX: Regex = Regex::new(r"^_\b").unwrap();
 
     // Whitespace
     pub static ref WHITESPACE_REGEX: Regex = Regex::new(r"^([ \t\r\n])+").unwrap();
 
     // Comments
-    pub static ref COMMENT_REGEX: Regex = Regex::new(r"^//.*[\r\n]").unwrap();
-    pub static ref DOC_COMMENT_REGEX: Regex = Regex::new(r"^(/\*\*).*\*/").unwrap();
+    pub static ref COMMENT_REGEX: Regex = Regex::new(r"^//[^\r\n]*").unwrap();
+    pub static ref DOC_COMMENT_REGEX: Regex = Regex::new(r"^/\*\*(?s:.*?)\*/").unwrap();
 }
 
 impl<'a> Spec<'a> {
@@ -128,8 +128,8 @@ impl<'a> Spec<'a> {
             Spec::brand,
             Spec::effect,
             Spec::export,
-            Spec::else_,
             Spec::else_if,
+            Spec::else_,
             Spec::for_,
             Spec::from,
             Spec::function,
@@ -146,6 +146,10 @@ impl<'a> Spec<'a> {
             Spec::typeclass,
             Spec::while_,
             Spec::with,
+            Spec::yield_,
+            // Macro identifiers start with `@`, so they need to come before the
+            // symbols
+            Spec::macro_identifier,
             // Symbols + operators
             Spec::dot_dot_dot,
             Spec::dot_dot,
@@ -196,7 +200,6 @@ impl<'a> Spec<'a> {
             Spec::star,
             Spec::tilde,
             // Identifiers
-            Spec::macro_identifier, // needs to come before identifier as they're idential, but macros have a ! at the end
             Spec::identifier,
             // Whitespace
             Spec::whitespace,
@@ -354,6 +357,10 @@ impl<'a> Spec<'a> {
         find_regex(input, position, &WITH_REGEX, TokenKind::With)
     }
 
+    fn yield_(input: &'a str, position: usize) -> Option<Token<'a>> {
+        find_regex(input, position, &YIELD_REGEX, TokenKind::Yield)
+    }
+
     fn and(input: &'a str, position: usize) -> Option<Token<'a>> {
         find_regex(input, position, &AND_REGEX, TokenKind::And)
     }
@@ -579,7 +586,7 @@ impl<'a> Spec<'a> {
         find_regex(input, position, &UNDERSCORE_REGEX, TokenKind::Underscore)
     }
 
-    // Comments are started with two slashes and end with a newline
+    // Comments are started with two slashes and run to the end of the line
     fn comment(input: &'a str, position: usize) -> Option<Token<'a>> {
         find_regex(input, position, &COMMENT_REGEX, TokenKind::Comment)
     }
@@ -602,8 +609,6 @@ fn find_regex<'a>(
     re.find(input).map(|m| {
         let pos = Position::new(position + m.start(), position + m.end());
 
-        println!("PROCESSING: {:?}", kind);
-
         Token::new(kind, m.as_str(), pos)
     })
 }
@@ -1,4 +1,8 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TokenKind {
     // Literals
     BooleanLiteral,
@@ -94,9 +98,14 @@ pub enum TokenKind {
     // Comments
     Comment,
     DocComment,
+
+    // A character no other token matches, only found in concrete syntax
+    // trees
+    Unknown,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Token<'a> {
     kind: TokenKind,
     value: &'a str,
@@ -232,6 +241,10 @@ impl<'a> Token<'a> {
         Token::new(TokenKind::With, value, position)
     }
 
+    pub fn yield_(value: &'a str, position: Position) -> Token<'a> {
+        Token::new(TokenKind::Yield, value, position)
+    }
+
     pub fn and(value: &'a str, position: Position) -> Token<'a> {
         Token::new(TokenKind::And, value, position)
     }
@@ -446,6 +459,7 @@ impl<'a> Token<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Position {
     pub start: usize,
     pub end: usize,
@@ -456,3 +470,15 @@ impl Position {
         Position { start, end }
     }
 }
+
+// Anything that covers a range of the source, so that diagnostics can point
+// at tokens and nodes alike.
+pub trait Spanned {
+    fn position(&self) -> Position;
+}
+
+impl<'a> Spanned for Token<'a> {
+    fn position(&self) -> Position {
+        self.position
+    }
+}
@@ -1,10 +1,16 @@
-use super::{spec::Spec, token::Token};
+use super::{
+    spec::Spec,
+    token::{Position, Token, TokenKind},
+};
 
 pub struct Tokenizer<'a> {
     spec: Spec<'a>,
     input: &'a str,
     length: usize,
     position: usize,
+    // Whether the last token that wasn't whitespace or a comment ended an
+    // operand, in which case a `/` is a division rather than a regexp.
+    operand: bool,
 }
 
 impl<'a> Tokenizer<'a> {
@@ -14,6 +20,7 @@ impl<'a> Tokenizer<'a> {
             input: "",
             length: 0,
             position: 0,
+            operand: false,
         }
     }
 
@@ -22,9 +29,16 @@ impl<'a> Tokenizer<'a> {
     }
 
     pub fn init(&mut self, input: &'a str) {
+        self.init_at(input, 0);
+    }
+
+    // Starts tokenizing partway through the input, so that token positions
+    // stay relative to the whole input.
+    pub fn init_at(&mut self, input: &'a str, position: usize) {
         self.input = input;
         self.length = input.len();
-        self.position = 0;
+        self.position = position;
+        self.operand = false;
     }
 
     // TODO: Eventually we want to get rid of the use of Spec and just use
@@ -39,7 +53,17 @@ impl<'a> Tokenizer<'a> {
         let input = &self.input[self.position..];
 
         for spec_fn in &self.spec.spec {
-            if let Some(token) = spec_fn(input, self.position) {
+            if let Some(mut token) = spec_fn(input, self.position) {
+                if token.kind() == TokenKind::RegexpLiteral && self.operand {
+                    let position = Position::new(self.position, self.position + 1);
+                    token = Token::new(TokenKind::Slash, &input[..1], position);
+                }
+
+                match token.kind() {
+                    TokenKind::WhiteSpace | TokenKind::Comment | TokenKind::DocComment => {}
+                    kind => self.operand = ends_operand(kind),
+                }
+
                 self.position = token.position().end;
                 return Some(token);
             }
@@ -56,3 +80,17 @@ impl<'a> Iterator for Tokenizer<'a> {
         self.next_token()
     }
 }
+
+fn ends_operand(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Identifier
+            | TokenKind::BooleanLiteral
+            | TokenKind::NumberLiteral
+            | TokenKind::RegexpLiteral
+            | TokenKind::StringLiteral
+            | TokenKind::TemplateLiteral
+            | TokenKind::RightParen
+            | TokenKind::RightBracket
+    )
+}
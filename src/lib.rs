@@ -1,11 +1,25 @@
 #![recursion_limit = "256"]
 
 mod lexing;
-use lexing::*;
+pub use lexing::*;
 
 mod parsing;
 pub use parsing::*;
 
+mod loading;
+pub use loading::*;
+
+mod dump;
+pub use dump::*;
+
+pub mod syntax;
+use syntax::{build_green_tree, SyntaxNode};
+
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "serde")]
+pub use serialization::*;
+
 pub fn tokenize(input: &str) -> Vec<Token<'_>> {
     let mut tokenizer = Tokenizer::asura();
 
@@ -13,6 +27,30 @@ pub fn tokenize(input: &str) -> Vec<Token<'_>> {
     tokenizer.collect()
 }
 
+pub fn parse(input: &str) -> Result<SourceFile<'_>, ParseError> {
+    Parser::new(input)?.parse_module()
+}
+
+// Parses `input`, the text of `old` after `edit`, reusing the parts of
+// `old` the edit didn't touch, see incremental::reparse. `source` is the
+// text `old` was parsed from.
+pub fn reparse<'a>(
+    old: SourceFile<'_>,
+    source: &str,
+    input: &'a str,
+    edit: Edit,
+) -> Result<SourceFile<'a>, ParseError> {
+    incremental::reparse(old, source, input, edit)
+}
+
+// The lossless concrete syntax tree of a module, see syntax::build, and the
+// errors in it. There is a tree even when there are errors.
+pub fn parse_syntax(input: &str) -> (SyntaxNode, Vec<ParseError>) {
+    let (tree, errors) = build_green_tree(input);
+
+    (SyntaxNode::new_root(tree), errors)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,8 +72,15 @@ mod tests {
         let contents: String = read_to_string("examples/fib.asura")?;
 
         let result = tokenize(&contents);
+        let dump = dump_tokens(&contents);
+
+        println!("{}", dump);
 
-        print_vector(result);
+        assert_eq!(dump.lines().count(), result.len());
+        assert_eq!(
+            dump.lines().next(),
+            Some("1:1     Import              \"import\"")
+        );
 
         Ok(())
     }
@@ -45,8 +90,17 @@ mod tests {
         let contents: String = read_to_string("examples/state.asura")?;
 
         let result = tokenize(&contents);
+        let dump = dump_tokens(&contents);
 
-        print_vector(result);
+        println!("{}", dump);
+
+        assert_eq!(dump.lines().count(), result.len());
+        assert_eq!(
+            dump.lines().next(),
+            Some(
+                "1:1     Comment             \"// Effects can utilize generics, and can utilize\""
+            )
+        );
 
         Ok(())
     }
@@ -56,22 +110,2956 @@ mod tests {
         let contents: String = read_to_string("examples/queue.asura")?;
 
         let result = tokenize(&contents);
+        let dump = dump_tokens(&contents);
+
+        println!("{}", dump);
+
+        assert_eq!(dump.lines().count(), result.len());
+        assert_eq!(
+            dump.lines().next(),
+            Some("1:1     Import              \"import\"")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_impl_blocks_with_return_clauses() -> Result<(), Box<dyn Error>> {
+        let input = "impl<E> for Fail<E> {
+  handler try(error: E) {
+    Either.Left(error)
+  } {
+    return(a) { Either.Right(a) }
+  }
+
+  fun catch<A>(f: (E) -> A) {
+    handler(error: E) {
+      f(error)
+    }
+  }
+}";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::ImplBlock(block)] = result.as_slice() else {
+            panic!("Expected a single impl block, found {:?}", result);
+        };
+
+        assert_eq!(block.effect, "Fail");
+        assert_eq!(block.type_parameters.len(), 1);
+        assert_eq!(block.type_arguments.len(), 1);
+        assert_eq!(block.members.len(), 2);
+
+        let AST::HandlerDeclaration(handler) = &file[block.members[0]] else {
+            panic!("Expected a handler, found {:?}", block.members[0]);
+        };
+
+        assert_eq!(handler.name, Some("try"));
+        assert_eq!(handler.parameters[0].name, "error");
+        assert_eq!(handler.body.len(), 1);
+        assert!(handler.operations.is_empty());
+        assert_eq!(handler.return_clause.as_ref().unwrap().parameter.name, "a");
+
+        let AST::FunctionDeclaration(catch) = &file[block.members[1]] else {
+            panic!("Expected a function, found {:?}", block.members[1]);
+        };
+
+        assert_eq!(catch.name, "catch");
+        assert_eq!(catch.type_parameters[0].name, "A");
+        assert!(matches!(
+            file.arena.nodes(&catch.body).as_slice(),
+            [AST::HandlerDeclaration(HandlerDeclaration {
+                name: None,
+                ..
+            })]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_default_handlers() -> Result<(), Box<dyn Error>> {
+        let input = "impl for Calc {
+  handler(op) {
+    match op {
+      Add(a, b) -> resume(a + b),
+      Divide(a, b) -> resume(safe_divide(a, b)),
+    }
+  }
+}";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::ImplBlock(block)] = result.as_slice() else {
+            panic!("Expected a single impl block, found {:?}", result);
+        };
+        let [AST::HandlerDeclaration(handler)] = file.arena.nodes(&block.members).as_slice() else {
+            panic!("Expected a single handler, found {:?}", block.members);
+        };
+        let [AST::Match(value)] = file.arena.nodes(&handler.body).as_slice() else {
+            panic!("Expected a match, found {:?}", handler.body);
+        };
+
+        assert_eq!(handler.name, None);
+        assert_eq!(value.cases.len(), 2);
+        assert!(matches!(
+            &value.cases[0].test,
+            MatchTest::Pattern(Pattern::Constructor(ConstructorPattern { name: "Add", arguments, .. }))
+                if arguments.len() == 2
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_handlers_returning_operations() -> Result<(), Box<dyn Error>> {
+        let input = "impl<A> for State<A> {
+  handler local(initial: A) {
+    reset(initial)
+
+    return {
+      get() { current },
+      modify(f) {
+        f(current)
+      }
+    }
+  }
+}";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::ImplBlock(block)] = result.as_slice() else {
+            panic!("Expected a single impl block, found {:?}", result);
+        };
+        let [AST::HandlerDeclaration(handler)] = file.arena.nodes(&block.members).as_slice() else {
+            panic!("Expected a single handler, found {:?}", block.members);
+        };
+
+        assert_eq!(handler.name, Some("local"));
+        assert_eq!(handler.body.len(), 1);
+        assert_eq!(handler.operations.len(), 2);
+        assert_eq!(handler.operations[0].name, "get");
+        assert_eq!(handler.operations[1].parameters[0].name, "f");
+        assert_eq!(handler.return_clause, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_members_other_than_fun_or_handler_in_impl_blocks() {
+        let result = parse("impl for Calc {\n  x\n}");
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_parses_with_expressions() -> Result<(), Box<dyn Error>> {
+        let file = parse("main() with Calc ++ Fail.try()")?;
+        let result = file.statements();
+
+        let [AST::With(with)] = result.as_slice() else {
+            panic!("Expected a with expression, found {:?}", result);
+        };
+
+        assert!(matches!(&file[with.body], AST::Call(_)));
+
+        let AST::Binary(handlers) = &file[with.handlers] else {
+            panic!("Expected concatenated handlers, found {:?}", with.handlers);
+        };
+
+        assert_eq!(handlers.operator, BinaryOperator::Concat);
+        assert!(matches!(
+            &file[handlers.left],
+            AST::Identifier(Identifier { value: "Calc", .. })
+        ));
+        assert!(matches!(&file[handlers.right], AST::Call(_)));
+        assert_eq!(with.position, Position::new(0, 30));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_concatenation_as_right_associative() -> Result<(), Box<dyn Error>> {
+        let file = parse("a + b ++ c ++ d")?;
+        let result = file.statements();
+
+        let [AST::Binary(outer)] = result.as_slice() else {
+            panic!("Expected a binary expression, found {:?}", result);
+        };
+        let (AST::Binary(left), AST::Binary(right)) = (&file[outer.left], &file[outer.right])
+        else {
+            panic!("Expected nested binary expressions, found {:?}", outer);
+        };
 
-        print_vector(result);
+        assert_eq!(outer.operator, BinaryOperator::Concat);
+        assert_eq!(left.operator, BinaryOperator::Add);
+        assert_eq!(right.operator, BinaryOperator::Concat);
 
         Ok(())
     }
 
-    fn print_vector<A>(vec: Vec<A>)
-    where
-        A: std::fmt::Debug,
-    {
-        println!("[");
+    #[test]
+    fn it_lexes_slashes_after_operands_as_division() -> Result<(), Box<dyn Error>> {
+        for input in ["x = a / b / c", "x = a / 2 + b / 3", "x = f(a) / g(b) / 2"] {
+            let file = parse(input)?;
 
-        for item in vec {
-            println!("    {:?},", item);
+            assert!(
+                !file
+                    .arena
+                    .iter()
+                    .any(|(_, node)| matches!(node, AST::RegexpLiteral(_))),
+                "Expected no regexps in {:?}",
+                input
+            );
         }
 
-        println!("]");
+        let file = parse("x = a / b\ny = /a+/")?;
+        let regexps = file
+            .arena
+            .iter()
+            .filter_map(|(_, node)| match node {
+                AST::RegexpLiteral(literal) => Some(literal.value),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(regexps, vec!["/a+/"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_a_comment_on_the_last_line() -> Result<(), Box<dyn Error>> {
+        let file = parse("x = 1 // c")?;
+
+        assert!(matches!(file.statements().as_slice(), [AST::LetBinding(_)]));
+        assert_eq!(tokenize("// c").len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_lambdas() -> Result<(), Box<dyn Error>> {
+        let file = parse("State.get_and_update((a) -> a * 3)\nFail.catch(() -> 0)")?;
+        let result = file.statements();
+
+        let [AST::Call(update), AST::Call(catch)] = result.as_slice() else {
+            panic!("Expected two calls, found {:?}", result);
+        };
+        let [AST::Lambda(triple)] = file.arena.nodes(&update.arguments).as_slice() else {
+            panic!("Expected a lambda, found {:?}", update.arguments);
+        };
+        let [AST::Lambda(zero)] = file.arena.nodes(&catch.arguments).as_slice() else {
+            panic!("Expected a lambda, found {:?}", catch.arguments);
+        };
+
+        assert_eq!(triple.parameters[0].name, "a");
+        assert!(matches!(
+            file.arena.nodes(&triple.body).as_slice(),
+            [AST::Binary(_)]
+        ));
+        assert_eq!(triple.position, Position::new(21, 33));
+        assert!(zero.parameters.is_empty());
+        assert!(matches!(
+            file.arena.nodes(&zero.body).as_slice(),
+            [AST::IntegerLiteral(_)]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_annotated_lambdas_with_block_bodies() -> Result<(), Box<dyn Error>> {
+        let file = parse("<A>(a: A, f: (A) -> A): A -> {\n  f(a)\n}")?;
+        let result = file.statements();
+
+        let [AST::Lambda(lambda)] = result.as_slice() else {
+            panic!("Expected a lambda, found {:?}", result);
+        };
+
+        assert_eq!(lambda.type_parameters[0].name, "A");
+        assert_eq!(lambda.parameters.len(), 2);
+        assert!(lambda.parameters.iter().all(|p| p.annotation.is_some()));
+        assert!(lambda.return_type.is_some());
+        assert!(matches!(
+            file.arena.nodes(&lambda.body).as_slice(),
+            [AST::Call(_)]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_distinguishes_parenthesised_expressions_from_lambdas() -> Result<(), Box<dyn Error>> {
+        let file = parse("(a) * 2\nmatch b { (c) -> c }")?;
+        let result = file.statements();
+
+        assert!(matches!(result[0], AST::Binary(_)));
+
+        let AST::Match(value) = &result[1] else {
+            panic!("Expected a match, found {:?}", result[1]);
+        };
+
+        assert!(matches!(
+            value.cases[0].test,
+            MatchTest::Pattern(Pattern::Identifier(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_mutable_bindings_and_reassignments() -> Result<(), Box<dyn Error>> {
+        let input = "impl<A> for State<A> {
+  handler local(initial: A) {
+    current := initial
+
+    return {
+      get() { current },
+      modify(f) {
+        Pair(b, a) = f(current)
+        current = a
+        resume(current = b)
+      }
+    }
+  }
+}";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::ImplBlock(block)] = result.as_slice() else {
+            panic!("Expected a single impl block, found {:?}", result);
+        };
+        let [AST::HandlerDeclaration(handler)] = file.arena.nodes(&block.members).as_slice() else {
+            panic!("Expected a single handler, found {:?}", block.members);
+        };
+
+        assert!(matches!(
+            file.arena.nodes(&handler.body).as_slice(),
+            [AST::LetBinding(LetBinding {
+                pattern: Pattern::Identifier(Identifier {
+                    value: "current",
+                    ..
+                }),
+                mutable: true,
+                ..
+            })]
+        ));
+
+        let modify = file.arena.nodes(&handler.operations[1].body);
+
+        let [destructure, reassign, AST::Call(resume)] = modify.as_slice() else {
+            panic!("Expected three statements, found {:?}", modify);
+        };
+
+        assert!(matches!(
+            destructure,
+            AST::LetBinding(LetBinding {
+                pattern: Pattern::Constructor(_),
+                mutable: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            reassign,
+            AST::Assignment(Assignment {
+                target: Identifier {
+                    value: "current",
+                    ..
+                },
+                ..
+            })
+        ));
+        assert!(matches!(
+            file.arena.nodes(&resume.arguments).as_slice(),
+            [AST::Assignment(_)]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_binds_constants_when_the_name_is_not_mutable() -> Result<(), Box<dyn Error>> {
+        let file = parse("a := 1\nfun f(a) {\n  a = 2\n}\na = 3")?;
+        let result = file.statements();
+
+        let [_, AST::FunctionDeclaration(f), reassign] = result.as_slice() else {
+            panic!("Expected three statements, found {:?}", result);
+        };
+
+        assert!(matches!(
+            file.arena.nodes(&f.body).as_slice(),
+            [AST::LetBinding(LetBinding { mutable: false, .. })]
+        ));
+        assert!(matches!(reassign, AST::Assignment(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_invalid_binding_targets() {
+        assert!(parse("f(a) + 1 = 2").is_err());
+    }
+
+    #[test]
+    fn it_parses_control_flow_statements() -> Result<(), Box<dyn Error>> {
+        let input = "fun worker() {
+  while(Some(value) = IntQueue.dequeue()) {
+    Console.log(value)
+  }
+
+  for (a of list) {
+    Queue.enqueue(a)
+  }
+
+  for waiter of waiters {
+    waiter(None)
+  }
+
+  if (is_shutdown) return resume(false)
+
+  if is_shutdown { return resume(false) } else if ready { yield 1 } else { yield }
+}";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::FunctionDeclaration(worker)] = result.as_slice() else {
+            panic!("Expected a function, found {:?}", result);
+        };
+        let [AST::While(loop_), AST::ForOf(parenthesised), AST::ForOf(bare), AST::If(short), AST::If(long)] =
+            file.arena.nodes(&worker.body).as_slice()
+        else {
+            panic!("Expected five statements, found {:?}", worker.body);
+        };
+
+        assert!(matches!(
+            loop_.pattern,
+            Some(Pattern::Constructor(ConstructorPattern {
+                name: "Some",
+                ..
+            }))
+        ));
+        assert!(matches!(&file[loop_.condition], AST::Call(_)));
+        assert!(matches!(
+            parenthesised.pattern,
+            Pattern::Identifier(Identifier { value: "a", .. })
+        ));
+        assert!(matches!(
+            &file[bare.iterable],
+            AST::Identifier(Identifier {
+                value: "waiters",
+                ..
+            })
+        ));
+        assert!(matches!(
+            file.arena.nodes(&short.consequent).as_slice(),
+            [AST::Return(Return { value: Some(_), .. })]
+        ));
+        assert_eq!(short.alternative, None);
+
+        let Some(AST::If(else_if)) = long.alternative.map(|id| &file[id]) else {
+            panic!("Expected an else if, found {:?}", long.alternative);
+        };
+
+        assert!(matches!(
+            file.arena.nodes(&else_if.consequent).as_slice(),
+            [AST::Yield(Yield { value: Some(_), .. })]
+        ));
+        assert!(matches!(
+            else_if.alternative.map(|id| &file[id]),
+            Some(AST::Block(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_while_loops_without_bindings() -> Result<(), Box<dyn Error>> {
+        let file = parse("while (a) || b {\n  step()\n}\nwhile running {}")?;
+        let result = file.statements();
+
+        let [AST::While(first), AST::While(second)] = result.as_slice() else {
+            panic!("Expected two loops, found {:?}", result);
+        };
+
+        assert_eq!(first.pattern, None);
+        assert!(matches!(&file[first.condition], AST::Binary(_)));
+        assert!(matches!(&file[second.condition], AST::Identifier(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_type_annotations() -> Result<(), Box<dyn Error>> {
+        let input = "fun f(
+  a: Effect<Console, Unit>,
+  b: (E) -> A,
+  c: A -> (B, A),
+  d: [A],
+  e: A | B & C,
+  g: { x: Int, ...Rest },
+  h: Option<Queue<Int>>,
+): Either<E, (A, ...B)> { a }";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::FunctionDeclaration(f)] = result.as_slice() else {
+            panic!("Expected a function, found {:?}", result);
+        };
+        let types = f
+            .parameters
+            .iter()
+            .map(|parameter| parameter.annotation.clone().unwrap().annotated)
+            .collect::<Vec<_>>();
+        let reference = |name| Type::Reference(name, vec![]);
+
+        assert_eq!(
+            types,
+            vec![
+                Type::Reference("Effect", vec![reference("Console"), Type::Unit]),
+                Type::Function(
+                    vec![],
+                    vec![reference("E")],
+                    vec![],
+                    Box::new(reference("A"))
+                ),
+                Type::Function(
+                    vec![],
+                    vec![reference("A")],
+                    vec![],
+                    Box::new(Type::Tuple(vec![
+                        TupleData::Member(Box::new(reference("B"))),
+                        TupleData::Member(Box::new(reference("A"))),
+                    ]))
+                ),
+                Type::Array(Box::new(reference("A"))),
+                Type::Union(vec![
+                    reference("A"),
+                    Type::Intersection(vec![reference("B"), reference("C")]),
+                ]),
+                Type::Struct(vec![
+                    StructData::Member("x", Box::new(Type::Int)),
+                    StructData::Spread(Box::new(reference("Rest"))),
+                ]),
+                Type::Option(Box::new(Type::Reference("Queue", vec![Type::Int]))),
+            ]
+        );
+        assert_eq!(
+            f.return_type.as_ref().unwrap().annotated,
+            Type::Either(
+                Box::new(reference("E")),
+                Box::new(Type::Tuple(vec![
+                    TupleData::Member(Box::new(reference("A"))),
+                    TupleData::Spread(Box::new(reference("B"))),
+                ]))
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_effect_declarations() -> Result<(), Box<dyn Error>> {
+        let input = "effect Fail<E>(E) -> Never
+
+effect State<A> {
+  get() -> A
+  modify<B>(A -> (B, A)) -> B
+}
+
+fun safe_divide(a: Int, b: Int): Effect<Fail<CalcError.CannotDivideByZero>, Int> { a }";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::EffectDeclaration(fail), AST::EffectDeclaration(state), AST::FunctionDeclaration(f)] =
+            result.as_slice()
+        else {
+            panic!("Expected two effects and a function, found {:?}", result);
+        };
+
+        assert_eq!(fail.operations.len(), 1);
+        assert_eq!(fail.operations[0].name, None);
+        assert_eq!(fail.operations[0].return_type.annotated, Type::Never);
+        assert_eq!(state.operations.len(), 2);
+        assert_eq!(state.operations[1].name, Some("modify"));
+        assert_eq!(state.operations[1].type_parameters[0].name, "B");
+        assert!(matches!(
+            state.operations[1].parameters[0].annotated,
+            Type::Function(..)
+        ));
+        assert_eq!(
+            f.return_type.as_ref().unwrap().annotated,
+            Type::Reference(
+                "Effect",
+                vec![
+                    Type::Reference(
+                        "Fail",
+                        vec![Type::Reference("CalcError.CannotDivideByZero", vec![])]
+                    ),
+                    Type::Int,
+                ]
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_built_in_types_with_the_wrong_arity() {
+        assert!(parse("fun f(a: Option<A, B>) { a }").is_err());
+        assert!(parse("fun f(a: Int<A>) { a }").is_err());
+    }
+
+    #[test]
+    fn it_rejects_bounds_on_function_type_parameters() {
+        let input = "fun g(c: <B: Eq<B>>(B) -> B) {}";
+
+        assert_eq!(
+            parse(input).err(),
+            Some(ParseError::new(
+                "Type parameters of function types can't have bounds",
+                Position::new(10, 17),
+            ))
+        );
+        assert!(parse("fun g(c: <B>(B) -> B) {}").is_ok());
+    }
+
+    #[test]
+    fn it_parses_algebraic_data_types() -> Result<(), Box<dyn Error>> {
+        let input = "type Either<E, A> =
+  | Left(E)
+  | Right(A)
+
+type CalcError =
+  | CannotDivideByZero
+
+type Shape = Circle { radius: Float } | Rectangle(width: Float, height: Float)
+
+type Expr<A> =
+  | IntLiteral(Int): Expr<Int>
+  | Equal(Expr<Int>, Expr<Int>): Expr<Boolean>";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::TypeDeclaration(either), AST::TypeDeclaration(error), AST::TypeDeclaration(shape), AST::TypeDeclaration(expr)] =
+            result.as_slice()
+        else {
+            panic!("Expected four type declarations, found {:?}", result);
+        };
+
+        assert_eq!(either.name, "Either");
+        assert_eq!(either.type_parameters.len(), 2);
+        assert_eq!(
+            either
+                .type_constructors
+                .iter()
+                .map(|constructor| constructor.name)
+                .collect::<Vec<_>>(),
+            vec!["Left", "Right"]
+        );
+        assert_eq!(either.type_constructors[0].parameters[0].name, None);
+        assert!(error.type_constructors[0].parameters.is_empty());
+        assert_eq!(
+            shape.type_constructors[0].parameters[0].name,
+            Some("radius")
+        );
+        assert_eq!(
+            shape.type_constructors[1].parameters[1].name,
+            Some("height")
+        );
+        assert_eq!(
+            expr.type_constructors[1]
+                .return_type
+                .as_ref()
+                .unwrap()
+                .annotated,
+            Type::Reference("Expr", vec![Type::Boolean])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_qualified_constructors() -> Result<(), Box<dyn Error>> {
+        let input = "match result {
+  Either.Left(CalcError.CannotDivideByZero) -> Either.Right(0),
+  Either.Right(value) -> Fail(CalcError.CannotDivideByZero),
+}";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::Match(value)] = result.as_slice() else {
+            panic!("Expected a match, found {:?}", result);
+        };
+        let MatchTest::Pattern(Pattern::Constructor(left)) = &value.cases[0].test else {
+            panic!(
+                "Expected a constructor pattern, found {:?}",
+                value.cases[0].test
+            );
+        };
+
+        assert_eq!(left.qualifier, vec!["Either"]);
+        assert_eq!(left.name, "Left");
+        assert!(matches!(
+            &left.arguments[..],
+            [Pattern::Constructor(ConstructorPattern { name: "CannotDivideByZero", arguments, .. })]
+                if arguments.is_empty()
+        ));
+        assert!(matches!(&file[value.cases[0].body], AST::Call(_)));
+        assert!(matches!(
+            value.cases[1].test,
+            MatchTest::Pattern(Pattern::Constructor(ConstructorPattern {
+                name: "Right",
+                ..
+            }))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_imports() -> Result<(), Box<dyn Error>> {
+        let input = "import Console from 'std:Console'
+import { a, b as c } from './letters'
+import * as Queue from \"std:Queue\"
+import type Prelude, { Option as Maybe } from 'std:Prelude'";
+        let file = parse(input)?;
+        let imports = file.arena.nodes(&file.imports);
+
+        let [AST::ImportDeclaration(default), AST::ImportDeclaration(named), AST::ImportDeclaration(namespace), AST::ImportDeclaration(types)] =
+            imports.as_slice()
+        else {
+            panic!("Expected four imports, found {:?}", file.imports);
+        };
+
+        assert_eq!(default.default, Some("Console"));
+        assert_eq!(default.specifier, "std:Console");
+        assert_eq!(named.default, None);
+        assert_eq!(named.named[1].name, "b");
+        assert_eq!(named.named[1].alias, Some("c"));
+        assert_eq!(namespace.namespace, Some("Queue"));
+        assert_eq!(namespace.specifier, "std:Queue");
+        assert!(types.type_only);
+        assert_eq!(types.default, Some("Prelude"));
+        assert_eq!(types.named[0].alias, Some("Maybe"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_builds_an_export_table() -> Result<(), Box<dyn Error>> {
+        let input = "export fun main() { 1 }
+export type Answer = | Yes | No
+export effect Fail<E>(E) -> Never
+export a = pair
+export { main as run }
+export { log } from 'std:Console'
+export * as Queue from 'std:Queue'";
+        let file = parse(input)?;
+        let exports = file
+            .exports
+            .iter()
+            .map(|export| (export.exported, export.local, export.from))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            exports,
+            vec![
+                ("main", Some("main"), None),
+                ("Answer", Some("Answer"), None),
+                ("Fail", Some("Fail"), None),
+                ("a", Some("a"), None),
+                ("run", Some("main"), None),
+                ("log", Some("log"), Some("std:Console")),
+                ("Queue", None, Some("std:Queue")),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_exporting_expressions() {
+        assert!(parse("export main()").is_err());
+    }
+
+    #[test]
+    fn it_parses_typeclass_declarations() -> Result<(), Box<dyn Error>> {
+        let input = "typeclass Ord<A>: Eq<A>, Show<A> {
+  type Key: Eq<Key>
+  fun compare(a: A, b: A): Int
+  fun max(a: A, b: A): A {
+    match compare(a, b) {
+      n < 0 -> b,
+      _ -> a,
+    }
+  }
+}";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::TypeclassDeclaration(ord)] = result.as_slice() else {
+            panic!("Expected a typeclass, found {:?}", result);
+        };
+
+        assert_eq!(ord.name, "Ord");
+        assert_eq!(ord.superclasses.len(), 2);
+        assert_eq!(ord.associated_types[0].name, "Key");
+        assert!(ord.associated_types[0].bound.is_some());
+        assert_eq!(ord.methods.len(), 2);
+        assert_eq!(ord.methods[0].default, None);
+        assert_eq!(ord.methods[1].default.as_ref().map(Vec::len), Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_distinguishes_instances_from_effect_impls() -> Result<(), Box<dyn Error>> {
+        let input = "impl<A> Show for Option<A> {
+  type Output = String
+  fun show(value: Option<A>): String { \"Option\" }
+}
+
+impl<A> for Queue<A> {
+  fun enqueue_all(list: [A]) { list }
+}";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::InstanceDeclaration(show), AST::ImplBlock(queue)] = result.as_slice() else {
+            panic!("Expected an instance and an impl block, found {:?}", result);
+        };
+
+        assert_eq!(show.typeclass, "Show");
+        assert_eq!(
+            show.target.annotated,
+            Type::Option(Box::new(Type::Reference("A", vec![])))
+        );
+        assert_eq!(
+            show.associated_types[0].default.as_ref().unwrap().annotated,
+            Type::String
+        );
+        assert!(matches!(
+            file.arena.nodes(&show.members).as_slice(),
+            [AST::FunctionDeclaration(_)]
+        ));
+        assert_eq!(queue.effect, "Queue");
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_struct_declarations() -> Result<(), Box<dyn Error>> {
+        let file = parse("struct Point<A> {\n  x: A\n  y: A\n}")?;
+        let result = file.statements();
+
+        let [AST::StructDeclaration(point)] = result.as_slice() else {
+            panic!("Expected a struct, found {:?}", result);
+        };
+
+        assert_eq!(point.name, "Point");
+        assert_eq!(point.type_parameters.len(), 1);
+        assert!(matches!(
+            point.fields.as_slice(),
+            [StructData::Member("x", _), StructData::Member("y", _)]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_records_and_field_access() -> Result<(), Box<dyn Error>> {
+        let file = parse("point = { x: 1, y, ...rest }\nempty = {}\n{ a: 1 }.a")?;
+        let result = file.statements();
+
+        let [AST::LetBinding(point), AST::LetBinding(empty), AST::MemberAccess(access)] =
+            result.as_slice()
+        else {
+            panic!(
+                "Expected two bindings and a member access, found {:?}",
+                result
+            );
+        };
+
+        let AST::Record(record) = &file[point.value] else {
+            panic!("Expected a record, found {:?}", point.value);
+        };
+
+        assert!(matches!(
+            record.members.as_slice(),
+            [
+                RecordMember::Field(Identifier { value: "x", .. }, _),
+                RecordMember::Shorthand(Identifier { value: "y", .. }),
+                RecordMember::Spread(_),
+            ]
+        ));
+        assert!(
+            matches!(&file[empty.value], AST::Record(Record { members, .. }) if members.is_empty())
+        );
+        assert!(matches!(&file[access.object], AST::Record(_)));
+        assert_eq!(access.property.value, "a");
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_distinguishes_blocks_from_records() -> Result<(), Box<dyn Error>> {
+        let file = parse("f = () -> { a }\ng = () -> { a, }\nh = () -> { b = a\nb }")?;
+        let result = file.statements();
+
+        let [AST::LetBinding(f), AST::LetBinding(g), AST::LetBinding(h)] = result.as_slice() else {
+            panic!("Expected three bindings, found {:?}", result);
+        };
+
+        let bodies = [f, g, h].map(|binding| match &file[binding.value] {
+            AST::Lambda(lambda) => file.arena.nodes(&lambda.body),
+            value => panic!("Expected a lambda, found {:?}", value),
+        });
+
+        assert!(matches!(bodies[0].as_slice(), [AST::Identifier(_)]));
+        assert!(matches!(bodies[1].as_slice(), [AST::Record(_)]));
+        assert!(matches!(
+            bodies[2].as_slice(),
+            [AST::LetBinding(_), AST::Identifier(_)]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_arrays_and_tuples() -> Result<(), Box<dyn Error>> {
+        let file = parse("values ++ [a, ...rest]\n(a, b)\n(a)\n()")?;
+        let result = file.statements();
+
+        let [AST::Binary(concat), AST::Tuple(pair), AST::Identifier(_), AST::Tuple(unit)] =
+            result.as_slice()
+        else {
+            panic!(
+                "Expected a concat, two tuples and a grouping, found {:?}",
+                result
+            );
+        };
+
+        let AST::Array(array) = &file[concat.right] else {
+            panic!("Expected an array, found {:?}", concat.right);
+        };
+
+        assert!(matches!(
+            array.elements.as_slice(),
+            [Element::Member(_), Element::Spread(_)]
+        ));
+        assert_eq!(pair.elements.len(), 2);
+        assert!(unit.elements.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_destructures_arrays_tuples_and_records() -> Result<(), Box<dyn Error>> {
+        let input = "(a, b) = pair
+{ x, y: [first, ...others] } = point
+match values {
+  [] -> 0,
+  [head, ...tail] -> head,
+}";
+        let file = parse(input)?;
+        let result = file.statements();
+
+        let [AST::LetBinding(pair), AST::LetBinding(point), AST::Match(values)] = result.as_slice()
+        else {
+            panic!("Expected two bindings and a match, found {:?}", result);
+        };
+
+        assert!(matches!(&pair.pattern, Pattern::Tuple(tuple) if tuple.elements.len() == 2));
+
+        let Pattern::Record(record) = &point.pattern else {
+            panic!("Expected a record pattern, found {:?}", point.pattern);
+        };
+
+        assert_eq!(record.fields[0].name.value, "x");
+        assert!(matches!(
+            &record.fields[1].pattern,
+            Pattern::Array(ArrayPattern { elements, rest: Some(_), .. }) if elements.len() == 1
+        ));
+        assert!(matches!(
+            &values.cases[1].test,
+            MatchTest::Pattern(Pattern::Array(ArrayPattern { rest: Some(_), .. }))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_returns_records_from_handlers() -> Result<(), Box<dyn Error>> {
+        let file = parse("handler pair() {\n  return { a, b }\n}")?;
+        let result = file.statements();
+
+        let [AST::HandlerDeclaration(handler)] = result.as_slice() else {
+            panic!("Expected a handler, found {:?}", result);
+        };
+
+        assert!(handler.operations.is_empty());
+        assert!(matches!(
+            file.arena.nodes(&handler.body).as_slice(),
+            [AST::Return(_)]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_the_examples() -> Result<(), Box<dyn Error>> {
+        for name in ["fib", "state", "pattern_matching", "queue"] {
+            let contents = read_to_string(format!("examples/{}.asura", name))?;
+
+            parse(&contents)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_template_literals() -> Result<(), Box<dyn Error>> {
+        let file = parse("`fib(${n}) = ${fib(n - 1) + { 1 }}`")?;
+        let result = file.statements();
+
+        let [AST::TemplateLiteral(template)] = result.as_slice() else {
+            panic!("Expected a template literal, found {:?}", result);
+        };
+
+        assert_eq!(template.template, vec!["fib(", ") = ", ""]);
+        assert!(matches!(
+            file.arena.nodes(&template.values).as_slice(),
+            [AST::Identifier(Identifier { value: "n", position }), AST::Binary(_)]
+                if *position == Position::new(7, 8)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_unterminated_interpolations() {
+        assert!(parse("`a ${b`").is_err());
+        assert!(parse("`a ${b c}`").is_err());
+    }
+
+    #[test]
+    fn it_links_nodes_to_their_parents() -> Result<(), Box<dyn Error>> {
+        let file = parse("fun f() {\n  g(a + 1)\n}")?;
+
+        let (sum, _) = file
+            .arena
+            .iter()
+            .find(|(_, node)| matches!(node, AST::Binary(_)))
+            .expect("Expected a binary expression");
+        let ancestors = file
+            .arena
+            .ancestors(sum)
+            .map(|id| &file[id])
+            .collect::<Vec<_>>();
+
+        assert!(matches!(
+            ancestors.as_slice(),
+            [AST::Call(_), AST::FunctionDeclaration(_)]
+        ));
+        assert_eq!(file.arena.parent(file.body[0]), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_only_keeps_nodes_reachable_from_the_body() -> Result<(), Box<dyn Error>> {
+        let input = "x := 1
+x = 2
+(a, b) = pair
+while (Some(c) = next()) { c }
+match v {
+  Some(d) -> d,
+  n < 0 -> n,
+}";
+        let file = parse(input)?;
+
+        for (id, node) in file.arena.iter() {
+            assert!(
+                file.arena.parent(id).is_some() || file.body.contains(&id),
+                "Found an unreachable node {:?}",
+                node
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_counts_every_node_reachable_from_the_body() -> Result<(), Box<dyn Error>> {
+        let file = parse("f(a + 1)")?;
+
+        assert_eq!(NodeCounter::count(&file), 5);
+
+        for name in ["fib", "state", "pattern_matching", "queue"] {
+            let contents = read_to_string(format!("examples/{}.asura", name))?;
+            let file = parse(&contents)?;
+
+            assert_eq!(NodeCounter::count(&file), file.arena.len());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_collects_identifiers_in_source_order() -> Result<(), Box<dyn Error>> {
+        let file = parse("(a, b) = pair\nx := a.y\nx = b")?;
+        let identifiers = IdentifierCollector::collect(&file);
+        let names = identifiers
+            .iter()
+            .map(|identifier| identifier.value)
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["a", "b", "pair", "x", "a", "y", "x", "b"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rewrites_identifiers_in_place() -> Result<(), Box<dyn Error>> {
+        struct Rename;
+
+        impl<'a> VisitorMut<'a> for Rename {
+            fn visit_identifier_mut(
+                &mut self,
+                _arena: &mut Arena<'a>,
+                identifier: &mut Identifier<'a>,
+            ) {
+                if identifier.value == "a" {
+                    identifier.value = "z";
+                }
+            }
+        }
+
+        let mut file = parse("a = 1\nf(a, { a: a })")?;
+        Rename.visit_source_file_mut(&mut file);
+
+        let names = IdentifierCollector::collect(&file)
+            .iter()
+            .map(|identifier| identifier.value)
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["z", "f", "z", "z", "z"]);
+        assert_eq!(file, parse("z = 1\nf(z, { z: z })")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_folds_nodes_into_other_kinds_of_node() -> Result<(), Box<dyn Error>> {
+        struct ConstantFolder;
+
+        impl<'a> Fold<'a> for ConstantFolder {
+            fn fold_binary(&mut self, arena: &mut Arena<'a>, binary: Binary) -> AST<'a> {
+                let folded = parsing::fold::fold_binary(self, arena, binary);
+
+                if let AST::Binary(binary) = &folded {
+                    if let (AST::IntegerLiteral(left), AST::IntegerLiteral(right)) =
+                        (&arena[binary.left], &arena[binary.right])
+                    {
+                        match binary.operator {
+                            BinaryOperator::Add => {
+                                return AST::integer_literal(
+                                    left.value + right.value,
+                                    binary.position,
+                                )
+                            }
+                            BinaryOperator::Multiply => {
+                                return AST::integer_literal(
+                                    left.value * right.value,
+                                    binary.position,
+                                )
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
+                folded
+            }
+        }
+
+        let file = ConstantFolder.fold_source_file(parse("f(1 + 2 * 3)")?);
+        let call = match &file[file.body[0]] {
+            AST::Call(call) => call,
+            node => panic!("Expected a call, found {:?}", node),
+        };
+
+        assert_eq!(
+            file[call.arguments[0]],
+            AST::integer_literal(7, Position::new(2, 11))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_exposes_nodes_through_accessors() -> Result<(), Box<dyn Error>> {
+        let input = "fun add<A>(a: A, b): A {\n  `${a}`\n}";
+        let file = parse(input)?;
+
+        let AST::FunctionDeclaration(function) = &file[file.body[0]] else {
+            panic!("Expected a function, found {:?}", file.statements());
+        };
+
+        assert_eq!(function.name(), "add");
+        assert_eq!(function.position(), Position::new(0, input.len()));
+        assert_eq!(function.type_parameters()[0].name(), "A");
+        assert_eq!(
+            function
+                .parameters()
+                .iter()
+                .map(|parameter| (parameter.name(), parameter.annotation().is_some()))
+                .collect::<Vec<_>>(),
+            [("a", true), ("b", false)]
+        );
+        assert_eq!(
+            function
+                .return_type()
+                .map(|annotation| annotation.annotated()),
+            Some(&Type::Reference("A", vec![]))
+        );
+
+        let AST::TemplateLiteral(template) = &file[function.body()[0]] else {
+            panic!("Expected a template literal");
+        };
+
+        assert_eq!(template.template(), ["", ""]);
+        assert_eq!(file[function.body()[0]].position(), Position::new(27, 33));
+        assert_eq!(
+            file[template.values()[0]],
+            AST::identifier("a", Position::new(30, 31))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_builds_nodes_with_constructors() {
+        let mut arena = Arena::new();
+        let one = arena.alloc(AST::integer_literal(1, Position::new(4, 5)));
+        let parameter = FunctionParameter::new(
+            "x",
+            Some(TypeAnnotation::new(Type::Int, Position::new(13, 16))),
+            Position::new(10, 16),
+        );
+        let function = arena.alloc(AST::function_declaration(
+            "f",
+            vec![],
+            vec![parameter],
+            None,
+            vec![one],
+            Position::new(0, 20),
+        ));
+        let file = SourceFile::new(arena, vec![function]);
+
+        let AST::FunctionDeclaration(function) = &file[file.body[0]] else {
+            panic!("Expected a function");
+        };
+
+        assert_eq!(
+            function.parameters()[0].annotation().map(Spanned::position),
+            Some(Position::new(13, 16))
+        );
+        assert_eq!(file.arena.parent(one), Some(file.body[0]));
+    }
+
+    #[test]
+    fn it_prints_the_examples_back_to_equivalent_source() -> Result<(), Box<dyn Error>> {
+        for name in ["fib", "state", "pattern_matching", "queue"] {
+            let contents = read_to_string(format!("examples/{}.asura", name))?;
+
+            assert_round_trips(&contents)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_prints_every_kind_of_node_back_to_equivalent_source() -> Result<(), Box<dyn Error>> {
+        assert_round_trips(
+            r#"import type Default, { a, b as c } from './a'
+import * as NS from "std:ns"
+export { a, c as d }
+export * as All from './all'
+export fun f<A: Show<A>>(a: A, b): (Int) -> Int {
+  x := 1
+  x = x + 1
+  y = -(-x) * (1 + 2) ** 3 ** 4 - !true
+  s = "a" ++ 'say "hi"' ++ "a'b\"c" ++ `t${x + 1}u${ {a: 1} }`
+  r = { a: 1, b, ...c }
+  q = { b, }
+  t = ((1,), (), (1, 2), [1, ...xs])
+  m = (1).a.b(c)(d)
+  l = <T>(x: T): Option<T> -> Some(x)
+  k = (x) -> {
+    y = x
+    y
+  }
+  w = (f(1) with h) with g
+  z = (a with b) + 1
+  { a, ...rest } = r
+  [first, ...others] = xs
+  (p, Either.Left(v), _, 2) = t
+  if a && b || c { return } else if c { yield 1 } else { return 2.0 }
+  while Some(x) = next() { x }
+  while (a < b) { a }
+  for [a, b] of pairs { a }
+  match x {
+    0 -> 0,
+    { a: 1, b } -> b,
+    n < 2 -> n,
+    Cons(h, Nil) -> h,
+  }
+}
+type List<A> = Nil: List<A> | Cons(head: A, List<A>)
+struct Point<A> { x: A, y: [Int], ...Rest }
+effect Fail<E>(E) -> Never
+effect Queue<A> {
+  enqueue(A): Unit
+  dequeue<B>(): Option<A> | B & C
+}
+typeclass Ord<A>: Eq<A>, Show<A> {
+  type Key: Eq<Key> = Int
+  fun compare(a: A, b: A): Int
+  fun max(a: A, b: A): A { a }
+}
+impl<A> Ord<A> for List<A> {
+  type Key = A
+  fun compare(a: A, b: A): Int { 0 }
+}
+impl<A> for Queue<A> {
+  handler queue(initial: [A]) {
+    items := initial
+    return {
+      enqueue(a) { resume(Unit) }
+    }
+  } {
+    return(a) { a }
+  }
+}
+fun g(a: { x: Int, ...R }, b: (Int, ...T), c: <B>(B, _) -> (Int,), d: /ab+/) {}"#,
+        )
+    }
+
+    #[test]
+    fn it_displays_types_as_source() {
+        let function = Type::Function(
+            vec![Type::Reference("A", vec![])],
+            vec![Type::Union(vec![Type::Int, Type::String])],
+            vec![],
+            Box::new(Type::Option(Box::new(Type::Reference("A", vec![])))),
+        );
+
+        assert_eq!(function.to_string(), "<A>(Int | String) -> Option<A>");
+        assert_eq!(
+            Type::Array(Box::new(Type::Intersection(vec![
+                Type::Union(vec![Type::Int, Type::Float]),
+                function
+            ])))
+            .to_source(),
+            "[(Int | Float) & (<A>(Int | String) -> Option<A>)]"
+        );
+        assert_eq!(
+            Type::Tuple(vec![TupleData::Member(Box::new(Type::Infer))]).to_string(),
+            "(_,)"
+        );
+    }
+
+    #[test]
+    fn it_displays_nodes_with_their_arena() -> Result<(), Box<dyn Error>> {
+        let file = parse("a * (b + c)")?;
+
+        assert_eq!(
+            file[file.body[0]].display(&file.arena).to_string(),
+            "a * (b + c)"
+        );
+        assert_eq!(file.to_string(), "a * (b + c)\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_prints_the_concrete_syntax_tree_back_to_the_source() -> Result<(), Box<dyn Error>> {
+        let mut sources = vec![
+            "/** Adds */ fun add ( a:Int,b ) {\n\t// sum\r\n  a+ b ;\n}\n\n\nx = `a${ add(1,2) }b` with h\n".to_string(),
+        ];
+
+        for name in ["fib", "state", "pattern_matching", "queue"] {
+            sources.push(read_to_string(format!("examples/{}.asura", name))?);
+        }
+
+        for source in sources {
+            let (syntax, errors) = parse_syntax(&source);
+
+            assert_eq!(errors, vec![]);
+            assert_eq!(syntax.to_string(), source);
+            assert_eq!(syntax.position(), Position::new(0, source.len()));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_what_does_not_parse_in_the_concrete_syntax_tree() {
+        use syntax::SyntaxKind;
+
+        let source = "x = `a${ f(1) }b`\ny = ) 1\nz = 2 § \n";
+        let (syntax, errors) = parse_syntax(source);
+
+        assert_eq!(syntax.to_string(), source);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.position())
+                .collect::<Vec<_>>(),
+            vec![Position::new(22, 23), Position::new(32, 34)]
+        );
+        assert_eq!(
+            syntax
+                .children()
+                .iter()
+                .map(|node| (node.kind(), node.text()))
+                .collect::<Vec<_>>(),
+            vec![
+                (SyntaxKind::LetBinding, "x = `a${ f(1) }b`".to_string()),
+                (SyntaxKind::Error, "y = ) 1".to_string()),
+                (SyntaxKind::LetBinding, "z = 2".to_string()),
+                (SyntaxKind::Error, "§".to_string()),
+            ]
+        );
+
+        let f = syntax.token_at(source.find('f').unwrap()).unwrap();
+
+        assert_eq!(
+            f.parent()
+                .ancestors()
+                .map(|node| node.kind())
+                .collect::<Vec<_>>(),
+            vec![
+                SyntaxKind::Call,
+                SyntaxKind::TemplateLiteral,
+                SyntaxKind::LetBinding,
+                SyntaxKind::Module
+            ]
+        );
+        assert_eq!(
+            syntax.token_at(source.find('§').unwrap()).unwrap().kind(),
+            TokenKind::Unknown
+        );
+    }
+
+    #[test]
+    fn it_views_concrete_syntax_nodes_as_typed_nodes() -> Result<(), Box<dyn Error>> {
+        use syntax::{views::*, SyntaxKind, SyntaxView};
+
+        let source = "fun add(a: Int, b) {\n  // sum\n  a + b\n}\n";
+        let (syntax, _) = parse_syntax(source);
+        let module = Module::cast(syntax.clone()).unwrap();
+        let function = FunctionDeclaration::cast(module.statements()[0].clone()).unwrap();
+
+        assert_eq!(function.name().unwrap().text(), "add");
+        assert_eq!(
+            function
+                .parameters()
+                .iter()
+                .map(|parameter| parameter.name().unwrap().text().to_string())
+                .collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            function.parameters()[0]
+                .annotation()
+                .unwrap()
+                .syntax()
+                .text(),
+            "Int"
+        );
+
+        let comment = function
+            .syntax()
+            .child_tokens()
+            .into_iter()
+            .find(|token| token.kind() == TokenKind::Comment)
+            .unwrap();
+
+        assert!(comment.is_trivia());
+        assert_eq!(comment.text(), "// sum");
+
+        let body = Binary::cast(function.body()[0].clone()).unwrap();
+
+        assert_eq!(body.operator().unwrap().text(), "+");
+        assert_eq!(body.right().unwrap().kind(), SyntaxKind::Identifier);
+
+        let b = syntax.token_at(source.rfind('b').unwrap()).unwrap();
+
+        assert_eq!(b.text(), "b");
+        assert_eq!(
+            b.parent()
+                .ancestors()
+                .map(|node| node.kind())
+                .collect::<Vec<_>>(),
+            vec![
+                SyntaxKind::Binary,
+                SyntaxKind::FunctionDeclaration,
+                SyntaxKind::Module
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_attaches_doc_comments_to_declarations() -> Result<(), Box<dyn Error>> {
+        let input = "/**
+ * Computes the nth Fibonacci number.
+ *
+ * Uses the naive recursive definition.
+ *
+ * # Examples
+ *
+ * fib(10) == 55
+ *
+ * @param n which number
+ *   to compute
+ * @returns the nth Fibonacci number
+ */
+export fun fib(n) { n }
+
+/** Either one thing or another. */
+type Either<L, R> = Left(L) | Right(R)
+
+// Not a doc comment
+effect State<S> {
+  get() -> S
+}";
+        let file = parse(input)?;
+
+        let AST::ExportDeclaration(export) = &file[file.body[0]] else {
+            panic!("Expected an export, found {:?}", file.statements());
+        };
+        let ExportKind::Declaration(function) = export.kind() else {
+            panic!("Expected an exported declaration");
+        };
+        let AST::FunctionDeclaration(function) = &file[*function] else {
+            panic!("Expected a function");
+        };
+        let docs = function.docs().expect("Expected doc comments");
+
+        assert_eq!(docs.summary(), "Computes the nth Fibonacci number.");
+        assert_eq!(docs.description(), "Uses the naive recursive definition.");
+        assert_eq!(docs.sections().len(), 1);
+        assert_eq!(docs.sections()[0].title(), "Examples");
+        assert_eq!(docs.sections()[0].body(), "fib(10) == 55");
+        assert_eq!(docs.params()[0].name(), "n");
+        assert_eq!(docs.params()[0].description(), "which number to compute");
+        assert_eq!(docs.returns(), Some("the nth Fibonacci number"));
+        assert_eq!(
+            docs.position(),
+            Position::new(0, input.find("*/").unwrap() + 2)
+        );
+
+        let AST::TypeDeclaration(declaration) = &file[file.body[1]] else {
+            panic!("Expected a type declaration");
+        };
+
+        assert_eq!(
+            declaration.docs().map(Docs::summary),
+            Some("Either one thing or another.")
+        );
+
+        let AST::EffectDeclaration(effect) = &file[file.body[2]] else {
+            panic!("Expected an effect declaration");
+        };
+
+        assert_eq!(effect.docs(), None);
+        assert!(DocChecker::check(&file).is_empty());
+        assert_round_trips(input)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_warns_about_stale_doc_comments() -> Result<(), Box<dyn Error>> {
+        let input = "/**
+ * @param a the first
+ * @param c renamed since
+ * @param a the first again
+ */
+fun add(a, b) { a + b }
+
+/** @param T the state */
+effect State<S> {
+  get() -> S
+}";
+        let file = parse(input)?;
+        let warnings = DocChecker::check(&file);
+        let at = |text: &str| {
+            let start = input.find(text).unwrap();
+
+            Position::new(start, start + text.len())
+        };
+
+        assert_eq!(
+            warnings,
+            [
+                DocWarning {
+                    message: "`c` is not a parameter of `add`".to_string(),
+                    position: at("@param c renamed since"),
+                },
+                DocWarning {
+                    message: "`a` is documented more than once".to_string(),
+                    position: at("@param a the first again"),
+                },
+                DocWarning {
+                    message: "`T` is not a type parameter of `State`".to_string(),
+                    position: at("@param T the state"),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_brand_and_alias_declarations() -> Result<(), Box<dyn Error>> {
+        let input = "brand UserId = Int
+brand Email = String & Validated
+
+/** A pair of the same type. */
+export alias Pair<A> = (A, A)
+
+alias IntQueue = Queue<Int>
+
+fun worker() {
+  IntQueue.dequeue()
+}
+
+worker() with IntQueue.unbounded()";
+        let file = parse(input)?;
+
+        let AST::TypeAlias(user_id) = &file[file.body[0]] else {
+            panic!("Expected a brand, found {:?}", file.statements());
+        };
+
+        assert_eq!(user_id.name(), "UserId");
+        assert_eq!(
+            user_id.aliased().annotated(),
+            &Type::Intersection(vec![Type::Int, Type::Brand("UserId")])
+        );
+        assert_eq!(user_id.branded(), Some(Type::Int));
+
+        let AST::TypeAlias(email) = &file[file.body[1]] else {
+            panic!("Expected a brand");
+        };
+
+        assert_eq!(
+            email.branded(),
+            Some(Type::Intersection(vec![
+                Type::String,
+                Type::Reference("Validated", vec![])
+            ]))
+        );
+
+        let AST::ExportDeclaration(export) = &file[file.body[2]] else {
+            panic!("Expected an export");
+        };
+        let ExportKind::Declaration(pair) = export.kind() else {
+            panic!("Expected an exported declaration");
+        };
+        let AST::TypeAlias(pair) = &file[*pair] else {
+            panic!("Expected an alias");
+        };
+
+        assert_eq!(pair.type_parameters()[0].name(), "A");
+        assert_eq!(pair.branded(), None);
+        assert_eq!(
+            pair.docs().map(Docs::summary),
+            Some("A pair of the same type.")
+        );
+        assert_eq!(
+            file.exports
+                .iter()
+                .map(|export| export.exported)
+                .collect::<Vec<_>>(),
+            ["Pair"]
+        );
+
+        let AST::TypeAlias(queue) = &file[file.body[3]] else {
+            panic!("Expected an alias");
+        };
+
+        assert_eq!(
+            queue.aliased().annotated(),
+            &Type::Reference("Queue", vec![Type::Int])
+        );
+
+        // The alias names the effect wherever the effect's name could go.
+        let AST::FunctionDeclaration(worker) = &file[file.body[4]] else {
+            panic!("Expected a function");
+        };
+        let AST::Call(call) = &file[worker.body()[0]] else {
+            panic!("Expected a call");
+        };
+
+        assert_eq!(
+            file[call.callee()].to_source(&file.arena),
+            "IntQueue.dequeue"
+        );
+
+        let AST::With(with) = &file[file.body[5]] else {
+            panic!("Expected a with expression");
+        };
+
+        assert_eq!(
+            file[with.handlers()].to_source(&file.arena),
+            "IntQueue.unbounded()"
+        );
+
+        assert_round_trips(input)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_dispatches_member_calls() -> Result<(), Box<dyn Error>> {
+        let contents = read_to_string("examples/queue.asura")?;
+        let input = format!(
+            "{}
+fun map(list, f) {{ list }}
+
+fun wrap() {{
+  Queue.missing()
+  [1].map(worker)
+  {{ callback: worker }}.callback()
+}}",
+            contents
+        );
+        let file = parse(&input)?;
+        let dispatches = MethodCalls::dispatch(&file);
+        let dispatch = |callee: &str| {
+            dispatches
+                .iter()
+                .find(|(&id, _)| {
+                    let AST::Call(call) = &file[id] else {
+                        return false;
+                    };
+
+                    file[call.callee()].to_source(&file.arena) == callee
+                })
+                .map(|(_, dispatch)| dispatch.clone())
+                .unwrap_or_else(|| panic!("`{}` wasn't dispatched", callee))
+        };
+
+        assert_eq!(
+            dispatch("IntQueue.dequeue"),
+            Dispatch::Operation {
+                effect: "Queue",
+                operation: "dequeue",
+            }
+        );
+        assert_eq!(
+            dispatch("Queue.shutdown"),
+            Dispatch::Operation {
+                effect: "Queue",
+                operation: "shutdown",
+            }
+        );
+        assert!(matches!(
+            dispatch("Queue.unbounded"),
+            Dispatch::ImplMember { effect: "Queue", member }
+                if matches!(&file[member], AST::HandlerDeclaration(_))
+        ));
+        assert!(matches!(
+            dispatch("Queue.enqueue_all"),
+            Dispatch::ImplMember { effect: "Queue", member }
+                if matches!(&file[member], AST::FunctionDeclaration(_))
+        ));
+        assert_eq!(
+            dispatch("Console.log"),
+            Dispatch::Imported {
+                specifier: "std:Console",
+                name: "log",
+            }
+        );
+        assert_eq!(
+            dispatch("worker.fork"),
+            Dispatch::Builtin(BuiltinMethod::Fork)
+        );
+        assert_eq!(
+            dispatch("fiber.join"),
+            Dispatch::Builtin(BuiltinMethod::Join)
+        );
+        assert_eq!(dispatch("Queue.missing"), Dispatch::Unresolved);
+        assert_eq!(dispatch("[1].map"), Dispatch::Uniform { function: "map" });
+        assert_eq!(
+            dispatch("{ callback: worker }.callback"),
+            Dispatch::Field { name: "callback" }
+        );
+
+        // What the receiver resolves to decides, not how it is spelled, and
+        // fields it is known to have come first.
+        let file = parse(
+            "effect Queue { dequeue(): Int }
+
+fun map(list, f) { list }
+
+fun shadowed(Queue) { Queue.dequeue() }
+
+fun fields(worker) {
+  m = { map: (n) -> n }
+  m.map(1)
+  record = { fork: worker }
+  record.fork()
+}",
+        )?;
+        let dispatches = MethodCalls::dispatch(&file);
+        let mut dispatched = dispatches.values().cloned().collect::<Vec<_>>();
+
+        dispatched.sort_by_key(|dispatch| format!("{:?}", dispatch));
+
+        assert_eq!(
+            dispatched,
+            [
+                Dispatch::Field { name: "dequeue" },
+                Dispatch::Field { name: "fork" },
+                Dispatch::Field { name: "map" },
+            ]
+        );
+
+        let contents = read_to_string("examples/pattern_matching.asura")?;
+        let file = parse(&contents)?;
+        let dispatches = MethodCalls::dispatch(&file);
+        let mut dispatched = dispatches
+            .iter()
+            .map(|(&id, dispatch)| {
+                let AST::Call(call) = &file[id] else {
+                    panic!("Expected a call");
+                };
+
+                (file[call.callee()].to_source(&file.arena), dispatch.clone())
+            })
+            .collect::<Vec<_>>();
+
+        dispatched.sort_by_key(|(callee, _)| callee.clone());
+        dispatched.dedup();
+
+        assert!(matches!(
+            &dispatched[..],
+            [
+                (either_left, Dispatch::Constructor { type_name: "Either", constructor: "Left" }),
+                (either_right, Dispatch::Constructor { type_name: "Either", constructor: "Right" }),
+                (catch, Dispatch::ImplMember { effect: "Fail", .. }),
+                (try_, Dispatch::ImplMember { effect: "Fail", .. }),
+                (add, Dispatch::Constructor { type_name: "Op", constructor: "Add" }),
+                ..
+            ] if either_left == "Either.Left"
+                && either_right == "Either.Right"
+                && catch == "Fail.catch"
+                && try_ == "Fail.try"
+                && add == "Op.Add"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reparses_random_edits_like_a_full_parse() -> Result<(), Box<dyn Error>> {
+        let snippets = [
+            "",
+            " ",
+            "\n",
+            "x",
+            "1",
+            "42",
+            "a + 1",
+            "(",
+            ")",
+            "{",
+            "}",
+            ",",
+            "=",
+            ":=",
+            "value",
+            "State",
+            "fun f() {}\n",
+            "// note\n",
+            "\"text\"",
+            "\nreturn 2\n",
+            "\nx = 1\n",
+            "\nvalues = []\n",
+            "if (a) b = 1\n",
+        ];
+        // xorshift, so that failures can be reproduced without a dependency
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut random = |bound: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % bound as u64) as usize
+        };
+
+        for name in ["fib", "state", "pattern_matching", "queue"] {
+            let original = read_to_string(format!("examples/{}.asura", name))?;
+            let mut source = original.clone();
+
+            for _ in 0..60 {
+                let start = random(source.len() + 1);
+                let end = (start + random(8)).min(source.len());
+                let text = match random(3) {
+                    0 => {
+                        let from = random(original.len());
+
+                        original[from..(from + random(40)).min(original.len())].to_string()
+                    }
+                    _ => snippets[random(snippets.len())].to_string(),
+                };
+                let edited = format!("{}{}{}", &source[..start], text, &source[end..]);
+                let edit = Edit::new(Position::new(start, end), text.len());
+                let expected = parse(&edited);
+
+                assert_eq!(
+                    reparse(parse(&source)?, &source, &edited, edit),
+                    expected,
+                    "{:?} in\n{}",
+                    edit,
+                    edited
+                );
+
+                // Keep editing the module while it parses
+                source = if expected.is_ok() {
+                    edited
+                } else {
+                    original.clone()
+                };
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reuses_the_nodes_an_edit_does_not_touch() -> Result<(), Box<dyn Error>> {
+        let source = read_to_string("examples/queue.asura")?;
+        let start = source.find("Queue.shutdown()").unwrap();
+        let edited = source.replacen("Queue.shutdown()", "Queue.shutdown(true)", 1);
+        let edit = Edit::new(Position::new(start + 15, start + 15), 4);
+
+        assert_eq!(
+            reparse(parse(&source)?, &source, &edited, edit)?,
+            parse(&edited)?
+        );
+
+        // Renames a node before the edited statement, which only a reused
+        // node keeps.
+        let mut old = parse(&source)?;
+        let list = old
+            .arena
+            .iter()
+            .find(|(_, node)| matches!(node, AST::Identifier(identifier) if identifier.value == "list"))
+            .map(|(id, _)| id)
+            .unwrap();
+        let AST::Identifier(identifier) = old.arena.take(list) else {
+            unreachable!()
+        };
+        let console = source.find("Console").unwrap();
+
+        old.arena.replace(
+            list,
+            AST::Identifier(Identifier {
+                value: &source[console..console + 7],
+                ..identifier
+            }),
+        );
+
+        let file = reparse(old, &source, &edited, edit)?;
+
+        // Reused nodes borrow from the new source, so the old one can go
+        drop(source);
+
+        let AST::Identifier(renamed) = &file[list] else {
+            panic!("Expected an identifier, found {:?}", file[list]);
+        };
+
+        assert_eq!(renamed.value, "Console");
+        assert!(file.arena.iter().all(|(_, node)| match node {
+            AST::Identifier(identifier) => edited
+                .as_bytes()
+                .as_ptr_range()
+                .contains(&identifier.value.as_ptr()),
+            _ => true,
+        }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reparses_trees_not_borrowed_from_the_source() -> Result<(), Box<dyn Error>> {
+        let source = read_to_string("examples/queue.asura")?;
+        let copy = source.clone();
+        let start = source.find("Queue.shutdown()").unwrap();
+        let edited = source.replacen("Queue.shutdown()", "Queue.shutdown(true)", 1);
+        let edit = Edit::new(Position::new(start + 15, start + 15), 4);
+
+        assert_eq!(
+            reparse(parse(&source)?, &copy, &edited, edit)?,
+            parse(&edited)?
+        );
+
+        let mut arena = Arena::new();
+        let a = arena.alloc(AST::identifier("a", Position::new(0, 1)));
+        let b = arena.alloc(AST::identifier("b", Position::new(2, 3)));
+        let built = SourceFile::new(arena, vec![a, b]);
+
+        assert_eq!(
+            reparse(built, "a\nb", "a\nbc", Edit::new(Position::new(3, 3), 1))?,
+            parse("a\nbc")?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_dumps_the_ast_with_positions() -> Result<(), Box<dyn Error>> {
+        let input = "type Shape = Circle(radius: Float)
+
+fun area(shape: Shape): Float {
+  match shape {
+    Circle(r) -> r * r
+  }
+}
+
+point = { x: 1, ...origin }
+";
+
+        let file = parse(input)?;
+
+        assert_eq!(
+            dump_ast(input, &file),
+            "(Module
+  (TypeDeclaration 1:1-1:35 Shape
+    (TypeConstructor 1:14-1:35 Circle
+      (TypeAnnotation 1:21-1:34 radius: Float)))
+  (FunctionDeclaration 3:1-7:2 area
+    (FunctionParameter 3:10-3:22 shape
+      (TypeAnnotation 3:17-3:22 Shape))
+    (TypeAnnotation 3:25-3:30 Float)
+    (Match 4:3-6:4
+      (Identifier 4:9-4:14 shape)
+      (MatchCase 5:5-5:23
+        (Pattern 5:5-5:14 Circle(r))
+        (Binary 5:18-5:23 Multiply
+          (Identifier 5:18-5:19 r)
+          (Identifier 5:22-5:23 r)))))
+  (LetBinding 9:1-9:28
+    (Pattern 9:1-9:6 point)
+    (Record 9:9-9:28
+      (Field 9:11-9:12 x
+        (IntegerLiteral 9:14-9:15 1))
+      (Spread
+        (Identifier 9:20-9:26 origin)))))
+"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_resolves_the_names_in_the_examples() -> Result<(), Box<dyn Error>> {
+        for example in ["fib", "pattern_matching", "queue", "state"] {
+            let contents = read_to_string(format!("examples/{}.asura", example))?;
+            let file = parse(&contents)?;
+            let resolution = Resolver::resolve(&file);
+
+            assert_eq!(resolution.diagnostics(), &[], "in {}", example);
+        }
+
+        let contents = read_to_string("examples/queue.asura")?;
+        let file = parse(&contents)?;
+        let resolution = Resolver::resolve(&file);
+        let resolved = |source: &str| {
+            file.arena
+                .iter()
+                .filter(|(_, node)| node.to_source(&file.arena) == source)
+                .map(|(id, _)| {
+                    let declaration = resolution
+                        .reference(id)
+                        .unwrap_or_else(|| panic!("`{}` wasn't resolved", source));
+
+                    resolution.declaration(declaration)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert!(resolved("resume")
+            .iter()
+            .all(|declaration| declaration.kind() == DeclarationKind::Resume));
+        assert!(resolved("values").iter().all(|declaration| {
+            declaration.kind() == DeclarationKind::Variable { mutable: true }
+        }));
+        assert_eq!(
+            resolved("IntQueue.dequeue")[0].kind(),
+            DeclarationKind::Operation
+        );
+        assert_eq!(
+            resolved("Queue.unbounded")[0].kind(),
+            DeclarationKind::Handler
+        );
+        assert_eq!(resolved("Console")[0].kind(), DeclarationKind::Import);
+        assert!(resolved("None")[0].is_builtin());
+
+        let contents = read_to_string("examples/pattern_matching.asura")?;
+        let file = parse(&contents)?;
+        let resolution = Resolver::resolve(&file);
+        let right = file
+            .arena
+            .iter()
+            .find(|(_, node)| node.to_source(&file.arena) == "Either.Right")
+            .and_then(|(id, _)| resolution.reference(id))
+            .map(|id| resolution.declaration(id))
+            .ok_or("`Either.Right` wasn't resolved")?;
+
+        assert_eq!(right.kind(), DeclarationKind::Constructor);
+        assert_eq!(
+            right
+                .position()
+                .map(|position| &contents[position.start..position.end]),
+            Some("Right(A)")
+        );
+        assert_eq!(right.uses().len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_binds_names_to_the_nearest_declaration() -> Result<(), Box<dyn Error>> {
+        let input = "a = 1
+fun f(a) { a }
+f(a)";
+        let file = parse(input)?;
+        let resolution = Resolver::resolve(&file);
+        let kinds = file
+            .arena
+            .iter()
+            .filter(|(_, node)| matches!(node, AST::Identifier(identifier) if identifier.value() == "a"))
+            .map(|(id, _)| resolution.declaration(resolution.reference(id).unwrap()).kind())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                DeclarationKind::Parameter,
+                DeclarationKind::Variable { mutable: false },
+            ]
+        );
+        assert_eq!(
+            resolution.diagnostics(),
+            &[NameDiagnostic {
+                kind: NameDiagnosticKind::Shadowed,
+                message: "`a` shadows an earlier `a`".to_string(),
+                position: Position::new(12, 13),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_keys_names_without_nodes_by_their_site() -> Result<(), Box<dyn Error>> {
+        let file = parse(
+            "fun f(x) {
+  match x {
+    Some(y) -> y,
+    y -> y,
+  }
+}",
+        )?;
+        let resolution = Resolver::resolve(&file);
+        let function = file.body[0];
+        let (match_, _) = file
+            .arena
+            .iter()
+            .find(|(_, node)| matches!(node, AST::Match(_)))
+            .ok_or("Expected a match")?;
+        let kind = |site, name| {
+            resolution
+                .name(site, name)
+                .map(|id| resolution.declaration(id).kind())
+        };
+
+        assert_eq!(
+            kind(Site::Node(function), "x"),
+            Some(DeclarationKind::Parameter)
+        );
+        assert_eq!(
+            kind(Site::Case(match_, 0), "Some"),
+            Some(DeclarationKind::Constructor)
+        );
+        assert_ne!(
+            resolution.name(Site::Case(match_, 0), "y"),
+            resolution.name(Site::Case(match_, 1), "y")
+        );
+        assert_eq!(
+            kind(Site::Case(match_, 1), "y"),
+            Some(DeclarationKind::Variable { mutable: false })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_unresolved_shadowed_and_unused_names() -> Result<(), Box<dyn Error>> {
+        let input = "import { log, error as fail } from 'std:Console'
+
+type Shape = Circle(Float) | Square(Float)
+
+fun area<A>(shape: Shape, unused: Int): Float {
+  match shape {
+    Circle(r) -> r * r * pi,
+    Shape.Square(side) -> side * side,
+    Shape.Triangle(x) -> x,
+  }
+}
+
+fun main(): Missing {
+  total := 0
+  total = area(Circle(1.0), 0)
+  shape = Circle(2.0)
+  if (true) {
+    shape = Square(1.0)
+    log(shape)
+  }
+  missing(total)
+}";
+        let file = parse(input)?;
+        let resolution = Resolver::resolve(&file);
+        let diagnostics = resolution
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let position = diagnostic.position;
+
+                (
+                    diagnostic.kind,
+                    diagnostic.message.as_str(),
+                    &input[position.start..position.end],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    NameDiagnosticKind::Unused,
+                    "`fail` is never used",
+                    "error as fail"
+                ),
+                (NameDiagnosticKind::Unused, "`A` is never used", "A"),
+                (
+                    NameDiagnosticKind::Unused,
+                    "`unused` is never used",
+                    "unused: Int"
+                ),
+                (NameDiagnosticKind::Unresolved, "`pi` is not defined", "pi"),
+                (
+                    NameDiagnosticKind::Unresolved,
+                    "`Shape` has no constructor `Triangle`",
+                    "Shape.Triangle(x)"
+                ),
+                (
+                    NameDiagnosticKind::Unresolved,
+                    "`Missing` is not defined",
+                    "Missing"
+                ),
+                (NameDiagnosticKind::Unused, "`shape` is never used", "shape"),
+                (
+                    NameDiagnosticKind::Shadowed,
+                    "`shape` shadows an earlier `shape`",
+                    "shape"
+                ),
+                (
+                    NameDiagnosticKind::Unresolved,
+                    "`missing` is not defined",
+                    "missing"
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_resolves_receivers_named_like_their_constructor_as_types() -> Result<(), Box<dyn Error>> {
+        let input = "type Wrap<A> = Wrap(A)
+
+fun unwrap(wrapped: Wrap<Int>): Int {
+  match wrapped {
+    Wrap(value) -> value,
+  }
+}
+
+fun main() {
+  Wrap.Missing(1)
+  unwrap(Wrap.Wrap(\"s\"))
+}";
+        let file = parse(input)?;
+        let resolution = Resolver::resolve(&file);
+        let diagnostics = resolution
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(diagnostics, vec!["`Wrap` has no constructor `Missing`"]);
+
+        let typing = TypeChecker::check(&file);
+        let errors = typing
+            .errors()
+            .iter()
+            .map(|error| {
+                (
+                    error.message.as_str(),
+                    &input[error.position.start..error.position.end],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            errors,
+            vec![(
+                "Expected `Wrap<Int>`, found `Wrap<String>`",
+                "Wrap.Wrap(\"s\")"
+            )]
+        );
+
+        let dispatches = MethodCalls::dispatch(&file);
+        let dispatched = file
+            .arena
+            .iter()
+            .filter_map(|(id, _)| dispatches.get(&id))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            dispatched,
+            vec![
+                &Dispatch::Unresolved,
+                &Dispatch::Constructor {
+                    type_name: "Wrap",
+                    constructor: "Wrap"
+                }
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_loads_a_module_and_its_imports() -> Result<(), Box<dyn Error>> {
+        let file_system = MemoryFileSystem::new()
+            .with(
+                "/app/main.asura",
+                "import Console from 'std:Console'
+import { area } from './shapes/circle'
+import * as Util from './util.asura'
+
+Console.log(Util.show(area(2.0)))",
+            )
+            .with(
+                "/app/shapes/circle.asura",
+                "import { square } from '../util'
+
+export fun area(radius: Float): Float { square(radius) * 3.14 }",
+            )
+            .with(
+                "/app/util.asura",
+                "export fun square(x: Float): Float { x * x }
+export fun show(x: Float): String { `${x}` }",
+            );
+        let sources = Sources::new();
+        let mut loader = Loader::new(&sources, file_system);
+        let main = loader.load("/app/main.asura")?;
+        let paths = loader
+            .modules()
+            .map(|(_, module)| module.path().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            paths,
+            vec![
+                "std:Console",
+                "/app/util.asura",
+                "/app/shapes/circle.asura",
+                "/app/main.asura",
+            ]
+        );
+
+        let dependencies = loader
+            .module(main)
+            .dependencies()
+            .iter()
+            .map(|dependency| {
+                (
+                    dependency.specifier(),
+                    loader.module(dependency.module()).path().clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            dependencies,
+            vec![
+                ("std:Console", ModulePath::Std("Console")),
+                (
+                    "./shapes/circle",
+                    ModulePath::File("/app/shapes/circle.asura".into())
+                ),
+                ("./util.asura", ModulePath::File("/app/util.asura".into())),
+            ]
+        );
+
+        // Loaded modules are cached, however they are reached.
+        assert_eq!(loader.load("/app/shapes/../main.asura")?, main);
+        assert_eq!(loader.modules().count(), 4);
+
+        let console = loader.module(loader.module(main).dependencies()[0].module());
+
+        assert_eq!(Resolver::resolve(console.file()).diagnostics(), &[]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_loads_the_examples_from_disk() -> Result<(), Box<dyn Error>> {
+        let sources = Sources::new();
+        let mut loader = Loader::new(&sources, OsFileSystem);
+
+        for example in ["fib", "pattern_matching", "queue", "state"] {
+            loader.load(format!("examples/{}.asura", example))?;
+        }
+
+        assert_eq!(loader.modules().count(), 5);
+        assert!(loader
+            .modules()
+            .any(|(_, module)| *module.path() == ModulePath::Std("Console")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_import_cycles_with_their_trace() {
+        let file_system = MemoryFileSystem::new()
+            .with("/app/main.asura", "import { a } from './a'")
+            .with(
+                "/app/a.asura",
+                "import { b } from './b'\nexport fun a() { b() }",
+            )
+            .with("/app/b.asura", "export { a as b } from './a'");
+        let sources = Sources::new();
+        let mut loader = Loader::new(&sources, file_system);
+        let error = loader.load("/app/main.asura").unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Import cycle: /app/a.asura -> /app/b.asura -> /app/a.asura"
+        );
+        assert!(matches!(
+            error,
+            LoadError::Cycle { position, .. } if position == Position::new(0, 28)
+        ));
+    }
+
+    #[test]
+    fn it_reports_modules_that_cannot_be_found() {
+        let file_system = MemoryFileSystem::new()
+            .with("/app/main.asura", "import { a } from './missing'")
+            .with("/app/std.asura", "import Nope from 'std:Nope'")
+            .with("/app/bare.asura", "import React from 'react'")
+            .with("/app/broken.asura", "import { a } from './invalid'")
+            .with("/app/invalid.asura", "fun (");
+        let sources = Sources::new();
+        let mut loader = Loader::new(&sources, file_system);
+
+        for (path, specifier) in [
+            ("/app/main.asura", "./missing"),
+            ("/app/std.asura", "std:Nope"),
+            ("/app/bare.asura", "react"),
+        ] {
+            assert!(matches!(
+                loader.load(path),
+                Err(LoadError::NotFound { specifier: missing, .. }) if missing == specifier
+            ));
+        }
+
+        assert!(matches!(
+            loader.load("/app/broken.asura"),
+            Err(LoadError::Parse { path, .. })
+                if path == ModulePath::File("/app/invalid.asura".into())
+        ));
+        assert!(matches!(
+            loader.load("/app/nowhere.asura"),
+            Err(LoadError::Unreadable { .. })
+        ));
+        assert_eq!(loader.modules().count(), 0);
+    }
+
+    #[test]
+    fn it_type_checks_the_examples() -> Result<(), Box<dyn Error>> {
+        let expected = [
+            ("fib", "fib(n - 1)", "Int"),
+            // Effects aren't tracked, and imported handlers aren't checked.
+            ("fib", "main(10)", "Unit"),
+            ("fib", "main(10) with Console.Platform", "Any"),
+            (
+                "pattern_matching",
+                "Calc ++ Fail.try()",
+                "Handler<Int, Either<",
+            ),
+            (
+                "pattern_matching",
+                "main() with Calc ++ Fail.catch(() -> 0)",
+                "Int",
+            ),
+            ("queue", "IntQueue.dequeue()", "Option<Int>"),
+            ("queue", "worker.fork()", "Fiber<Unit>"),
+            ("state", "main() with State.local(10)", "(Int, Int)"),
+        ];
+
+        for (example, source, type_) in expected {
+            let contents = read_to_string(format!("examples/{}.asura", example))?;
+            let file = parse(&contents)?;
+            let typing = TypeChecker::check(&file);
+            let (id, _) = file
+                .arena
+                .iter()
+                .find(|(_, node)| node.to_source(&file.arena) == source)
+                .ok_or_else(|| format!("`{}` isn't in {}", source, example))?;
+
+            assert_eq!(typing.errors(), &[], "in {}", example);
+            assert!(
+                typing
+                    .type_of(id)
+                    .is_some_and(|inferred| inferred.to_string().starts_with(type_)),
+                "`{}` is {:?}",
+                source,
+                typing.type_of(id).map(Type::to_string)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_generalizes_top_level_functions() -> Result<(), Box<dyn Error>> {
+        let input = "fun id(x) { x }
+fun pair(a, b) { (id(a), id(b)) }
+fun even(n) { if n == 0 { true } else { odd(n - 1) } }
+fun odd(n) { if n == 0 { false } else { even(n - 1) } }
+
+both = pair(1, \"one\")
+local = (x) -> x
+local(odd(1))
+local(\"one\")";
+        let file = parse(input)?;
+        let typing = TypeChecker::check(&file);
+        let type_of = |source: &str| {
+            file.arena
+                .iter()
+                .find(|(_, node)| node.to_source(&file.arena) == source)
+                .and_then(|(id, _)| typing.type_of(id))
+                .map(Type::to_string)
+        };
+
+        assert_eq!(
+            type_of("pair(1, \"one\")").as_deref(),
+            Some("(Int, String)")
+        );
+        assert_eq!(type_of("even").as_deref(), Some("(Int) -> Boolean"));
+
+        // Lambdas bound to local names aren't generalized
+        let errors: Vec<_> = typing
+            .errors()
+            .iter()
+            .map(|error| {
+                (
+                    error.message.as_str(),
+                    &input[error.position.start..error.position.end],
+                )
+            })
+            .collect();
+
+        assert_eq!(errors, [("Expected `Boolean`, found `String`", "\"one\"")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_type_errors_at_their_spans() -> Result<(), Box<dyn Error>> {
+        let input = "alias Name = String
+
+struct Point { x: Int, y: Int }
+
+type Shape = Circle(Float) | Square(Float)
+
+fun greet(name: Name): Int {
+  name
+}
+
+fun area(shape: Shape): Float {
+  match shape {
+    Circle(r) -> r * r,
+    Square(side) -> \"side\",
+  }
+}
+
+fun main(point: Point) {
+  greet(42)
+  area(Circle(1.0), 2)
+  if 1 { print(point.z) }
+  [1, \"two\"]
+}";
+        let file = parse(input)?;
+        let typing = TypeChecker::check(&file);
+        let errors: Vec<_> = typing
+            .errors()
+            .iter()
+            .map(|error| {
+                (
+                    error.message.as_str(),
+                    &input[error.position.start..error.position.end],
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            errors,
+            [
+                ("Expected `Int`, found `Name`", "name"),
+                ("Expected `Float`, found `String`", "\"side\""),
+                ("Expected `Name`, found `Int`", "42"),
+                ("Expected 1 argument, found 2", "area(Circle(1.0), 2)"),
+                ("Expected `Boolean`, found `Int`", "1"),
+                ("`Point` has no field `z`", "z"),
+                ("Expected `Int`, found `String`", "\"two\""),
+            ]
+        );
+        assert_eq!(typing.errors()[0].expected, Type::Int);
+        assert_eq!(
+            typing.errors()[0].found,
+            Type::Alias("Name", Box::new(Type::String))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_members_of_types_without_them() -> Result<(), Box<dyn Error>> {
+        let input = "fun f(n: Int): Int { n }
+
+fun top(value: Unknown): Int { value }
+
+fun main() {
+  x = 1
+  y = x.foo
+  z = f.nope
+  z(1, 2, 3) + \"s\"
+  top(x)
+  worker = () -> 1
+  worker.fork().join()
+}";
+        let file = parse(input)?;
+        let typing = TypeChecker::check(&file);
+        let errors: Vec<_> = typing
+            .errors()
+            .iter()
+            .map(|error| {
+                (
+                    error.message.as_str(),
+                    &input[error.position.start..error.position.end],
+                )
+            })
+            .collect();
+
+        // What follows a missing field isn't checked any further.
+        assert_eq!(
+            errors,
+            [
+                ("Expected `Int`, found `Unknown`", "value"),
+                ("`Int` has no field `foo`", "foo"),
+                ("`(Int) -> Int` has no field `nope`", "nope"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_names_unknown_types_in_messages_like_type_parameters() -> Result<(), Box<dyn Error>> {
+        let input = "fun apply(f: (Int) -> Int): Int { f(1) }
+
+fun main<A>(a: A) {
+  apply((x, y) -> x)
+  apply((x, y) -> a)
+}";
+        let file = parse(input)?;
+        let typing = TypeChecker::check(&file);
+        let errors: Vec<_> = typing
+            .errors()
+            .iter()
+            .map(|error| {
+                (
+                    error.message.as_str(),
+                    &input[error.position.start..error.position.end],
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            errors,
+            [
+                (
+                    "Expected `(Int) -> Int`, found `(A, B) -> A`",
+                    "(x, y) -> x"
+                ),
+                (
+                    "Expected `(Int) -> Int`, found `(B, C) -> A`",
+                    "(x, y) -> a"
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_type_parameters_rigid_in_their_declarations() -> Result<(), Box<dyn Error>> {
+        let input = "type Box<A> = Full(A) | Empty
+
+fun f<A>(a: A): A { 1 }
+fun id<A>(a: A): A { a }
+
+fun main() {
+  fun local<B>(b: B): B { b }
+
+  (id(1), id(\"one\"), local(1), local(\"one\"), Full(1), Full(\"one\"))
+}";
+        let file = parse(input)?;
+        let typing = TypeChecker::check(&file);
+        let errors: Vec<_> = typing
+            .errors()
+            .iter()
+            .map(|error| {
+                (
+                    error.message.as_str(),
+                    &input[error.position.start..error.position.end],
+                )
+            })
+            .collect();
+
+        assert_eq!(errors, [("Expected `A`, found `Int`", "1")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_macro_declarations_and_invocations() -> Result<(), Box<dyn Error>> {
+        let input = "macro unless(condition, body) {
+  if !$condition {
+    $body
+  }
+}
+
+@derive!(Show, Eq)
+
+fun main(): @schema!(users) {
+  @log!(\"starting\", [1, (2, 3)])
+  rows = @sql![SELECT * FROM users].first()
+  rows
+}
+
+impl for Console {
+  @handlers! { log, warn }
+}";
+        let file = parse(input)?;
+
+        let AST::MacroDeclaration(declaration) = &file[file.body[0]] else {
+            panic!(
+                "Expected a macro declaration, found {:?}",
+                file.statements()
+            );
+        };
+
+        assert_eq!(declaration.name(), "unless");
+        assert_eq!(declaration.parameters()[1].name(), "body");
+        assert_eq!(declaration.body().delimiter(), Delimiter::Brace);
+        assert_eq!(
+            declaration.body().text(),
+            "{\n  if !$condition {\n    $body\n  }\n}"
+        );
+        assert_eq!(declaration.body().trees().len(), 5);
+
+        let AST::MacroInvocation(derive) = &file[file.body[1]] else {
+            panic!("Expected a macro invocation");
+        };
+        let arguments = derive.arguments();
+
+        assert_eq!(derive.name(), "derive");
+        assert_eq!(derive.delimiter(), Delimiter::Parenthesis);
+        assert_eq!(derive.position(), Position::new(68, 86));
+        assert_eq!(arguments.len(), 2);
+        assert!(matches!(
+            arguments[1],
+            [TokenTree::Token(token)] if token.value() == "Eq"
+        ));
+
+        let AST::FunctionDeclaration(main) = &file[file.body[2]] else {
+            panic!("Expected a function");
+        };
+
+        assert!(matches!(
+            main.return_type().map(TypeAnnotation::annotated),
+            Some(Type::Macro(schema)) if schema.name() == "schema"
+        ));
+
+        let AST::MacroInvocation(log) = &file[main.body()[0]] else {
+            panic!("Expected a macro invocation");
+        };
+
+        assert_eq!(log.arguments().len(), 2);
+        assert!(matches!(
+            log.arguments()[1],
+            [TokenTree::Delimited(array)] if array.trees().len() == 3
+        ));
+
+        let AST::LetBinding(binding) = &file[main.body()[1]] else {
+            panic!("Expected a binding");
+        };
+        let AST::Call(call) = &file[binding.value()] else {
+            panic!("Expected a call");
+        };
+        let AST::MemberAccess(member) = &file[call.callee()] else {
+            panic!("Expected a member access");
+        };
+
+        assert!(matches!(
+            &file[member.object()],
+            AST::MacroInvocation(sql) if sql.delimiter() == Delimiter::Bracket
+                && sql.tokens().len() == 4
+        ));
+
+        let AST::ImplBlock(block) = &file[file.body[3]] else {
+            panic!("Expected an impl block");
+        };
+
+        assert!(matches!(
+            &file[block.members()[0]],
+            AST::MacroInvocation(handlers) if handlers.name() == "handlers"
+        ));
+
+        assert_round_trips(input)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_unbalanced_macro_arguments() {
+        assert_eq!(
+            parse("@log!(a, [b)").unwrap_err(),
+            ParseError::new("Unmatched `)`", Position::new(11, 12))
+        );
+        assert_eq!(
+            parse("@log!(a, b").unwrap_err(),
+            ParseError::new("Unclosed `(`", Position::new(5, 6))
+        );
+        assert!(parse("@log! a").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_round_trips_modules_through_json() -> Result<(), Box<dyn Error>> {
+        for name in ["fib", "state", "pattern_matching", "queue"] {
+            let contents = read_to_string(format!("examples/{}.asura", name))?;
+            let file = parse(&contents)?;
+            let json = serde_json::to_string(&file)?;
+            let owned = serde_json::from_str::<OwnedSourceFile>(&json)?;
+
+            assert_eq!(owned.lend(), file);
+            assert_eq!(serde_json::to_string(&owned)?, json);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_deserializes_strings_that_need_unescaping() -> Result<(), Box<dyn Error>> {
+        let file = parse(r#"greeting = 'say "hi"\n'"#)?;
+        let json = serde_json::to_string(&file)?;
+
+        assert_eq!(serde_json::from_str::<OwnedSourceFile>(&json)?.lend(), file);
+
+        let tokens = tokenize(r"/\d+/");
+        let json = serde_json::to_string(&tokens)?;
+        let owned: Vec<OwnedToken> = serde_json::from_reader(json.as_bytes())?;
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json)?[0],
+            serde_json::json!({
+                "kind": "RegexpLiteral",
+                "value": r"/\d+/",
+                "position": { "start": 0, "end": 5 },
+            })
+        );
+        assert_eq!(owned.lend(), tokens);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_rejects_serialized_modules_that_refer_to_missing_nodes() -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_value(parse("f(x)")?)?;
+        let mut forward = json.clone();
+        let mut missing = json.clone();
+
+        forward["arena"]["nodes"][0] = json["arena"]["nodes"][2].clone();
+        missing["body"][0] = serde_json::json!(3);
+
+        assert!(serde_json::from_value::<OwnedSourceFile>(json).is_ok());
+        assert!(serde_json::from_value::<OwnedSourceFile>(forward).is_err());
+        assert!(serde_json::from_value::<OwnedSourceFile>(missing).is_err());
+
+        Ok(())
+    }
+
+    // Printing a parsed module and parsing it again gives back the same
+    // tree, up to positions, and printing that tree gives the same source.
+    fn assert_round_trips(source: &str) -> Result<(), Box<dyn Error>> {
+        let file = parse(source)?;
+        let printed = file.to_source();
+        let reparsed = parse(&printed).map_err(|error| format!("{:?} in\n{}", error, printed))?;
+
+        assert_eq!(
+            without_positions(&reparsed),
+            without_positions(&file),
+            "{}",
+            printed
+        );
+        assert_eq!(reparsed.to_source(), printed);
+
+        Ok(())
+    }
+
+    fn without_positions(file: &SourceFile) -> String {
+        let position = regex::Regex::new(r"Position \{ start: \d+, end: \d+ \}").unwrap();
+
+        position
+            .replace_all(&format!("{:?}", file), "Position")
+            .into_owned()
     }
 }
@@ -0,0 +1,53 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// Where the loader reads modules from. Paths are normalized before they get
+// here, see loader::normalize.
+pub trait FileSystem {
+    fn read(&self, path: &Path) -> io::Result<String>;
+}
+
+// The disk.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFileSystem;
+
+impl FileSystem for OsFileSystem {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+// Files kept in memory, for tests and for editors with unsaved buffers.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFileSystem {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> MemoryFileSystem {
+        MemoryFileSystem::default()
+    }
+
+    pub fn insert(&mut self, path: impl Into<PathBuf>, contents: impl Into<String>) {
+        self.files.insert(path.into(), contents.into());
+    }
+
+    pub fn with(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.insert(path, contents);
+        self
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files.get(path).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} does not exist", path.display()),
+            )
+        })
+    }
+}
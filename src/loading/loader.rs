@@ -0,0 +1,378 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt, io,
+    path::{Component, Path, PathBuf},
+};
+
+use typed_arena::Arena;
+
+use crate::{
+    lexing::Position,
+    loading::file_system::*,
+    parse,
+    parsing::{ast::*, error::ParseError},
+};
+
+// The standard library, bundled into the compiler and imported as
+// `std:Name`.
+const STD: [(&str, &str); 1] = [("Console", include_str!("std/Console.asura"))];
+
+// Where a module comes from.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum ModulePath {
+    Std(&'static str), // std:Console
+    File(PathBuf),     // A normalized path to an .asura file
+}
+
+impl fmt::Display for ModulePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModulePath::Std(name) => write!(f, "std:{}", name),
+            ModulePath::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct ModuleId(usize);
+
+impl ModuleId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+// An import or re-export, and the module it resolved to.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Dependency<'s> {
+    pub(crate) specifier: &'s str,
+    pub(crate) position: Position,
+    pub(crate) module: ModuleId,
+}
+
+impl<'s> Dependency<'s> {
+    pub fn specifier(&self) -> &'s str {
+        self.specifier
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn module(&self) -> ModuleId {
+        self.module
+    }
+}
+
+// The text of the modules a loader reads, which their trees borrow their
+// names from. Sources are kept until this is dropped, along with the
+// loaders and modules borrowing from it, e.g. when an editor reloads a
+// project.
+#[derive(Default)]
+pub struct Sources {
+    sources: Arena<String>,
+}
+
+impl Sources {
+    pub fn new() -> Sources {
+        Sources::default()
+    }
+
+    fn add(&self, source: String) -> &str {
+        self.sources.alloc(source)
+    }
+}
+
+impl fmt::Debug for Sources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sources")
+            .field("len", &self.sources.len())
+            .finish()
+    }
+}
+
+// A parsed module.
+#[derive(Debug)]
+pub struct Module<'s> {
+    pub(crate) path: ModulePath,
+    pub(crate) source: &'s str,
+    pub(crate) file: SourceFile<'s>,
+    pub(crate) dependencies: Vec<Dependency<'s>>,
+}
+
+impl<'s> Module<'s> {
+    pub fn path(&self) -> &ModulePath {
+        &self.path
+    }
+
+    pub fn source(&self) -> &'s str {
+        self.source
+    }
+
+    pub fn file(&self) -> &SourceFile<'s> {
+        &self.file
+    }
+
+    pub fn dependencies(&self) -> &[Dependency<'s>] {
+        &self.dependencies
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LoadError {
+    // An import whose module doesn't exist.
+    NotFound {
+        specifier: String,
+        importer: ModulePath,
+        position: Position,
+    },
+    Unreadable {
+        path: ModulePath,
+        message: String,
+    },
+    Parse {
+        path: ModulePath,
+        error: ParseError,
+    },
+    // Modules importing each other, from the module the cycle was found in
+    // back to itself. `position` is that of the import closing the cycle, in
+    // the second to last module of the trace.
+    Cycle {
+        trace: Vec<ModulePath>,
+        position: Position,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotFound {
+                specifier,
+                importer,
+                position,
+            } => write!(
+                f,
+                "Cannot find module `{}` imported by {} at {}..{}",
+                specifier, importer, position.start, position.end
+            ),
+            LoadError::Unreadable { path, message } => {
+                write!(f, "Cannot read {}: {}", path, message)
+            }
+            LoadError::Parse { path, error } => write!(f, "{} in {}", error, path),
+            LoadError::Cycle { trace, .. } => {
+                let trace: Vec<_> = trace.iter().map(ModulePath::to_string).collect();
+
+                write!(f, "Import cycle: {}", trace.join(" -> "))
+            }
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+// Loads a module along with everything it imports, transitively. Modules
+// are parsed once and cached by path, so loading a module that was already
+// loaded, directly or as a dependency, only looks it up.
+//
+// Specifiers are either `std:Name`, for the bundled standard library, or
+// paths relative to the importing module starting with `./` or `../`, with
+// the `.asura` extension being optional.
+#[derive(Debug)]
+pub struct Loader<'s, F: FileSystem> {
+    sources: &'s Sources,
+    file_system: F,
+    modules: Vec<Module<'s>>, // Dependencies before the modules importing them
+    cache: HashMap<ModulePath, ModuleId>,
+}
+
+impl<'s, F: FileSystem> Loader<'s, F> {
+    pub fn new(sources: &'s Sources, file_system: F) -> Loader<'s, F> {
+        Loader {
+            sources,
+            file_system,
+            modules: vec![],
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<ModuleId, LoadError> {
+        let path = ModulePath::File(normalize(path.as_ref()));
+
+        self.load_module(path, None, &mut vec![])
+    }
+
+    pub fn module(&self, id: ModuleId) -> &Module<'s> {
+        &self.modules[id.0]
+    }
+
+    // Every module loaded so far, each one after the modules it imports.
+    pub fn modules(&self) -> impl Iterator<Item = (ModuleId, &Module<'s>)> {
+        self.modules
+            .iter()
+            .enumerate()
+            .map(|(index, module)| (ModuleId(index), module))
+    }
+
+    pub fn find(&self, path: &ModulePath) -> Option<ModuleId> {
+        self.cache.get(path).copied()
+    }
+
+    // `loading` holds the modules whose imports are being loaded, outermost
+    // first, so that an import of one of them closes a cycle. `import` is
+    // the specifier and position of the import of `path`, if any.
+    fn load_module(
+        &mut self,
+        path: ModulePath,
+        import: Option<(&'s str, Position)>,
+        loading: &mut Vec<ModulePath>,
+    ) -> Result<ModuleId, LoadError> {
+        if let Some(&id) = self.cache.get(&path) {
+            return Ok(id);
+        }
+
+        let source = match (self.read(&path), import, loading.last()) {
+            (Ok(source), _, _) => source,
+            (Err(error), Some((specifier, position)), Some(importer))
+                if error.kind() == io::ErrorKind::NotFound =>
+            {
+                return Err(LoadError::NotFound {
+                    specifier: specifier.to_string(),
+                    importer: importer.clone(),
+                    position,
+                });
+            }
+            (Err(error), _, _) => {
+                return Err(LoadError::Unreadable {
+                    path,
+                    message: error.to_string(),
+                })
+            }
+        };
+        let file = parse(source).map_err(|error| LoadError::Parse {
+            path: path.clone(),
+            error,
+        })?;
+        let mut dependencies = vec![];
+
+        loading.push(path.clone());
+
+        for (specifier, position) in specifiers(&file) {
+            let dependency = resolve(&path, specifier).ok_or_else(|| LoadError::NotFound {
+                specifier: specifier.to_string(),
+                importer: path.clone(),
+                position,
+            })?;
+
+            if let Some(start) = loading.iter().position(|loaded| *loaded == dependency) {
+                let mut trace = loading[start..].to_vec();
+
+                trace.push(dependency);
+
+                return Err(LoadError::Cycle { trace, position });
+            }
+
+            dependencies.push(Dependency {
+                specifier,
+                position,
+                module: self.load_module(dependency, Some((specifier, position)), loading)?,
+            });
+        }
+
+        loading.pop();
+
+        let id = ModuleId(self.modules.len());
+
+        self.cache.insert(path.clone(), id);
+        self.modules.push(Module {
+            path,
+            source,
+            file,
+            dependencies,
+        });
+
+        Ok(id)
+    }
+
+    fn read(&self, path: &ModulePath) -> io::Result<&'s str> {
+        match path {
+            ModulePath::Std(name) => Ok(std_source(name).unwrap_or_default()),
+            ModulePath::File(file) => Ok(self.sources.add(self.file_system.read(file)?)),
+        }
+    }
+}
+
+fn std_source(name: &str) -> Option<&'static str> {
+    STD.iter()
+        .find(|(std_name, _)| *std_name == name)
+        .map(|(_, source)| *source)
+}
+
+// The module a specifier in `importer` refers to, if it can refer to one.
+fn resolve(importer: &ModulePath, specifier: &str) -> Option<ModulePath> {
+    if let Some(name) = specifier.strip_prefix("std:") {
+        return STD
+            .iter()
+            .find(|(std_name, _)| *std_name == name)
+            .map(|(name, _)| ModulePath::Std(name));
+    }
+
+    // The standard library only imports from itself.
+    let ModulePath::File(importer) = importer else {
+        return None;
+    };
+
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return None;
+    }
+
+    let mut path = importer.parent().unwrap_or(Path::new("")).join(specifier);
+
+    if path.extension().is_none() {
+        path.set_extension("asura");
+    }
+
+    Some(ModulePath::File(normalize(&path)))
+}
+
+// The modules a module imports or re-exports from, in source order.
+fn specifiers<'s>(file: &SourceFile<'s>) -> Vec<(&'s str, Position)> {
+    let mut specifiers = vec![];
+
+    for &id in &file.body {
+        match &file[id] {
+            AST::ImportDeclaration(import) => specifiers.push((import.specifier, import.position)),
+            AST::ExportDeclaration(ExportDeclaration {
+                kind:
+                    ExportKind::Named {
+                        from: Some(from), ..
+                    }
+                    | ExportKind::All { from, .. },
+                position,
+            }) => specifiers.push((*from, *position)),
+            _ => {}
+        }
+    }
+
+    specifiers
+}
+
+// Removes `.` and resolves `..` without touching the file system, so that
+// the same module reached through different paths is only loaded once.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+
+    normalized
+}
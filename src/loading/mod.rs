@@ -0,0 +1,5 @@
+pub use file_system::*;
+pub use loader::*;
+
+pub mod file_system;
+pub mod loader;
@@ -0,0 +1,128 @@
+use std::ops::Index;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Position, AST};
+
+// Identifies a node within the Arena that owns it. Ids are handed out in
+// allocation order, and since children are always parsed before their
+// parent, a node's id is greater than the ids of everything beneath it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeId(u32);
+
+impl NodeId {
+    pub(crate) fn new(index: usize) -> NodeId {
+        NodeId(index as u32)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+// Owns every node of a parsed module. Nodes refer to their children by
+// NodeId, so cloning a node never copies the subtree beneath it, and passes
+// can attach side tables keyed by NodeId.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Arena<'a> {
+    nodes: Vec<AST<'a>>,
+    // Derived from the nodes by alloc, so not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    parents: Vec<Option<NodeId>>,
+}
+
+impl<'a> Arena<'a> {
+    pub fn new() -> Arena<'a> {
+        Arena::default()
+    }
+
+    // Adds a node whose children have already been allocated, and links
+    // them back to it.
+    pub fn alloc(&mut self, node: AST<'a>) -> NodeId {
+        let id = NodeId(self.nodes.len() as u32);
+
+        for child in node.children() {
+            self.parents[child.index()] = Some(id);
+        }
+
+        self.nodes.push(node);
+        self.parents.push(None);
+
+        id
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&AST<'a>> {
+        self.nodes.get(id.index())
+    }
+
+    // None for top-level statements.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.parents[id.index()]
+    }
+
+    // The parent, grandparent and so on up to the enclosing top-level
+    // statement.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.parent(id), |&parent| self.parent(parent))
+    }
+
+    pub fn nodes(&self, ids: &[NodeId]) -> Vec<&AST<'a>> {
+        ids.iter().map(|&id| &self[id]).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &AST<'a>)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (NodeId(index as u32), node))
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // Swaps in a new node, linking its children to it, and returns the old
+    // one.
+    pub(crate) fn replace(&mut self, id: NodeId, node: AST<'a>) -> AST<'a> {
+        for child in node.children() {
+            self.parents[child.index()] = Some(id);
+        }
+
+        std::mem::replace(&mut self.nodes[id.index()], node)
+    }
+
+    // Moves a node out, leaving an empty block in its place until it is
+    // put back with `replace`.
+    pub(crate) fn take(&mut self, id: NodeId) -> AST<'a> {
+        let position = Position::new(0, 0);
+
+        std::mem::replace(&mut self.nodes[id.index()], AST::block(vec![], position))
+    }
+
+    // The nodes in allocation order, for rebuilding an arena around them.
+    pub(crate) fn into_nodes(self) -> Vec<AST<'a>> {
+        self.nodes
+    }
+
+    // Drops every node allocated after the first `len`, undoing a
+    // speculative parse.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.nodes.truncate(len);
+        self.parents.truncate(len);
+    }
+}
+
+impl<'a> Index<NodeId> for Arena<'a> {
+    type Output = AST<'a>;
+
+    fn index(&self, id: NodeId) -> &AST<'a> {
+        &self.nodes[id.index()]
+    }
+}
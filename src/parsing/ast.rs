@@ -1,6 +1,13 @@
-use crate::{lexing::Position, Type};
+use crate::{
+    lexing::{Position, Spanned, Token, TokenKind},
+    Arena, Docs, NodeId, StructData, Type,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum AST<'a> {
     BooleanLiteral(BooleanLiteral),
     IntegerLiteral(IntegerLiteral),
@@ -9,147 +16,875 @@ pub enum AST<'a> {
     StringLiteral(StringLiteral<'a>),
     TemplateLiteral(TemplateLiteral<'a>),
     Identifier(Identifier<'a>),
+    Record(Record<'a>),
+    Array(Array),
+    Tuple(Tuple),
+    Call(Call),
+    MemberAccess(MemberAccess<'a>),
+    Binary(Binary),
+    Unary(Unary),
+    Block(Block),
+    With(With),
+    Lambda(Lambda<'a>),
+    LetBinding(LetBinding<'a>),
+    Assignment(Assignment<'a>),
+    If(If),
+    While(While<'a>),
+    ForOf(ForOf<'a>),
+    Return(Return),
+    Yield(Yield),
     ImportDeclaration(ImportDeclaration<'a>),
+    ExportDeclaration(ExportDeclaration<'a>),
     Match(Match<'a>),
     FunctionDeclaration(FunctionDeclaration<'a>),
     TypeDeclaration(TypeDeclaration<'a>),
     TypeAlias(TypeAlias<'a>),
+    StructDeclaration(StructDeclaration<'a>),
+    EffectDeclaration(EffectDeclaration<'a>),
+    ImplBlock(ImplBlock<'a>),
+    TypeclassDeclaration(TypeclassDeclaration<'a>),
+    InstanceDeclaration(InstanceDeclaration<'a>),
+    HandlerDeclaration(HandlerDeclaration<'a>),
+    MacroDeclaration(MacroDeclaration<'a>),
+    MacroInvocation(MacroInvocation<'a>),
 }
 
+// A parsed module. Every node lives in `arena`, and `body` lists the ids of
+// its top-level statements in source order.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct SourceFile<'a> {
-    pub body: &'a Vec<AST<'a>>,
+    pub arena: Arena<'a>,
+    pub body: Vec<NodeId>,
+    // Derived from the body by SourceFile::new, so not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub imports: Vec<NodeId>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub exports: Vec<ModuleExport<'a>>,
+}
+
+// One name made visible to other modules.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ModuleExport<'a> {
+    pub exported: &'a str, // "*" when re-exporting all of another module's names
+    pub local: Option<&'a str>, // None for `export * from '...'`
+    pub from: Option<&'a str>, // The specifier of a re-export
+    pub position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BooleanLiteral {
-    value: bool,
-    position: Position,
+    pub(crate) value: bool,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct IntegerLiteral {
-    value: i64,
-    position: Position,
+    pub(crate) value: i64,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NumberLiteral {
-    value: f64,
-    position: Position,
+    pub(crate) value: f64,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct RegexpLiteral<'a> {
-    value: &'a str,
-    position: Position,
+    pub(crate) value: &'a str,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct StringLiteral<'a> {
-    value: &'a str,
-    position: Position,
+    pub(crate) value: &'a str,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct TemplateLiteral<'a> {
-    template: &'a Vec<&'a str>,
-    values: &'a Vec<AST<'a>>,
+    pub(crate) template: Vec<&'a str>, // The text around each `${value}`, one more than `values`
+    pub(crate) values: Vec<NodeId>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Identifier<'a> {
-    value: &'a str,
-    position: Position,
+    pub(crate) value: &'a str,
+    pub(crate) position: Position,
+}
+
+// { name: value, shorthand, ...spread }
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Record<'a> {
+    pub(crate) members: Vec<RecordMember<'a>>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum RecordMember<'a> {
+    Field(Identifier<'a>, NodeId),
+    Shorthand(Identifier<'a>),
+    Spread(NodeId),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Array {
+    pub(crate) elements: Vec<Element>,
+    pub(crate) position: Position,
+}
+
+// The empty tuple is the unit value.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Tuple {
+    pub(crate) elements: Vec<Element>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Element {
+    Member(NodeId),
+    Spread(NodeId),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct ImportDeclaration<'a> {
-    name: &'a str, // TODO: Support named imports?
-    specifier: &'a str,
-    position: Position,
+    pub(crate) default: Option<&'a str>, // import Console from 'std:Console'
+    pub(crate) namespace: Option<&'a str>, // import * as Console from 'std:Console'
+    pub(crate) named: Vec<NamedBinding<'a>>, // import { log, error as fail } from 'std:Console'
+    pub(crate) type_only: bool,          // import type { Console } from 'std:Console'
+    pub(crate) specifier: &'a str,
+    pub(crate) position: Position,
+}
+
+// `name` or `name as alias` in an import or export list.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct NamedBinding<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) alias: Option<&'a str>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ExportDeclaration<'a> {
+    pub(crate) kind: ExportKind<'a>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum ExportKind<'a> {
+    Declaration(NodeId), // export fun f() { ... }
+    Named {
+        bindings: Vec<NamedBinding<'a>>,
+        from: Option<&'a str>,
+    }, // export { a, b as c } or export { a } from './a'
+    All {
+        alias: Option<&'a str>,
+        from: &'a str,
+    }, // export * from './a' or export * as A from './a'
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Call {
+    pub(crate) callee: NodeId,
+    pub(crate) arguments: Vec<NodeId>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MemberAccess<'a> {
+    pub(crate) object: NodeId,
+    pub(crate) property: Identifier<'a>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Exponent,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanEqual,
+    GreaterThan,
+    GreaterThanEqual,
+    And,
+    Or,
+    Concat, // `++` joins collections, and handlers into a single handler
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Binary {
+    pub(crate) operator: BinaryOperator,
+    pub(crate) left: NodeId,
+    pub(crate) right: NodeId,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UnaryOperator {
+    Negate,
+    Not,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Unary {
+    pub(crate) operator: UnaryOperator,
+    pub(crate) operand: NodeId,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Block {
+    pub(crate) body: Vec<NodeId>,
+    pub(crate) position: Position,
+}
+
+// `body with handlers` runs `body` with the effects in `handlers` handled.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct With {
+    pub(crate) body: NodeId,
+    pub(crate) handlers: NodeId,
+    pub(crate) position: Position,
+}
+
+// `pattern = value` binds constants, `pattern := value` binds mutable variables.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LetBinding<'a> {
+    pub(crate) pattern: Pattern<'a>,
+    pub(crate) mutable: bool,
+    pub(crate) value: NodeId,
+    pub(crate) position: Position,
+}
+
+// `target = value` where `target` was bound with `:=`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Assignment<'a> {
+    pub(crate) target: Identifier<'a>,
+    pub(crate) value: NodeId,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct If {
+    pub(crate) condition: NodeId,
+    pub(crate) consequent: Vec<NodeId>,
+    pub(crate) alternative: Option<NodeId>, // Either a Block or, for `else if`, an If
+    pub(crate) position: Position,
+}
+
+// `while condition { ... }`, or `while pattern = value { ... }` which loops
+// for as long as `value` matches `pattern`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct While<'a> {
+    pub(crate) pattern: Option<Pattern<'a>>,
+    pub(crate) condition: NodeId,
+    pub(crate) body: Vec<NodeId>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ForOf<'a> {
+    pub(crate) pattern: Pattern<'a>,
+    pub(crate) iterable: NodeId,
+    pub(crate) body: Vec<NodeId>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Return {
+    pub(crate) value: Option<NodeId>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Yield {
+    pub(crate) value: Option<NodeId>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Match<'a> {
-    value: Identifier<'a>,
-    position: Position,
-    // TODO: Add cases
-    // Predicates
-    // Patterns
-    // Catch-all
-    // With and without blocks
+    pub(crate) value: NodeId,
+    pub(crate) cases: Vec<MatchCase<'a>>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MatchCase<'a> {
+    pub(crate) test: MatchTest<'a>,
+    pub(crate) body: NodeId,
+    pub(crate) position: Position,
+}
+
+// A case either destructures the value with a pattern, or is guarded by a
+// boolean predicate such as `n < 2`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum MatchTest<'a> {
+    Pattern(Pattern<'a>),
+    Predicate(NodeId),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Pattern<'a> {
+    Wildcard(Position),
+    Identifier(Identifier<'a>),
+    Literal(Box<AST<'a>>), // Literals have no children, so patterns own them
+    Constructor(ConstructorPattern<'a>),
+    Record(RecordPattern<'a>),
+    Array(ArrayPattern<'a>),
+    Tuple(TuplePattern<'a>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ConstructorPattern<'a> {
+    pub(crate) qualifier: Vec<&'a str>, // ["Either"] in `Either.Left(a)`
+    pub(crate) name: &'a str,
+    pub(crate) arguments: Vec<Pattern<'a>>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RecordPattern<'a> {
+    pub(crate) fields: Vec<FieldPattern<'a>>,
+    pub(crate) rest: Option<Box<Pattern<'a>>>, // { a, ...rest }
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FieldPattern<'a> {
+    pub(crate) name: Identifier<'a>,
+    pub(crate) pattern: Pattern<'a>, // The name itself for shorthand fields
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ArrayPattern<'a> {
+    pub(crate) elements: Vec<Pattern<'a>>,
+    pub(crate) rest: Option<Box<Pattern<'a>>>, // [head, ...tail]
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TuplePattern<'a> {
+    pub(crate) elements: Vec<Pattern<'a>>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct FunctionDeclaration<'a> {
-    name: &'a str,
-    type_parameters: Vec<TypeParameter<'a>>,
-    parameters: Vec<FunctionParameter<'a>>,
-    return_type: Option<TypeAnnotation<'a>>,
-    body: Vec<AST<'a>>,
-    position: Position,
+    pub(crate) name: &'a str,
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) parameters: Vec<FunctionParameter<'a>>,
+    pub(crate) return_type: Option<TypeAnnotation<'a>>,
+    pub(crate) body: Vec<NodeId>,
+    pub(crate) docs: Option<Docs>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Lambda<'a> {
-    type_parameters: Vec<TypeParameter<'a>>,
-    parameters: Vec<FunctionParameter<'a>>,
-    return_type: Option<TypeAnnotation<'a>>,
-    body: Vec<AST<'a>>,
-    position: Position,
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) parameters: Vec<FunctionParameter<'a>>,
+    pub(crate) return_type: Option<TypeAnnotation<'a>>,
+    pub(crate) body: Vec<NodeId>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct FunctionParameter<'a> {
-    name: &'a str,
-    annotation: Option<TypeAnnotation<'a>>,
-    position: Position,
+    pub(crate) name: &'a str,
+    pub(crate) annotation: Option<TypeAnnotation<'a>>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct TypeParameter<'a> {
-    name: &'a str,
-    extends: Option<Type<'a>>,
-    position: Position,
+    pub(crate) name: &'a str,
+    pub(crate) extends: Option<Type<'a>>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct TypeDeclaration<'a> {
-    name: &'a str,
-    type_parameters: Vec<TypeParameter<'a>>,
-    type_constructors: Vec<TypeConstructor<'a>>,
-    position: Position,
+    pub(crate) name: &'a str,
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) type_constructors: Vec<TypeConstructor<'a>>,
+    pub(crate) docs: Option<Docs>,
+    pub(crate) position: Position,
 }
 
+// struct Point<A> { x: A, y: A }
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct StructDeclaration<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) fields: Vec<StructData<'a>>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct TypeConstructor<'a> {
-    name: &'a str,
-    type_parameters: Vec<TypeParameter<'a>>,
-    parameters: Vec<LabeledTypeAnnotation<'a>>,
-    return_type: Option<TypeAnnotation<'a>>,
-    position: Position,
+    pub(crate) name: &'a str,
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) parameters: Vec<LabeledTypeAnnotation<'a>>,
+    pub(crate) return_type: Option<TypeAnnotation<'a>>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct TypeAnnotation<'a> {
-    annotated: Type<'a>,
-    position: Position,
+    pub(crate) annotated: Type<'a>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct LabeledTypeAnnotation<'a> {
-    name: &'a str,
-    annotated: TypeAnnotation<'a>,
-    position: Position,
+    pub(crate) name: Option<&'a str>, // None for positional constructor parameters
+    pub(crate) annotated: TypeAnnotation<'a>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct EffectDeclaration<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) operations: Vec<EffectOperation<'a>>,
+    pub(crate) docs: Option<Docs>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct EffectOperation<'a> {
+    pub(crate) name: Option<&'a str>, // None for the single operation of `effect Fail<E>(E) -> Never`
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) parameters: Vec<TypeAnnotation<'a>>,
+    pub(crate) return_type: TypeAnnotation<'a>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ImplBlock<'a> {
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) effect: &'a str,
+    pub(crate) type_arguments: Vec<TypeAnnotation<'a>>,
+    pub(crate) members: Vec<NodeId>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TypeclassDeclaration<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) superclasses: Vec<TypeAnnotation<'a>>,
+    pub(crate) associated_types: Vec<AssociatedType<'a>>,
+    pub(crate) methods: Vec<TypeclassMethod<'a>>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TypeclassMethod<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) parameters: Vec<FunctionParameter<'a>>,
+    pub(crate) return_type: Option<TypeAnnotation<'a>>,
+    pub(crate) default: Option<Vec<NodeId>>, // The default implementation, if any
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct AssociatedType<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) bound: Option<TypeAnnotation<'a>>,
+    pub(crate) default: Option<TypeAnnotation<'a>>, // The definition, inside an instance
+    pub(crate) position: Position,
+}
+
+// impl<A> Show for Option<A> { ... }, as opposed to an ImplBlock which
+// implements the handlers of an effect.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct InstanceDeclaration<'a> {
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) typeclass: &'a str,
+    pub(crate) type_arguments: Vec<TypeAnnotation<'a>>,
+    pub(crate) target: TypeAnnotation<'a>,
+    pub(crate) associated_types: Vec<AssociatedType<'a>>,
+    pub(crate) members: Vec<NodeId>,
+    pub(crate) position: Position,
+}
+
+// Handlers come in two shapes. When `operations` is empty the handler is the
+// shorthand for an effect with a single operation, e.g. `handler(op) { ... }`,
+// and `parameters`/`body` are that operation's clause. Otherwise `parameters`
+// construct the handler, `body` holds its local state and `operations` are the
+// clauses from the trailing `return { ... }`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HandlerDeclaration<'a> {
+    pub(crate) name: Option<&'a str>, // None for an effect's default handler
+    pub(crate) parameters: Vec<FunctionParameter<'a>>,
+    pub(crate) body: Vec<NodeId>,
+    pub(crate) operations: Vec<OperationClause<'a>>,
+    pub(crate) return_clause: Option<ReturnClause<'a>>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct OperationClause<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) parameters: Vec<FunctionParameter<'a>>,
+    pub(crate) body: Vec<NodeId>,
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ReturnClause<'a> {
+    pub(crate) parameter: FunctionParameter<'a>,
+    pub(crate) body: Vec<NodeId>,
+    pub(crate) position: Position,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct TypeAlias<'a> {
-    name: &'a str,
-    type_parameters: Vec<TypeParameter<'a>>,
-    aliased: TypeAnnotation<'a>,
-    position: Position,
+    pub(crate) name: &'a str,
+    pub(crate) type_parameters: Vec<TypeParameter<'a>>,
+    pub(crate) aliased: TypeAnnotation<'a>,
+    pub(crate) docs: Option<Docs>,
+    pub(crate) position: Position,
+}
+
+// macro name(a, b) { ... }. The body is kept as token trees until macros are
+// expanded, with `$a` standing for the tokens passed as `a`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MacroDeclaration<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) parameters: Vec<FunctionParameter<'a>>,
+    pub(crate) body: Delimited<'a>,
+    pub(crate) position: Position,
+}
+
+// @name!(...), @name![...] or @name!{ ... }, as an expression, a statement,
+// a declaration or a type. The tokens between the delimiters are left
+// unparsed, since what they mean depends on the macro.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MacroInvocation<'a> {
+    pub(crate) name: &'a str, // Without the `@` and `!`
+    pub(crate) group: Delimited<'a>,
+    pub(crate) position: Position,
+}
+
+// A single token, or a group of them between matching brackets.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum TokenTree<'a> {
+    Token(Token<'a>),
+    Delimited(Delimited<'a>),
+}
+
+// The source text of the group is kept along with its tokens, so that it
+// can be printed back with the line breaks that separate statements in it.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Delimited<'a> {
+    pub(crate) delimiter: Delimiter,
+    pub(crate) trees: Vec<TokenTree<'a>>,
+    pub(crate) text: &'a str, // Including the delimiters
+    pub(crate) position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Delimiter {
+    Parenthesis,
+    Bracket,
+    Brace,
+}
+
+impl<'a> SourceFile<'a> {
+    pub fn new(arena: Arena<'a>, body: Vec<NodeId>) -> SourceFile<'a> {
+        let mut imports = vec![];
+        let mut exports = vec![];
+
+        for &id in &body {
+            match &arena[id] {
+                AST::ImportDeclaration(_) => imports.push(id),
+                AST::ExportDeclaration(export) => export.collect_exports(&arena, &mut exports),
+                _ => {}
+            }
+        }
+
+        SourceFile {
+            arena,
+            body,
+            imports,
+            exports,
+        }
+    }
+
+    // The top-level statements, in source order.
+    pub fn statements(&self) -> Vec<&AST<'a>> {
+        self.arena.nodes(&self.body)
+    }
+}
+
+impl<'a> std::ops::Index<NodeId> for SourceFile<'a> {
+    type Output = AST<'a>;
+
+    fn index(&self, id: NodeId) -> &AST<'a> {
+        &self.arena[id]
+    }
+}
+
+impl<'a> ExportDeclaration<'a> {
+    fn collect_exports(&self, arena: &Arena<'a>, exports: &mut Vec<ModuleExport<'a>>) {
+        match &self.kind {
+            ExportKind::Declaration(declaration) => {
+                let mut names = vec![];
+
+                declared_names(&arena[*declaration], &mut names);
+
+                exports.extend(names.into_iter().map(|name| ModuleExport {
+                    exported: name,
+                    local: Some(name),
+                    from: None,
+                    position: self.position,
+                }));
+            }
+            ExportKind::Named { bindings, from } => {
+                exports.extend(bindings.iter().map(|binding| ModuleExport {
+                    exported: binding.alias.unwrap_or(binding.name),
+                    local: Some(binding.name),
+                    from: *from,
+                    position: binding.position,
+                }))
+            }
+            ExportKind::All { alias, from } => exports.push(ModuleExport {
+                exported: alias.unwrap_or("*"),
+                local: None,
+                from: Some(from),
+                position: self.position,
+            }),
+        }
+    }
+}
+
+impl<'a> AST<'a> {
+    // The ids of this node's direct children, in source order.
+    pub fn children(&self) -> Vec<NodeId> {
+        let mut children = vec![];
+
+        match self {
+            AST::BooleanLiteral(_)
+            | AST::IntegerLiteral(_)
+            | AST::NumberLiteral(_)
+            | AST::RegexpLiteral(_)
+            | AST::StringLiteral(_)
+            | AST::Identifier(_)
+            | AST::ImportDeclaration(_)
+            | AST::TypeDeclaration(_)
+            | AST::TypeAlias(_)
+            | AST::StructDeclaration(_)
+            | AST::EffectDeclaration(_)
+            | AST::MacroDeclaration(_)
+            | AST::MacroInvocation(_) => {}
+            AST::TemplateLiteral(template) => children.extend(&template.values),
+            AST::Record(record) => {
+                for member in &record.members {
+                    match member {
+                        RecordMember::Field(_, value) | RecordMember::Spread(value) => {
+                            children.push(*value)
+                        }
+                        RecordMember::Shorthand(_) => {}
+                    }
+                }
+            }
+            AST::Array(Array { elements, .. }) | AST::Tuple(Tuple { elements, .. }) => children
+                .extend(elements.iter().map(|element| match element {
+                    Element::Member(value) | Element::Spread(value) => *value,
+                })),
+            AST::Call(call) => {
+                children.push(call.callee);
+                children.extend(&call.arguments);
+            }
+            AST::MemberAccess(member) => children.push(member.object),
+            AST::Binary(binary) => children.extend([binary.left, binary.right]),
+            AST::Unary(unary) => children.push(unary.operand),
+            AST::Block(block) => children.extend(&block.body),
+            AST::With(with) => children.extend([with.body, with.handlers]),
+            AST::Lambda(lambda) => children.extend(&lambda.body),
+            AST::LetBinding(binding) => children.push(binding.value),
+            AST::Assignment(assignment) => children.push(assignment.value),
+            AST::If(if_) => {
+                children.push(if_.condition);
+                children.extend(&if_.consequent);
+                children.extend(if_.alternative);
+            }
+            AST::While(while_) => {
+                children.push(while_.condition);
+                children.extend(&while_.body);
+            }
+            AST::ForOf(for_of) => {
+                children.push(for_of.iterable);
+                children.extend(&for_of.body);
+            }
+            AST::Return(Return { value, .. }) | AST::Yield(Yield { value, .. }) => {
+                children.extend(*value)
+            }
+            AST::ExportDeclaration(export) => {
+                if let ExportKind::Declaration(declaration) = &export.kind {
+                    children.push(*declaration);
+                }
+            }
+            AST::Match(match_) => {
+                children.push(match_.value);
+
+                for case in &match_.cases {
+                    if let MatchTest::Predicate(predicate) = &case.test {
+                        children.push(*predicate);
+                    }
+
+                    children.push(case.body);
+                }
+            }
+            AST::FunctionDeclaration(function) => children.extend(&function.body),
+            AST::ImplBlock(block) => children.extend(&block.members),
+            AST::TypeclassDeclaration(typeclass) => {
+                for method in &typeclass.methods {
+                    children.extend(method.default.iter().flatten());
+                }
+            }
+            AST::InstanceDeclaration(instance) => children.extend(&instance.members),
+            AST::HandlerDeclaration(handler) => {
+                children.extend(&handler.body);
+
+                for operation in &handler.operations {
+                    children.extend(&operation.body);
+                }
+
+                if let Some(clause) = &handler.return_clause {
+                    children.extend(&clause.body);
+                }
+            }
+        }
+
+        children
+    }
+}
+
+// The module-level names introduced by a declaration.
+pub(crate) fn declared_names<'a>(declaration: &AST<'a>, names: &mut Vec<&'a str>) {
+    match declaration {
+        AST::FunctionDeclaration(function) => names.push(function.name),
+        AST::TypeDeclaration(declaration) => names.push(declaration.name),
+        AST::StructDeclaration(declaration) => names.push(declaration.name),
+        AST::TypeAlias(alias) => names.push(alias.name),
+        AST::EffectDeclaration(effect) => names.push(effect.name),
+        AST::TypeclassDeclaration(typeclass) => names.push(typeclass.name),
+        AST::MacroDeclaration(declaration) => names.push(declaration.name),
+        AST::LetBinding(binding) => pattern_names(&binding.pattern, names),
+        _ => {}
+    }
+}
+
+pub(crate) fn pattern_names<'a>(pattern: &Pattern<'a>, names: &mut Vec<&'a str>) {
+    match pattern {
+        Pattern::Identifier(identifier) => names.push(identifier.value),
+        Pattern::Constructor(constructor) => {
+            for argument in &constructor.arguments {
+                pattern_names(argument, names);
+            }
+        }
+        Pattern::Record(record) => {
+            for field in &record.fields {
+                pattern_names(&field.pattern, names);
+            }
+
+            if let Some(rest) = &record.rest {
+                pattern_names(rest, names);
+            }
+        }
+        Pattern::Array(array) => {
+            for element in &array.elements {
+                pattern_names(element, names);
+            }
+
+            if let Some(rest) = &array.rest {
+                pattern_names(rest, names);
+            }
+        }
+        Pattern::Tuple(tuple) => {
+            for element in &tuple.elements {
+                pattern_names(element, names);
+            }
+        }
+        Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+    }
 }
 
 impl<'a> AST<'a> {
@@ -173,7 +908,1499 @@ impl<'a> AST<'a> {
         AST::StringLiteral(StringLiteral { value, position })
     }
 
-    pub fn template_literal(template: &'a Vec<&'a str>, values: &'a Vec<AST<'a>>) -> Self {
-        AST::TemplateLiteral(TemplateLiteral { template, values })
+    pub fn template_literal(
+        template: Vec<&'a str>,
+        values: Vec<NodeId>,
+        position: Position,
+    ) -> Self {
+        AST::TemplateLiteral(TemplateLiteral {
+            template,
+            values,
+            position,
+        })
+    }
+
+    pub fn identifier(value: &'a str, position: Position) -> Self {
+        AST::Identifier(Identifier { value, position })
+    }
+
+    pub fn record(members: Vec<RecordMember<'a>>, position: Position) -> Self {
+        AST::Record(Record { members, position })
+    }
+
+    pub fn array(elements: Vec<Element>, position: Position) -> Self {
+        AST::Array(Array { elements, position })
+    }
+
+    pub fn tuple(elements: Vec<Element>, position: Position) -> Self {
+        AST::Tuple(Tuple { elements, position })
+    }
+
+    pub fn call(callee: NodeId, arguments: Vec<NodeId>, position: Position) -> Self {
+        AST::Call(Call {
+            callee,
+            arguments,
+            position,
+        })
+    }
+
+    pub fn member_access(object: NodeId, property: Identifier<'a>, position: Position) -> Self {
+        AST::MemberAccess(MemberAccess {
+            object,
+            property,
+            position,
+        })
+    }
+
+    pub fn binary(
+        operator: BinaryOperator,
+        left: NodeId,
+        right: NodeId,
+        position: Position,
+    ) -> Self {
+        AST::Binary(Binary {
+            operator,
+            left,
+            right,
+            position,
+        })
+    }
+
+    pub fn unary(operator: UnaryOperator, operand: NodeId, position: Position) -> Self {
+        AST::Unary(Unary {
+            operator,
+            operand,
+            position,
+        })
+    }
+
+    pub fn block(body: Vec<NodeId>, position: Position) -> Self {
+        AST::Block(Block { body, position })
+    }
+
+    pub fn with(body: NodeId, handlers: NodeId, position: Position) -> Self {
+        AST::With(With {
+            body,
+            handlers,
+            position,
+        })
+    }
+
+    pub fn lambda(
+        type_parameters: Vec<TypeParameter<'a>>,
+        parameters: Vec<FunctionParameter<'a>>,
+        return_type: Option<TypeAnnotation<'a>>,
+        body: Vec<NodeId>,
+        position: Position,
+    ) -> Self {
+        AST::Lambda(Lambda {
+            type_parameters,
+            parameters,
+            return_type,
+            body,
+            position,
+        })
+    }
+
+    pub fn let_binding(
+        pattern: Pattern<'a>,
+        mutable: bool,
+        value: NodeId,
+        position: Position,
+    ) -> Self {
+        AST::LetBinding(LetBinding {
+            pattern,
+            mutable,
+            value,
+            position,
+        })
+    }
+
+    pub fn assignment(target: Identifier<'a>, value: NodeId, position: Position) -> Self {
+        AST::Assignment(Assignment {
+            target,
+            value,
+            position,
+        })
+    }
+
+    pub fn if_(
+        condition: NodeId,
+        consequent: Vec<NodeId>,
+        alternative: Option<NodeId>,
+        position: Position,
+    ) -> Self {
+        AST::If(If {
+            condition,
+            consequent,
+            alternative,
+            position,
+        })
+    }
+
+    pub fn while_(
+        pattern: Option<Pattern<'a>>,
+        condition: NodeId,
+        body: Vec<NodeId>,
+        position: Position,
+    ) -> Self {
+        AST::While(While {
+            pattern,
+            condition,
+            body,
+            position,
+        })
+    }
+
+    pub fn for_of(
+        pattern: Pattern<'a>,
+        iterable: NodeId,
+        body: Vec<NodeId>,
+        position: Position,
+    ) -> Self {
+        AST::ForOf(ForOf {
+            pattern,
+            iterable,
+            body,
+            position,
+        })
+    }
+
+    pub fn return_(value: Option<NodeId>, position: Position) -> Self {
+        AST::Return(Return { value, position })
+    }
+
+    pub fn yield_(value: Option<NodeId>, position: Position) -> Self {
+        AST::Yield(Yield { value, position })
+    }
+
+    pub fn match_(value: NodeId, cases: Vec<MatchCase<'a>>, position: Position) -> Self {
+        AST::Match(Match {
+            value,
+            cases,
+            position,
+        })
+    }
+
+    pub fn function_declaration(
+        name: &'a str,
+        type_parameters: Vec<TypeParameter<'a>>,
+        parameters: Vec<FunctionParameter<'a>>,
+        return_type: Option<TypeAnnotation<'a>>,
+        body: Vec<NodeId>,
+        position: Position,
+    ) -> Self {
+        AST::FunctionDeclaration(FunctionDeclaration {
+            name,
+            type_parameters,
+            parameters,
+            return_type,
+            body,
+            docs: None,
+            position,
+        })
+    }
+
+    pub fn import_declaration(
+        default: Option<&'a str>,
+        namespace: Option<&'a str>,
+        named: Vec<NamedBinding<'a>>,
+        type_only: bool,
+        specifier: &'a str,
+        position: Position,
+    ) -> Self {
+        AST::ImportDeclaration(ImportDeclaration {
+            default,
+            namespace,
+            named,
+            type_only,
+            specifier,
+            position,
+        })
+    }
+
+    pub fn export_declaration(kind: ExportKind<'a>, position: Position) -> Self {
+        AST::ExportDeclaration(ExportDeclaration { kind, position })
+    }
+
+    pub fn struct_declaration(
+        name: &'a str,
+        type_parameters: Vec<TypeParameter<'a>>,
+        fields: Vec<StructData<'a>>,
+        position: Position,
+    ) -> Self {
+        AST::StructDeclaration(StructDeclaration {
+            name,
+            type_parameters,
+            fields,
+            position,
+        })
+    }
+
+    pub fn type_declaration(
+        name: &'a str,
+        type_parameters: Vec<TypeParameter<'a>>,
+        type_constructors: Vec<TypeConstructor<'a>>,
+        position: Position,
+    ) -> Self {
+        AST::TypeDeclaration(TypeDeclaration {
+            name,
+            type_parameters,
+            type_constructors,
+            docs: None,
+            position,
+        })
+    }
+
+    pub fn effect_declaration(
+        name: &'a str,
+        type_parameters: Vec<TypeParameter<'a>>,
+        operations: Vec<EffectOperation<'a>>,
+        position: Position,
+    ) -> Self {
+        AST::EffectDeclaration(EffectDeclaration {
+            name,
+            type_parameters,
+            operations,
+            docs: None,
+            position,
+        })
+    }
+
+    pub fn impl_block(
+        type_parameters: Vec<TypeParameter<'a>>,
+        effect: &'a str,
+        type_arguments: Vec<TypeAnnotation<'a>>,
+        members: Vec<NodeId>,
+        position: Position,
+    ) -> Self {
+        AST::ImplBlock(ImplBlock {
+            type_parameters,
+            effect,
+            type_arguments,
+            members,
+            position,
+        })
+    }
+
+    pub fn typeclass_declaration(
+        name: &'a str,
+        type_parameters: Vec<TypeParameter<'a>>,
+        superclasses: Vec<TypeAnnotation<'a>>,
+        associated_types: Vec<AssociatedType<'a>>,
+        methods: Vec<TypeclassMethod<'a>>,
+        position: Position,
+    ) -> Self {
+        AST::TypeclassDeclaration(TypeclassDeclaration {
+            name,
+            type_parameters,
+            superclasses,
+            associated_types,
+            methods,
+            position,
+        })
+    }
+
+    pub fn instance_declaration(
+        type_parameters: Vec<TypeParameter<'a>>,
+        typeclass: &'a str,
+        type_arguments: Vec<TypeAnnotation<'a>>,
+        target: TypeAnnotation<'a>,
+        associated_types: Vec<AssociatedType<'a>>,
+        members: Vec<NodeId>,
+        position: Position,
+    ) -> Self {
+        AST::InstanceDeclaration(InstanceDeclaration {
+            type_parameters,
+            typeclass,
+            type_arguments,
+            target,
+            associated_types,
+            members,
+            position,
+        })
+    }
+
+    pub fn handler_declaration(
+        name: Option<&'a str>,
+        parameters: Vec<FunctionParameter<'a>>,
+        body: Vec<NodeId>,
+        operations: Vec<OperationClause<'a>>,
+        return_clause: Option<ReturnClause<'a>>,
+        position: Position,
+    ) -> Self {
+        AST::HandlerDeclaration(HandlerDeclaration {
+            name,
+            parameters,
+            body,
+            operations,
+            return_clause,
+            position,
+        })
+    }
+
+    pub fn type_alias(
+        name: &'a str,
+        type_parameters: Vec<TypeParameter<'a>>,
+        aliased: TypeAnnotation<'a>,
+        position: Position,
+    ) -> Self {
+        AST::TypeAlias(TypeAlias {
+            name,
+            type_parameters,
+            aliased,
+            docs: None,
+            position,
+        })
+    }
+
+    pub fn macro_declaration(
+        name: &'a str,
+        parameters: Vec<FunctionParameter<'a>>,
+        body: Delimited<'a>,
+        position: Position,
+    ) -> Self {
+        AST::MacroDeclaration(MacroDeclaration {
+            name,
+            parameters,
+            body,
+            position,
+        })
+    }
+
+    pub fn macro_invocation(invocation: MacroInvocation<'a>) -> Self {
+        AST::MacroInvocation(invocation)
+    }
+
+    // Attaches the doc comment in front of a declaration. Only functions,
+    // types, aliases and effects keep their docs; other nodes are returned
+    // unchanged.
+    pub fn with_docs(mut self, docs: Option<Docs>) -> Self {
+        if let AST::FunctionDeclaration(FunctionDeclaration { docs: slot, .. })
+        | AST::TypeDeclaration(TypeDeclaration { docs: slot, .. })
+        | AST::TypeAlias(TypeAlias { docs: slot, .. })
+        | AST::EffectDeclaration(EffectDeclaration { docs: slot, .. }) = &mut self
+        {
+            *slot = docs;
+        }
+
+        self
+    }
+}
+
+// Read-only accessors, so that code outside the parser can inspect nodes.
+// Nodes are built with the constructors on AST above, and the parts they are
+// made of with the `new` functions below.
+
+impl BooleanLiteral {
+    pub fn value(&self) -> bool {
+        self.value
+    }
+}
+
+impl IntegerLiteral {
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+}
+
+impl NumberLiteral {
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+impl<'a> RegexpLiteral<'a> {
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+}
+
+impl<'a> StringLiteral<'a> {
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+}
+
+impl<'a> TemplateLiteral<'a> {
+    // The text around each `${value}`, one more than `values`
+    pub fn template(&self) -> &[&'a str] {
+        &self.template
+    }
+
+    pub fn values(&self) -> &[NodeId] {
+        &self.values
+    }
+}
+
+impl<'a> Identifier<'a> {
+    pub fn new(value: &'a str, position: Position) -> Self {
+        Identifier { value, position }
+    }
+
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+}
+
+impl<'a> Record<'a> {
+    pub fn members(&self) -> &[RecordMember<'a>] {
+        &self.members
+    }
+}
+
+impl Array {
+    pub fn elements(&self) -> &[Element] {
+        &self.elements
+    }
+}
+
+impl Tuple {
+    pub fn elements(&self) -> &[Element] {
+        &self.elements
+    }
+}
+
+impl<'a> ImportDeclaration<'a> {
+    pub fn default(&self) -> Option<&'a str> {
+        self.default
+    }
+
+    pub fn namespace(&self) -> Option<&'a str> {
+        self.namespace
+    }
+
+    pub fn named(&self) -> &[NamedBinding<'a>] {
+        &self.named
+    }
+
+    pub fn type_only(&self) -> bool {
+        self.type_only
+    }
+
+    pub fn specifier(&self) -> &'a str {
+        self.specifier
+    }
+}
+
+impl<'a> NamedBinding<'a> {
+    pub fn new(name: &'a str, alias: Option<&'a str>, position: Position) -> Self {
+        NamedBinding {
+            name,
+            alias,
+            position,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn alias(&self) -> Option<&'a str> {
+        self.alias
+    }
+}
+
+impl<'a> ExportDeclaration<'a> {
+    pub fn kind(&self) -> &ExportKind<'a> {
+        &self.kind
+    }
+}
+
+impl Call {
+    pub fn callee(&self) -> NodeId {
+        self.callee
+    }
+
+    pub fn arguments(&self) -> &[NodeId] {
+        &self.arguments
+    }
+}
+
+impl<'a> MemberAccess<'a> {
+    pub fn object(&self) -> NodeId {
+        self.object
+    }
+
+    pub fn property(&self) -> &Identifier<'a> {
+        &self.property
+    }
+}
+
+impl Binary {
+    pub fn operator(&self) -> BinaryOperator {
+        self.operator
+    }
+
+    pub fn left(&self) -> NodeId {
+        self.left
+    }
+
+    pub fn right(&self) -> NodeId {
+        self.right
+    }
+}
+
+impl Unary {
+    pub fn operator(&self) -> UnaryOperator {
+        self.operator
+    }
+
+    pub fn operand(&self) -> NodeId {
+        self.operand
+    }
+}
+
+impl Block {
+    pub fn body(&self) -> &[NodeId] {
+        &self.body
+    }
+}
+
+impl With {
+    pub fn body(&self) -> NodeId {
+        self.body
+    }
+
+    pub fn handlers(&self) -> NodeId {
+        self.handlers
+    }
+}
+
+impl<'a> LetBinding<'a> {
+    pub fn pattern(&self) -> &Pattern<'a> {
+        &self.pattern
+    }
+
+    pub fn mutable(&self) -> bool {
+        self.mutable
+    }
+
+    pub fn value(&self) -> NodeId {
+        self.value
+    }
+}
+
+impl<'a> Assignment<'a> {
+    pub fn target(&self) -> &Identifier<'a> {
+        &self.target
+    }
+
+    pub fn value(&self) -> NodeId {
+        self.value
+    }
+}
+
+impl If {
+    pub fn condition(&self) -> NodeId {
+        self.condition
+    }
+
+    pub fn consequent(&self) -> &[NodeId] {
+        &self.consequent
+    }
+
+    // Either a Block or, for `else if`, an If
+    pub fn alternative(&self) -> Option<NodeId> {
+        self.alternative
+    }
+}
+
+impl<'a> While<'a> {
+    pub fn pattern(&self) -> Option<&Pattern<'a>> {
+        self.pattern.as_ref()
+    }
+
+    pub fn condition(&self) -> NodeId {
+        self.condition
+    }
+
+    pub fn body(&self) -> &[NodeId] {
+        &self.body
+    }
+}
+
+impl<'a> ForOf<'a> {
+    pub fn pattern(&self) -> &Pattern<'a> {
+        &self.pattern
+    }
+
+    pub fn iterable(&self) -> NodeId {
+        self.iterable
+    }
+
+    pub fn body(&self) -> &[NodeId] {
+        &self.body
+    }
+}
+
+impl Return {
+    pub fn value(&self) -> Option<NodeId> {
+        self.value
+    }
+}
+
+impl Yield {
+    pub fn value(&self) -> Option<NodeId> {
+        self.value
+    }
+}
+
+impl<'a> Match<'a> {
+    pub fn value(&self) -> NodeId {
+        self.value
+    }
+
+    pub fn cases(&self) -> &[MatchCase<'a>] {
+        &self.cases
+    }
+}
+
+impl<'a> MatchCase<'a> {
+    pub fn new(test: MatchTest<'a>, body: NodeId, position: Position) -> Self {
+        MatchCase {
+            test,
+            body,
+            position,
+        }
+    }
+
+    pub fn test(&self) -> &MatchTest<'a> {
+        &self.test
+    }
+
+    pub fn body(&self) -> NodeId {
+        self.body
+    }
+}
+
+impl<'a> ConstructorPattern<'a> {
+    pub fn new(
+        qualifier: Vec<&'a str>,
+        name: &'a str,
+        arguments: Vec<Pattern<'a>>,
+        position: Position,
+    ) -> Self {
+        ConstructorPattern {
+            qualifier,
+            name,
+            arguments,
+            position,
+        }
+    }
+
+    pub fn qualifier(&self) -> &[&'a str] {
+        &self.qualifier
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn arguments(&self) -> &[Pattern<'a>] {
+        &self.arguments
+    }
+}
+
+impl<'a> RecordPattern<'a> {
+    pub fn new(
+        fields: Vec<FieldPattern<'a>>,
+        rest: Option<Box<Pattern<'a>>>,
+        position: Position,
+    ) -> Self {
+        RecordPattern {
+            fields,
+            rest,
+            position,
+        }
+    }
+
+    pub fn fields(&self) -> &[FieldPattern<'a>] {
+        &self.fields
+    }
+
+    pub fn rest(&self) -> Option<&Pattern<'a>> {
+        self.rest.as_deref()
+    }
+}
+
+impl<'a> FieldPattern<'a> {
+    pub fn new(name: Identifier<'a>, pattern: Pattern<'a>) -> Self {
+        FieldPattern { name, pattern }
+    }
+
+    pub fn name(&self) -> &Identifier<'a> {
+        &self.name
+    }
+
+    // The name itself for shorthand fields
+    pub fn pattern(&self) -> &Pattern<'a> {
+        &self.pattern
+    }
+}
+
+impl<'a> ArrayPattern<'a> {
+    pub fn new(
+        elements: Vec<Pattern<'a>>,
+        rest: Option<Box<Pattern<'a>>>,
+        position: Position,
+    ) -> Self {
+        ArrayPattern {
+            elements,
+            rest,
+            position,
+        }
+    }
+
+    pub fn elements(&self) -> &[Pattern<'a>] {
+        &self.elements
+    }
+
+    pub fn rest(&self) -> Option<&Pattern<'a>> {
+        self.rest.as_deref()
+    }
+}
+
+impl<'a> TuplePattern<'a> {
+    pub fn new(elements: Vec<Pattern<'a>>, position: Position) -> Self {
+        TuplePattern { elements, position }
+    }
+
+    pub fn elements(&self) -> &[Pattern<'a>] {
+        &self.elements
+    }
+}
+
+impl<'a> FunctionDeclaration<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn parameters(&self) -> &[FunctionParameter<'a>] {
+        &self.parameters
+    }
+
+    pub fn return_type(&self) -> Option<&TypeAnnotation<'a>> {
+        self.return_type.as_ref()
+    }
+
+    pub fn body(&self) -> &[NodeId] {
+        &self.body
+    }
+
+    pub fn docs(&self) -> Option<&Docs> {
+        self.docs.as_ref()
+    }
+}
+
+impl<'a> Lambda<'a> {
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn parameters(&self) -> &[FunctionParameter<'a>] {
+        &self.parameters
+    }
+
+    pub fn return_type(&self) -> Option<&TypeAnnotation<'a>> {
+        self.return_type.as_ref()
+    }
+
+    pub fn body(&self) -> &[NodeId] {
+        &self.body
+    }
+}
+
+impl<'a> FunctionParameter<'a> {
+    pub fn new(name: &'a str, annotation: Option<TypeAnnotation<'a>>, position: Position) -> Self {
+        FunctionParameter {
+            name,
+            annotation,
+            position,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn annotation(&self) -> Option<&TypeAnnotation<'a>> {
+        self.annotation.as_ref()
+    }
+}
+
+impl<'a> TypeParameter<'a> {
+    pub fn new(name: &'a str, extends: Option<Type<'a>>, position: Position) -> Self {
+        TypeParameter {
+            name,
+            extends,
+            position,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn extends(&self) -> Option<&Type<'a>> {
+        self.extends.as_ref()
+    }
+}
+
+impl<'a> TypeDeclaration<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn type_constructors(&self) -> &[TypeConstructor<'a>] {
+        &self.type_constructors
+    }
+
+    pub fn docs(&self) -> Option<&Docs> {
+        self.docs.as_ref()
+    }
+}
+
+impl<'a> StructDeclaration<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn fields(&self) -> &[StructData<'a>] {
+        &self.fields
+    }
+}
+
+impl<'a> TypeConstructor<'a> {
+    pub fn new(
+        name: &'a str,
+        type_parameters: Vec<TypeParameter<'a>>,
+        parameters: Vec<LabeledTypeAnnotation<'a>>,
+        return_type: Option<TypeAnnotation<'a>>,
+        position: Position,
+    ) -> Self {
+        TypeConstructor {
+            name,
+            type_parameters,
+            parameters,
+            return_type,
+            position,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn parameters(&self) -> &[LabeledTypeAnnotation<'a>] {
+        &self.parameters
+    }
+
+    pub fn return_type(&self) -> Option<&TypeAnnotation<'a>> {
+        self.return_type.as_ref()
+    }
+}
+
+impl<'a> TypeAnnotation<'a> {
+    pub fn new(annotated: Type<'a>, position: Position) -> Self {
+        TypeAnnotation {
+            annotated,
+            position,
+        }
+    }
+
+    pub fn annotated(&self) -> &Type<'a> {
+        &self.annotated
+    }
+}
+
+impl<'a> LabeledTypeAnnotation<'a> {
+    pub fn new(name: Option<&'a str>, annotated: TypeAnnotation<'a>, position: Position) -> Self {
+        LabeledTypeAnnotation {
+            name,
+            annotated,
+            position,
+        }
+    }
+
+    // None for positional constructor parameters
+    pub fn name(&self) -> Option<&'a str> {
+        self.name
+    }
+
+    pub fn annotated(&self) -> &TypeAnnotation<'a> {
+        &self.annotated
+    }
+}
+
+impl<'a> EffectDeclaration<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn operations(&self) -> &[EffectOperation<'a>] {
+        &self.operations
+    }
+
+    pub fn docs(&self) -> Option<&Docs> {
+        self.docs.as_ref()
+    }
+}
+
+impl<'a> EffectOperation<'a> {
+    pub fn new(
+        name: Option<&'a str>,
+        type_parameters: Vec<TypeParameter<'a>>,
+        parameters: Vec<TypeAnnotation<'a>>,
+        return_type: TypeAnnotation<'a>,
+        position: Position,
+    ) -> Self {
+        EffectOperation {
+            name,
+            type_parameters,
+            parameters,
+            return_type,
+            position,
+        }
+    }
+
+    // None for the single operation of `effect Fail<E>(E) -> Never`
+    pub fn name(&self) -> Option<&'a str> {
+        self.name
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn parameters(&self) -> &[TypeAnnotation<'a>] {
+        &self.parameters
+    }
+
+    pub fn return_type(&self) -> &TypeAnnotation<'a> {
+        &self.return_type
+    }
+}
+
+impl<'a> ImplBlock<'a> {
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn effect(&self) -> &'a str {
+        self.effect
+    }
+
+    pub fn type_arguments(&self) -> &[TypeAnnotation<'a>] {
+        &self.type_arguments
+    }
+
+    pub fn members(&self) -> &[NodeId] {
+        &self.members
+    }
+}
+
+impl<'a> TypeclassDeclaration<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn superclasses(&self) -> &[TypeAnnotation<'a>] {
+        &self.superclasses
+    }
+
+    pub fn associated_types(&self) -> &[AssociatedType<'a>] {
+        &self.associated_types
+    }
+
+    pub fn methods(&self) -> &[TypeclassMethod<'a>] {
+        &self.methods
+    }
+}
+
+impl<'a> TypeclassMethod<'a> {
+    pub fn new(
+        name: &'a str,
+        type_parameters: Vec<TypeParameter<'a>>,
+        parameters: Vec<FunctionParameter<'a>>,
+        return_type: Option<TypeAnnotation<'a>>,
+        default: Option<Vec<NodeId>>,
+        position: Position,
+    ) -> Self {
+        TypeclassMethod {
+            name,
+            type_parameters,
+            parameters,
+            return_type,
+            default,
+            position,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn parameters(&self) -> &[FunctionParameter<'a>] {
+        &self.parameters
+    }
+
+    pub fn return_type(&self) -> Option<&TypeAnnotation<'a>> {
+        self.return_type.as_ref()
+    }
+
+    // The default implementation, if any
+    pub fn default(&self) -> Option<&[NodeId]> {
+        self.default.as_deref()
+    }
+}
+
+impl<'a> AssociatedType<'a> {
+    pub fn new(
+        name: &'a str,
+        bound: Option<TypeAnnotation<'a>>,
+        default: Option<TypeAnnotation<'a>>,
+        position: Position,
+    ) -> Self {
+        AssociatedType {
+            name,
+            bound,
+            default,
+            position,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn bound(&self) -> Option<&TypeAnnotation<'a>> {
+        self.bound.as_ref()
+    }
+
+    // The definition, inside an instance
+    pub fn default(&self) -> Option<&TypeAnnotation<'a>> {
+        self.default.as_ref()
+    }
+}
+
+impl<'a> InstanceDeclaration<'a> {
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn typeclass(&self) -> &'a str {
+        self.typeclass
+    }
+
+    pub fn type_arguments(&self) -> &[TypeAnnotation<'a>] {
+        &self.type_arguments
+    }
+
+    pub fn target(&self) -> &TypeAnnotation<'a> {
+        &self.target
+    }
+
+    pub fn associated_types(&self) -> &[AssociatedType<'a>] {
+        &self.associated_types
+    }
+
+    pub fn members(&self) -> &[NodeId] {
+        &self.members
+    }
+}
+
+impl<'a> HandlerDeclaration<'a> {
+    // None for an effect's default handler
+    pub fn name(&self) -> Option<&'a str> {
+        self.name
+    }
+
+    pub fn parameters(&self) -> &[FunctionParameter<'a>] {
+        &self.parameters
+    }
+
+    pub fn body(&self) -> &[NodeId] {
+        &self.body
+    }
+
+    pub fn operations(&self) -> &[OperationClause<'a>] {
+        &self.operations
+    }
+
+    pub fn return_clause(&self) -> Option<&ReturnClause<'a>> {
+        self.return_clause.as_ref()
+    }
+}
+
+impl<'a> OperationClause<'a> {
+    pub fn new(
+        name: &'a str,
+        parameters: Vec<FunctionParameter<'a>>,
+        body: Vec<NodeId>,
+        position: Position,
+    ) -> Self {
+        OperationClause {
+            name,
+            parameters,
+            body,
+            position,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn parameters(&self) -> &[FunctionParameter<'a>] {
+        &self.parameters
+    }
+
+    pub fn body(&self) -> &[NodeId] {
+        &self.body
+    }
+}
+
+impl<'a> ReturnClause<'a> {
+    pub fn new(parameter: FunctionParameter<'a>, body: Vec<NodeId>, position: Position) -> Self {
+        ReturnClause {
+            parameter,
+            body,
+            position,
+        }
+    }
+
+    pub fn parameter(&self) -> &FunctionParameter<'a> {
+        &self.parameter
+    }
+
+    pub fn body(&self) -> &[NodeId] {
+        &self.body
+    }
+}
+
+impl<'a> TypeAlias<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter<'a>] {
+        &self.type_parameters
+    }
+
+    pub fn aliased(&self) -> &TypeAnnotation<'a> {
+        &self.aliased
+    }
+
+    // The type a `brand Name = Type` declaration brands, i.e. the aliased
+    // type without the brand's own label. None for plain aliases.
+    pub fn branded(&self) -> Option<Type<'a>> {
+        let Type::Intersection(members) = &self.aliased.annotated else {
+            return None;
+        };
+
+        match members.split_last()? {
+            (Type::Brand(brand), [type_]) if *brand == self.name => Some(type_.clone()),
+            (Type::Brand(brand), rest) if *brand == self.name => {
+                Some(Type::Intersection(rest.to_vec()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn docs(&self) -> Option<&Docs> {
+        self.docs.as_ref()
+    }
+}
+
+impl<'a> MacroDeclaration<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn parameters(&self) -> &[FunctionParameter<'a>] {
+        &self.parameters
+    }
+
+    pub fn body(&self) -> &Delimited<'a> {
+        &self.body
+    }
+}
+
+impl<'a> MacroInvocation<'a> {
+    pub fn new(name: &'a str, group: Delimited<'a>, position: Position) -> Self {
+        MacroInvocation {
+            name,
+            group,
+            position,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    // The brackets and the tokens between them.
+    pub fn group(&self) -> &Delimited<'a> {
+        &self.group
+    }
+
+    pub fn delimiter(&self) -> Delimiter {
+        self.group.delimiter
+    }
+
+    pub fn tokens(&self) -> &[TokenTree<'a>] {
+        &self.group.trees
+    }
+
+    // The tokens split at the commas that aren't nested in brackets.
+    pub fn arguments(&self) -> Vec<&[TokenTree<'a>]> {
+        if self.group.trees.is_empty() {
+            return vec![];
+        }
+
+        self.group
+            .trees
+            .split(
+                |tree| matches!(tree, TokenTree::Token(token) if token.kind() == TokenKind::Comma),
+            )
+            .collect()
+    }
+}
+
+impl<'a> Delimited<'a> {
+    pub fn new(
+        delimiter: Delimiter,
+        trees: Vec<TokenTree<'a>>,
+        text: &'a str,
+        position: Position,
+    ) -> Self {
+        Delimited {
+            delimiter,
+            trees,
+            text,
+            position,
+        }
+    }
+
+    pub fn delimiter(&self) -> Delimiter {
+        self.delimiter
+    }
+
+    pub fn trees(&self) -> &[TokenTree<'a>] {
+        &self.trees
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+}
+
+impl Delimiter {
+    // The delimiter a token opens, if any.
+    pub fn opened_by(kind: TokenKind) -> Option<Delimiter> {
+        match kind {
+            TokenKind::LeftParen => Some(Delimiter::Parenthesis),
+            TokenKind::LeftBracket => Some(Delimiter::Bracket),
+            TokenKind::LeftBrace => Some(Delimiter::Brace),
+            _ => None,
+        }
+    }
+
+    pub fn closing(&self) -> TokenKind {
+        match self {
+            Delimiter::Parenthesis => TokenKind::RightParen,
+            Delimiter::Bracket => TokenKind::RightBracket,
+            Delimiter::Brace => TokenKind::RightBrace,
+        }
+    }
+
+    pub fn open(&self) -> &'static str {
+        match self {
+            Delimiter::Parenthesis => "(",
+            Delimiter::Bracket => "[",
+            Delimiter::Brace => "{",
+        }
+    }
+
+    pub fn close(&self) -> &'static str {
+        match self {
+            Delimiter::Parenthesis => ")",
+            Delimiter::Bracket => "]",
+            Delimiter::Brace => "}",
+        }
+    }
+}
+
+macro_rules! impl_spanned {
+    ($($node:ident$(<$lifetime:lifetime>)?),* $(,)?) => {
+        $(
+            impl$(<$lifetime>)? Spanned for $node$(<$lifetime>)? {
+                fn position(&self) -> Position {
+                    self.position
+                }
+            }
+        )*
+    };
+}
+
+impl_spanned!(
+    BooleanLiteral,
+    IntegerLiteral,
+    NumberLiteral,
+    RegexpLiteral<'a>,
+    StringLiteral<'a>,
+    TemplateLiteral<'a>,
+    Identifier<'a>,
+    Record<'a>,
+    Array,
+    Tuple,
+    ImportDeclaration<'a>,
+    NamedBinding<'a>,
+    ExportDeclaration<'a>,
+    Call,
+    MemberAccess<'a>,
+    Binary,
+    Unary,
+    Block,
+    With,
+    LetBinding<'a>,
+    Assignment<'a>,
+    If,
+    While<'a>,
+    ForOf<'a>,
+    Return,
+    Yield,
+    Match<'a>,
+    MatchCase<'a>,
+    ConstructorPattern<'a>,
+    RecordPattern<'a>,
+    ArrayPattern<'a>,
+    TuplePattern<'a>,
+    FunctionDeclaration<'a>,
+    Lambda<'a>,
+    FunctionParameter<'a>,
+    TypeParameter<'a>,
+    TypeDeclaration<'a>,
+    StructDeclaration<'a>,
+    TypeConstructor<'a>,
+    TypeAnnotation<'a>,
+    LabeledTypeAnnotation<'a>,
+    EffectDeclaration<'a>,
+    EffectOperation<'a>,
+    ImplBlock<'a>,
+    TypeclassDeclaration<'a>,
+    TypeclassMethod<'a>,
+    AssociatedType<'a>,
+    InstanceDeclaration<'a>,
+    HandlerDeclaration<'a>,
+    OperationClause<'a>,
+    ReturnClause<'a>,
+    TypeAlias<'a>,
+    MacroDeclaration<'a>,
+    MacroInvocation<'a>,
+    Delimited<'a>,
+);
+
+impl<'a> Spanned for AST<'a> {
+    fn position(&self) -> Position {
+        match self {
+            AST::BooleanLiteral(node) => node.position(),
+            AST::IntegerLiteral(node) => node.position(),
+            AST::NumberLiteral(node) => node.position(),
+            AST::RegexpLiteral(node) => node.position(),
+            AST::StringLiteral(node) => node.position(),
+            AST::TemplateLiteral(node) => node.position(),
+            AST::Identifier(node) => node.position(),
+            AST::Record(node) => node.position(),
+            AST::Array(node) => node.position(),
+            AST::Tuple(node) => node.position(),
+            AST::Call(node) => node.position(),
+            AST::MemberAccess(node) => node.position(),
+            AST::Binary(node) => node.position(),
+            AST::Unary(node) => node.position(),
+            AST::Block(node) => node.position(),
+            AST::With(node) => node.position(),
+            AST::Lambda(node) => node.position(),
+            AST::LetBinding(node) => node.position(),
+            AST::Assignment(node) => node.position(),
+            AST::If(node) => node.position(),
+            AST::While(node) => node.position(),
+            AST::ForOf(node) => node.position(),
+            AST::Return(node) => node.position(),
+            AST::Yield(node) => node.position(),
+            AST::ImportDeclaration(node) => node.position(),
+            AST::ExportDeclaration(node) => node.position(),
+            AST::Match(node) => node.position(),
+            AST::FunctionDeclaration(node) => node.position(),
+            AST::TypeDeclaration(node) => node.position(),
+            AST::TypeAlias(node) => node.position(),
+            AST::StructDeclaration(node) => node.position(),
+            AST::EffectDeclaration(node) => node.position(),
+            AST::ImplBlock(node) => node.position(),
+            AST::TypeclassDeclaration(node) => node.position(),
+            AST::InstanceDeclaration(node) => node.position(),
+            AST::HandlerDeclaration(node) => node.position(),
+            AST::MacroDeclaration(node) => node.position(),
+            AST::MacroInvocation(node) => node.position(),
+        }
+    }
+}
+
+impl<'a> Spanned for TokenTree<'a> {
+    fn position(&self) -> Position {
+        match self {
+            TokenTree::Token(token) => token.position(),
+            TokenTree::Delimited(delimited) => delimited.position(),
+        }
+    }
+}
+
+impl<'a> Spanned for Pattern<'a> {
+    fn position(&self) -> Position {
+        match self {
+            Pattern::Wildcard(position) => *position,
+            Pattern::Identifier(identifier) => identifier.position(),
+            Pattern::Literal(literal) => literal.position(),
+            Pattern::Constructor(constructor) => constructor.position(),
+            Pattern::Record(record) => record.position(),
+            Pattern::Array(array) => array.position(),
+            Pattern::Tuple(tuple) => tuple.position(),
+        }
+    }
+}
+
+// From the field's name to the end of its pattern.
+impl<'a> Spanned for FieldPattern<'a> {
+    fn position(&self) -> Position {
+        Position::new(self.name.position.start, self.pattern.position().end)
     }
 }
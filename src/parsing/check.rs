@@ -0,0 +1,2194 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    mem,
+};
+
+use crate::{
+    lexing::{Position, Spanned},
+    parsing::{arena::*, ast::*, dispatch::*, resolve::*, types::*, visit::*},
+};
+
+// A value whose type isn't the one its context expects.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypeError<'a> {
+    pub message: String,
+    pub expected: Type<'a>,
+    pub found: Type<'a>,
+    pub position: Position,
+}
+
+#[derive(Debug, Default)]
+pub struct Typing<'a> {
+    pub(crate) types: HashMap<NodeId, Type<'a>>,
+    pub(crate) errors: Vec<TypeError<'a>>,
+}
+
+impl<'a> Typing<'a> {
+    // The type inferred for an expression or declaration. Types the checker
+    // couldn't pin down are left as variables, printed as `?0`, and what it
+    // doesn't check is Any.
+    pub fn type_of(&self, node: NodeId) -> Option<&Type<'a>> {
+        self.types.get(&node)
+    }
+
+    // In source order.
+    pub fn errors(&self) -> &[TypeError<'a>] {
+        &self.errors
+    }
+}
+
+// A type with variables that are replaced by fresh ones at every use, like
+// `<A>(A) -> A`.
+#[derive(Debug, Clone)]
+struct Scheme<'a> {
+    variables: Vec<usize>,
+    type_: Type<'a>,
+}
+
+impl<'a> Scheme<'a> {
+    fn mono(type_: Type<'a>) -> Scheme<'a> {
+        Scheme {
+            variables: vec![],
+            type_,
+        }
+    }
+}
+
+// What a name in an annotation stands for.
+#[derive(Debug, Clone)]
+enum TypeName<'a> {
+    Nominal, // A type declaration or an effect
+    Alias(Vec<&'a str>, Type<'a>),
+    Struct(Vec<&'a str>, Vec<StructData<'a>>),
+}
+
+#[derive(Debug, Default)]
+struct EffectSignature<'a> {
+    parameters: Vec<usize>,
+    operations: Vec<(Option<&'a str>, Scheme<'a>)>, // Generic over the effect's parameters
+}
+
+// A top-level function or handler, generalized once it is checked.
+#[derive(Debug)]
+struct Binding<'a> {
+    node: NodeId,
+    effect: Option<(&'a str, Vec<Type<'a>>)>, // The effect it is an `impl for` member of
+    scope: Vec<(&'a str, Type<'a>)>,          // Its type parameters and those of its impl
+    type_: Type<'a>,
+}
+
+// Infers the type of every expression in a module, Hindley-Milner style:
+// each node gets a type, with variables for what isn't known yet, the
+// constraints between them are solved by unification as they are found, and
+// top-level functions and `impl for` members are generalized, so that
+// `fun id(x) { x }` can be used at any type. They are checked callees
+// first, a group of mutually recursive ones at a time. Local bindings and
+// lambdas aren't generalized.
+//
+// Names are looked up through the Resolver, and member calls through
+// MethodCalls. Annotations constrain what they annotate, with aliases kept
+// in the inferred types so that errors name them, e.g. "Expected `IntQueue`,
+// found `String`".
+//
+// Some of the language is only checked loosely:
+//
+// - Effects aren't tracked. The effects of a function type are dropped from
+//   its annotation, so they are never unified, and `Effect<Console, Unit>`
+//   is just `Unit`: fib's `main` is `(Int) -> Unit`.
+// - Handlers are `Handler<A, B>`, turning a computation returning `A` into
+//   one returning `B` by their return clause. `++` composes them, the left
+//   one handling first. What operation clauses return isn't checked.
+// - Imports are Any until modules are checked together, and so is what
+//   follows an error, e.g. a missing field, or a `with` whose handler is
+//   imported. Any and Never fit anywhere, while Unknown, the top type, only
+//   fits where Unknown is expected.
+// - A function taking fewer parameters can be passed where one taking more
+//   is expected, like `Fail.catch(() -> 0)`.
+// - Branches of matches and ifs whose value is thrown away don't need to
+//   agree.
+// - Typeclass instances aren't checked.
+#[derive(Debug, Default)]
+pub struct TypeChecker<'a> {
+    resolution: Resolution<'a>,
+    dispatches: HashMap<NodeId, Dispatch<'a>>,
+    substitution: Vec<Option<Type<'a>>>, // What each variable was unified with
+    declarations: HashMap<DeclarationId, Scheme<'a>>,
+    globals: Vec<DeclarationId>, // Module variables used by functions before they are bound
+    functions: HashMap<&'a str, DeclarationId>, // Top-level, for `list.map(f)`
+    type_names: HashMap<&'a str, TypeName<'a>>,
+    type_scope: Vec<(&'a str, Type<'a>)>,
+    expanding: Vec<&'a str>, // Aliases being expanded, to stop at cycles
+    effects: HashMap<&'a str, EffectSignature<'a>>,
+    effect: Option<(&'a str, Vec<Type<'a>>)>, // The effect whose handlers are being checked
+    returns: Vec<Type<'a>>,                   // What `return` returns, innermost last
+    typing: Typing<'a>,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn check(file: &SourceFile<'a>) -> Typing<'a> {
+        let resolution = Resolver::resolve(file);
+        let mut checker = TypeChecker {
+            dispatches: MethodCalls::dispatch_resolved(file, &resolution),
+            resolution,
+            ..TypeChecker::default()
+        };
+
+        checker.declare_prelude();
+        checker.declare_types(&file.arena, &file.body);
+        checker.check_bindings(&file.arena, &file.body);
+
+        for &id in &file.body {
+            if !is_declaration(&file.arena, id) {
+                checker.infer_statement(&file.arena, id);
+            }
+        }
+
+        checker.finish()
+    }
+
+    fn finish(mut self) -> Typing<'a> {
+        let types = mem::take(&mut self.typing.types)
+            .into_iter()
+            .map(|(id, type_)| (id, self.zonk(&type_, &HashMap::new())))
+            .collect();
+        let mut typing = Typing {
+            types,
+            errors: mem::take(&mut self.typing.errors),
+        };
+
+        typing.errors.sort_by_key(|error| error.position.start);
+        typing
+    }
+
+    // Declarations
+
+    fn declare_prelude(&mut self) {
+        let a = self.fresh();
+        let b = self.fresh();
+        let function = |arguments: Vec<Type<'a>>, returned: Type<'a>| {
+            Type::Function(vec![], arguments, vec![], Box::new(returned))
+        };
+        let option = Type::Option(Box::new(a.clone()));
+        let either = Type::Either(Box::new(a.clone()), Box::new(b.clone()));
+        let prelude = [
+            ("Some", function(vec![a.clone()], option.clone())),
+            ("None", option),
+            ("Left", function(vec![a.clone()], either.clone())),
+            ("Right", function(vec![b], either)),
+            (
+                "assert_equal",
+                function(vec![a.clone(), a.clone()], Type::Unit),
+            ),
+            ("print", function(vec![a], Type::Unit)),
+        ];
+
+        for (index, declaration) in self.resolution.declarations().iter().enumerate() {
+            if !declaration.is_builtin() {
+                continue;
+            }
+
+            if let Some((_, type_)) = prelude.iter().find(|(name, _)| *name == declaration.name()) {
+                let scheme = self.generalize(type_);
+
+                self.declarations.insert(DeclarationId(index), scheme);
+            }
+        }
+    }
+
+    // Types, aliases and effects first, so that annotations can name them
+    // in any order, then constructors and operations.
+    fn declare_types(&mut self, arena: &Arena<'a>, body: &[NodeId]) {
+        let declarations: Vec<_> = body
+            .iter()
+            .filter_map(|&id| declared_node(arena, id))
+            .collect();
+
+        for &id in &declarations {
+            let type_name = match &arena[id] {
+                AST::TypeDeclaration(declaration) => (declaration.name, TypeName::Nominal),
+                AST::EffectDeclaration(effect) => (effect.name, TypeName::Nominal),
+                AST::TypeAlias(alias) => (
+                    alias.name,
+                    TypeName::Alias(
+                        names(&alias.type_parameters),
+                        alias.aliased.annotated.clone(),
+                    ),
+                ),
+                AST::StructDeclaration(declaration) => (
+                    declaration.name,
+                    TypeName::Struct(
+                        names(&declaration.type_parameters),
+                        declaration.fields.clone(),
+                    ),
+                ),
+                _ => continue,
+            };
+
+            self.type_names.insert(type_name.0, type_name.1);
+        }
+
+        for &id in &declarations {
+            match &arena[id] {
+                AST::TypeDeclaration(declaration) => self.declare_constructors(id, declaration),
+                AST::EffectDeclaration(effect) => self.declare_operations(id, effect),
+                AST::TypeclassDeclaration(typeclass) => self.declare_methods(id, typeclass),
+                AST::FunctionDeclaration(function) => {
+                    if let Some(declaration) = self.resolution.definition(id) {
+                        self.functions.insert(function.name, declaration);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn declare_constructors(&mut self, node: NodeId, declaration: &TypeDeclaration<'a>) {
+        let bound = self.type_scope.len();
+        let parameters = self.bind_type_parameters(&declaration.type_parameters);
+        let result = named(declaration.name, parameters);
+
+        for constructor in &declaration.type_constructors {
+            let inner = self.type_scope.len();
+
+            self.bind_type_parameters(&constructor.type_parameters);
+
+            let arguments: Vec<_> = constructor
+                .parameters
+                .iter()
+                .map(|parameter| self.annotation(&parameter.annotated.annotated))
+                .collect();
+            let returned = match &constructor.return_type {
+                Some(annotation) => self.annotation(&annotation.annotated),
+                None => result.clone(),
+            };
+            let type_ = match arguments.is_empty() {
+                true => returned,
+                false => Type::Function(vec![], arguments, vec![], Box::new(returned)),
+            };
+
+            self.type_scope.truncate(inner);
+
+            if let Some(id) = self.member(node, constructor.name) {
+                let parameters = [
+                    names(&declaration.type_parameters),
+                    names(&constructor.type_parameters),
+                ];
+                let scheme = self.quantify(&type_, &parameters.concat());
+
+                self.declarations.insert(id, scheme);
+            }
+        }
+
+        self.type_scope.truncate(bound);
+    }
+
+    fn declare_operations(&mut self, node: NodeId, effect: &EffectDeclaration<'a>) {
+        let bound = self.type_scope.len();
+
+        self.bind_type_parameters(&effect.type_parameters);
+
+        // Shared by the operations, so that an impl's arguments can be given
+        // for all of them.
+        let parameters = self.flexible(&names(&effect.type_parameters));
+        let mut signature = EffectSignature {
+            parameters: variables(&parameters),
+            operations: vec![],
+        };
+
+        for operation in &effect.operations {
+            let inner = self.type_scope.len();
+
+            self.bind_type_parameters(&operation.type_parameters);
+
+            let arguments = operation
+                .parameters
+                .iter()
+                .map(|parameter| self.annotation(&parameter.annotated))
+                .collect();
+            let returned = self.annotation(&operation.return_type.annotated);
+            let type_ = Type::Function(vec![], arguments, vec![], Box::new(returned));
+            let mut mapping = self.flexible(&names(&operation.type_parameters));
+
+            mapping.extend(parameters.iter().cloned());
+
+            let scheme = self.generalize(&self.substitute(&type_, &mapping));
+
+            self.type_scope.truncate(inner);
+
+            if let Some(id) = operation.name.and_then(|name| self.member(node, name)) {
+                self.declarations.insert(id, scheme.clone());
+            }
+
+            signature.operations.push((operation.name, scheme));
+        }
+
+        self.type_scope.truncate(bound);
+        self.effects.insert(effect.name, signature);
+    }
+
+    fn declare_methods(&mut self, node: NodeId, typeclass: &TypeclassDeclaration<'a>) {
+        let bound = self.type_scope.len();
+
+        self.bind_type_parameters(&typeclass.type_parameters);
+
+        for method in &typeclass.methods {
+            let inner = self.type_scope.len();
+
+            self.bind_type_parameters(&method.type_parameters);
+
+            let arguments = method
+                .parameters
+                .iter()
+                .map(|parameter| match &parameter.annotation {
+                    Some(annotation) => self.annotation(&annotation.annotated),
+                    None => self.fresh(),
+                })
+                .collect();
+            let returned = match &method.return_type {
+                Some(annotation) => self.annotation(&annotation.annotated),
+                None => self.fresh(),
+            };
+            let type_ = Type::Function(vec![], arguments, vec![], Box::new(returned));
+
+            self.type_scope.truncate(inner);
+
+            if let Some(id) = self.member(node, method.name) {
+                let parameters = [
+                    names(&typeclass.type_parameters),
+                    names(&method.type_parameters),
+                ];
+                let scheme = self.quantify(&type_, &parameters.concat());
+
+                self.declarations.insert(id, scheme);
+            }
+        }
+
+        self.type_scope.truncate(bound);
+    }
+
+    // Type parameters are rigid where they are bound, only equal to
+    // themselves, so that `fun f<A>(a: A): A { 1 }` is an error. Whatever
+    // uses the declaration sees them as variables instead, see `quantify`.
+    // A constructor, operation or method of the declaration `node`.
+    fn member(&self, node: NodeId, name: &str) -> Option<DeclarationId> {
+        self.resolution
+            .member(self.resolution.definition(node)?, name)
+    }
+
+    fn bind_type_parameters(&mut self, parameters: &[TypeParameter<'a>]) -> Vec<Type<'a>> {
+        parameters
+            .iter()
+            .map(|parameter| {
+                let rigid = Type::Reference(parameter.name, vec![]);
+
+                self.type_scope.push((parameter.name, rigid.clone()));
+                rigid
+            })
+            .collect()
+    }
+
+    // Replaces rigid type parameters with the types `mapping` gives them.
+    fn substitute(&self, type_: &Type<'a>, mapping: &[(&'a str, Type<'a>)]) -> Type<'a> {
+        map_type(
+            &self.zonk(type_, &HashMap::new()),
+            &mut |type_| match type_ {
+                Type::Reference(name, arguments) if arguments.is_empty() => mapping
+                    .iter()
+                    .find(|(parameter, _)| parameter == name)
+                    .map(|(_, type_)| type_.clone()),
+                _ => None,
+            },
+        )
+    }
+
+    // Fresh variables for the type parameters a declaration binds.
+    fn flexible(&mut self, parameters: &[&'a str]) -> Vec<(&'a str, Type<'a>)> {
+        parameters
+            .iter()
+            .map(|&parameter| (parameter, self.fresh()))
+            .collect()
+    }
+
+    // Generalizes the type of a declaration over its type parameters.
+    fn quantify(&mut self, type_: &Type<'a>, parameters: &[&'a str]) -> Scheme<'a> {
+        let mapping = self.flexible(parameters);
+
+        self.generalize(&self.substitute(type_, &mapping))
+    }
+
+    // The type a declaration gives the names that refer to it.
+    fn type_of_declaration(&mut self, id: DeclarationId) -> Type<'a> {
+        match self.resolution.declaration(id).kind() {
+            // An effect used as a value is its default handler.
+            DeclarationKind::Effect => {
+                let variable = self.fresh();
+
+                return handler(variable.clone(), variable);
+            }
+            DeclarationKind::Import
+            | DeclarationKind::Type
+            | DeclarationKind::Alias
+            | DeclarationKind::Struct
+            | DeclarationKind::Typeclass
+            | DeclarationKind::AssociatedType
+            | DeclarationKind::TypeParameter
+            | DeclarationKind::Macro => return Type::Any,
+            _ => {}
+        }
+
+        if let Some(scheme) = self.declarations.get(&id).cloned() {
+            return self.instantiate(&scheme, HashMap::new());
+        }
+
+        // Used before it is bound, like a module variable read by a function
+        // or a local function called before its declaration.
+        let type_ = self.fresh();
+
+        if let DeclarationKind::Variable { .. } = self.resolution.declaration(id).kind() {
+            self.globals.push(id);
+        }
+
+        self.declarations.insert(id, Scheme::mono(type_.clone()));
+        type_
+    }
+
+    fn bind(&mut self, id: DeclarationId, type_: Type<'a>, position: Position) {
+        match self.declarations.get(&id).cloned() {
+            Some(scheme) if scheme.variables.is_empty() => {
+                self.expect(&scheme.type_, &type_, position)
+            }
+            _ => {
+                self.declarations.insert(id, Scheme::mono(type_));
+            }
+        }
+    }
+
+    fn bind_parameter(
+        &mut self,
+        site: Site,
+        parameter: &FunctionParameter<'a>,
+        expected: Option<Type<'a>>,
+    ) -> Type<'a> {
+        let type_ = match (&parameter.annotation, &expected) {
+            (Some(annotation), _) => {
+                let type_ = self.annotation(&annotation.annotated);
+
+                if let Some(expected) = &expected {
+                    self.expect(expected, &type_, annotation.position);
+                }
+
+                type_
+            }
+            (None, Some(expected)) => expected.clone(),
+            (None, None) => self.fresh(),
+        };
+
+        if let Some(id) = self.resolution.name(site, parameter.name) {
+            self.bind(id, type_.clone(), parameter.position);
+        }
+
+        type_
+    }
+
+    // Annotations
+
+    fn annotation(&mut self, type_: &Type<'a>) -> Type<'a> {
+        match type_ {
+            Type::Reference(name, arguments) => self.named_type(name, arguments),
+            // Effects aren't tracked, see TypeChecker.
+            Type::Function(type_parameters, arguments, _, returned) => {
+                let bound = self.type_scope.len();
+
+                for parameter in type_parameters {
+                    if let Type::Reference(name, _) = parameter {
+                        let variable = self.fresh();
+
+                        self.type_scope.push((name, variable));
+                    }
+                }
+
+                let arguments = self.annotations(arguments);
+                let returned = self.annotation(returned);
+
+                self.type_scope.truncate(bound);
+
+                Type::Function(vec![], arguments, vec![], Box::new(returned))
+            }
+            Type::Regexp(_) | Type::Template(_) => Type::String,
+            Type::Infer => self.fresh(),
+            Type::Macro(_) => Type::Any,
+            Type::Option(inner) => Type::Option(Box::new(self.annotation(inner))),
+            Type::Array(inner) => Type::Array(Box::new(self.annotation(inner))),
+            Type::Alias(name, inner) => Type::Alias(name, Box::new(self.annotation(inner))),
+            Type::Either(left, right) => Type::Either(
+                Box::new(self.annotation(left)),
+                Box::new(self.annotation(right)),
+            ),
+            Type::Union(members) => Type::Union(self.annotations(members)),
+            Type::Intersection(members) => Type::Intersection(self.annotations(members)),
+            Type::Tuple(members) => Type::Tuple(
+                members
+                    .iter()
+                    .map(|member| match member {
+                        TupleData::Member(type_) => {
+                            TupleData::Member(Box::new(self.annotation(type_)))
+                        }
+                        TupleData::Spread(type_) => {
+                            TupleData::Spread(Box::new(self.annotation(type_)))
+                        }
+                    })
+                    .collect(),
+            ),
+            Type::Struct(members) => Type::Struct(self.struct_members(members)),
+            _ => type_.clone(),
+        }
+    }
+
+    fn annotations(&mut self, types: &[Type<'a>]) -> Vec<Type<'a>> {
+        types.iter().map(|type_| self.annotation(type_)).collect()
+    }
+
+    fn struct_members(&mut self, members: &[StructData<'a>]) -> Vec<StructData<'a>> {
+        members
+            .iter()
+            .map(|member| match member {
+                StructData::Member(name, type_) => {
+                    StructData::Member(name, Box::new(self.annotation(type_)))
+                }
+                StructData::Spread(type_) => StructData::Spread(Box::new(self.annotation(type_))),
+            })
+            .collect()
+    }
+
+    // `CalcError.CannotDivideByZero` is a CalcError.
+    fn named_type(&mut self, name: &'a str, arguments: &[Type<'a>]) -> Type<'a> {
+        let head = name.split('.').next().unwrap_or(name);
+        let arguments = self.annotations(arguments);
+
+        if let Some((_, type_)) = self
+            .type_scope
+            .iter()
+            .rev()
+            .find(|(bound, _)| *bound == head)
+        {
+            return type_.clone();
+        }
+
+        if self.expanding.contains(&head) {
+            return Type::Any;
+        }
+
+        match self.type_names.get(head).cloned() {
+            Some(TypeName::Nominal) => named(head, arguments),
+            Some(TypeName::Alias(parameters, aliased)) => {
+                let aliased = self.expand(head, &parameters, arguments, |checker| {
+                    checker.annotation(&aliased)
+                });
+
+                Type::Alias(head, Box::new(aliased))
+            }
+            Some(TypeName::Struct(parameters, fields)) => {
+                let fields = self.expand(head, &parameters, arguments, |checker| {
+                    checker.struct_members(&fields)
+                });
+
+                Type::Alias(head, Box::new(Type::Struct(fields)))
+            }
+            // Only the result of a computation is checked.
+            None if head == "Effect" => arguments.last().cloned().unwrap_or(Type::Unit),
+            // Imported, or reported by the resolver.
+            None => Type::Any,
+        }
+    }
+
+    fn expand<T>(
+        &mut self,
+        name: &'a str,
+        parameters: &[&'a str],
+        mut arguments: Vec<Type<'a>>,
+        expand: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let bound = self.type_scope.len();
+
+        arguments.resize_with(parameters.len(), || Type::Any);
+        self.type_scope
+            .extend(parameters.iter().copied().zip(arguments));
+        self.expanding.push(name);
+
+        let expanded = expand(self);
+
+        self.expanding.pop();
+        self.type_scope.truncate(bound);
+
+        expanded
+    }
+
+    // Unification
+
+    fn fresh(&mut self) -> Type<'a> {
+        self.substitution.push(None);
+
+        Type::Variable(self.substitution.len() - 1)
+    }
+
+    // Follows variables to what they were unified with.
+    fn resolve(&self, type_: &Type<'a>) -> Type<'a> {
+        let mut type_ = type_;
+
+        while let Type::Variable(variable) = type_ {
+            match &self.substitution[*variable] {
+                Some(bound) => type_ = bound,
+                None => break,
+            }
+        }
+
+        type_.clone()
+    }
+
+    // Follows variables and aliases.
+    fn strip(&self, type_: &Type<'a>) -> Type<'a> {
+        match self.resolve(type_) {
+            Type::Alias(_, inner) => self.strip(&inner),
+            type_ => type_,
+        }
+    }
+
+    // Replaces every variable with what it was unified with, or what
+    // `mapping` maps it to.
+    fn zonk(&self, type_: &Type<'a>, mapping: &HashMap<usize, Type<'a>>) -> Type<'a> {
+        map_variables(type_, &mut |variable| match mapping.get(&variable) {
+            Some(type_) => Some(type_.clone()),
+            None => self.substitution[variable]
+                .as_ref()
+                .map(|bound| self.zonk(bound, mapping)),
+        })
+    }
+
+    fn free_variables(&self, type_: &Type<'a>) -> Vec<usize> {
+        let mut variables = vec![];
+
+        map_variables(&self.zonk(type_, &HashMap::new()), &mut |variable| {
+            if !variables.contains(&variable) {
+                variables.push(variable);
+            }
+
+            None
+        });
+
+        variables
+    }
+
+    // Quantifies the variables of a type that the module's variables don't
+    // mention.
+    fn generalize(&self, type_: &Type<'a>) -> Scheme<'a> {
+        let globals: HashSet<_> = self
+            .globals
+            .iter()
+            .filter_map(|id| self.declarations.get(id))
+            .flat_map(|scheme| self.free_variables(&scheme.type_))
+            .collect();
+        let type_ = self.zonk(type_, &HashMap::new());
+        let variables = self
+            .free_variables(&type_)
+            .into_iter()
+            .filter(|variable| !globals.contains(variable))
+            .collect();
+
+        Scheme { variables, type_ }
+    }
+
+    // `given` maps some of the quantified variables to known types, like an
+    // effect's parameters to the arguments of the impl being checked.
+    fn instantiate(
+        &mut self,
+        scheme: &Scheme<'a>,
+        mut given: HashMap<usize, Type<'a>>,
+    ) -> Type<'a> {
+        for &variable in &scheme.variables {
+            if let Entry::Vacant(entry) = given.entry(variable) {
+                entry.insert(self.fresh());
+            }
+        }
+
+        self.zonk(&scheme.type_, &given)
+    }
+
+    fn occurs(&self, variable: usize, type_: &Type<'a>) -> bool {
+        self.free_variables(type_).contains(&variable)
+    }
+
+    // Unifies the type a context expects with the one found there.
+    fn unify(&mut self, expected: &Type<'a>, found: &Type<'a>) -> bool {
+        let expected = self.resolve(expected);
+        let found = self.resolve(found);
+
+        match (&expected, &found) {
+            (Type::Variable(a), Type::Variable(b)) if a == b => true,
+            (Type::Never | Type::Any, _) | (_, Type::Never | Type::Any) => true,
+            (Type::Variable(variable), type_) | (type_, Type::Variable(variable)) => {
+                if self.occurs(*variable, type_) {
+                    return false;
+                }
+
+                self.substitution[*variable] = Some(type_.clone());
+                true
+            }
+            // Anything is an Unknown, but an Unknown is nothing else.
+            (Type::Unknown, _) => true,
+            (Type::Alias(_, inner), _) => self.unify(inner, &found),
+            (_, Type::Alias(_, inner)) => self.unify(&expected, inner),
+            (Type::Option(a), Type::Option(b)) | (Type::Array(a), Type::Array(b)) => {
+                self.unify(a, b)
+            }
+            (Type::Either(a, b), Type::Either(c, d)) => self.unify(a, c) & self.unify(b, d),
+            (Type::Tuple(a), Type::Tuple(b)) if a.len() == b.len() => {
+                a.iter().zip(b).all(|pair| match pair {
+                    (TupleData::Member(a), TupleData::Member(b))
+                    | (TupleData::Spread(a), TupleData::Spread(b)) => self.unify(a, b),
+                    _ => false,
+                })
+            }
+            (Type::Struct(a), Type::Struct(b)) if a.len() == b.len() => a.iter().all(|member| {
+                let StructData::Member(name, a) = member else {
+                    return true;
+                };
+
+                b.iter().any(|member| match member {
+                    StructData::Member(other, b) => other == name && self.unify(a, b),
+                    StructData::Spread(_) => false,
+                })
+            }),
+            // Extra arguments are ignored by functions that don't take them.
+            (Type::Function(_, a, _, r), Type::Function(_, b, _, s)) if b.len() <= a.len() => {
+                a.iter().zip(b).all(|(a, b)| self.unify(a, b)) & self.unify(r, s)
+            }
+            (Type::Reference(n, a), Type::Reference(m, b)) if n == m && a.len() == b.len() => {
+                a.iter().zip(b).all(|(a, b)| self.unify(a, b))
+            }
+            (Type::Union(a), Type::Union(b)) | (Type::Intersection(a), Type::Intersection(b))
+                if a.len() == b.len() =>
+            {
+                a.iter().zip(b).all(|(a, b)| self.unify(a, b))
+            }
+            _ => expected == found,
+        }
+    }
+
+    fn expect(&mut self, expected: &Type<'a>, found: &Type<'a>, position: Position) {
+        if !self.unify(expected, found) {
+            let expected = self.zonk(expected, &HashMap::new());
+            let found = self.zonk(found, &HashMap::new());
+            let [shown_expected, shown_found] = named_variables([&expected, &found]);
+
+            self.error(
+                format!("Expected `{}`, found `{}`", shown_expected, shown_found),
+                expected,
+                found,
+                position,
+            );
+        }
+    }
+
+    fn error(&mut self, message: String, expected: Type<'a>, found: Type<'a>, position: Position) {
+        self.typing.errors.push(TypeError {
+            message,
+            expected,
+            found,
+            position,
+        });
+    }
+
+    fn arity_error(
+        &mut self,
+        expected: usize,
+        found: usize,
+        expected_type: &Type<'a>,
+        found_type: &Type<'a>,
+        position: Position,
+    ) {
+        let plural = if expected == 1 { "" } else { "s" };
+
+        self.error(
+            format!("Expected {} argument{}, found {}", expected, plural, found),
+            self.zonk(expected_type, &HashMap::new()),
+            self.zonk(found_type, &HashMap::new()),
+            position,
+        );
+    }
+
+    // Bindings
+
+    // Checks top-level functions and `impl for` members, callees first, and
+    // generalizes each group of mutually recursive ones once it is checked.
+    fn check_bindings(&mut self, arena: &Arena<'a>, body: &[NodeId]) {
+        let mut bindings = vec![];
+
+        for &id in body {
+            let Some(id) = declared_node(arena, id) else {
+                continue;
+            };
+
+            match &arena[id] {
+                AST::FunctionDeclaration(_) | AST::HandlerDeclaration(_) => {
+                    bindings.push(self.binding(arena, id, &[], None))
+                }
+                AST::ImplBlock(block) => {
+                    for &member in &block.members {
+                        bindings.push(self.binding(
+                            arena,
+                            member,
+                            &block.type_parameters,
+                            Some(block),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let indices: HashMap<_, _> = bindings
+            .iter()
+            .enumerate()
+            .filter_map(|(index, binding)| {
+                self.resolution
+                    .definition(binding.node)
+                    .map(|declaration| (declaration, index))
+            })
+            .collect();
+        let edges: Vec<Vec<usize>> = bindings
+            .iter()
+            .map(|binding| {
+                let mut references = References {
+                    resolution: &self.resolution,
+                    dispatches: &self.dispatches,
+                    functions: &self.functions,
+                    found: vec![],
+                };
+
+                references.visit_node(arena, binding.node);
+                references
+                    .found
+                    .iter()
+                    .filter_map(|declaration| indices.get(declaration).copied())
+                    .collect()
+            })
+            .collect();
+
+        for component in components(&edges) {
+            for &index in &component {
+                if let Some(declaration) = self.resolution.definition(bindings[index].node) {
+                    let type_ = bindings[index].type_.clone();
+
+                    self.declarations.insert(declaration, Scheme::mono(type_));
+                }
+            }
+
+            for &index in &component {
+                self.check_binding(arena, &bindings[index]);
+            }
+
+            for &index in &component {
+                let binding = &bindings[index];
+                let parameters: Vec<_> = binding.scope.iter().map(|(name, _)| *name).collect();
+                let scheme = self.quantify(&binding.type_, &parameters);
+
+                self.typing
+                    .types
+                    .insert(binding.node, binding.type_.clone());
+
+                if let Some(declaration) = self.resolution.definition(binding.node) {
+                    self.declarations.insert(declaration, scheme);
+                }
+            }
+        }
+    }
+
+    fn binding(
+        &mut self,
+        arena: &Arena<'a>,
+        node: NodeId,
+        impl_parameters: &[TypeParameter<'a>],
+        block: Option<&ImplBlock<'a>>,
+    ) -> Binding<'a> {
+        let bound = self.type_scope.len();
+
+        self.bind_type_parameters(impl_parameters);
+
+        let effect = block.map(|block| {
+            let arguments = block
+                .type_arguments
+                .iter()
+                .map(|argument| self.annotation(&argument.annotated))
+                .collect();
+
+            (block.effect, arguments)
+        });
+        let type_ = match &arena[node] {
+            AST::FunctionDeclaration(function) => self.signature(node, function),
+            _ => self.fresh(),
+        };
+        let scope = self.type_scope.split_off(bound);
+
+        Binding {
+            node,
+            effect,
+            scope,
+            type_,
+        }
+    }
+
+    fn check_binding(&mut self, arena: &Arena<'a>, binding: &Binding<'a>) {
+        let bound = self.type_scope.len();
+
+        self.type_scope.extend(binding.scope.iter().cloned());
+        self.effect = binding.effect.clone();
+
+        match &arena[binding.node] {
+            AST::FunctionDeclaration(function) => {
+                self.check_function(arena, function, &binding.type_)
+            }
+            AST::HandlerDeclaration(declaration) => {
+                let type_ = self.infer_handler(arena, binding.node, declaration);
+
+                self.expect(&binding.type_, &type_, declaration.position);
+            }
+            _ => {}
+        }
+
+        self.effect = None;
+        self.type_scope.truncate(bound);
+    }
+
+    // Binds a function's type parameters in the current type scope, and its
+    // parameters.
+    fn signature(&mut self, node: NodeId, function: &FunctionDeclaration<'a>) -> Type<'a> {
+        self.bind_type_parameters(&function.type_parameters);
+
+        let arguments = function
+            .parameters
+            .iter()
+            .map(|parameter| self.bind_parameter(Site::Node(node), parameter, None))
+            .collect();
+        let returned = match &function.return_type {
+            Some(annotation) => self.annotation(&annotation.annotated),
+            None => self.fresh(),
+        };
+
+        Type::Function(vec![], arguments, vec![], Box::new(returned))
+    }
+
+    fn check_function(
+        &mut self,
+        arena: &Arena<'a>,
+        function: &FunctionDeclaration<'a>,
+        type_: &Type<'a>,
+    ) {
+        if let Type::Function(_, _, _, returned) = type_ {
+            self.check_returns(arena, &function.body, returned, function.position);
+        }
+    }
+
+    // Checks a body whose value, like any `return` in it, is returned.
+    fn check_returns(
+        &mut self,
+        arena: &Arena<'a>,
+        body: &[NodeId],
+        returned: &Type<'a>,
+        position: Position,
+    ) {
+        self.returns.push(returned.clone());
+
+        let found = self.infer_body(arena, body);
+        let position = body
+            .last()
+            .map(|&last| arena[last].position())
+            .unwrap_or(position);
+
+        self.expect(returned, &found, position);
+        self.returns.pop();
+    }
+
+    // A body whose value isn't used, like an operation clause.
+    fn check_clause(&mut self, arena: &Arena<'a>, body: &[NodeId]) {
+        let returned = self.fresh();
+
+        self.returns.push(returned);
+        self.check_statements(arena, body);
+        self.returns.pop();
+    }
+
+    // Handlers
+
+    fn infer_handler(
+        &mut self,
+        arena: &Arena<'a>,
+        node: NodeId,
+        declaration: &HandlerDeclaration<'a>,
+    ) -> Type<'a> {
+        let (input, output) = match &declaration.return_clause {
+            Some(clause) => {
+                let input = self.bind_parameter(Site::Return(node), &clause.parameter, None);
+                let output = self.fresh();
+
+                self.check_returns(arena, &clause.body, &output, clause.position);
+
+                (input, output)
+            }
+            None => {
+                let variable = self.fresh();
+
+                (variable.clone(), variable)
+            }
+        };
+        let resume = |returned: Type<'a>| {
+            Type::Function(vec![], vec![returned], vec![], Box::new(output.clone()))
+        };
+
+        // Without clauses, the body handles the effect's only operation, and
+        // the parameters are its arguments.
+        let parameters = if declaration.operations.is_empty() {
+            let (arguments, returned) = self.operation(None, declaration.parameters.len());
+
+            for (parameter, argument) in declaration.parameters.iter().zip(arguments) {
+                self.bind_parameter(Site::Node(node), parameter, Some(argument));
+            }
+
+            if let Some(id) = self.resolution.name(Site::Node(node), "resume") {
+                self.declarations.insert(id, Scheme::mono(resume(returned)));
+            }
+
+            self.check_clause(arena, &declaration.body);
+
+            vec![]
+        } else {
+            let parameters = declaration
+                .parameters
+                .iter()
+                .map(|parameter| self.bind_parameter(Site::Node(node), parameter, None))
+                .collect();
+
+            self.check_statements(arena, &declaration.body);
+
+            for (index, clause) in declaration.operations.iter().enumerate() {
+                let site = Site::Clause(node, index);
+                let (arguments, returned) =
+                    self.operation(Some(clause.name), clause.parameters.len());
+
+                for (parameter, argument) in clause.parameters.iter().zip(arguments) {
+                    self.bind_parameter(site, parameter, Some(argument));
+                }
+
+                if let Some(id) = self.resolution.name(site, "resume") {
+                    self.declarations.insert(id, Scheme::mono(resume(returned)));
+                }
+
+                self.check_clause(arena, &clause.body);
+            }
+
+            parameters
+        };
+        let handler = handler(input, output);
+
+        match declaration.name {
+            Some(_) => Type::Function(vec![], parameters, vec![], Box::new(handler)),
+            None => handler,
+        }
+    }
+
+    // The arguments and result of an operation of the effect being
+    // handled, with its parameters as the impl gives them. `None` is the
+    // only operation of an effect.
+    fn operation(&mut self, name: Option<&str>, arity: usize) -> (Vec<Type<'a>>, Type<'a>) {
+        let signature = self.effect.as_ref().and_then(|(effect, arguments)| {
+            let signature = self.effects.get(effect)?;
+            let (_, scheme) = signature
+                .operations
+                .iter()
+                .find(|(operation, _)| *operation == name)
+                .or(match (name, signature.operations.as_slice()) {
+                    (None, [only]) => Some(only),
+                    _ => None,
+                })?;
+            let given = signature
+                .parameters
+                .iter()
+                .copied()
+                .zip(arguments.iter().cloned())
+                .collect();
+
+            Some((scheme.clone(), given))
+        });
+
+        match signature.map(|(scheme, given)| self.instantiate(&scheme, given)) {
+            Some(Type::Function(_, arguments, _, returned)) => (arguments, *returned),
+            _ => ((0..arity).map(|_| self.fresh()).collect(), self.fresh()),
+        }
+    }
+
+    // Expressions
+
+    fn infer(&mut self, arena: &Arena<'a>, id: NodeId) -> Type<'a> {
+        let type_ = self.infer_node(arena, id);
+
+        self.typing.types.insert(id, type_.clone());
+        type_
+    }
+
+    // The type of a body is that of its last statement.
+    fn infer_body(&mut self, arena: &Arena<'a>, body: &[NodeId]) -> Type<'a> {
+        let Some((&last, statements)) = body.split_last() else {
+            return Type::Unit;
+        };
+
+        self.check_statements(arena, statements);
+        self.infer(arena, last)
+    }
+
+    fn check_statements(&mut self, arena: &Arena<'a>, body: &[NodeId]) {
+        for &id in body {
+            self.infer_statement(arena, id);
+        }
+    }
+
+    // A statement whose value is thrown away.
+    fn infer_statement(&mut self, arena: &Arena<'a>, id: NodeId) {
+        let type_ = match &arena[id] {
+            AST::Match(match_) => self.infer_match(arena, id, match_, false),
+            AST::If(if_) => self.infer_if(arena, if_, false),
+            _ => {
+                self.infer(arena, id);
+                return;
+            }
+        };
+
+        self.typing.types.insert(id, type_);
+    }
+
+    fn infer_node(&mut self, arena: &Arena<'a>, id: NodeId) -> Type<'a> {
+        match &arena[id] {
+            AST::BooleanLiteral(_) => Type::Boolean,
+            AST::IntegerLiteral(_) => Type::Int,
+            AST::NumberLiteral(_) => Type::Float,
+            AST::StringLiteral(_) => Type::String,
+            AST::RegexpLiteral(_) => Type::Any,
+            AST::TemplateLiteral(template) => {
+                for &value in &template.values {
+                    self.infer(arena, value);
+                }
+
+                Type::String
+            }
+            AST::Identifier(_) => match self.resolution.reference(id) {
+                Some(declaration) => self.type_of_declaration(declaration),
+                None => Type::Any,
+            },
+            AST::Record(record) => self.infer_record(arena, id, record),
+            AST::Array(array) => {
+                let element = self.fresh();
+
+                for member in &array.elements {
+                    let (value, expected) = match member {
+                        Element::Member(value) => (*value, element.clone()),
+                        Element::Spread(value) => (*value, Type::Array(Box::new(element.clone()))),
+                    };
+                    let found = self.infer(arena, value);
+
+                    self.expect(&expected, &found, arena[value].position());
+                }
+
+                Type::Array(Box::new(element))
+            }
+            AST::Tuple(tuple) => Type::Tuple(
+                tuple
+                    .elements
+                    .iter()
+                    .map(|element| match element {
+                        Element::Member(value) => {
+                            TupleData::Member(Box::new(self.infer(arena, *value)))
+                        }
+                        Element::Spread(value) => {
+                            TupleData::Spread(Box::new(self.infer(arena, *value)))
+                        }
+                    })
+                    .collect(),
+            ),
+            AST::Call(call) => self.infer_call(arena, id, call),
+            AST::MemberAccess(member) => self.infer_member(arena, id, member),
+            AST::Binary(binary) => self.infer_binary(arena, binary),
+            AST::Unary(unary) => {
+                let operand = self.infer(arena, unary.operand);
+
+                match unary.operator {
+                    UnaryOperator::Negate => operand,
+                    UnaryOperator::Not => {
+                        self.expect(&Type::Boolean, &operand, arena[unary.operand].position());
+
+                        Type::Boolean
+                    }
+                }
+            }
+            AST::Block(block) => self.infer_body(arena, &block.body),
+            AST::With(with) => {
+                let body = self.infer(arena, with.body);
+                let handlers = self.infer(arena, with.handlers);
+
+                if self.strip(&handlers) == Type::Any {
+                    return Type::Any;
+                }
+
+                let output = self.fresh();
+
+                self.expect(
+                    &handler(body, output.clone()),
+                    &handlers,
+                    arena[with.handlers].position(),
+                );
+
+                output
+            }
+            AST::Lambda(lambda) => {
+                let bound = self.type_scope.len();
+
+                self.bind_type_parameters(&lambda.type_parameters);
+
+                let arguments = lambda
+                    .parameters
+                    .iter()
+                    .map(|parameter| self.bind_parameter(Site::Node(id), parameter, None))
+                    .collect();
+                let returned = match &lambda.return_type {
+                    Some(annotation) => self.annotation(&annotation.annotated),
+                    None => self.fresh(),
+                };
+
+                self.check_returns(arena, &lambda.body, &returned, lambda.position);
+                self.type_scope.truncate(bound);
+
+                // Lambdas aren't generalized, so their type parameters are
+                // just unknown outside them.
+                let type_ = Type::Function(vec![], arguments, vec![], Box::new(returned));
+                let mapping = self.flexible(&names(&lambda.type_parameters));
+
+                self.substitute(&type_, &mapping)
+            }
+            AST::LetBinding(binding) => {
+                let value = self.infer(arena, binding.value);
+
+                self.check_pattern(Site::Node(id), &binding.pattern, &value);
+
+                Type::Unit
+            }
+            AST::Assignment(assignment) => {
+                let value = self.infer(arena, assignment.value);
+
+                if let Some(declaration) = self.resolution.reference(id) {
+                    let expected = self.type_of_declaration(declaration);
+
+                    self.expect(&expected, &value, arena[assignment.value].position());
+                }
+
+                value
+            }
+            AST::If(if_) => self.infer_if(arena, if_, true),
+            AST::While(while_) => {
+                let condition = self.infer(arena, while_.condition);
+
+                match &while_.pattern {
+                    Some(pattern) => self.check_pattern(Site::Node(id), pattern, &condition),
+                    None => self.expect(
+                        &Type::Boolean,
+                        &condition,
+                        arena[while_.condition].position(),
+                    ),
+                }
+
+                self.check_statements(arena, &while_.body);
+
+                Type::Unit
+            }
+            AST::ForOf(for_of) => {
+                let iterable = self.infer(arena, for_of.iterable);
+                let element = self.fresh();
+
+                self.expect(
+                    &Type::Array(Box::new(element.clone())),
+                    &iterable,
+                    arena[for_of.iterable].position(),
+                );
+                self.check_pattern(Site::Node(id), &for_of.pattern, &element);
+                self.check_statements(arena, &for_of.body);
+
+                Type::Unit
+            }
+            AST::Return(return_) => {
+                let (value, position) = match return_.value {
+                    Some(value) => (self.infer(arena, value), arena[value].position()),
+                    None => (Type::Unit, return_.position),
+                };
+
+                if let Some(returned) = self.returns.last().cloned() {
+                    self.expect(&returned, &value, position);
+                }
+
+                Type::Never
+            }
+            AST::Yield(yield_) => {
+                if let Some(value) = yield_.value {
+                    self.infer(arena, value);
+                }
+
+                Type::Unit
+            }
+            AST::Match(match_) => self.infer_match(arena, id, match_, true),
+            AST::FunctionDeclaration(function) => {
+                let bound = self.type_scope.len();
+                let type_ = self.signature(id, function);
+
+                if let Some(declaration) = self.resolution.definition(id) {
+                    self.bind(declaration, type_.clone(), function.position);
+                }
+
+                self.check_function(arena, function, &type_);
+                self.type_scope.truncate(bound);
+
+                // Generalized over its own type parameters only, where a
+                // top-level function is over all of its free variables.
+                let parameters = names(&function.type_parameters);
+                let mapping = self.flexible(&parameters);
+                let type_ = self.substitute(&type_, &mapping);
+
+                if let (Some(declaration), false) =
+                    (self.resolution.definition(id), parameters.is_empty())
+                {
+                    let scheme = Scheme {
+                        variables: variables(&mapping),
+                        type_: type_.clone(),
+                    };
+
+                    self.declarations.insert(declaration, scheme);
+                }
+
+                type_
+            }
+            AST::HandlerDeclaration(declaration) => {
+                let type_ = self.infer_handler(arena, id, declaration);
+
+                if let Some(id) = self.resolution.definition(id) {
+                    self.bind(id, type_.clone(), declaration.position);
+                }
+
+                type_
+            }
+            AST::MacroInvocation(_) => Type::Any,
+            AST::ImportDeclaration(_)
+            | AST::ExportDeclaration(_)
+            | AST::TypeDeclaration(_)
+            | AST::TypeAlias(_)
+            | AST::StructDeclaration(_)
+            | AST::EffectDeclaration(_)
+            | AST::ImplBlock(_)
+            | AST::TypeclassDeclaration(_)
+            | AST::InstanceDeclaration(_)
+            | AST::MacroDeclaration(_) => Type::Unit,
+        }
+    }
+
+    fn infer_record(&mut self, arena: &Arena<'a>, id: NodeId, record: &Record<'a>) -> Type<'a> {
+        let mut members: Vec<StructData<'a>> = vec![];
+        let add = |members: &mut Vec<StructData<'a>>, name: &'a str, type_: Type<'a>| {
+            members
+                .retain(|member| !matches!(member, StructData::Member(other, _) if *other == name));
+            members.push(StructData::Member(name, Box::new(type_)));
+        };
+
+        for member in &record.members {
+            match member {
+                RecordMember::Field(name, value) => {
+                    let type_ = self.infer(arena, *value);
+
+                    add(&mut members, name.value, type_);
+                }
+                RecordMember::Shorthand(name) => {
+                    let type_ = match self.resolution.name(Site::Node(id), name.value) {
+                        Some(declaration) => self.type_of_declaration(declaration),
+                        None => Type::Any,
+                    };
+
+                    add(&mut members, name.value, type_);
+                }
+                RecordMember::Spread(value) => {
+                    let type_ = self.infer(arena, *value);
+
+                    match self.strip(&type_) {
+                        Type::Struct(spread) => {
+                            for member in spread {
+                                match member {
+                                    StructData::Member(name, type_) => {
+                                        add(&mut members, name, *type_)
+                                    }
+                                    spread => members.push(spread),
+                                }
+                            }
+                        }
+                        _ => members.push(StructData::Spread(Box::new(type_))),
+                    }
+                }
+            }
+        }
+
+        Type::Struct(members)
+    }
+
+    fn infer_call(&mut self, arena: &Arena<'a>, id: NodeId, call: &Call) -> Type<'a> {
+        let mut arguments = vec![];
+        let callee = match (&arena[call.callee], self.dispatches.get(&id).cloned()) {
+            // `list.map(f)` is `map(list, f)`, unless `list` turns out to
+            // have a `map` field.
+            (AST::MemberAccess(member), Some(Dispatch::Uniform { function })) => {
+                let receiver = self.infer(arena, member.object);
+                let field = match self.strip(&receiver) {
+                    Type::Struct(members) => field(&members, function),
+                    _ => None,
+                };
+                let type_ = match (field, self.functions.get(function).copied()) {
+                    (Some(type_), _) => type_,
+                    (None, Some(function)) => {
+                        arguments.push((receiver, arena[member.object].position()));
+                        self.type_of_declaration(function)
+                    }
+                    (None, None) => Type::Any,
+                };
+
+                self.typing.types.insert(call.callee, type_.clone());
+
+                type_
+            }
+            // Calling an effect performs its only operation.
+            (AST::Identifier(_), _) if self.effect_name(call.callee).is_some() => {
+                let effect = self.effect_name(call.callee).unwrap_or_default();
+                let scheme = self.effects.get(effect).and_then(|signature| {
+                    match signature.operations.as_slice() {
+                        [(None, scheme)] => Some(scheme.clone()),
+                        _ => None,
+                    }
+                });
+                let type_ = match scheme {
+                    Some(scheme) => self.instantiate(&scheme, HashMap::new()),
+                    None => Type::Any,
+                };
+
+                self.typing.types.insert(call.callee, type_.clone());
+
+                type_
+            }
+            _ => self.infer(arena, call.callee),
+        };
+
+        for &argument in &call.arguments {
+            let type_ = self.infer(arena, argument);
+
+            arguments.push((type_, arena[argument].position()));
+        }
+
+        self.apply(&callee, arguments, call.position)
+    }
+
+    fn effect_name(&self, id: NodeId) -> Option<&'a str> {
+        let declaration = self.resolution.declaration(self.resolution.reference(id)?);
+
+        (declaration.kind() == DeclarationKind::Effect).then_some(declaration.name())
+    }
+
+    fn apply(
+        &mut self,
+        callee: &Type<'a>,
+        arguments: Vec<(Type<'a>, Position)>,
+        position: Position,
+    ) -> Type<'a> {
+        match self.strip(callee) {
+            Type::Function(_, parameters, _, returned) => {
+                if parameters.len() != arguments.len() {
+                    let found = Type::Function(
+                        vec![],
+                        arguments.iter().map(|(type_, _)| type_.clone()).collect(),
+                        vec![],
+                        returned.clone(),
+                    );
+
+                    self.arity_error(parameters.len(), arguments.len(), callee, &found, position);
+                }
+
+                for (parameter, (argument, position)) in parameters.iter().zip(&arguments) {
+                    self.expect(parameter, argument, *position);
+                }
+
+                *returned
+            }
+            Type::Any | Type::Never => Type::Any,
+            _ => {
+                let returned = self.fresh();
+                let found = Type::Function(
+                    vec![],
+                    arguments.into_iter().map(|(type_, _)| type_).collect(),
+                    vec![],
+                    Box::new(returned.clone()),
+                );
+
+                self.expect(callee, &found, position);
+
+                returned
+            }
+        }
+    }
+
+    fn infer_member(
+        &mut self,
+        arena: &Arena<'a>,
+        id: NodeId,
+        member: &MemberAccess<'a>,
+    ) -> Type<'a> {
+        // Constructors, operations and impl members.
+        if let Some(declaration) = self.resolution.reference(id) {
+            return self.member_of(member.object, declaration);
+        }
+
+        let object = self.infer(arena, member.object);
+
+        let name = member.property.value;
+        let position = arena[member.object].position();
+        let function = |arguments: Vec<Type<'a>>, returned: Type<'a>| {
+            Type::Function(vec![], arguments, vec![], Box::new(returned))
+        };
+
+        if let Type::Struct(members) = self.strip(&object) {
+            if let Some(type_) = field(&members, name) {
+                return type_;
+            }
+        }
+
+        match BuiltinMethod::from_name(name) {
+            Some(BuiltinMethod::Fork) => {
+                let returned = self.fresh();
+
+                self.expect(&function(vec![], returned.clone()), &object, position);
+
+                function(vec![], named("Fiber", vec![returned]))
+            }
+            Some(BuiltinMethod::Join) => {
+                let returned = self.fresh();
+
+                self.expect(&named("Fiber", vec![returned.clone()]), &object, position);
+
+                function(vec![], returned)
+            }
+            // Open records, and objects whose type isn't known yet, may
+            // still have it.
+            None => match self.strip(&object) {
+                Type::Variable(_) | Type::Any | Type::Never => Type::Any,
+                Type::Struct(members)
+                    if members
+                        .iter()
+                        .any(|member| matches!(member, StructData::Spread(_))) =>
+                {
+                    Type::Any
+                }
+                _ => {
+                    let object = self.zonk(&object, &HashMap::new());
+                    let [shown] = named_variables([&object]);
+
+                    self.error(
+                        format!("`{}` has no field `{}`", shown, name),
+                        object,
+                        Type::Any,
+                        member.property.position,
+                    );
+
+                    Type::Any
+                }
+            },
+        }
+    }
+
+    // `IntQueue.dequeue` is the `dequeue` of a `Queue<Int>`.
+    fn member_of(&mut self, owner: NodeId, declaration: DeclarationId) -> Type<'a> {
+        let alias = self
+            .resolution
+            .reference(owner)
+            .map(|owner| self.resolution.declaration(owner))
+            .filter(|owner| owner.kind() == DeclarationKind::Alias)
+            .map(|owner| owner.name());
+        let scheme = self.declarations.get(&declaration).cloned();
+        let (Some(alias), Some(scheme)) = (alias, scheme) else {
+            return self.type_of_declaration(declaration);
+        };
+        let aliased = self.named_type(alias, &[]);
+        let given = match self.strip(&aliased) {
+            Type::Reference(effect, arguments) => match self.effects.get(effect) {
+                Some(signature) => signature
+                    .parameters
+                    .iter()
+                    .copied()
+                    .zip(arguments)
+                    .collect(),
+                None => HashMap::new(),
+            },
+            _ => HashMap::new(),
+        };
+
+        self.instantiate(&scheme, given)
+    }
+
+    fn infer_binary(&mut self, arena: &Arena<'a>, binary: &Binary) -> Type<'a> {
+        let left = self.infer(arena, binary.left);
+        let right = self.infer(arena, binary.right);
+        let left_position = arena[binary.left].position();
+        let right_position = arena[binary.right].position();
+
+        match binary.operator {
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::LessThan
+            | BinaryOperator::LessThanEqual
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::GreaterThanEqual => {
+                self.expect(&left, &right, right_position);
+
+                Type::Boolean
+            }
+            BinaryOperator::And | BinaryOperator::Or => {
+                self.expect(&Type::Boolean, &left, left_position);
+                self.expect(&Type::Boolean, &right, right_position);
+
+                Type::Boolean
+            }
+            // Handlers compose, the left one handling the computation first.
+            BinaryOperator::Concat if self.is_handler(&left) || self.is_handler(&right) => {
+                let input = self.fresh();
+                let middle = self.fresh();
+                let output = self.fresh();
+
+                self.expect(
+                    &handler(input.clone(), middle.clone()),
+                    &left,
+                    left_position,
+                );
+                self.expect(&handler(middle, output.clone()), &right, right_position);
+
+                handler(input, output)
+            }
+            _ => {
+                self.expect(&left, &right, right_position);
+
+                left
+            }
+        }
+    }
+
+    fn is_handler(&self, type_: &Type<'a>) -> bool {
+        matches!(self.strip(type_), Type::Reference("Handler", _))
+    }
+
+    fn infer_if(&mut self, arena: &Arena<'a>, if_: &If, used: bool) -> Type<'a> {
+        let condition = self.infer(arena, if_.condition);
+
+        self.expect(&Type::Boolean, &condition, arena[if_.condition].position());
+
+        match (if_.alternative, used) {
+            (Some(alternative), true) => {
+                let result = self.fresh();
+                let consequent = self.infer_body(arena, &if_.consequent);
+                let position = match if_.consequent.last() {
+                    Some(&last) => arena[last].position(),
+                    None => if_.position,
+                };
+
+                self.expect(&result, &consequent, position);
+
+                let alternative_type = self.infer(arena, alternative);
+
+                self.expect(&result, &alternative_type, arena[alternative].position());
+
+                result
+            }
+            (alternative, _) => {
+                self.check_statements(arena, &if_.consequent);
+
+                if let Some(alternative) = alternative {
+                    self.infer_statement(arena, alternative);
+                }
+
+                Type::Unit
+            }
+        }
+    }
+
+    fn infer_match(
+        &mut self,
+        arena: &Arena<'a>,
+        id: NodeId,
+        match_: &Match<'a>,
+        used: bool,
+    ) -> Type<'a> {
+        let value = self.infer(arena, match_.value);
+        let result = self.fresh();
+
+        for (index, case) in match_.cases.iter().enumerate() {
+            match &case.test {
+                MatchTest::Pattern(pattern) => {
+                    self.check_pattern(Site::Case(id, index), pattern, &value)
+                }
+                MatchTest::Predicate(predicate) => {
+                    let found = self.infer(arena, *predicate);
+
+                    self.expect(&Type::Boolean, &found, arena[*predicate].position());
+                }
+            }
+
+            if used {
+                let body = self.infer(arena, case.body);
+
+                self.expect(&result, &body, arena[case.body].position());
+            } else {
+                self.infer_statement(arena, case.body);
+            }
+        }
+
+        match used {
+            true => result,
+            false => Type::Unit,
+        }
+    }
+
+    // Patterns
+
+    // Checks a pattern against the type of the value it matches, binding the
+    // names it declares.
+    fn check_pattern(&mut self, site: Site, pattern: &Pattern<'a>, expected: &Type<'a>) {
+        let position = pattern.position();
+
+        match pattern {
+            Pattern::Wildcard(_) => {
+                // `_` is the matched value in the arm's body.
+                if let Some(id) = self.resolution.name(site, "_") {
+                    self.bind(id, expected.clone(), position);
+                }
+            }
+            Pattern::Identifier(identifier) => match self.resolution.name(site, identifier.value) {
+                Some(id)
+                    if self.resolution.declaration(id).kind() == DeclarationKind::Constructor =>
+                {
+                    self.check_constructor(site, Some(id), &[], expected, position)
+                }
+                Some(id) => self.bind(id, expected.clone(), position),
+                None => self.check_constructor(site, None, &[], expected, position),
+            },
+            Pattern::Literal(literal) => {
+                let found = match literal.as_ref() {
+                    AST::BooleanLiteral(_) => Type::Boolean,
+                    AST::IntegerLiteral(_) => Type::Int,
+                    AST::NumberLiteral(_) => Type::Float,
+                    AST::StringLiteral(_) | AST::TemplateLiteral(_) => Type::String,
+                    _ => Type::Any,
+                };
+
+                self.expect(expected, &found, position);
+            }
+            Pattern::Constructor(constructor) => {
+                let id = self.resolution.name(site, constructor.name);
+
+                self.check_constructor(site, id, &constructor.arguments, expected, position);
+            }
+            Pattern::Record(record) => {
+                let members = match self.strip(expected) {
+                    Type::Struct(members) => Some(members),
+                    _ => None,
+                };
+                let mut fields = vec![];
+
+                for field in &record.fields {
+                    let name = field.name.value;
+                    let type_ = match &members {
+                        Some(members) => members.iter().find_map(|member| match member {
+                            StructData::Member(other, type_) if *other == name => {
+                                Some(*type_.clone())
+                            }
+                            _ => None,
+                        }),
+                        None => Some(self.fresh()),
+                    };
+                    let type_ = type_.unwrap_or_else(|| {
+                        let found = self.zonk(expected, &HashMap::new());
+                        let [shown] = named_variables([&found]);
+
+                        self.error(
+                            format!("`{}` has no field `{}`", shown, name),
+                            found,
+                            Type::Any,
+                            field.name.position,
+                        );
+
+                        Type::Any
+                    });
+
+                    self.check_pattern(site, &field.pattern, &type_);
+                    fields.push(StructData::Member(name, Box::new(type_)));
+                }
+
+                match (&record.rest, members) {
+                    (None, None) => self.expect(expected, &Type::Struct(fields), position),
+                    (Some(rest), Some(members)) => {
+                        let rest_members = members
+                            .into_iter()
+                            .filter(|member| match member {
+                                StructData::Member(name, _) => {
+                                    !record.fields.iter().any(|field| field.name.value == *name)
+                                }
+                                StructData::Spread(_) => true,
+                            })
+                            .collect();
+
+                        self.check_pattern(site, rest, &Type::Struct(rest_members));
+                    }
+                    (Some(rest), None) => self.check_pattern(site, rest, &Type::Any),
+                    (None, Some(_)) => {}
+                }
+            }
+            Pattern::Array(array) => {
+                let element = self.fresh();
+                let type_ = Type::Array(Box::new(element.clone()));
+
+                self.expect(expected, &type_, position);
+
+                for pattern in &array.elements {
+                    self.check_pattern(site, pattern, &element);
+                }
+
+                if let Some(rest) = &array.rest {
+                    self.check_pattern(site, rest, &type_);
+                }
+            }
+            Pattern::Tuple(tuple) => {
+                let elements: Vec<_> = tuple.elements.iter().map(|_| self.fresh()).collect();
+                let type_ = Type::Tuple(
+                    elements
+                        .iter()
+                        .map(|element| TupleData::Member(Box::new(element.clone())))
+                        .collect(),
+                );
+
+                self.expect(expected, &type_, position);
+
+                for (pattern, element) in tuple.elements.iter().zip(&elements) {
+                    self.check_pattern(site, pattern, element);
+                }
+            }
+        }
+    }
+
+    fn check_constructor(
+        &mut self,
+        site: Site,
+        constructor: Option<DeclarationId>,
+        arguments: &[Pattern<'a>],
+        expected: &Type<'a>,
+        position: Position,
+    ) {
+        let Some(constructor) = constructor else {
+            for argument in arguments {
+                self.check_pattern(site, argument, &Type::Any);
+            }
+
+            return;
+        };
+        let type_ = self.type_of_declaration(constructor);
+        let (parameters, result) = match self.resolve(&type_) {
+            Type::Function(_, parameters, _, returned) => (parameters, *returned),
+            type_ => (vec![], type_),
+        };
+
+        self.expect(expected, &result, position);
+
+        if parameters.len() != arguments.len() {
+            let found = Type::Function(
+                vec![],
+                arguments.iter().map(|_| Type::Any).collect(),
+                vec![],
+                Box::new(result),
+            );
+
+            self.arity_error(parameters.len(), arguments.len(), &type_, &found, position);
+        }
+
+        for (index, argument) in arguments.iter().enumerate() {
+            let parameter = parameters.get(index).cloned().unwrap_or(Type::Any);
+
+            self.check_pattern(site, argument, &parameter);
+        }
+    }
+}
+
+// The declarations one of a module's functions refers to.
+struct References<'r, 'a> {
+    resolution: &'r Resolution<'a>,
+    dispatches: &'r HashMap<NodeId, Dispatch<'a>>,
+    functions: &'r HashMap<&'a str, DeclarationId>,
+    found: Vec<DeclarationId>,
+}
+
+impl<'r, 'a> Visitor<'a> for References<'r, 'a> {
+    fn visit_node(&mut self, arena: &Arena<'a>, id: NodeId) {
+        self.found.extend(self.resolution.reference(id));
+
+        if let Some(Dispatch::Uniform { function }) = self.dispatches.get(&id) {
+            self.found.extend(self.functions.get(function));
+        }
+
+        walk_node(self, arena, id)
+    }
+}
+
+// The strongly connected components of a graph, each one after the ones it
+// has edges to (Tarjan's algorithm).
+fn components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct Search<'e> {
+        edges: &'e [Vec<usize>],
+        indices: Vec<Option<usize>>,
+        lowlinks: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl Search<'_> {
+        fn visit(&mut self, node: usize) {
+            let index = self.stack.len() + self.components.iter().map(Vec::len).sum::<usize>();
+
+            self.indices[node] = Some(index);
+            self.lowlinks[node] = index;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+
+            for &next in &self.edges[node] {
+                match self.indices[next] {
+                    None => {
+                        self.visit(next);
+                        self.lowlinks[node] = self.lowlinks[node].min(self.lowlinks[next]);
+                    }
+                    Some(index) if self.on_stack[next] => {
+                        self.lowlinks[node] = self.lowlinks[node].min(index);
+                    }
+                    _ => {}
+                }
+            }
+
+            if Some(self.lowlinks[node]) == self.indices[node] {
+                let mut component = vec![];
+
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+
+                    if member == node {
+                        break;
+                    }
+                }
+
+                component.reverse();
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut search = Search {
+        edges,
+        indices: vec![None; edges.len()],
+        lowlinks: vec![0; edges.len()],
+        stack: vec![],
+        on_stack: vec![false; edges.len()],
+        components: vec![],
+    };
+
+    for node in 0..edges.len() {
+        if search.indices[node].is_none() {
+            search.visit(node);
+        }
+    }
+
+    search.components
+}
+
+// Rebuilds a type, replacing the types `replace` has a replacement for.
+fn map_type<'a>(
+    type_: &Type<'a>,
+    replace: &mut dyn FnMut(&Type<'a>) -> Option<Type<'a>>,
+) -> Type<'a> {
+    if let Some(replaced) = replace(type_) {
+        return replaced;
+    }
+
+    let mut map = |type_: &Type<'a>| map_type(type_, replace);
+
+    match type_ {
+        Type::Option(inner) => Type::Option(Box::new(map(inner))),
+        Type::Array(inner) => Type::Array(Box::new(map(inner))),
+        Type::Alias(name, inner) => Type::Alias(name, Box::new(map(inner))),
+        Type::Either(left, right) => {
+            let left = map(left);
+
+            Type::Either(Box::new(left), Box::new(map(right)))
+        }
+        Type::Template(types) => Type::Template(types.iter().map(map).collect()),
+        Type::Reference(name, types) => Type::Reference(name, types.iter().map(map).collect()),
+        Type::Union(types) => Type::Union(types.iter().map(map).collect()),
+        Type::Intersection(types) => Type::Intersection(types.iter().map(map).collect()),
+        Type::Tuple(members) => Type::Tuple(
+            members
+                .iter()
+                .map(|member| match member {
+                    TupleData::Member(type_) => TupleData::Member(Box::new(map(type_))),
+                    TupleData::Spread(type_) => TupleData::Spread(Box::new(map(type_))),
+                })
+                .collect(),
+        ),
+        Type::Struct(members) => Type::Struct(
+            members
+                .iter()
+                .map(|member| match member {
+                    StructData::Member(name, type_) => {
+                        StructData::Member(name, Box::new(map(type_)))
+                    }
+                    StructData::Spread(type_) => StructData::Spread(Box::new(map(type_))),
+                })
+                .collect(),
+        ),
+        Type::Function(type_parameters, arguments, effects, returned) => {
+            let type_parameters = type_parameters.iter().map(&mut map).collect();
+            let arguments = arguments.iter().map(&mut map).collect();
+            let effects = effects.iter().map(&mut map).collect();
+
+            Type::Function(type_parameters, arguments, effects, Box::new(map(returned)))
+        }
+        _ => type_.clone(),
+    }
+}
+
+// Rebuilds a type, replacing the variables `replace` has a type for.
+fn map_variables<'a>(
+    type_: &Type<'a>,
+    replace: &mut dyn FnMut(usize) -> Option<Type<'a>>,
+) -> Type<'a> {
+    map_type(type_, &mut |type_| match type_ {
+        Type::Variable(variable) => replace(*variable),
+        _ => None,
+    })
+}
+
+// Types as error messages show them: the variables still unknown are named
+// like type parameters, each the same way in all of them, so that
+// `(?3, ?4) -> ?3` reads `(A, B) -> A`. Names the types use are skipped.
+fn named_variables<'a, const N: usize>(types: [&Type<'a>; N]) -> [Type<'a>; N] {
+    let mut used = HashSet::new();
+
+    for type_ in types {
+        map_type(type_, &mut |type_| {
+            if let Type::Reference(name, _) | Type::Alias(name, _) | Type::Brand(name) = type_ {
+                used.insert(*name);
+            }
+
+            None
+        });
+    }
+
+    let mut names = VARIABLE_NAMES
+        .iter()
+        .copied()
+        .filter(|name| !used.contains(name));
+    let mut named: HashMap<usize, Type<'a>> = HashMap::new();
+
+    types.map(|type_| {
+        map_variables(type_, &mut |variable| match named.entry(variable) {
+            Entry::Occupied(entry) => Some(entry.get().clone()),
+            Entry::Vacant(entry) => {
+                let name = Type::Reference(names.next()?, vec![]);
+
+                Some(entry.insert(name).clone())
+            }
+        })
+    })
+}
+
+const VARIABLE_NAMES: [&str; 26] = [
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S",
+    "T", "U", "V", "W", "X", "Y", "Z",
+];
+
+// The type of a struct's field.
+fn field<'a>(members: &[StructData<'a>], name: &str) -> Option<Type<'a>> {
+    members.iter().find_map(|member| match member {
+        StructData::Member(field, type_) if *field == name => Some(*type_.clone()),
+        _ => None,
+    })
+}
+
+// Option and Either are built in, whether or not a module declares them.
+fn named<'a>(name: &'a str, mut arguments: Vec<Type<'a>>) -> Type<'a> {
+    match (name, arguments.len()) {
+        ("Option", 1) => Type::Option(Box::new(arguments.remove(0))),
+        ("Either", 2) => {
+            let right = arguments.remove(1);
+
+            Type::Either(Box::new(arguments.remove(0)), Box::new(right))
+        }
+        _ => Type::Reference(name, arguments),
+    }
+}
+
+fn handler<'a>(input: Type<'a>, output: Type<'a>) -> Type<'a> {
+    Type::Reference("Handler", vec![input, output])
+}
+
+fn variables(mapping: &[(&str, Type<'_>)]) -> Vec<usize> {
+    mapping
+        .iter()
+        .filter_map(|(_, type_)| match type_ {
+            Type::Variable(variable) => Some(*variable),
+            _ => None,
+        })
+        .collect()
+}
+
+fn names<'a>(parameters: &[TypeParameter<'a>]) -> Vec<&'a str> {
+    parameters.iter().map(|parameter| parameter.name).collect()
+}
+
+// The declaration a top-level statement is, looking through `export`.
+fn declared_node(arena: &Arena<'_>, id: NodeId) -> Option<NodeId> {
+    match &arena[id] {
+        AST::ExportDeclaration(ExportDeclaration {
+            kind: ExportKind::Declaration(declaration),
+            ..
+        }) => Some(*declaration),
+        AST::ExportDeclaration(_) => None,
+        _ => Some(id),
+    }
+}
+
+// Declarations are checked up front rather than in statement order.
+fn is_declaration(arena: &Arena<'_>, id: NodeId) -> bool {
+    match declared_node(arena, id).map(|id| &arena[id]) {
+        Some(
+            AST::FunctionDeclaration(_)
+            | AST::HandlerDeclaration(_)
+            | AST::ImplBlock(_)
+            | AST::TypeDeclaration(_)
+            | AST::TypeAlias(_)
+            | AST::StructDeclaration(_)
+            | AST::EffectDeclaration(_)
+            | AST::TypeclassDeclaration(_)
+            | AST::InstanceDeclaration(_)
+            | AST::MacroDeclaration(_),
+        ) => true,
+        Some(_) => false,
+        None => true,
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parsing::{arena::*, ast::*, resolve::*, visit::*};
+
+// What a member call `receiver.method(arguments)` calls. Member calls are
+// parsed like any other call of a member access, and dispatched by what the
+// receiver resolves to, in this order:
+//
+// 1. An operation of the effect the receiver names, directly or through an
+//    alias: `Queue.dequeue()`, `IntQueue.dequeue()`.
+// 2. A function or handler from an `impl for` block of that effect:
+//    `Queue.unbounded()`, `Fail.catch(() -> 0)`.
+// 3. A constructor of the type the receiver names: `Either.Left(error)`.
+// 4. A member of the module a default or namespace import binds the
+//    receiver to: `Console.log(message)`.
+// 5. A function stored in a field the receiver is known to have:
+//    `{ fork: worker }.fork()`.
+// 6. A method every function or fiber has: `worker.fork()`, `fiber.join()`.
+// 7. A function or typeclass method of the module, called with the receiver
+//    as its first argument: `list.map(f)` is `map(list, f)`.
+// 8. Otherwise, a function stored in a field of the receiver.
+//
+// Receivers naming an effect or a type only dispatch to their members, so
+// `Queue.missing()` is Unresolved rather than falling through to 5-8. Any
+// other receiver is a value, even one spelled like an effect, such as a
+// parameter named `Queue`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Dispatch<'a> {
+    Operation {
+        effect: &'a str,
+        operation: &'a str,
+    },
+    ImplMember {
+        effect: &'a str,
+        member: NodeId, // The function or handler declaration
+    },
+    Constructor {
+        type_name: &'a str,
+        constructor: &'a str,
+    },
+    Imported {
+        specifier: &'a str,
+        name: &'a str,
+    },
+    Builtin(BuiltinMethod),
+    Uniform {
+        function: &'a str,
+    },
+    Field {
+        name: &'a str,
+    },
+    Unresolved,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BuiltinMethod {
+    Fork, // Runs a function in a new fiber
+    Join, // Waits for a fiber to finish
+}
+
+impl BuiltinMethod {
+    pub fn from_name(name: &str) -> Option<BuiltinMethod> {
+        match name {
+            "fork" => Some(BuiltinMethod::Fork),
+            "join" => Some(BuiltinMethod::Join),
+            _ => None,
+        }
+    }
+}
+
+// Dispatches every member call in a module, by the id of the call, from
+// what the Resolver linked the receiver and the member to. A receiver that
+// is a value only has a field when it is a record literal, or a variable
+// bound to one, that has it.
+#[derive(Debug)]
+pub struct MethodCalls<'r, 'a> {
+    resolution: &'r Resolution<'a>,
+    functions: HashSet<&'a str>, // Top-level functions and typeclass methods
+    pub dispatches: HashMap<NodeId, Dispatch<'a>>,
+}
+
+impl<'r, 'a> MethodCalls<'r, 'a> {
+    pub fn dispatch(file: &SourceFile<'a>) -> HashMap<NodeId, Dispatch<'a>> {
+        MethodCalls::dispatch_resolved(file, &Resolver::resolve(file))
+    }
+
+    // Like `dispatch`, with names already resolved.
+    pub fn dispatch_resolved(
+        file: &SourceFile<'a>,
+        resolution: &'r Resolution<'a>,
+    ) -> HashMap<NodeId, Dispatch<'a>> {
+        let mut calls = MethodCalls {
+            resolution,
+            functions: HashSet::new(),
+            dispatches: HashMap::new(),
+        };
+
+        for &id in &file.body {
+            calls.declare(&file.arena, id);
+        }
+
+        calls.visit_source_file(file);
+        calls.dispatches
+    }
+
+    fn declare(&mut self, arena: &Arena<'a>, id: NodeId) {
+        match &arena[id] {
+            AST::ExportDeclaration(export) => {
+                if let ExportKind::Declaration(declaration) = export.kind {
+                    self.declare(arena, declaration);
+                }
+            }
+            AST::FunctionDeclaration(function) => {
+                self.functions.insert(function.name);
+            }
+            AST::TypeclassDeclaration(typeclass) => {
+                self.functions
+                    .extend(typeclass.methods.iter().map(|method| method.name));
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch_call(
+        &self,
+        arena: &Arena<'a>,
+        callee: NodeId,
+        member: &MemberAccess<'a>,
+    ) -> Dispatch<'a> {
+        let resolution = self.resolution;
+        let method = member.property.value;
+
+        if let Some(owner) = resolution.reference(member.object) {
+            let declaration = resolution.declaration(resolution.follow_aliases(owner));
+
+            match declaration.kind() {
+                DeclarationKind::Effect | DeclarationKind::Type => {
+                    let Some(found) = resolution.reference(callee) else {
+                        return Dispatch::Unresolved;
+                    };
+
+                    return match (resolution.declaration(found).kind(), resolution.node(found)) {
+                        (DeclarationKind::Operation, _) => Dispatch::Operation {
+                            effect: declaration.name(),
+                            operation: method,
+                        },
+                        (DeclarationKind::Constructor, _) => Dispatch::Constructor {
+                            type_name: declaration.name(),
+                            constructor: method,
+                        },
+                        (_, Some(member)) => Dispatch::ImplMember {
+                            effect: declaration.name(),
+                            member,
+                        },
+                        _ => Dispatch::Unresolved,
+                    };
+                }
+                DeclarationKind::Import => {
+                    let name = declaration.name();
+                    let import = resolution.node(owner).map(|node| &arena[node]);
+
+                    if let Some(AST::ImportDeclaration(import)) = import {
+                        if import.default == Some(name) || import.namespace == Some(name) {
+                            return Dispatch::Imported {
+                                specifier: import.specifier,
+                                name: method,
+                            };
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if self.has_field(arena, member.object, method) {
+            Dispatch::Field { name: method }
+        } else if let Some(builtin) = BuiltinMethod::from_name(method) {
+            Dispatch::Builtin(builtin)
+        } else if self.functions.contains(method) {
+            Dispatch::Uniform { function: method }
+        } else {
+            Dispatch::Field { name: method }
+        }
+    }
+
+    fn has_field(&self, arena: &Arena<'a>, receiver: NodeId, name: &str) -> bool {
+        match &arena[receiver] {
+            AST::Record(record) => record.members.iter().any(|member| match member {
+                RecordMember::Field(field, _) | RecordMember::Shorthand(field) => {
+                    field.value == name
+                }
+                RecordMember::Spread(_) => false,
+            }),
+            AST::Identifier(_) => self
+                .resolution
+                .reference(receiver)
+                .and_then(|variable| self.resolution.value(variable))
+                .is_some_and(|value| self.has_field(arena, value, name)),
+            _ => false,
+        }
+    }
+}
+
+impl<'r, 'a> Visitor<'a> for MethodCalls<'r, 'a> {
+    fn visit_node(&mut self, arena: &Arena<'a>, id: NodeId) {
+        if let AST::Call(call) = &arena[id] {
+            if let AST::MemberAccess(member) = &arena[call.callee] {
+                let dispatch = self.dispatch_call(arena, call.callee, member);
+
+                self.dispatches.insert(id, dispatch);
+            }
+        }
+
+        walk_node(self, arena, id)
+    }
+}
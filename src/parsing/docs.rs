@@ -0,0 +1,272 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{lexing::Position, Spanned, Token};
+
+// The doc comment in front of a declaration, e.g.
+//
+//     /**
+//      * Computes the nth Fibonacci number.
+//      *
+//      * # Examples
+//      *
+//      * fib(10) == 55
+//      *
+//      * @param n which number to compute
+//      * @returns the nth Fibonacci number
+//      */
+//
+// Its markdown is split into a summary, the first paragraph, a description,
+// the paragraphs that follow, and the sections started by headings. Lines
+// starting with a tag like `@param` are collected separately.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Docs {
+    pub(crate) text: String, // The comment as written, for printing it back
+    pub(crate) summary: String,
+    pub(crate) description: String,
+    pub(crate) sections: Vec<DocSection>,
+    pub(crate) params: Vec<DocParam>,
+    pub(crate) returns: Option<String>,
+    pub(crate) position: Position,
+}
+
+// # Title, and the text up to the next heading or tag
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DocSection {
+    pub(crate) title: String,
+    pub(crate) body: String,
+}
+
+// @param name description
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DocParam {
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) position: Position, // Of the whole tag, for reporting stale ones
+}
+
+// A problem with a doc comment that doesn't stop the module from compiling.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DocWarning {
+    pub message: String,
+    pub position: Position,
+}
+
+// Where a line of text in the comment goes.
+enum Target {
+    Preamble,
+    Section,
+    Param,
+    Returns,
+}
+
+impl Docs {
+    pub fn parse(comment: &Token<'_>) -> Docs {
+        let text = comment.value();
+        let start = comment.position().start;
+        let mut preamble = String::new();
+        let mut sections: Vec<DocSection> = vec![];
+        let mut params: Vec<DocParam> = vec![];
+        let mut returns: Option<String> = None;
+        let mut target = Target::Preamble;
+
+        for (offset, line) in lines(text) {
+            let position = Position::new(start + offset, start + offset + line.len());
+
+            if let Some(tag) = line.strip_prefix('@') {
+                let (name, rest) = split_word(tag);
+
+                target = match name {
+                    "param" => {
+                        let (name, description) = split_word(rest);
+
+                        params.push(DocParam {
+                            name: name.to_string(),
+                            description: description.to_string(),
+                            position,
+                        });
+
+                        Target::Param
+                    }
+                    "returns" | "return" => {
+                        returns = Some(rest.to_string());
+
+                        Target::Returns
+                    }
+                    // Other tags are left for tools that understand them.
+                    _ => Target::Preamble,
+                };
+            } else if let Some(title) = line.strip_prefix('#') {
+                sections.push(DocSection {
+                    title: title.trim_start_matches('#').trim().to_string(),
+                    body: String::new(),
+                });
+
+                target = Target::Section;
+            } else {
+                // A blank line ends a tag's description.
+                if line.is_empty() && matches!(target, Target::Param | Target::Returns) {
+                    target = if sections.is_empty() {
+                        Target::Preamble
+                    } else {
+                        Target::Section
+                    };
+                }
+
+                match target {
+                    Target::Preamble => push_line(&mut preamble, line),
+                    Target::Section => push_line(&mut sections.last_mut().unwrap().body, line),
+                    Target::Param => {
+                        push_words(&mut params.last_mut().unwrap().description, line);
+                    }
+                    Target::Returns => push_words(returns.as_mut().unwrap(), line),
+                }
+            }
+        }
+
+        let preamble = preamble.trim();
+        let (summary, description) = preamble.split_once("\n\n").unwrap_or((preamble, ""));
+
+        for section in &mut sections {
+            section.body = section.body.trim().to_string();
+        }
+
+        Docs {
+            text: text.to_string(),
+            summary: summary.split_whitespace().collect::<Vec<_>>().join(" "),
+            description: description.trim().to_string(),
+            sections,
+            params,
+            returns,
+            position: comment.position(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn sections(&self) -> &[DocSection] {
+        &self.sections
+    }
+
+    pub fn params(&self) -> &[DocParam] {
+        &self.params
+    }
+
+    pub fn returns(&self) -> Option<&str> {
+        self.returns.as_deref()
+    }
+
+    // Warnings for @param tags naming something other than one of the given
+    // parameters, or naming one of them twice. `kind` describes what the
+    // names are, e.g. "a parameter of `fib`".
+    pub fn check_params(&self, names: &[&str], kind: &str) -> Vec<DocWarning> {
+        let mut warnings = vec![];
+
+        for (index, param) in self.params.iter().enumerate() {
+            let message = if !names.contains(&param.name.as_str()) {
+                format!("`{}` is not {}", param.name, kind)
+            } else if self.params[..index]
+                .iter()
+                .any(|other| other.name == param.name)
+            {
+                format!("`{}` is documented more than once", param.name)
+            } else {
+                continue;
+            };
+
+            warnings.push(DocWarning {
+                message,
+                position: param.position,
+            });
+        }
+
+        warnings
+    }
+}
+
+impl DocSection {
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+}
+
+impl DocParam {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Spanned for Docs {
+    fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl Spanned for DocParam {
+    fn position(&self) -> Position {
+        self.position
+    }
+}
+
+// The lines between `/**` and `*/`, without the `*` that usually starts
+// them, along with where each one starts within the comment.
+fn lines(text: &str) -> Vec<(usize, &str)> {
+    let body = &text[3..text.len() - 2];
+    let mut offset = 3;
+    let mut lines = vec![];
+
+    for line in body.split('\n') {
+        let trimmed = line.trim_start();
+        let trimmed = trimmed.strip_prefix('*').unwrap_or(trimmed);
+        let trimmed = trimmed.strip_prefix(' ').unwrap_or(trimmed);
+        let content = trimmed.trim_end();
+
+        lines.push((offset + line.len() - trimmed.len(), content));
+        offset += line.len() + 1;
+    }
+
+    lines
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+
+    match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (text, ""),
+    }
+}
+
+fn push_line(text: &mut String, line: &str) {
+    text.push_str(line);
+    text.push('\n');
+}
+
+fn push_words(text: &mut String, line: &str) {
+    if !text.is_empty() {
+        text.push(' ');
+    }
+
+    text.push_str(line.trim());
+}
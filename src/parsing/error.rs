@@ -0,0 +1,38 @@
+use std::{error::Error, fmt};
+
+use crate::lexing::Position;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    message: String,
+    position: Position,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, position: Position) -> ParseError {
+        ParseError {
+            message: message.into(),
+            position,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.position.start, self.position.end
+        )
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,1210 @@
+use crate::parsing::{arena::*, ast::*, types::*};
+
+// Rebuilds a module bottom-up. Each method takes a node by value and returns
+// its replacement, so unlike VisitorMut a node may become a different kind
+// of node. The defaults fold the node's children in source order and put the
+// node back together unchanged.
+pub trait Fold<'a> {
+    fn fold_source_file(&mut self, file: SourceFile<'a>) -> SourceFile<'a> {
+        fold_source_file(self, file)
+    }
+
+    fn fold_node(&mut self, arena: &mut Arena<'a>, id: NodeId) -> NodeId {
+        fold_node(self, arena, id)
+    }
+
+    fn fold_boolean_literal(&mut self, _arena: &mut Arena<'a>, literal: BooleanLiteral) -> AST<'a> {
+        AST::BooleanLiteral(literal)
+    }
+
+    fn fold_integer_literal(&mut self, _arena: &mut Arena<'a>, literal: IntegerLiteral) -> AST<'a> {
+        AST::IntegerLiteral(literal)
+    }
+
+    fn fold_number_literal(&mut self, _arena: &mut Arena<'a>, literal: NumberLiteral) -> AST<'a> {
+        AST::NumberLiteral(literal)
+    }
+
+    fn fold_regexp_literal(
+        &mut self,
+        _arena: &mut Arena<'a>,
+        literal: RegexpLiteral<'a>,
+    ) -> AST<'a> {
+        AST::RegexpLiteral(literal)
+    }
+
+    fn fold_string_literal(
+        &mut self,
+        _arena: &mut Arena<'a>,
+        literal: StringLiteral<'a>,
+    ) -> AST<'a> {
+        AST::StringLiteral(literal)
+    }
+
+    fn fold_template_literal(
+        &mut self,
+        arena: &mut Arena<'a>,
+        literal: TemplateLiteral<'a>,
+    ) -> AST<'a> {
+        fold_template_literal(self, arena, literal)
+    }
+
+    fn fold_identifier(
+        &mut self,
+        _arena: &mut Arena<'a>,
+        identifier: Identifier<'a>,
+    ) -> Identifier<'a> {
+        identifier
+    }
+
+    fn fold_record(&mut self, arena: &mut Arena<'a>, record: Record<'a>) -> AST<'a> {
+        fold_record(self, arena, record)
+    }
+
+    fn fold_record_member(
+        &mut self,
+        arena: &mut Arena<'a>,
+        member: RecordMember<'a>,
+    ) -> RecordMember<'a> {
+        fold_record_member(self, arena, member)
+    }
+
+    fn fold_array(&mut self, arena: &mut Arena<'a>, array: Array) -> AST<'a> {
+        fold_array(self, arena, array)
+    }
+
+    fn fold_tuple(&mut self, arena: &mut Arena<'a>, tuple: Tuple) -> AST<'a> {
+        fold_tuple(self, arena, tuple)
+    }
+
+    fn fold_element(&mut self, arena: &mut Arena<'a>, element: Element) -> Element {
+        fold_element(self, arena, element)
+    }
+
+    fn fold_call(&mut self, arena: &mut Arena<'a>, call: Call) -> AST<'a> {
+        fold_call(self, arena, call)
+    }
+
+    fn fold_member_access(&mut self, arena: &mut Arena<'a>, member: MemberAccess<'a>) -> AST<'a> {
+        fold_member_access(self, arena, member)
+    }
+
+    fn fold_binary(&mut self, arena: &mut Arena<'a>, binary: Binary) -> AST<'a> {
+        fold_binary(self, arena, binary)
+    }
+
+    fn fold_unary(&mut self, arena: &mut Arena<'a>, unary: Unary) -> AST<'a> {
+        fold_unary(self, arena, unary)
+    }
+
+    fn fold_block(&mut self, arena: &mut Arena<'a>, block: Block) -> AST<'a> {
+        fold_block(self, arena, block)
+    }
+
+    fn fold_with(&mut self, arena: &mut Arena<'a>, with: With) -> AST<'a> {
+        fold_with(self, arena, with)
+    }
+
+    fn fold_lambda(&mut self, arena: &mut Arena<'a>, lambda: Lambda<'a>) -> AST<'a> {
+        fold_lambda(self, arena, lambda)
+    }
+
+    fn fold_let_binding(&mut self, arena: &mut Arena<'a>, binding: LetBinding<'a>) -> AST<'a> {
+        fold_let_binding(self, arena, binding)
+    }
+
+    fn fold_assignment(&mut self, arena: &mut Arena<'a>, assignment: Assignment<'a>) -> AST<'a> {
+        fold_assignment(self, arena, assignment)
+    }
+
+    fn fold_if(&mut self, arena: &mut Arena<'a>, if_: If) -> AST<'a> {
+        fold_if(self, arena, if_)
+    }
+
+    fn fold_while(&mut self, arena: &mut Arena<'a>, while_: While<'a>) -> AST<'a> {
+        fold_while(self, arena, while_)
+    }
+
+    fn fold_for_of(&mut self, arena: &mut Arena<'a>, for_of: ForOf<'a>) -> AST<'a> {
+        fold_for_of(self, arena, for_of)
+    }
+
+    fn fold_return(&mut self, arena: &mut Arena<'a>, return_: Return) -> AST<'a> {
+        fold_return(self, arena, return_)
+    }
+
+    fn fold_yield(&mut self, arena: &mut Arena<'a>, yield_: Yield) -> AST<'a> {
+        fold_yield(self, arena, yield_)
+    }
+
+    fn fold_import_declaration(
+        &mut self,
+        _arena: &mut Arena<'a>,
+        import: ImportDeclaration<'a>,
+    ) -> AST<'a> {
+        AST::ImportDeclaration(import)
+    }
+
+    fn fold_export_declaration(
+        &mut self,
+        arena: &mut Arena<'a>,
+        export: ExportDeclaration<'a>,
+    ) -> AST<'a> {
+        fold_export_declaration(self, arena, export)
+    }
+
+    fn fold_match(&mut self, arena: &mut Arena<'a>, match_: Match<'a>) -> AST<'a> {
+        fold_match(self, arena, match_)
+    }
+
+    fn fold_match_case(&mut self, arena: &mut Arena<'a>, case: MatchCase<'a>) -> MatchCase<'a> {
+        fold_match_case(self, arena, case)
+    }
+
+    fn fold_pattern(&mut self, arena: &mut Arena<'a>, pattern: Pattern<'a>) -> Pattern<'a> {
+        fold_pattern(self, arena, pattern)
+    }
+
+    fn fold_function_declaration(
+        &mut self,
+        arena: &mut Arena<'a>,
+        function: FunctionDeclaration<'a>,
+    ) -> AST<'a> {
+        fold_function_declaration(self, arena, function)
+    }
+
+    fn fold_function_parameter(
+        &mut self,
+        arena: &mut Arena<'a>,
+        parameter: FunctionParameter<'a>,
+    ) -> FunctionParameter<'a> {
+        fold_function_parameter(self, arena, parameter)
+    }
+
+    fn fold_type_parameter(
+        &mut self,
+        arena: &mut Arena<'a>,
+        parameter: TypeParameter<'a>,
+    ) -> TypeParameter<'a> {
+        fold_type_parameter(self, arena, parameter)
+    }
+
+    fn fold_type_declaration(
+        &mut self,
+        arena: &mut Arena<'a>,
+        declaration: TypeDeclaration<'a>,
+    ) -> AST<'a> {
+        fold_type_declaration(self, arena, declaration)
+    }
+
+    fn fold_type_constructor(
+        &mut self,
+        arena: &mut Arena<'a>,
+        constructor: TypeConstructor<'a>,
+    ) -> TypeConstructor<'a> {
+        fold_type_constructor(self, arena, constructor)
+    }
+
+    fn fold_type_alias(&mut self, arena: &mut Arena<'a>, alias: TypeAlias<'a>) -> AST<'a> {
+        fold_type_alias(self, arena, alias)
+    }
+
+    fn fold_struct_declaration(
+        &mut self,
+        arena: &mut Arena<'a>,
+        declaration: StructDeclaration<'a>,
+    ) -> AST<'a> {
+        fold_struct_declaration(self, arena, declaration)
+    }
+
+    fn fold_effect_declaration(
+        &mut self,
+        arena: &mut Arena<'a>,
+        effect: EffectDeclaration<'a>,
+    ) -> AST<'a> {
+        fold_effect_declaration(self, arena, effect)
+    }
+
+    fn fold_effect_operation(
+        &mut self,
+        arena: &mut Arena<'a>,
+        operation: EffectOperation<'a>,
+    ) -> EffectOperation<'a> {
+        fold_effect_operation(self, arena, operation)
+    }
+
+    fn fold_impl_block(&mut self, arena: &mut Arena<'a>, block: ImplBlock<'a>) -> AST<'a> {
+        fold_impl_block(self, arena, block)
+    }
+
+    fn fold_typeclass_declaration(
+        &mut self,
+        arena: &mut Arena<'a>,
+        typeclass: TypeclassDeclaration<'a>,
+    ) -> AST<'a> {
+        fold_typeclass_declaration(self, arena, typeclass)
+    }
+
+    fn fold_typeclass_method(
+        &mut self,
+        arena: &mut Arena<'a>,
+        method: TypeclassMethod<'a>,
+    ) -> TypeclassMethod<'a> {
+        fold_typeclass_method(self, arena, method)
+    }
+
+    fn fold_associated_type(
+        &mut self,
+        arena: &mut Arena<'a>,
+        associated: AssociatedType<'a>,
+    ) -> AssociatedType<'a> {
+        fold_associated_type(self, arena, associated)
+    }
+
+    fn fold_instance_declaration(
+        &mut self,
+        arena: &mut Arena<'a>,
+        instance: InstanceDeclaration<'a>,
+    ) -> AST<'a> {
+        fold_instance_declaration(self, arena, instance)
+    }
+
+    fn fold_handler_declaration(
+        &mut self,
+        arena: &mut Arena<'a>,
+        handler: HandlerDeclaration<'a>,
+    ) -> AST<'a> {
+        fold_handler_declaration(self, arena, handler)
+    }
+
+    fn fold_operation_clause(
+        &mut self,
+        arena: &mut Arena<'a>,
+        clause: OperationClause<'a>,
+    ) -> OperationClause<'a> {
+        fold_operation_clause(self, arena, clause)
+    }
+
+    fn fold_return_clause(
+        &mut self,
+        arena: &mut Arena<'a>,
+        clause: ReturnClause<'a>,
+    ) -> ReturnClause<'a> {
+        fold_return_clause(self, arena, clause)
+    }
+
+    fn fold_macro_declaration(
+        &mut self,
+        arena: &mut Arena<'a>,
+        declaration: MacroDeclaration<'a>,
+    ) -> AST<'a> {
+        fold_macro_declaration(self, arena, declaration)
+    }
+
+    fn fold_macro_invocation(
+        &mut self,
+        _arena: &mut Arena<'a>,
+        invocation: MacroInvocation<'a>,
+    ) -> MacroInvocation<'a> {
+        invocation
+    }
+
+    fn fold_type_annotation(
+        &mut self,
+        arena: &mut Arena<'a>,
+        annotation: TypeAnnotation<'a>,
+    ) -> TypeAnnotation<'a> {
+        fold_type_annotation(self, arena, annotation)
+    }
+
+    fn fold_labeled_type_annotation(
+        &mut self,
+        arena: &mut Arena<'a>,
+        annotation: LabeledTypeAnnotation<'a>,
+    ) -> LabeledTypeAnnotation<'a> {
+        fold_labeled_type_annotation(self, arena, annotation)
+    }
+
+    fn fold_type(&mut self, arena: &mut Arena<'a>, type_: Type<'a>) -> Type<'a> {
+        fold_type(self, arena, type_)
+    }
+
+    fn fold_tuple_data(&mut self, arena: &mut Arena<'a>, data: TupleData<'a>) -> TupleData<'a> {
+        fold_tuple_data(self, arena, data)
+    }
+
+    fn fold_struct_data(&mut self, arena: &mut Arena<'a>, data: StructData<'a>) -> StructData<'a> {
+        fold_struct_data(self, arena, data)
+    }
+}
+
+pub fn fold_source_file<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    file: SourceFile<'a>,
+) -> SourceFile<'a> {
+    let SourceFile {
+        mut arena, body, ..
+    } = file;
+    let body = fold_nodes(folder, &mut arena, body);
+
+    SourceFile::new(arena, body)
+}
+
+// Folds the node in place: the replacement keeps the node's id, and its
+// children are linked back to it.
+pub fn fold_node<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    id: NodeId,
+) -> NodeId {
+    let node = arena.take(id);
+    let node = fold_ast(folder, arena, node);
+    arena.replace(id, node);
+
+    id
+}
+
+// Dispatches to the method for the node's variant. Used for nodes in the
+// arena and for the literals owned by patterns.
+pub fn fold_ast<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    node: AST<'a>,
+) -> AST<'a> {
+    match node {
+        AST::BooleanLiteral(literal) => folder.fold_boolean_literal(arena, literal),
+        AST::IntegerLiteral(literal) => folder.fold_integer_literal(arena, literal),
+        AST::NumberLiteral(literal) => folder.fold_number_literal(arena, literal),
+        AST::RegexpLiteral(literal) => folder.fold_regexp_literal(arena, literal),
+        AST::StringLiteral(literal) => folder.fold_string_literal(arena, literal),
+        AST::TemplateLiteral(literal) => folder.fold_template_literal(arena, literal),
+        AST::Identifier(identifier) => AST::Identifier(folder.fold_identifier(arena, identifier)),
+        AST::Record(record) => folder.fold_record(arena, record),
+        AST::Array(array) => folder.fold_array(arena, array),
+        AST::Tuple(tuple) => folder.fold_tuple(arena, tuple),
+        AST::Call(call) => folder.fold_call(arena, call),
+        AST::MemberAccess(member) => folder.fold_member_access(arena, member),
+        AST::Binary(binary) => folder.fold_binary(arena, binary),
+        AST::Unary(unary) => folder.fold_unary(arena, unary),
+        AST::Block(block) => folder.fold_block(arena, block),
+        AST::With(with) => folder.fold_with(arena, with),
+        AST::Lambda(lambda) => folder.fold_lambda(arena, lambda),
+        AST::LetBinding(binding) => folder.fold_let_binding(arena, binding),
+        AST::Assignment(assignment) => folder.fold_assignment(arena, assignment),
+        AST::If(if_) => folder.fold_if(arena, if_),
+        AST::While(while_) => folder.fold_while(arena, while_),
+        AST::ForOf(for_of) => folder.fold_for_of(arena, for_of),
+        AST::Return(return_) => folder.fold_return(arena, return_),
+        AST::Yield(yield_) => folder.fold_yield(arena, yield_),
+        AST::ImportDeclaration(import) => folder.fold_import_declaration(arena, import),
+        AST::ExportDeclaration(export) => folder.fold_export_declaration(arena, export),
+        AST::Match(match_) => folder.fold_match(arena, match_),
+        AST::FunctionDeclaration(function) => folder.fold_function_declaration(arena, function),
+        AST::TypeDeclaration(declaration) => folder.fold_type_declaration(arena, declaration),
+        AST::TypeAlias(alias) => folder.fold_type_alias(arena, alias),
+        AST::StructDeclaration(declaration) => folder.fold_struct_declaration(arena, declaration),
+        AST::EffectDeclaration(effect) => folder.fold_effect_declaration(arena, effect),
+        AST::ImplBlock(block) => folder.fold_impl_block(arena, block),
+        AST::TypeclassDeclaration(typeclass) => folder.fold_typeclass_declaration(arena, typeclass),
+        AST::InstanceDeclaration(instance) => folder.fold_instance_declaration(arena, instance),
+        AST::HandlerDeclaration(handler) => folder.fold_handler_declaration(arena, handler),
+        AST::MacroDeclaration(declaration) => folder.fold_macro_declaration(arena, declaration),
+        AST::MacroInvocation(invocation) => {
+            AST::MacroInvocation(folder.fold_macro_invocation(arena, invocation))
+        }
+    }
+}
+
+fn fold_nodes<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    ids: Vec<NodeId>,
+) -> Vec<NodeId> {
+    ids.into_iter()
+        .map(|id| folder.fold_node(arena, id))
+        .collect()
+}
+
+fn fold_all<'a, F: Fold<'a> + ?Sized, T>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    items: Vec<T>,
+    fold: fn(&mut F, &mut Arena<'a>, T) -> T,
+) -> Vec<T> {
+    items
+        .into_iter()
+        .map(|item| fold(folder, arena, item))
+        .collect()
+}
+
+pub fn fold_template_literal<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    literal: TemplateLiteral<'a>,
+) -> AST<'a> {
+    AST::TemplateLiteral(TemplateLiteral {
+        values: fold_nodes(folder, arena, literal.values),
+        ..literal
+    })
+}
+
+pub fn fold_record<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    record: Record<'a>,
+) -> AST<'a> {
+    AST::Record(Record {
+        members: fold_all(folder, arena, record.members, F::fold_record_member),
+        ..record
+    })
+}
+
+pub fn fold_record_member<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    member: RecordMember<'a>,
+) -> RecordMember<'a> {
+    match member {
+        RecordMember::Field(name, value) => {
+            let name = folder.fold_identifier(arena, name);
+            RecordMember::Field(name, folder.fold_node(arena, value))
+        }
+        RecordMember::Shorthand(name) => {
+            RecordMember::Shorthand(folder.fold_identifier(arena, name))
+        }
+        RecordMember::Spread(value) => RecordMember::Spread(folder.fold_node(arena, value)),
+    }
+}
+
+pub fn fold_array<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    array: Array,
+) -> AST<'a> {
+    AST::Array(Array {
+        elements: fold_all(folder, arena, array.elements, F::fold_element),
+        ..array
+    })
+}
+
+pub fn fold_tuple<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    tuple: Tuple,
+) -> AST<'a> {
+    AST::Tuple(Tuple {
+        elements: fold_all(folder, arena, tuple.elements, F::fold_element),
+        ..tuple
+    })
+}
+
+pub fn fold_element<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    element: Element,
+) -> Element {
+    match element {
+        Element::Member(value) => Element::Member(folder.fold_node(arena, value)),
+        Element::Spread(value) => Element::Spread(folder.fold_node(arena, value)),
+    }
+}
+
+pub fn fold_call<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    call: Call,
+) -> AST<'a> {
+    AST::Call(Call {
+        callee: folder.fold_node(arena, call.callee),
+        arguments: fold_nodes(folder, arena, call.arguments),
+        ..call
+    })
+}
+
+pub fn fold_member_access<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    member: MemberAccess<'a>,
+) -> AST<'a> {
+    AST::MemberAccess(MemberAccess {
+        object: folder.fold_node(arena, member.object),
+        property: folder.fold_identifier(arena, member.property),
+        ..member
+    })
+}
+
+pub fn fold_binary<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    binary: Binary,
+) -> AST<'a> {
+    AST::Binary(Binary {
+        left: folder.fold_node(arena, binary.left),
+        right: folder.fold_node(arena, binary.right),
+        ..binary
+    })
+}
+
+pub fn fold_unary<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    unary: Unary,
+) -> AST<'a> {
+    AST::Unary(Unary {
+        operand: folder.fold_node(arena, unary.operand),
+        ..unary
+    })
+}
+
+pub fn fold_block<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    block: Block,
+) -> AST<'a> {
+    AST::Block(Block {
+        body: fold_nodes(folder, arena, block.body),
+        ..block
+    })
+}
+
+pub fn fold_with<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    with: With,
+) -> AST<'a> {
+    AST::With(With {
+        body: folder.fold_node(arena, with.body),
+        handlers: folder.fold_node(arena, with.handlers),
+        ..with
+    })
+}
+
+pub fn fold_lambda<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    lambda: Lambda<'a>,
+) -> AST<'a> {
+    AST::Lambda(Lambda {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            lambda.type_parameters,
+            F::fold_type_parameter,
+        ),
+        parameters: fold_all(folder, arena, lambda.parameters, F::fold_function_parameter),
+        return_type: lambda
+            .return_type
+            .map(|return_type| folder.fold_type_annotation(arena, return_type)),
+        body: fold_nodes(folder, arena, lambda.body),
+        ..lambda
+    })
+}
+
+pub fn fold_let_binding<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    binding: LetBinding<'a>,
+) -> AST<'a> {
+    AST::LetBinding(LetBinding {
+        pattern: folder.fold_pattern(arena, binding.pattern),
+        value: folder.fold_node(arena, binding.value),
+        ..binding
+    })
+}
+
+pub fn fold_assignment<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    assignment: Assignment<'a>,
+) -> AST<'a> {
+    AST::Assignment(Assignment {
+        target: folder.fold_identifier(arena, assignment.target),
+        value: folder.fold_node(arena, assignment.value),
+        ..assignment
+    })
+}
+
+pub fn fold_if<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    if_: If,
+) -> AST<'a> {
+    AST::If(If {
+        condition: folder.fold_node(arena, if_.condition),
+        consequent: fold_nodes(folder, arena, if_.consequent),
+        alternative: if_
+            .alternative
+            .map(|alternative| folder.fold_node(arena, alternative)),
+        ..if_
+    })
+}
+
+pub fn fold_while<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    while_: While<'a>,
+) -> AST<'a> {
+    AST::While(While {
+        pattern: while_
+            .pattern
+            .map(|pattern| folder.fold_pattern(arena, pattern)),
+        condition: folder.fold_node(arena, while_.condition),
+        body: fold_nodes(folder, arena, while_.body),
+        ..while_
+    })
+}
+
+pub fn fold_for_of<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    for_of: ForOf<'a>,
+) -> AST<'a> {
+    AST::ForOf(ForOf {
+        pattern: folder.fold_pattern(arena, for_of.pattern),
+        iterable: folder.fold_node(arena, for_of.iterable),
+        body: fold_nodes(folder, arena, for_of.body),
+        ..for_of
+    })
+}
+
+pub fn fold_return<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    return_: Return,
+) -> AST<'a> {
+    AST::Return(Return {
+        value: return_.value.map(|value| folder.fold_node(arena, value)),
+        ..return_
+    })
+}
+
+pub fn fold_yield<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    yield_: Yield,
+) -> AST<'a> {
+    AST::Yield(Yield {
+        value: yield_.value.map(|value| folder.fold_node(arena, value)),
+        ..yield_
+    })
+}
+
+pub fn fold_export_declaration<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    export: ExportDeclaration<'a>,
+) -> AST<'a> {
+    let kind = match export.kind {
+        ExportKind::Declaration(declaration) => {
+            ExportKind::Declaration(folder.fold_node(arena, declaration))
+        }
+        kind => kind,
+    };
+
+    AST::ExportDeclaration(ExportDeclaration { kind, ..export })
+}
+
+pub fn fold_match<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    match_: Match<'a>,
+) -> AST<'a> {
+    AST::Match(Match {
+        value: folder.fold_node(arena, match_.value),
+        cases: fold_all(folder, arena, match_.cases, F::fold_match_case),
+        ..match_
+    })
+}
+
+pub fn fold_match_case<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    case: MatchCase<'a>,
+) -> MatchCase<'a> {
+    let test = match case.test {
+        MatchTest::Pattern(pattern) => MatchTest::Pattern(folder.fold_pattern(arena, pattern)),
+        MatchTest::Predicate(predicate) => MatchTest::Predicate(folder.fold_node(arena, predicate)),
+    };
+
+    MatchCase {
+        test,
+        body: folder.fold_node(arena, case.body),
+        ..case
+    }
+}
+
+pub fn fold_pattern<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    pattern: Pattern<'a>,
+) -> Pattern<'a> {
+    match pattern {
+        Pattern::Wildcard(position) => Pattern::Wildcard(position),
+        Pattern::Identifier(identifier) => {
+            Pattern::Identifier(folder.fold_identifier(arena, identifier))
+        }
+        Pattern::Literal(literal) => Pattern::Literal(Box::new(fold_ast(folder, arena, *literal))),
+        Pattern::Constructor(constructor) => Pattern::Constructor(ConstructorPattern {
+            arguments: fold_all(folder, arena, constructor.arguments, F::fold_pattern),
+            ..constructor
+        }),
+        Pattern::Record(record) => {
+            let fields = record
+                .fields
+                .into_iter()
+                .map(|field| FieldPattern {
+                    name: folder.fold_identifier(arena, field.name),
+                    pattern: folder.fold_pattern(arena, field.pattern),
+                })
+                .collect();
+            let rest = record
+                .rest
+                .map(|rest| Box::new(folder.fold_pattern(arena, *rest)));
+
+            Pattern::Record(RecordPattern {
+                fields,
+                rest,
+                ..record
+            })
+        }
+        Pattern::Array(array) => {
+            let elements = fold_all(folder, arena, array.elements, F::fold_pattern);
+            let rest = array
+                .rest
+                .map(|rest| Box::new(folder.fold_pattern(arena, *rest)));
+
+            Pattern::Array(ArrayPattern {
+                elements,
+                rest,
+                ..array
+            })
+        }
+        Pattern::Tuple(tuple) => Pattern::Tuple(TuplePattern {
+            elements: fold_all(folder, arena, tuple.elements, F::fold_pattern),
+            ..tuple
+        }),
+    }
+}
+
+pub fn fold_function_declaration<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    function: FunctionDeclaration<'a>,
+) -> AST<'a> {
+    AST::FunctionDeclaration(FunctionDeclaration {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            function.type_parameters,
+            F::fold_type_parameter,
+        ),
+        parameters: fold_all(
+            folder,
+            arena,
+            function.parameters,
+            F::fold_function_parameter,
+        ),
+        return_type: function
+            .return_type
+            .map(|return_type| folder.fold_type_annotation(arena, return_type)),
+        body: fold_nodes(folder, arena, function.body),
+        ..function
+    })
+}
+
+pub fn fold_function_parameter<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    parameter: FunctionParameter<'a>,
+) -> FunctionParameter<'a> {
+    FunctionParameter {
+        annotation: parameter
+            .annotation
+            .map(|annotation| folder.fold_type_annotation(arena, annotation)),
+        ..parameter
+    }
+}
+
+pub fn fold_type_parameter<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    parameter: TypeParameter<'a>,
+) -> TypeParameter<'a> {
+    TypeParameter {
+        extends: parameter
+            .extends
+            .map(|extends| folder.fold_type(arena, extends)),
+        ..parameter
+    }
+}
+
+pub fn fold_type_declaration<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    declaration: TypeDeclaration<'a>,
+) -> AST<'a> {
+    AST::TypeDeclaration(TypeDeclaration {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            declaration.type_parameters,
+            F::fold_type_parameter,
+        ),
+        type_constructors: fold_all(
+            folder,
+            arena,
+            declaration.type_constructors,
+            F::fold_type_constructor,
+        ),
+        ..declaration
+    })
+}
+
+pub fn fold_type_constructor<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    constructor: TypeConstructor<'a>,
+) -> TypeConstructor<'a> {
+    TypeConstructor {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            constructor.type_parameters,
+            F::fold_type_parameter,
+        ),
+        parameters: fold_all(
+            folder,
+            arena,
+            constructor.parameters,
+            F::fold_labeled_type_annotation,
+        ),
+        return_type: constructor
+            .return_type
+            .map(|return_type| folder.fold_type_annotation(arena, return_type)),
+        ..constructor
+    }
+}
+
+pub fn fold_type_alias<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    alias: TypeAlias<'a>,
+) -> AST<'a> {
+    AST::TypeAlias(TypeAlias {
+        type_parameters: fold_all(folder, arena, alias.type_parameters, F::fold_type_parameter),
+        aliased: folder.fold_type_annotation(arena, alias.aliased),
+        ..alias
+    })
+}
+
+pub fn fold_struct_declaration<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    declaration: StructDeclaration<'a>,
+) -> AST<'a> {
+    AST::StructDeclaration(StructDeclaration {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            declaration.type_parameters,
+            F::fold_type_parameter,
+        ),
+        fields: fold_all(folder, arena, declaration.fields, F::fold_struct_data),
+        ..declaration
+    })
+}
+
+pub fn fold_effect_declaration<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    effect: EffectDeclaration<'a>,
+) -> AST<'a> {
+    AST::EffectDeclaration(EffectDeclaration {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            effect.type_parameters,
+            F::fold_type_parameter,
+        ),
+        operations: fold_all(folder, arena, effect.operations, F::fold_effect_operation),
+        ..effect
+    })
+}
+
+pub fn fold_effect_operation<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    operation: EffectOperation<'a>,
+) -> EffectOperation<'a> {
+    EffectOperation {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            operation.type_parameters,
+            F::fold_type_parameter,
+        ),
+        parameters: fold_all(folder, arena, operation.parameters, F::fold_type_annotation),
+        return_type: folder.fold_type_annotation(arena, operation.return_type),
+        ..operation
+    }
+}
+
+pub fn fold_impl_block<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    block: ImplBlock<'a>,
+) -> AST<'a> {
+    AST::ImplBlock(ImplBlock {
+        type_parameters: fold_all(folder, arena, block.type_parameters, F::fold_type_parameter),
+        type_arguments: fold_all(folder, arena, block.type_arguments, F::fold_type_annotation),
+        members: fold_nodes(folder, arena, block.members),
+        ..block
+    })
+}
+
+pub fn fold_typeclass_declaration<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    typeclass: TypeclassDeclaration<'a>,
+) -> AST<'a> {
+    AST::TypeclassDeclaration(TypeclassDeclaration {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            typeclass.type_parameters,
+            F::fold_type_parameter,
+        ),
+        superclasses: fold_all(
+            folder,
+            arena,
+            typeclass.superclasses,
+            F::fold_type_annotation,
+        ),
+        associated_types: fold_all(
+            folder,
+            arena,
+            typeclass.associated_types,
+            F::fold_associated_type,
+        ),
+        methods: fold_all(folder, arena, typeclass.methods, F::fold_typeclass_method),
+        ..typeclass
+    })
+}
+
+pub fn fold_typeclass_method<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    method: TypeclassMethod<'a>,
+) -> TypeclassMethod<'a> {
+    TypeclassMethod {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            method.type_parameters,
+            F::fold_type_parameter,
+        ),
+        parameters: fold_all(folder, arena, method.parameters, F::fold_function_parameter),
+        return_type: method
+            .return_type
+            .map(|return_type| folder.fold_type_annotation(arena, return_type)),
+        default: method
+            .default
+            .map(|default| fold_nodes(folder, arena, default)),
+        ..method
+    }
+}
+
+pub fn fold_associated_type<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    associated: AssociatedType<'a>,
+) -> AssociatedType<'a> {
+    AssociatedType {
+        bound: associated
+            .bound
+            .map(|bound| folder.fold_type_annotation(arena, bound)),
+        default: associated
+            .default
+            .map(|default| folder.fold_type_annotation(arena, default)),
+        ..associated
+    }
+}
+
+pub fn fold_instance_declaration<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    instance: InstanceDeclaration<'a>,
+) -> AST<'a> {
+    AST::InstanceDeclaration(InstanceDeclaration {
+        type_parameters: fold_all(
+            folder,
+            arena,
+            instance.type_parameters,
+            F::fold_type_parameter,
+        ),
+        type_arguments: fold_all(
+            folder,
+            arena,
+            instance.type_arguments,
+            F::fold_type_annotation,
+        ),
+        target: folder.fold_type_annotation(arena, instance.target),
+        associated_types: fold_all(
+            folder,
+            arena,
+            instance.associated_types,
+            F::fold_associated_type,
+        ),
+        members: fold_nodes(folder, arena, instance.members),
+        ..instance
+    })
+}
+
+pub fn fold_handler_declaration<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    handler: HandlerDeclaration<'a>,
+) -> AST<'a> {
+    AST::HandlerDeclaration(HandlerDeclaration {
+        parameters: fold_all(
+            folder,
+            arena,
+            handler.parameters,
+            F::fold_function_parameter,
+        ),
+        body: fold_nodes(folder, arena, handler.body),
+        operations: fold_all(folder, arena, handler.operations, F::fold_operation_clause),
+        return_clause: handler
+            .return_clause
+            .map(|clause| folder.fold_return_clause(arena, clause)),
+        ..handler
+    })
+}
+
+pub fn fold_operation_clause<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    clause: OperationClause<'a>,
+) -> OperationClause<'a> {
+    OperationClause {
+        parameters: fold_all(folder, arena, clause.parameters, F::fold_function_parameter),
+        body: fold_nodes(folder, arena, clause.body),
+        ..clause
+    }
+}
+
+pub fn fold_return_clause<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    clause: ReturnClause<'a>,
+) -> ReturnClause<'a> {
+    ReturnClause {
+        parameter: folder.fold_function_parameter(arena, clause.parameter),
+        body: fold_nodes(folder, arena, clause.body),
+        ..clause
+    }
+}
+
+pub fn fold_macro_declaration<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    declaration: MacroDeclaration<'a>,
+) -> AST<'a> {
+    AST::MacroDeclaration(MacroDeclaration {
+        parameters: fold_all(
+            folder,
+            arena,
+            declaration.parameters,
+            F::fold_function_parameter,
+        ),
+        ..declaration
+    })
+}
+
+pub fn fold_type_annotation<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    annotation: TypeAnnotation<'a>,
+) -> TypeAnnotation<'a> {
+    TypeAnnotation {
+        annotated: folder.fold_type(arena, annotation.annotated),
+        ..annotation
+    }
+}
+
+pub fn fold_labeled_type_annotation<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    annotation: LabeledTypeAnnotation<'a>,
+) -> LabeledTypeAnnotation<'a> {
+    LabeledTypeAnnotation {
+        annotated: folder.fold_type_annotation(arena, annotation.annotated),
+        ..annotation
+    }
+}
+
+pub fn fold_type<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    type_: Type<'a>,
+) -> Type<'a> {
+    match type_ {
+        Type::Option(inner) => Type::Option(Box::new(folder.fold_type(arena, *inner))),
+        Type::Array(inner) => Type::Array(Box::new(folder.fold_type(arena, *inner))),
+        Type::Alias(name, inner) => Type::Alias(name, Box::new(folder.fold_type(arena, *inner))),
+        Type::Either(left, right) => {
+            let left = Box::new(folder.fold_type(arena, *left));
+            Type::Either(left, Box::new(folder.fold_type(arena, *right)))
+        }
+        Type::Template(types) => Type::Template(fold_all(folder, arena, types, F::fold_type)),
+        Type::Reference(name, types) => {
+            Type::Reference(name, fold_all(folder, arena, types, F::fold_type))
+        }
+        Type::Union(types) => Type::Union(fold_all(folder, arena, types, F::fold_type)),
+        Type::Intersection(types) => {
+            Type::Intersection(fold_all(folder, arena, types, F::fold_type))
+        }
+        Type::Tuple(members) => Type::Tuple(fold_all(folder, arena, members, F::fold_tuple_data)),
+        Type::Struct(members) => {
+            Type::Struct(fold_all(folder, arena, members, F::fold_struct_data))
+        }
+        Type::Function(type_parameters, arguments, effects, return_type) => {
+            let type_parameters = fold_all(folder, arena, type_parameters, F::fold_type);
+            let arguments = fold_all(folder, arena, arguments, F::fold_type);
+            let effects = fold_all(folder, arena, effects, F::fold_type);
+
+            Type::Function(
+                type_parameters,
+                arguments,
+                effects,
+                Box::new(folder.fold_type(arena, *return_type)),
+            )
+        }
+        Type::Macro(invocation) => Type::Macro(folder.fold_macro_invocation(arena, invocation)),
+        type_ => type_,
+    }
+}
+
+pub fn fold_tuple_data<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    data: TupleData<'a>,
+) -> TupleData<'a> {
+    match data {
+        TupleData::Member(type_) => TupleData::Member(Box::new(folder.fold_type(arena, *type_))),
+        TupleData::Spread(type_) => TupleData::Spread(Box::new(folder.fold_type(arena, *type_))),
+    }
+}
+
+pub fn fold_struct_data<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    arena: &mut Arena<'a>,
+    data: StructData<'a>,
+) -> StructData<'a> {
+    match data {
+        StructData::Member(name, type_) => {
+            StructData::Member(name, Box::new(folder.fold_type(arena, *type_)))
+        }
+        StructData::Spread(type_) => StructData::Spread(Box::new(folder.fold_type(arena, *type_))),
+    }
+}
@@ -0,0 +1,899 @@
+use std::cell::Cell;
+
+use crate::{
+    lexing::{Position, Spanned, Token, TokenKind},
+    parsing::{arena::*, ast::*, docs::*, error::ParseError, parser::Parser, types::*},
+};
+
+// A change to the source of a module: the text at `position` was replaced
+// by `length` bytes of new text.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Edit {
+    pub position: Position,
+    pub length: usize,
+}
+
+impl Edit {
+    pub fn new(position: Position, length: usize) -> Edit {
+        Edit { position, length }
+    }
+
+    // How far the edit moves the text after it.
+    pub fn delta(&self) -> isize {
+        self.length as isize - (self.position.end - self.position.start) as isize
+    }
+}
+
+// Parses `input`, the source of `old` after `edit`, reusing every node of
+// `old` outside the innermost statement around the edit. That statement is
+// parsed again on its own, then the statement around it and so on, until
+// one still parses to a statement ending where the edited one did and
+// binding the same names; failing that, the whole module is parsed again.
+// The result is the same as parsing `input` from scratch. `source` is the
+// text `old` was parsed from; the result only borrows from `input`, so
+// `source` can be dropped once this returns. When `old` doesn't borrow its
+// text from `source`, e.g. when `source` is a copy of it, nothing can be
+// reused and the whole module is parsed again.
+pub fn reparse<'a>(
+    old: SourceFile<'_>,
+    source: &str,
+    input: &'a str,
+    edit: Edit,
+) -> Result<SourceFile<'a>, ParseError> {
+    let path = enclosing(&old, edit);
+    let reparsed = (0..path.len()).rev().find_map(|depth| {
+        reparse_statement(&old, source, input, edit, &path[..=depth]).map(|arena| (depth, arena))
+    });
+
+    if let Some((depth, arena)) = reparsed {
+        if let Some(file) = splice(old, source, path[depth], arena, input, edit) {
+            return Ok(file);
+        }
+    }
+
+    Parser::new(input)?.parse_module()
+}
+
+// The top-level statement around the edit, the node inside it around the
+// edit, and so on down to the innermost one.
+fn enclosing(file: &SourceFile<'_>, edit: Edit) -> Vec<NodeId> {
+    let contains = |id: &NodeId| {
+        let position = file.arena[*id].position();
+
+        position.start < edit.position.start && edit.position.end <= position.end
+    };
+    let mut path: Vec<NodeId> = file.body.iter().copied().filter(contains).take(1).collect();
+
+    while let Some(&id) = path.last() {
+        match file.arena[id].children().into_iter().find(contains) {
+            Some(child) => path.push(child),
+            None => break,
+        }
+    }
+
+    path
+}
+
+// Parses the last statement on `path` again, if it is one of a list of
+// statements, returning the nodes it is made of now.
+fn reparse_statement<'a>(
+    old: &SourceFile<'_>,
+    source: &str,
+    input: &'a str,
+    edit: Edit,
+    path: &[NodeId],
+) -> Option<Arena<'a>> {
+    let arena = &old.arena;
+    let statement = path[path.len() - 1];
+    let (statements, closing, from) = match path.len() {
+        1 => (&old.body[..], None, 0),
+        depth => {
+            let parent = path[depth - 2];
+
+            (
+                statements(arena, input, parent, statement)?,
+                Some(TokenKind::RightBrace),
+                arena[parent].position().start,
+            )
+        }
+    };
+    let from = match statements.iter().position(|&id| id == statement)? {
+        0 => from,
+        index => arena[statements[index - 1]].position().end,
+    };
+
+    // The statement's nodes have to be the only ones allocated between its
+    // first one and itself, for the new ones to take their place.
+    let mut nodes = vec![statement];
+    let mut first = statement;
+    let mut count = 0;
+
+    while let Some(id) = nodes.pop() {
+        first = first.min(id);
+        count += 1;
+        nodes.extend(arena[id].children());
+    }
+
+    if statement.index() - first.index() + 1 != count {
+        return None;
+    }
+
+    let mut scope = vec![];
+
+    for &id in old.body.iter().take_while(|&&id| id != path[0]) {
+        bindings(arena, id, &mut scope)?;
+    }
+
+    for pair in path.windows(2) {
+        enter(arena, pair[0], pair[1], &mut scope)?;
+    }
+
+    // The names in scope are all bound before the statement, where the
+    // edit didn't move them.
+    let unmoved = Relocation {
+        first: usize::MAX,
+        offset: 0,
+        threshold: usize::MAX,
+        delta: 0,
+        source,
+        input,
+        stray: Cell::new(false),
+    };
+    let scope = scope
+        .into_iter()
+        .map(|(name, mutable)| (unmoved.str(name), mutable))
+        .collect();
+
+    if unmoved.stray.get() {
+        return None;
+    }
+
+    let position = arena[statement].position();
+    let end = (position.end as isize + edit.delta()) as usize;
+    let (new, root) = Parser::parse_statement_at(
+        input,
+        from,
+        Position::new(position.start, end),
+        closing,
+        scope,
+    )?;
+
+    // A handler's statements end at a `return { ... }` of its operations,
+    // which parsing a statement on its own would take for a `return`.
+    let in_handler = path.len() > 1
+        && matches!(&arena[path[path.len() - 2]], AST::HandlerDeclaration(handler) if handler.body.contains(&statement));
+
+    if in_handler && matches!(new[root], AST::Return(_)) {
+        return None;
+    }
+
+    // The statements after it were parsed knowing what it binds.
+    let (mut before, mut after) = (vec![], vec![]);
+
+    bindings(arena, statement, &mut before)?;
+    bindings(&new, root, &mut after)?;
+
+    (before == after).then_some(new)
+}
+
+// The list of statements `child` is in, if `parent` parses it as one of the
+// statements between a pair of braces.
+fn statements<'b>(
+    arena: &'b Arena<'_>,
+    input: &str,
+    parent: NodeId,
+    child: NodeId,
+) -> Option<&'b [NodeId]> {
+    let lists: Vec<&[NodeId]> = match &arena[parent] {
+        AST::Block(block) => vec![&block.body],
+        AST::FunctionDeclaration(function) => vec![&function.body],
+        AST::Lambda(lambda) => {
+            // `-> { ... }` rather than `-> expression`
+            let braced = lambda
+                .body
+                .last()
+                .is_none_or(|&last| arena[last].position().end < lambda.position.end);
+
+            if braced {
+                vec![&lambda.body]
+            } else {
+                vec![]
+            }
+        }
+        AST::While(while_) => vec![&while_.body],
+        AST::ForOf(for_of) => vec![&for_of.body],
+        AST::If(if_) if is_braced(arena, input, if_) => vec![&if_.consequent],
+        AST::HandlerDeclaration(handler) => {
+            let mut lists = vec![&handler.body[..]];
+
+            lists.extend(handler.operations.iter().map(|clause| &clause.body[..]));
+            lists.extend(handler.return_clause.iter().map(|clause| &clause.body[..]));
+            lists
+        }
+        AST::TypeclassDeclaration(typeclass) => typeclass
+            .methods
+            .iter()
+            .filter_map(|method| method.default.as_deref())
+            .collect(),
+        _ => vec![],
+    };
+
+    lists.into_iter().find(|list| list.contains(&child))
+}
+
+// Whether the consequent of an `if` is in braces, rather than the single
+// statement after a parenthesised condition.
+fn is_braced(arena: &Arena<'_>, input: &str, if_: &If) -> bool {
+    match if_.consequent[..] {
+        [statement] => {
+            let condition = arena[if_.condition].position();
+
+            input[condition.end..arena[statement].position().start].contains('{')
+        }
+        _ => true,
+    }
+}
+
+// Adds the names `parent` makes visible to `child`, following the scopes of
+// the parser: a function's parameters, the statements before `child` in the
+// same block, a match case's pattern and so on. Nested scopes are flattened
+// into one, which only keeps the nearest binding of each name visible.
+fn enter<'a>(
+    arena: &Arena<'a>,
+    parent: NodeId,
+    child: NodeId,
+    scope: &mut Vec<(&'a str, bool)>,
+) -> Option<()> {
+    match &arena[parent] {
+        AST::Block(block) => preceding(arena, &block.body, child, scope)?,
+        AST::FunctionDeclaration(function) => {
+            scope.push((function.name, false));
+            parameters(&function.parameters, scope);
+            preceding(arena, &function.body, child, scope)?;
+        }
+        AST::Lambda(lambda) => {
+            parameters(&lambda.parameters, scope);
+            preceding(arena, &lambda.body, child, scope)?;
+        }
+        AST::While(while_) if while_.body.contains(&child) => {
+            if let Some(pattern) = &while_.pattern {
+                names(pattern, false, scope);
+            }
+
+            preceding(arena, &while_.body, child, scope)?;
+        }
+        AST::ForOf(for_of) if for_of.body.contains(&child) => {
+            names(&for_of.pattern, false, scope);
+            preceding(arena, &for_of.body, child, scope)?;
+        }
+        AST::If(if_) if if_.consequent.contains(&child) => {
+            preceding(arena, &if_.consequent, child, scope)?
+        }
+        AST::If(if_) if if_.alternative == Some(child) => {
+            consequent_bindings(arena, if_)?;
+        }
+        AST::Match(match_) => {
+            for case in &match_.cases {
+                match &case.test {
+                    // Patterns are parsed without lambdas, see Parser::allow_lambdas
+                    MatchTest::Predicate(predicate) if *predicate == child => return None,
+                    MatchTest::Pattern(pattern) if case.body == child => {
+                        names(pattern, false, scope)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        AST::HandlerDeclaration(handler) => {
+            if let Some(clause) = &handler.return_clause {
+                if clause.body.contains(&child) {
+                    parameters(std::slice::from_ref(&clause.parameter), scope);
+
+                    return preceding(arena, &clause.body, child, scope);
+                }
+            }
+
+            parameters(&handler.parameters, scope);
+            preceding(arena, &handler.body, child, scope)?;
+
+            for clause in &handler.operations {
+                if clause.body.contains(&child) {
+                    parameters(&clause.parameters, scope);
+                    preceding(arena, &clause.body, child, scope)?;
+                }
+            }
+        }
+        AST::ImplBlock(ImplBlock { members, .. })
+        | AST::InstanceDeclaration(InstanceDeclaration { members, .. }) => {
+            preceding(arena, members, child, scope)?
+        }
+        AST::TypeclassDeclaration(typeclass) => {
+            for method in &typeclass.methods {
+                scope.push((method.name, false));
+
+                if let Some(default) = method
+                    .default
+                    .as_ref()
+                    .filter(|default| default.contains(&child))
+                {
+                    parameters(&method.parameters, scope);
+                    preceding(arena, default, child, scope)?;
+                    break;
+                }
+            }
+        }
+        // Interpolations are tokenized on their own
+        AST::TemplateLiteral(_) => return None,
+        _ => {}
+    }
+
+    Some(())
+}
+
+fn preceding<'a>(
+    arena: &Arena<'a>,
+    statements: &[NodeId],
+    child: NodeId,
+    scope: &mut Vec<(&'a str, bool)>,
+) -> Option<()> {
+    for &id in statements.iter().take_while(|&&id| id != child) {
+        bindings(arena, id, scope)?;
+    }
+
+    Some(())
+}
+
+// Adds the names a statement binds in the scope it is in, with whether
+// they are mutable. None when that can't be told from the tree.
+fn bindings<'a>(arena: &Arena<'a>, id: NodeId, scope: &mut Vec<(&'a str, bool)>) -> Option<()> {
+    match &arena[id] {
+        AST::LetBinding(binding) => names(&binding.pattern, binding.mutable, scope),
+        AST::FunctionDeclaration(function) => scope.push((function.name, false)),
+        AST::ExportDeclaration(ExportDeclaration {
+            kind: ExportKind::Declaration(declaration),
+            ..
+        }) => bindings(arena, *declaration, scope)?,
+        AST::ImplBlock(ImplBlock { members, .. })
+        | AST::InstanceDeclaration(InstanceDeclaration { members, .. }) => {
+            for &member in members {
+                bindings(arena, member, scope)?;
+            }
+        }
+        AST::TypeclassDeclaration(typeclass) => {
+            scope.extend(typeclass.methods.iter().map(|method| (method.name, false)))
+        }
+        AST::If(if_) => {
+            consequent_bindings(arena, if_)?;
+
+            if let Some(alternative) = if_.alternative {
+                bindings(arena, alternative, scope)?;
+            }
+        }
+        _ => {}
+    }
+
+    Some(())
+}
+
+// `if (condition) name = value` binds `name` around the `if`, while the same
+// statement in braces doesn't. Telling the two apart takes the source, so
+// statements like these are left to a full parse.
+fn consequent_bindings(arena: &Arena<'_>, if_: &If) -> Option<()> {
+    match if_.consequent[..] {
+        [statement] => {
+            let mut names = vec![];
+
+            bindings(arena, statement, &mut names)?;
+            names.is_empty().then_some(())
+        }
+        _ => Some(()),
+    }
+}
+
+fn parameters<'a>(parameters: &[FunctionParameter<'a>], scope: &mut Vec<(&'a str, bool)>) {
+    scope.extend(parameters.iter().map(|parameter| (parameter.name, false)));
+}
+
+fn names<'a>(pattern: &Pattern<'a>, mutable: bool, scope: &mut Vec<(&'a str, bool)>) {
+    let mut names = vec![];
+
+    pattern_names(pattern, &mut names);
+    scope.extend(names.into_iter().map(|name| (name, mutable)));
+}
+
+// Rebuilds the arena with the nodes of `statement` replaced by `new`. Node
+// ids and positions after the statement move by as much as it grew, and
+// the nodes are allocated in the order a full parse would allocate them.
+// The nodes kept from `old` borrow their text from `input` from then on,
+// where it sits at the same offsets as their positions. None when some of
+// that text wasn't borrowed from `source`.
+fn splice<'a>(
+    old: SourceFile<'_>,
+    source: &str,
+    statement: NodeId,
+    new: Arena<'a>,
+    input: &'a str,
+    edit: Edit,
+) -> Option<SourceFile<'a>> {
+    let SourceFile { arena, body, .. } = old;
+    let position = arena[statement].position();
+    let mut first = statement;
+    let mut nodes = vec![statement];
+
+    while let Some(id) = nodes.pop() {
+        first = first.min(id);
+        nodes.extend(arena[id].children());
+    }
+
+    let removed = statement.index() - first.index() + 1;
+    let inserted = Relocation {
+        first: 0,
+        offset: first.index() as isize,
+        threshold: usize::MAX,
+        delta: 0,
+        source: input,
+        input,
+        stray: Cell::new(false),
+    };
+    let kept = Relocation {
+        first: statement.index(),
+        offset: new.len() as isize - removed as isize,
+        threshold: position.end,
+        delta: edit.delta(),
+        source,
+        input,
+        stray: Cell::new(false),
+    };
+    let mut new = Some(new);
+    let mut spliced = Arena::new();
+
+    for (index, node) in arena.into_nodes().into_iter().enumerate() {
+        if index == first.index() {
+            for node in new.take().into_iter().flat_map(Arena::into_nodes) {
+                spliced.alloc(node.relocate(&inserted));
+            }
+        }
+
+        if index > statement.index() || index < first.index() {
+            spliced.alloc(node.relocate(&kept));
+        }
+    }
+
+    let body = body.into_iter().map(|id| id.relocate(&kept)).collect();
+
+    (!kept.stray.get()).then(|| SourceFile::new(spliced, body))
+}
+
+// Ids from `first` on move by `offset`, and positions from `threshold` on
+// move by `delta`. Text borrowed from `source` is borrowed from `input`
+// instead, where it moved just like positions did.
+struct Relocation<'s, 'a> {
+    first: usize,
+    offset: isize,
+    threshold: usize,
+    delta: isize,
+    source: &'s str,
+    input: &'a str,
+    // Set when a string turns out not to be borrowed from `source`.
+    stray: Cell<bool>,
+}
+
+impl<'a> Relocation<'_, 'a> {
+    fn offset(&self, offset: usize) -> usize {
+        if offset >= self.threshold {
+            (offset as isize + self.delta) as usize
+        } else {
+            offset
+        }
+    }
+
+    // Every string in a parsed tree is a slice of the source it was parsed
+    // from, apart from empty ones, which can be borrowed from anywhere. Any
+    // other string is replaced by an empty one and sets `stray`.
+    fn str(&self, value: &str) -> &'a str {
+        if value.is_empty() {
+            return "";
+        }
+
+        let text = (value.as_ptr() as usize)
+            .checked_sub(self.source.as_ptr() as usize)
+            .filter(|start| start + value.len() <= self.source.len())
+            .and_then(|start| {
+                let start = self.offset(start);
+
+                self.input.get(start..start + value.len())
+            });
+
+        text.unwrap_or_else(|| {
+            self.stray.set(true);
+            ""
+        })
+    }
+}
+
+// Moves a node, or part of one, into the arena of the new tree.
+trait Relocate<'a> {
+    type Relocated;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> Self::Relocated;
+}
+
+impl<'a> Relocate<'a> for NodeId {
+    type Relocated = NodeId;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> NodeId {
+        if self.index() >= relocation.first {
+            NodeId::new((self.index() as isize + relocation.offset) as usize)
+        } else {
+            self
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for Position {
+    type Relocated = Position;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> Position {
+        Position::new(relocation.offset(self.start), relocation.offset(self.end))
+    }
+}
+
+impl<'a> Relocate<'a> for &str {
+    type Relocated = &'a str;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> &'a str {
+        relocation.str(self)
+    }
+}
+
+impl<'a, T: Relocate<'a>> Relocate<'a> for Vec<T> {
+    type Relocated = Vec<T::Relocated>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> Self::Relocated {
+        self.into_iter()
+            .map(|item| item.relocate(relocation))
+            .collect()
+    }
+}
+
+impl<'a, T: Relocate<'a>> Relocate<'a> for Option<T> {
+    type Relocated = Option<T::Relocated>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> Self::Relocated {
+        self.map(|item| item.relocate(relocation))
+    }
+}
+
+impl<'a, T: Relocate<'a>> Relocate<'a> for Box<T> {
+    type Relocated = Box<T::Relocated>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> Self::Relocated {
+        Box::new((*self).relocate(relocation))
+    }
+}
+
+impl<'a> Relocate<'a> for Token<'_> {
+    type Relocated = Token<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> Token<'a> {
+        Token::new(
+            self.kind(),
+            relocation.str(self.value()),
+            self.position().relocate(relocation),
+        )
+    }
+}
+
+macro_rules! impl_relocate_nothing {
+    ($($type:ty),* $(,)?) => {
+        $(
+            impl<'a> Relocate<'a> for $type {
+                type Relocated = $type;
+
+                fn relocate(self, _relocation: &Relocation<'_, 'a>) -> $type {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_relocate_nothing!(
+    bool,
+    i64,
+    f64,
+    String,
+    BinaryOperator,
+    UnaryOperator,
+    Delimiter
+);
+
+// Destructures every field, so that adding one to a node without relocating
+// it doesn't compile.
+macro_rules! impl_relocate {
+    ($($node:ident$(<$lifetime:lifetime>)? { $($field:ident),* $(,)? }),* $(,)?) => {
+        $(impl_relocate!(@node $node$(<$lifetime>)? { $($field),* });)*
+    };
+    (@node $node:ident<$lifetime:lifetime> { $($field:ident),* }) => {
+        impl<$lifetime> Relocate<$lifetime> for $node<'_> {
+            type Relocated = $node<$lifetime>;
+
+            fn relocate(self, relocation: &Relocation<'_, $lifetime>) -> $node<$lifetime> {
+                let $node { $($field),* } = self;
+
+                $node { $($field: $field.relocate(relocation)),* }
+            }
+        }
+    };
+    (@node $node:ident { $($field:ident),* }) => {
+        impl<'a> Relocate<'a> for $node {
+            type Relocated = $node;
+
+            fn relocate(self, relocation: &Relocation<'_, 'a>) -> $node {
+                let $node { $($field),* } = self;
+
+                $node { $($field: $field.relocate(relocation)),* }
+            }
+        }
+    };
+}
+
+impl_relocate!(
+    BooleanLiteral { value, position },
+    IntegerLiteral { value, position },
+    NumberLiteral { value, position },
+    RegexpLiteral<'a> { value, position },
+    StringLiteral<'a> { value, position },
+    TemplateLiteral<'a> { template, values, position },
+    Identifier<'a> { value, position },
+    Record<'a> { members, position },
+    Array { elements, position },
+    Tuple { elements, position },
+    ImportDeclaration<'a> { default, namespace, named, type_only, specifier, position },
+    NamedBinding<'a> { name, alias, position },
+    ExportDeclaration<'a> { kind, position },
+    Call { callee, arguments, position },
+    MemberAccess<'a> { object, property, position },
+    Binary { operator, left, right, position },
+    Unary { operator, operand, position },
+    Block { body, position },
+    With { body, handlers, position },
+    LetBinding<'a> { pattern, mutable, value, position },
+    Assignment<'a> { target, value, position },
+    If { condition, consequent, alternative, position },
+    While<'a> { pattern, condition, body, position },
+    ForOf<'a> { pattern, iterable, body, position },
+    Return { value, position },
+    Yield { value, position },
+    Match<'a> { value, cases, position },
+    MatchCase<'a> { test, body, position },
+    ConstructorPattern<'a> { qualifier, name, arguments, position },
+    RecordPattern<'a> { fields, rest, position },
+    FieldPattern<'a> { name, pattern },
+    ArrayPattern<'a> { elements, rest, position },
+    TuplePattern<'a> { elements, position },
+    FunctionDeclaration<'a> { name, type_parameters, parameters, return_type, body, docs, position },
+    Lambda<'a> { type_parameters, parameters, return_type, body, position },
+    FunctionParameter<'a> { name, annotation, position },
+    TypeParameter<'a> { name, extends, position },
+    TypeDeclaration<'a> { name, type_parameters, type_constructors, docs, position },
+    StructDeclaration<'a> { name, type_parameters, fields, position },
+    TypeConstructor<'a> { name, type_parameters, parameters, return_type, position },
+    TypeAnnotation<'a> { annotated, position },
+    LabeledTypeAnnotation<'a> { name, annotated, position },
+    EffectDeclaration<'a> { name, type_parameters, operations, docs, position },
+    EffectOperation<'a> { name, type_parameters, parameters, return_type, position },
+    ImplBlock<'a> { type_parameters, effect, type_arguments, members, position },
+    TypeclassDeclaration<'a> { name, type_parameters, superclasses, associated_types, methods, position },
+    TypeclassMethod<'a> { name, type_parameters, parameters, return_type, default, position },
+    AssociatedType<'a> { name, bound, default, position },
+    InstanceDeclaration<'a> { type_parameters, typeclass, type_arguments, target, associated_types, members, position },
+    HandlerDeclaration<'a> { name, parameters, body, operations, return_clause, position },
+    OperationClause<'a> { name, parameters, body, position },
+    ReturnClause<'a> { parameter, body, position },
+    TypeAlias<'a> { name, type_parameters, aliased, docs, position },
+    MacroDeclaration<'a> { name, parameters, body, position },
+    MacroInvocation<'a> { name, group, position },
+    Delimited<'a> { delimiter, trees, text, position },
+    Docs { text, summary, description, sections, params, returns, position },
+    DocSection { title, body },
+    DocParam { name, description, position },
+);
+
+impl<'a> Relocate<'a> for AST<'_> {
+    type Relocated = AST<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> AST<'a> {
+        match self {
+            AST::BooleanLiteral(node) => AST::BooleanLiteral(node.relocate(relocation)),
+            AST::IntegerLiteral(node) => AST::IntegerLiteral(node.relocate(relocation)),
+            AST::NumberLiteral(node) => AST::NumberLiteral(node.relocate(relocation)),
+            AST::RegexpLiteral(node) => AST::RegexpLiteral(node.relocate(relocation)),
+            AST::StringLiteral(node) => AST::StringLiteral(node.relocate(relocation)),
+            AST::TemplateLiteral(node) => AST::TemplateLiteral(node.relocate(relocation)),
+            AST::Identifier(node) => AST::Identifier(node.relocate(relocation)),
+            AST::Record(node) => AST::Record(node.relocate(relocation)),
+            AST::Array(node) => AST::Array(node.relocate(relocation)),
+            AST::Tuple(node) => AST::Tuple(node.relocate(relocation)),
+            AST::Call(node) => AST::Call(node.relocate(relocation)),
+            AST::MemberAccess(node) => AST::MemberAccess(node.relocate(relocation)),
+            AST::Binary(node) => AST::Binary(node.relocate(relocation)),
+            AST::Unary(node) => AST::Unary(node.relocate(relocation)),
+            AST::Block(node) => AST::Block(node.relocate(relocation)),
+            AST::With(node) => AST::With(node.relocate(relocation)),
+            AST::Lambda(node) => AST::Lambda(node.relocate(relocation)),
+            AST::LetBinding(node) => AST::LetBinding(node.relocate(relocation)),
+            AST::Assignment(node) => AST::Assignment(node.relocate(relocation)),
+            AST::If(node) => AST::If(node.relocate(relocation)),
+            AST::While(node) => AST::While(node.relocate(relocation)),
+            AST::ForOf(node) => AST::ForOf(node.relocate(relocation)),
+            AST::Return(node) => AST::Return(node.relocate(relocation)),
+            AST::Yield(node) => AST::Yield(node.relocate(relocation)),
+            AST::ImportDeclaration(node) => AST::ImportDeclaration(node.relocate(relocation)),
+            AST::ExportDeclaration(node) => AST::ExportDeclaration(node.relocate(relocation)),
+            AST::Match(node) => AST::Match(node.relocate(relocation)),
+            AST::FunctionDeclaration(node) => AST::FunctionDeclaration(node.relocate(relocation)),
+            AST::TypeDeclaration(node) => AST::TypeDeclaration(node.relocate(relocation)),
+            AST::TypeAlias(node) => AST::TypeAlias(node.relocate(relocation)),
+            AST::StructDeclaration(node) => AST::StructDeclaration(node.relocate(relocation)),
+            AST::EffectDeclaration(node) => AST::EffectDeclaration(node.relocate(relocation)),
+            AST::ImplBlock(node) => AST::ImplBlock(node.relocate(relocation)),
+            AST::TypeclassDeclaration(node) => AST::TypeclassDeclaration(node.relocate(relocation)),
+            AST::InstanceDeclaration(node) => AST::InstanceDeclaration(node.relocate(relocation)),
+            AST::HandlerDeclaration(node) => AST::HandlerDeclaration(node.relocate(relocation)),
+            AST::MacroDeclaration(node) => AST::MacroDeclaration(node.relocate(relocation)),
+            AST::MacroInvocation(node) => AST::MacroInvocation(node.relocate(relocation)),
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for RecordMember<'_> {
+    type Relocated = RecordMember<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> RecordMember<'a> {
+        match self {
+            RecordMember::Field(name, value) => {
+                RecordMember::Field(name.relocate(relocation), value.relocate(relocation))
+            }
+            RecordMember::Shorthand(name) => RecordMember::Shorthand(name.relocate(relocation)),
+            RecordMember::Spread(value) => RecordMember::Spread(value.relocate(relocation)),
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for Element {
+    type Relocated = Element;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> Element {
+        match self {
+            Element::Member(value) => Element::Member(value.relocate(relocation)),
+            Element::Spread(value) => Element::Spread(value.relocate(relocation)),
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for ExportKind<'_> {
+    type Relocated = ExportKind<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> ExportKind<'a> {
+        match self {
+            ExportKind::Declaration(declaration) => {
+                ExportKind::Declaration(declaration.relocate(relocation))
+            }
+            ExportKind::Named { bindings, from } => ExportKind::Named {
+                bindings: bindings.relocate(relocation),
+                from: from.relocate(relocation),
+            },
+            ExportKind::All { alias, from } => ExportKind::All {
+                alias: alias.relocate(relocation),
+                from: from.relocate(relocation),
+            },
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for MatchTest<'_> {
+    type Relocated = MatchTest<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> MatchTest<'a> {
+        match self {
+            MatchTest::Pattern(pattern) => MatchTest::Pattern(pattern.relocate(relocation)),
+            MatchTest::Predicate(predicate) => MatchTest::Predicate(predicate.relocate(relocation)),
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for Pattern<'_> {
+    type Relocated = Pattern<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> Pattern<'a> {
+        match self {
+            Pattern::Wildcard(position) => Pattern::Wildcard(position.relocate(relocation)),
+            Pattern::Identifier(identifier) => Pattern::Identifier(identifier.relocate(relocation)),
+            Pattern::Literal(literal) => Pattern::Literal(literal.relocate(relocation)),
+            Pattern::Constructor(pattern) => Pattern::Constructor(pattern.relocate(relocation)),
+            Pattern::Record(pattern) => Pattern::Record(pattern.relocate(relocation)),
+            Pattern::Array(pattern) => Pattern::Array(pattern.relocate(relocation)),
+            Pattern::Tuple(pattern) => Pattern::Tuple(pattern.relocate(relocation)),
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for TokenTree<'_> {
+    type Relocated = TokenTree<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> TokenTree<'a> {
+        match self {
+            TokenTree::Token(token) => TokenTree::Token(token.relocate(relocation)),
+            TokenTree::Delimited(group) => TokenTree::Delimited(group.relocate(relocation)),
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for Type<'_> {
+    type Relocated = Type<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> Type<'a> {
+        match self {
+            Type::Unit => Type::Unit,
+            Type::Never => Type::Never,
+            Type::Unknown => Type::Unknown,
+            Type::Boolean => Type::Boolean,
+            Type::Int => Type::Int,
+            Type::Float => Type::Float,
+            Type::String => Type::String,
+            Type::Regexp(regexp) => Type::Regexp(regexp.relocate(relocation)),
+            Type::Template(types) => Type::Template(types.relocate(relocation)),
+            Type::Brand(label) => Type::Brand(label.relocate(relocation)),
+            Type::Option(type_) => Type::Option(type_.relocate(relocation)),
+            Type::Either(left, right) => {
+                Type::Either(left.relocate(relocation), right.relocate(relocation))
+            }
+            Type::Array(type_) => Type::Array(type_.relocate(relocation)),
+            Type::Tuple(members) => Type::Tuple(members.relocate(relocation)),
+            Type::Struct(members) => Type::Struct(members.relocate(relocation)),
+            Type::Function(type_parameters, arguments, effects, returned) => Type::Function(
+                type_parameters.relocate(relocation),
+                arguments.relocate(relocation),
+                effects.relocate(relocation),
+                returned.relocate(relocation),
+            ),
+            Type::Alias(name, type_) => {
+                Type::Alias(name.relocate(relocation), type_.relocate(relocation))
+            }
+            Type::Reference(name, arguments) => {
+                Type::Reference(name.relocate(relocation), arguments.relocate(relocation))
+            }
+            Type::Union(types) => Type::Union(types.relocate(relocation)),
+            Type::Intersection(types) => Type::Intersection(types.relocate(relocation)),
+            Type::Infer => Type::Infer,
+            Type::Variable(variable) => Type::Variable(variable),
+            Type::Any => Type::Any,
+            Type::Macro(invocation) => Type::Macro(invocation.relocate(relocation)),
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for TupleData<'_> {
+    type Relocated = TupleData<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> TupleData<'a> {
+        match self {
+            TupleData::Member(type_) => TupleData::Member(type_.relocate(relocation)),
+            TupleData::Spread(type_) => TupleData::Spread(type_.relocate(relocation)),
+        }
+    }
+}
+
+impl<'a> Relocate<'a> for StructData<'_> {
+    type Relocated = StructData<'a>;
+
+    fn relocate(self, relocation: &Relocation<'_, 'a>) -> StructData<'a> {
+        match self {
+            StructData::Member(name, type_) => {
+                StructData::Member(name.relocate(relocation), type_.relocate(relocation))
+            }
+            StructData::Spread(type_) => StructData::Spread(type_.relocate(relocation)),
+        }
+    }
+}
@@ -1,5 +1,31 @@
+pub use arena::*;
 pub use ast::*;
+pub use check::*;
+pub use dispatch::*;
+pub use docs::*;
+pub use error::*;
+pub use fold::Fold;
+pub use incremental::Edit;
+pub use parser::*;
+pub use passes::*;
+pub use printer::DisplayNode;
+pub use resolve::*;
 pub use types::*;
+pub use visit::Visitor;
+pub use visit_mut::VisitorMut;
 
+pub mod arena;
 pub mod ast;
+pub mod check;
+pub mod dispatch;
+pub mod docs;
+pub mod error;
+pub mod fold;
+pub mod incremental;
+pub mod parser;
+pub mod passes;
+pub mod printer;
+pub mod resolve;
 pub mod types;
+pub mod visit;
+pub mod visit_mut;
@@ -0,0 +1,2685 @@
+use std::{cell::Cell, collections::HashMap};
+
+use crate::{
+    lexing::{Position, Token, TokenKind, Tokenizer},
+    parsing::{arena::*, ast::*, docs::Docs, error::ParseError, types::*},
+};
+
+type ParseResult<T> = Result<T, ParseError>;
+
+struct FunctionParts<'a> {
+    name: &'a str,
+    type_parameters: Vec<TypeParameter<'a>>,
+    parameters: Vec<FunctionParameter<'a>>,
+    return_type: Option<TypeAnnotation<'a>>,
+    body: Option<Vec<NodeId>>,
+    position: Position,
+}
+
+pub struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token<'a>>,
+    // Whether a line break separates tokens[i] from the token before it.
+    // Statements are newline terminated, so the parser needs to know.
+    line_breaks: Vec<bool>,
+    // Doc comments, by the start of the token that follows them.
+    doc_comments: HashMap<usize, Token<'a>>,
+    index: usize,
+    // Cleared while parsing a match case's pattern, where `(a, b) -> ...`
+    // is a tuple pattern followed by the case's body, not a lambda.
+    allow_lambdas: bool,
+    // The names bound in each enclosing scope, and whether they were bound
+    // mutably with `:=`. `name = value` reassigns the nearest binding when
+    // it is mutable and introduces a new constant binding otherwise.
+    scopes: Vec<Vec<(&'a str, bool)>>,
+    // The furthest token the parser has looked at. When only part of the
+    // tokens were read, see parse_statement_at, looking past the last one
+    // means the parse may have depended on the tokens that weren't read.
+    reach: Cell<usize>,
+    arena: Arena<'a>,
+}
+
+// A position to rewind a speculative parse to: the token index, the scope
+// depth and the number of nodes allocated so far.
+#[derive(Clone, Copy)]
+struct Checkpoint {
+    index: usize,
+    depth: usize,
+    nodes: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> ParseResult<Parser<'a>> {
+        let (tokens, line_breaks, doc_comments) = tokenize(input, 0)?;
+
+        Ok(Parser {
+            input,
+            tokens,
+            line_breaks,
+            doc_comments,
+            index: 0,
+            allow_lambdas: true,
+            scopes: vec![vec![]],
+            reach: Cell::new(0),
+            arena: Arena::new(),
+        })
+    }
+
+    pub fn parse_module(&mut self) -> ParseResult<SourceFile<'a>> {
+        let mut body = vec![];
+
+        while !self.is_at_end() {
+            let statement = match self.peek_kind() {
+                Some(TokenKind::Import) => self.parse_import_declaration()?,
+                Some(TokenKind::Export) => self.parse_export_declaration()?,
+                _ => self.parse_statement()?,
+            };
+
+            body.push(statement);
+            self.end_statement(None)?;
+        }
+
+        Ok(SourceFile::new(std::mem::take(&mut self.arena), body))
+    }
+
+    // Parses as much of a module as it can, for the concrete syntax tree: a
+    // statement that doesn't parse is skipped, and so is a character no
+    // token matches. Returns the statements that did parse, and each error
+    // along with the position of what was skipped because of it.
+    pub(crate) fn parse_module_leniently(
+        input: &'a str,
+    ) -> (SourceFile<'a>, Vec<(ParseError, Position)>) {
+        let (((tokens, line_breaks, doc_comments), _), errors) = scan(input, 0, usize::MAX);
+        let mut skipped: Vec<_> = errors
+            .into_iter()
+            .map(|error| {
+                let position = error.position();
+                (error, position)
+            })
+            .collect();
+        let mut parser = Parser {
+            input,
+            tokens,
+            line_breaks,
+            doc_comments,
+            index: 0,
+            allow_lambdas: true,
+            scopes: vec![vec![]],
+            reach: Cell::new(0),
+            arena: Arena::new(),
+        };
+        let mut body = vec![];
+
+        while !parser.is_at_end() {
+            let checkpoint = parser.checkpoint();
+            let names = parser.scopes[0].len();
+            let statement = match parser.peek_kind() {
+                Some(TokenKind::Import) => parser.parse_import_declaration(),
+                Some(TokenKind::Export) => parser.parse_export_declaration(),
+                _ => parser.parse_statement(),
+            }
+            .and_then(|statement| parser.end_statement(None).map(|_| statement));
+
+            match statement {
+                Ok(statement) => body.push(statement),
+                Err(error) => {
+                    parser.restore(checkpoint);
+                    parser.scopes[0].truncate(names);
+                    parser.allow_lambdas = true;
+
+                    let start = parser.start();
+
+                    parser.skip_statement();
+                    skipped.push((error, parser.span(start)));
+                }
+            }
+        }
+
+        skipped.sort_by_key(|(_, position)| position.start);
+
+        (SourceFile::new(parser.arena, body), skipped)
+    }
+
+    // Parses the statement at `position` on its own, for reparse. Tokens
+    // are read from `from`, the end of the token before the statement, to a
+    // few tokens past it, and `scope` holds the names visible to it.
+    // `closing` closes the list of statements it is in, or is None at the
+    // top level. None when the statement no longer parses or no longer
+    // ends at `position.end`, or when telling would take more tokens.
+    pub(crate) fn parse_statement_at(
+        input: &'a str,
+        from: usize,
+        position: Position,
+        closing: Option<TokenKind>,
+        scope: Vec<(&'a str, bool)>,
+    ) -> Option<(Arena<'a>, NodeId)> {
+        let ((tokens, line_breaks, doc_comments), truncated) =
+            tokenize_until(input, from, position.end).ok()?;
+        let index = tokens
+            .iter()
+            .position(|token| token.position().start >= position.start)?;
+        let mut parser = Parser {
+            input,
+            tokens,
+            line_breaks,
+            doc_comments,
+            index,
+            allow_lambdas: true,
+            scopes: vec![scope],
+            reach: Cell::new(index),
+            arena: Arena::new(),
+        };
+
+        if parser.start() != position.start {
+            return None;
+        }
+
+        let statement = match (closing, parser.peek_kind()) {
+            (None, Some(TokenKind::Import)) => parser.parse_import_declaration(),
+            (None, Some(TokenKind::Export)) => parser.parse_export_declaration(),
+            _ => parser.parse_statement(),
+        }
+        .ok()?;
+        let ended = parser.span(position.start).end == position.end
+            && parser.end_statement(closing).is_ok();
+
+        if !ended
+            || truncated && parser.reach.get() >= parser.tokens.len()
+            || statement.index() + 1 != parser.arena.len()
+        {
+            return None;
+        }
+
+        Some((parser.arena, statement))
+    }
+
+    // Modules
+
+    // import [type] (Default | Default, { named } | { named } | * as Namespace) from 'specifier'
+    fn parse_import_declaration(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::Import, "`import`")?;
+
+        let type_only = self.eat(TokenKind::Type).is_some();
+        let mut default = None;
+        let mut namespace = None;
+        let mut named = vec![];
+
+        if self.eat(TokenKind::Star).is_some() {
+            self.expect_contextual("as")?;
+            namespace = Some(
+                self.expect(TokenKind::Identifier, "a namespace name")?
+                    .value(),
+            );
+        } else {
+            if self.check(TokenKind::Identifier) {
+                default = Some(self.advance().value());
+
+                if self.eat(TokenKind::Comma).is_none() {
+                    return self.finish_import(start, default, namespace, named, type_only);
+                }
+            }
+
+            named = self.parse_named_bindings()?;
+        }
+
+        self.finish_import(start, default, namespace, named, type_only)
+    }
+
+    fn finish_import(
+        &mut self,
+        start: usize,
+        default: Option<&'a str>,
+        namespace: Option<&'a str>,
+        named: Vec<NamedBinding<'a>>,
+        type_only: bool,
+    ) -> ParseResult<NodeId> {
+        self.expect(TokenKind::From, "`from`")?;
+
+        let specifier = self.parse_module_specifier()?;
+
+        Ok(self.alloc(AST::import_declaration(
+            default,
+            namespace,
+            named,
+            type_only,
+            specifier,
+            self.span(start),
+        )))
+    }
+
+    // export <declaration>, export { a, b as c } [from '...'], export * [as A] from '...'
+    fn parse_export_declaration(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::Export, "`export`")?;
+
+        let kind = if self.eat(TokenKind::Star).is_some() {
+            let alias = if self.eat_contextual("as") {
+                Some(
+                    self.expect(TokenKind::Identifier, "a namespace name")?
+                        .value(),
+                )
+            } else {
+                None
+            };
+
+            self.expect(TokenKind::From, "`from`")?;
+
+            ExportKind::All {
+                alias,
+                from: self.parse_module_specifier()?,
+            }
+        } else if self.check(TokenKind::LeftBrace) {
+            let bindings = self.parse_named_bindings()?;
+            let from = if self.eat(TokenKind::From).is_some() {
+                Some(self.parse_module_specifier()?)
+            } else {
+                None
+            };
+
+            ExportKind::Named { bindings, from }
+        } else {
+            let declaration_start = self.start();
+            let declaration = self.parse_statement()?;
+
+            if !is_exportable(&self.arena[declaration]) {
+                return Err(ParseError::new(
+                    "Only declarations can be exported",
+                    self.span(declaration_start),
+                ));
+            }
+
+            ExportKind::Declaration(declaration)
+        };
+
+        Ok(self.alloc(AST::export_declaration(kind, self.span(start))))
+    }
+
+    fn parse_named_bindings(&mut self) -> ParseResult<Vec<NamedBinding<'a>>> {
+        let mut bindings = vec![];
+
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            let start = self.start();
+            let name = self.expect(TokenKind::Identifier, "a name")?.value();
+            let alias = if self.eat_contextual("as") {
+                Some(self.expect(TokenKind::Identifier, "an alias")?.value())
+            } else {
+                None
+            };
+
+            bindings.push(NamedBinding {
+                name,
+                alias,
+                position: self.span(start),
+            });
+            self.end_list_item(TokenKind::RightBrace)?;
+        }
+
+        self.expect(TokenKind::RightBrace, "`}`")?;
+
+        Ok(bindings)
+    }
+
+    fn parse_module_specifier(&mut self) -> ParseResult<&'a str> {
+        let value = self
+            .expect(TokenKind::StringLiteral, "a module specifier")?
+            .value();
+
+        Ok(&value[1..value.len() - 1])
+    }
+
+    // Declarations
+
+    fn parse_statement(&mut self) -> ParseResult<NodeId> {
+        match self.peek_kind() {
+            Some(TokenKind::Function) => self.parse_function_declaration(),
+            Some(TokenKind::Implementation) => self.parse_impl_block(),
+            Some(TokenKind::Effect) => self.parse_effect_declaration(),
+            Some(TokenKind::Type) => self.parse_type_declaration(),
+            Some(TokenKind::Alias) | Some(TokenKind::Brand) => self.parse_type_alias(),
+            Some(TokenKind::Struct) => self.parse_struct_declaration(),
+            Some(TokenKind::Typeclass) => self.parse_typeclass_declaration(),
+            Some(TokenKind::Macro) => self.parse_macro_declaration(),
+            Some(TokenKind::If) => self.parse_if(),
+            Some(TokenKind::While) => self.parse_while(),
+            Some(TokenKind::For) => self.parse_for_of(),
+            Some(TokenKind::Return) | Some(TokenKind::Yield) => self.parse_return_or_yield(),
+            _ => self.parse_expression_statement(),
+        }
+    }
+
+    // if cond { ... } else if cond { ... } else { ... }, or with a
+    // parenthesised condition and a single statement: if (cond) return x
+    fn parse_if(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        if self.eat(TokenKind::ElseIf).is_none() {
+            self.expect(TokenKind::If, "`if`")?;
+        }
+
+        let parenthesised = self.check(TokenKind::LeftParen);
+        let condition = self.parse_with()?;
+        let consequent = if parenthesised && !self.check(TokenKind::LeftBrace) {
+            vec![self.parse_statement()?]
+        } else {
+            self.parse_block_body()?.0
+        };
+        let alternative = match self.peek_kind() {
+            Some(TokenKind::ElseIf) => Some(self.parse_if()?),
+            Some(TokenKind::Else) if self.peek_kind_at(1) == Some(TokenKind::If) => {
+                self.advance();
+
+                Some(self.parse_if()?)
+            }
+            Some(TokenKind::Else) => {
+                self.advance();
+
+                let (body, position) = self.parse_block_body()?;
+
+                Some(self.alloc(AST::block(body, position)))
+            }
+            _ => None,
+        };
+
+        Ok(self.alloc(AST::if_(
+            condition,
+            consequent,
+            alternative,
+            self.span(start),
+        )))
+    }
+
+    // while cond { ... }, while pattern = value { ... }, or either with the
+    // head in parentheses: while (Some(value) = next()) { ... }
+    fn parse_while(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::While, "`while`")?;
+
+        let checkpoint = self.checkpoint();
+        let mut head = None;
+
+        if self.eat(TokenKind::LeftParen).is_some() {
+            if let Ok(test) = self.parse_with() {
+                if self.eat(TokenKind::Equal).is_some() {
+                    let pattern = self.take_binding_pattern(test, start)?;
+                    let value = self.parse_expression()?;
+
+                    self.expect(TokenKind::RightParen, "`)`")?;
+                    head = Some((Some(pattern), value));
+                }
+            }
+
+            if head.is_none() {
+                self.restore(checkpoint);
+            }
+        }
+
+        let (pattern, condition) = match head {
+            Some(head) => head,
+            None => {
+                let test = self.parse_with()?;
+
+                if self.eat(TokenKind::Equal).is_some() {
+                    (
+                        Some(self.take_binding_pattern(test, start)?),
+                        self.parse_with()?,
+                    )
+                } else {
+                    (None, test)
+                }
+            }
+        };
+
+        self.enter_scope();
+
+        if let Some(pattern) = &pattern {
+            self.declare_pattern(pattern, false);
+        }
+
+        let (body, _) = self.parse_block_body()?;
+
+        self.exit_scope();
+
+        Ok(self.alloc(AST::while_(pattern, condition, body, self.span(start))))
+    }
+
+    // for pattern of iterable { ... } or for (pattern of iterable) { ... }
+    fn parse_for_of(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::For, "`for`")?;
+
+        let checkpoint = self.checkpoint();
+        let mut parenthesised = false;
+
+        if self.eat(TokenKind::LeftParen).is_some() {
+            parenthesised = self.parse_with().is_ok() && self.check(TokenKind::Of);
+            self.restore(checkpoint);
+
+            if parenthesised {
+                self.advance();
+            }
+        }
+
+        let test = self.parse_with()?;
+        let pattern = self.take_binding_pattern(test, start)?;
+
+        self.expect(TokenKind::Of, "`of`")?;
+
+        let iterable = self.parse_with()?;
+
+        if parenthesised {
+            self.expect(TokenKind::RightParen, "`)`")?;
+        }
+
+        self.enter_scope();
+        self.declare_pattern(&pattern, false);
+
+        let (body, _) = self.parse_block_body()?;
+
+        self.exit_scope();
+
+        Ok(self.alloc(AST::for_of(pattern, iterable, body, self.span(start))))
+    }
+
+    fn parse_return_or_yield(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let keyword = self.advance();
+        let value = if self.is_at_end()
+            || self.line_break_before()
+            || self.check(TokenKind::RightBrace)
+            || self.check(TokenKind::Semicolon)
+        {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+
+        if keyword.kind() == TokenKind::Yield {
+            Ok(self.alloc(AST::yield_(value, self.span(start))))
+        } else {
+            Ok(self.alloc(AST::return_(value, self.span(start))))
+        }
+    }
+
+    fn take_binding_pattern(
+        &mut self,
+        expression: NodeId,
+        start: usize,
+    ) -> ParseResult<Pattern<'a>> {
+        let pattern = self.to_pattern(expression).ok_or_else(|| {
+            ParseError::new("Invalid left-hand side of a binding", self.span(start))
+        })?;
+
+        self.discard(expression);
+
+        Ok(pattern)
+    }
+
+    fn to_pattern(&self, expression: NodeId) -> Option<Pattern<'a>> {
+        to_pattern(&self.arena, expression)
+    }
+
+    // pattern = value, pattern := value, or a bare expression
+    fn parse_expression_statement(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let expression = self.parse_expression()?;
+        let mutable = match self.peek_kind() {
+            Some(TokenKind::ColonEqual) => true,
+            Some(TokenKind::Equal) => false,
+            _ => return Ok(expression),
+        };
+        let pattern = self.take_binding_pattern(expression, start)?;
+
+        self.advance();
+
+        let value = self.parse_expression()?;
+
+        self.declare_pattern(&pattern, mutable);
+
+        Ok(self.alloc(AST::let_binding(pattern, mutable, value, self.span(start))))
+    }
+
+    fn parse_function_declaration(&mut self) -> ParseResult<NodeId> {
+        let docs = self.leading_docs();
+        let function = self.parse_function(true)?;
+        let declaration = AST::function_declaration(
+            function.name,
+            function.type_parameters,
+            function.parameters,
+            function.return_type,
+            function.body.unwrap_or_default(),
+            function.position,
+        );
+
+        Ok(self.alloc(declaration.with_docs(docs)))
+    }
+
+    // fun name<A>(parameters): ReturnType { body }, where the body is
+    // optional for typeclass methods without a default implementation.
+    fn parse_function(&mut self, body_required: bool) -> ParseResult<FunctionParts<'a>> {
+        let start = self.start();
+
+        self.expect(TokenKind::Function, "`fun`")?;
+
+        let name = self
+            .expect(TokenKind::Identifier, "a function name")?
+            .value();
+
+        self.declare(name, false);
+        self.enter_scope();
+
+        let type_parameters = self.parse_type_parameters()?;
+        let parameters = self.parse_parameters()?;
+        let return_type =
+            if self.eat(TokenKind::Colon).is_some() || self.eat(TokenKind::RightArrow).is_some() {
+                Some(self.parse_type_annotation(true)?)
+            } else {
+                None
+            };
+        let body = if body_required || self.check(TokenKind::LeftBrace) {
+            Some(self.parse_block_body()?.0)
+        } else {
+            None
+        };
+
+        self.exit_scope();
+
+        Ok(FunctionParts {
+            name,
+            type_parameters,
+            parameters,
+            return_type,
+            body,
+            position: self.span(start),
+        })
+    }
+
+    // typeclass Ord<A>: Eq<A>, Show<A> {
+    //   type Key
+    //   fun compare(a: A, b: A): Int
+    //   fun max(a: A, b: A): A { ... }
+    // }
+    fn parse_typeclass_declaration(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::Typeclass, "`typeclass`")?;
+
+        let name = self
+            .expect(TokenKind::Identifier, "a typeclass name")?
+            .value();
+        let type_parameters = self.parse_type_parameters()?;
+        let mut superclasses = vec![];
+        let mut associated_types = vec![];
+        let mut methods = vec![];
+
+        if self.eat(TokenKind::Colon).is_some() {
+            loop {
+                superclasses.push(self.parse_type_annotation(false)?);
+
+                if self.eat(TokenKind::Comma).is_none() {
+                    break;
+                }
+            }
+        }
+
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            match self.peek_kind() {
+                Some(TokenKind::Type) => associated_types.push(self.parse_associated_type()?),
+                Some(TokenKind::Function) => {
+                    let method = self.parse_function(false)?;
+
+                    methods.push(TypeclassMethod {
+                        name: method.name,
+                        type_parameters: method.type_parameters,
+                        parameters: method.parameters,
+                        return_type: method.return_type,
+                        default: method.body,
+                        position: method.position,
+                    });
+                }
+                _ => return self.error("`fun` or `type` inside a typeclass"),
+            }
+
+            self.end_statement(Some(TokenKind::RightBrace))?;
+        }
+
+        self.expect(TokenKind::RightBrace, "`}`")?;
+
+        Ok(self.alloc(AST::typeclass_declaration(
+            name,
+            type_parameters,
+            superclasses,
+            associated_types,
+            methods,
+            self.span(start),
+        )))
+    }
+
+    // type Item, type Item: Bound, or type Item = Default. Instances use the
+    // last form to define the type.
+    fn parse_associated_type(&mut self) -> ParseResult<AssociatedType<'a>> {
+        let start = self.start();
+
+        self.expect(TokenKind::Type, "`type`")?;
+
+        let name = self.expect(TokenKind::Identifier, "a type name")?.value();
+        let bound = if self.eat(TokenKind::Colon).is_some() {
+            Some(self.parse_type_annotation(true)?)
+        } else {
+            None
+        };
+        let default = if self.eat(TokenKind::Equal).is_some() {
+            Some(self.parse_type_annotation(true)?)
+        } else {
+            None
+        };
+
+        Ok(AssociatedType {
+            name,
+            bound,
+            default,
+            position: self.span(start),
+        })
+    }
+
+    // type Either<E, A> = | Left(E) | Right(A)
+    fn parse_type_declaration(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let docs = self.leading_docs();
+
+        self.expect(TokenKind::Type, "`type`")?;
+
+        let name = self.expect(TokenKind::Identifier, "a type name")?.value();
+        let type_parameters = self.parse_type_parameters()?;
+        let mut type_constructors = vec![];
+
+        self.expect(TokenKind::Equal, "`=`")?;
+        self.eat(TokenKind::Or);
+
+        loop {
+            type_constructors.push(self.parse_type_constructor()?);
+
+            if self.eat(TokenKind::Or).is_none() {
+                break;
+            }
+        }
+
+        let declaration =
+            AST::type_declaration(name, type_parameters, type_constructors, self.span(start));
+
+        Ok(self.alloc(declaration.with_docs(docs)))
+    }
+
+    // alias Name<A> = Type, or brand Name = Type. A brand is a type that
+    // isn't interchangeable with the one it brands, so `brand UserId = Int`
+    // aliases `Int & UserId`, where only UserIds carry the `UserId` label.
+    fn parse_type_alias(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let docs = self.leading_docs();
+        let brand = self.eat(TokenKind::Brand).is_some();
+
+        if !brand {
+            self.expect(TokenKind::Alias, "`alias`")?;
+        }
+
+        let name = self.expect(TokenKind::Identifier, "a type name")?.value();
+        let type_parameters = self.parse_type_parameters()?;
+
+        self.expect(TokenKind::Equal, "`=`")?;
+
+        let mut aliased = self.parse_type_annotation(true)?;
+
+        if brand {
+            aliased.annotated = match aliased.annotated {
+                Type::Intersection(mut members) => {
+                    members.push(Type::Brand(name));
+
+                    Type::Intersection(members)
+                }
+                type_ => Type::Intersection(vec![type_, Type::Brand(name)]),
+            };
+        }
+
+        let alias = AST::type_alias(name, type_parameters, aliased, self.span(start));
+
+        Ok(self.alloc(alias.with_docs(docs)))
+    }
+
+    // Nullary `None`, positional `Some(A)`, record `Point { x: Int, y: Int }`,
+    // each optionally with a GADT-style return type: `IntLiteral(Int): Expr<Int>`
+    // struct Point<A> { x: A, y: A, ...Rest }
+    fn parse_struct_declaration(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::Struct, "`struct`")?;
+
+        let name = self.expect(TokenKind::Identifier, "a struct name")?.value();
+        let type_parameters = self.parse_type_parameters()?;
+
+        if !self.check(TokenKind::LeftBrace) {
+            return self.error("`{`");
+        }
+
+        let Type::Struct(fields) = self.parse_primary_type()? else {
+            unreachable!("a `{{` always starts a struct type");
+        };
+
+        Ok(self.alloc(AST::struct_declaration(
+            name,
+            type_parameters,
+            fields,
+            self.span(start),
+        )))
+    }
+
+    fn parse_type_constructor(&mut self) -> ParseResult<TypeConstructor<'a>> {
+        let start = self.start();
+        let name = self
+            .expect(TokenKind::Identifier, "a constructor name")?
+            .value();
+        let type_parameters = self.parse_type_parameters()?;
+        let mut parameters = vec![];
+
+        if self.eat(TokenKind::LeftParen).is_some() {
+            while !self.check(TokenKind::RightParen) && !self.is_at_end() {
+                parameters.push(self.parse_labeled_type_annotation(false)?);
+
+                if self.eat(TokenKind::Comma).is_none() {
+                    break;
+                }
+            }
+
+            self.expect(TokenKind::RightParen, "`)`")?;
+        } else if self.eat(TokenKind::LeftBrace).is_some() {
+            while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+                parameters.push(self.parse_labeled_type_annotation(true)?);
+                self.end_list_item(TokenKind::RightBrace)?;
+            }
+
+            self.expect(TokenKind::RightBrace, "`}`")?;
+        }
+
+        // The return type stops short of unions, as `|` starts the next constructor.
+        let return_type = if self.eat(TokenKind::Colon).is_some() {
+            let return_start = self.start();
+            let annotated = self.parse_intersection_type()?;
+
+            Some(TypeAnnotation {
+                annotated,
+                position: self.span(return_start),
+            })
+        } else {
+            None
+        };
+
+        Ok(TypeConstructor {
+            name,
+            type_parameters,
+            parameters,
+            return_type,
+            position: self.span(start),
+        })
+    }
+
+    fn parse_labeled_type_annotation(
+        &mut self,
+        required: bool,
+    ) -> ParseResult<LabeledTypeAnnotation<'a>> {
+        let start = self.start();
+        let name = if required
+            || (self.check(TokenKind::Identifier) && self.peek_kind_at(1) == Some(TokenKind::Colon))
+        {
+            let name = self.expect(TokenKind::Identifier, "a field name")?.value();
+
+            self.expect(TokenKind::Colon, "`:`")?;
+
+            Some(name)
+        } else {
+            None
+        };
+        let annotated = self.parse_type_annotation(true)?;
+
+        Ok(LabeledTypeAnnotation {
+            name,
+            annotated,
+            position: self.span(start),
+        })
+    }
+
+    // effect Fail<E>(E) -> Never, or effect Queue<A> { enqueue(A): Boolean ... }
+    fn parse_effect_declaration(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let docs = self.leading_docs();
+
+        self.expect(TokenKind::Effect, "`effect`")?;
+
+        let name = self
+            .expect(TokenKind::Identifier, "an effect name")?
+            .value();
+        let type_parameters = self.parse_type_parameters()?;
+        let mut operations = vec![];
+
+        if self.check(TokenKind::LeftParen) {
+            operations.push(self.parse_effect_operation(None, start)?);
+        } else {
+            self.expect(TokenKind::LeftBrace, "`{` or `(`")?;
+
+            while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+                let operation_start = self.start();
+                let operation = self
+                    .expect(TokenKind::Identifier, "an operation name")?
+                    .value();
+
+                operations.push(self.parse_effect_operation(Some(operation), operation_start)?);
+                self.end_list_item(TokenKind::RightBrace)?;
+            }
+
+            self.expect(TokenKind::RightBrace, "`}`")?;
+        }
+
+        let declaration =
+            AST::effect_declaration(name, type_parameters, operations, self.span(start));
+
+        Ok(self.alloc(declaration.with_docs(docs)))
+    }
+
+    // <B>(A -> (B, A)) -> B, with either `->` or `:` before the return type
+    fn parse_effect_operation(
+        &mut self,
+        name: Option<&'a str>,
+        start: usize,
+    ) -> ParseResult<EffectOperation<'a>> {
+        let type_parameters = match name {
+            Some(_) => self.parse_type_parameters()?,
+            None => vec![],
+        };
+        let mut parameters = vec![];
+
+        self.expect(TokenKind::LeftParen, "`(`")?;
+
+        while !self.check(TokenKind::RightParen) && !self.is_at_end() {
+            parameters.push(self.parse_type_annotation(true)?);
+
+            if self.eat(TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+
+        self.expect(TokenKind::RightParen, "`)`")?;
+
+        if self.eat(TokenKind::RightArrow).is_none() {
+            self.expect(TokenKind::Colon, "`->` or `:`")?;
+        }
+
+        let return_type = self.parse_type_annotation(true)?;
+
+        Ok(EffectOperation {
+            name,
+            type_parameters,
+            parameters,
+            return_type,
+            position: self.span(start),
+        })
+    }
+
+    fn parse_impl_block(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::Implementation, "`impl`")?;
+
+        let type_parameters = self.parse_type_parameters()?;
+
+        // `impl Typeclass for Type` names the typeclass before `for`, while
+        // effect handlers are implemented with `impl for Effect`.
+        if self.check(TokenKind::Identifier) {
+            return self.parse_instance_declaration(start, type_parameters);
+        }
+
+        self.expect(TokenKind::For, "`for`")?;
+
+        let effect = self
+            .expect(TokenKind::Identifier, "an effect name")?
+            .value();
+        let type_arguments = self.parse_type_arguments()?;
+        let mut members = vec![];
+
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            let member = match self.peek_kind() {
+                Some(TokenKind::Function) => self.parse_function_declaration()?,
+                Some(TokenKind::Handler) => self.parse_handler()?,
+                Some(TokenKind::MacroIdentifier) => self.parse_macro_node()?,
+                _ => return self.error("`fun` or `handler` inside an impl block"),
+            };
+
+            members.push(member);
+            self.end_statement(Some(TokenKind::RightBrace))?;
+        }
+
+        self.expect(TokenKind::RightBrace, "`}`")?;
+
+        Ok(self.alloc(AST::impl_block(
+            type_parameters,
+            effect,
+            type_arguments,
+            members,
+            self.span(start),
+        )))
+    }
+
+    fn parse_instance_declaration(
+        &mut self,
+        start: usize,
+        type_parameters: Vec<TypeParameter<'a>>,
+    ) -> ParseResult<NodeId> {
+        let typeclass = self
+            .expect(TokenKind::Identifier, "a typeclass name")?
+            .value();
+        let type_arguments = self.parse_type_arguments()?;
+
+        self.expect(TokenKind::For, "`for`")?;
+
+        let target = self.parse_type_annotation(false)?;
+        let mut associated_types = vec![];
+        let mut members = vec![];
+
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            match self.peek_kind() {
+                Some(TokenKind::Type) => associated_types.push(self.parse_associated_type()?),
+                Some(TokenKind::Function) => members.push(self.parse_function_declaration()?),
+                Some(TokenKind::MacroIdentifier) => members.push(self.parse_macro_node()?),
+                _ => return self.error("`fun` or `type` inside an instance"),
+            }
+
+            self.end_statement(Some(TokenKind::RightBrace))?;
+        }
+
+        self.expect(TokenKind::RightBrace, "`}`")?;
+
+        Ok(self.alloc(AST::instance_declaration(
+            type_parameters,
+            typeclass,
+            type_arguments,
+            target,
+            associated_types,
+            members,
+            self.span(start),
+        )))
+    }
+
+    // handler name?(parameters) { state... return { clauses } } { return(a) { ... } }
+    fn parse_handler(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::Handler, "`handler`")?;
+
+        let name = self.eat(TokenKind::Identifier).map(|token| token.value());
+
+        self.enter_scope();
+
+        let parameters = self.parse_parameters()?;
+        let mut body = vec![];
+        let mut operations = vec![];
+
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            if self.check(TokenKind::Return) && self.is_operation_clauses_ahead() {
+                self.advance();
+                operations = self.parse_operation_clauses()?;
+                self.end_statement(Some(TokenKind::RightBrace))?;
+
+                if !self.check(TokenKind::RightBrace) {
+                    return self.error("`}` after a handler's operations");
+                }
+
+                break;
+            }
+
+            body.push(self.parse_statement()?);
+            self.end_statement(Some(TokenKind::RightBrace))?;
+        }
+
+        self.expect(TokenKind::RightBrace, "`}`")?;
+        self.exit_scope();
+
+        let return_clause = if self.check(TokenKind::LeftBrace) && !self.line_break_before() {
+            Some(self.parse_return_clause()?)
+        } else {
+            None
+        };
+
+        Ok(self.alloc(AST::handler_declaration(
+            name,
+            parameters,
+            body,
+            operations,
+            return_clause,
+            self.span(start),
+        )))
+    }
+
+    // `return { enqueue(a) { ... } }` lists a handler's operations, while
+    // `return { a, b }` is an ordinary return of a record.
+    fn is_operation_clauses_ahead(&self) -> bool {
+        self.peek_kind_at(1) == Some(TokenKind::LeftBrace)
+            && matches!(
+                (self.peek_kind_at(2), self.peek_kind_at(3)),
+                (Some(TokenKind::RightBrace), _)
+                    | (Some(TokenKind::Identifier), Some(TokenKind::LeftParen))
+            )
+    }
+
+    fn parse_operation_clauses(&mut self) -> ParseResult<Vec<OperationClause<'a>>> {
+        let mut operations = vec![];
+
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            let start = self.start();
+            let name = self
+                .expect(TokenKind::Identifier, "an operation name")?
+                .value();
+
+            self.enter_scope();
+
+            let parameters = self.parse_parameters()?;
+            let (body, _) = self.parse_block_body()?;
+
+            self.exit_scope();
+
+            operations.push(OperationClause {
+                name,
+                parameters,
+                body,
+                position: self.span(start),
+            });
+
+            self.end_list_item(TokenKind::RightBrace)?;
+        }
+
+        self.expect(TokenKind::RightBrace, "`}`")?;
+
+        Ok(operations)
+    }
+
+    fn parse_return_clause(&mut self) -> ParseResult<ReturnClause<'a>> {
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+
+        let start = self.start();
+
+        self.expect(TokenKind::Return, "`return`")?;
+        self.enter_scope();
+
+        let mut parameters = self.parse_parameters()?;
+
+        if parameters.len() != 1 {
+            return Err(ParseError::new(
+                "A return clause takes exactly one parameter",
+                self.span(start),
+            ));
+        }
+
+        let (body, _) = self.parse_block_body()?;
+        let position = self.span(start);
+
+        self.exit_scope();
+        self.end_statement(Some(TokenKind::RightBrace))?;
+        self.expect(TokenKind::RightBrace, "`}`")?;
+
+        Ok(ReturnClause {
+            parameter: parameters.remove(0),
+            body,
+            position,
+        })
+    }
+
+    fn parse_type_parameters(&mut self) -> ParseResult<Vec<TypeParameter<'a>>> {
+        let mut type_parameters = vec![];
+
+        if self.eat(TokenKind::LessThan).is_none() {
+            return Ok(type_parameters);
+        }
+
+        while !self.check_closing_angle() && !self.is_at_end() {
+            let start = self.start();
+            let name = self
+                .expect(TokenKind::Identifier, "a type parameter")?
+                .value();
+            let extends = if self.eat(TokenKind::Colon).is_some() {
+                Some(self.parse_type(true)?)
+            } else {
+                None
+            };
+
+            type_parameters.push(TypeParameter {
+                name,
+                extends,
+                position: self.span(start),
+            });
+
+            if self.eat(TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+
+        self.expect_closing_angle()?;
+
+        Ok(type_parameters)
+    }
+
+    fn parse_type_arguments(&mut self) -> ParseResult<Vec<TypeAnnotation<'a>>> {
+        let mut type_arguments = vec![];
+
+        if self.eat(TokenKind::LessThan).is_none() {
+            return Ok(type_arguments);
+        }
+
+        while !self.check_closing_angle() && !self.is_at_end() {
+            type_arguments.push(self.parse_type_annotation(true)?);
+
+            if self.eat(TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+
+        self.expect_closing_angle()?;
+
+        Ok(type_arguments)
+    }
+
+    fn parse_parameters(&mut self) -> ParseResult<Vec<FunctionParameter<'a>>> {
+        let mut parameters = vec![];
+
+        self.expect(TokenKind::LeftParen, "`(`")?;
+
+        while !self.check(TokenKind::RightParen) && !self.is_at_end() {
+            let start = self.start();
+            let name = self
+                .expect(TokenKind::Identifier, "a parameter name")?
+                .value();
+            let annotation = if self.eat(TokenKind::Colon).is_some() {
+                Some(self.parse_type_annotation(true)?)
+            } else {
+                None
+            };
+
+            self.declare(name, false);
+            parameters.push(FunctionParameter {
+                name,
+                annotation,
+                position: self.span(start),
+            });
+
+            if self.eat(TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+
+        self.expect(TokenKind::RightParen, "`)`")?;
+
+        Ok(parameters)
+    }
+
+    // Macros
+
+    // macro name(a, b) { ... }
+    fn parse_macro_declaration(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::Macro, "`macro`")?;
+
+        let name = self.expect(TokenKind::Identifier, "a macro name")?.value();
+
+        self.enter_scope();
+
+        let parameters = self.parse_parameters()?;
+
+        self.exit_scope();
+
+        if !self.check(TokenKind::LeftBrace) {
+            return self.error("`{`");
+        }
+
+        let body = self.parse_delimited()?;
+
+        Ok(self.alloc(AST::macro_declaration(
+            name,
+            parameters,
+            body,
+            self.span(start),
+        )))
+    }
+
+    fn parse_macro_node(&mut self) -> ParseResult<NodeId> {
+        let invocation = self.parse_macro_invocation()?;
+
+        Ok(self.alloc(AST::macro_invocation(invocation)))
+    }
+
+    // @name!(...), @name![...] or @name!{ ... }
+    fn parse_macro_invocation(&mut self) -> ParseResult<MacroInvocation<'a>> {
+        let start = self.start();
+        let value = self
+            .expect(TokenKind::MacroIdentifier, "a macro invocation")?
+            .value();
+
+        if self.peek_kind().and_then(Delimiter::opened_by).is_none() {
+            return self.error("`(`, `[` or `{` after a macro name");
+        }
+
+        let group = self.parse_delimited()?;
+
+        Ok(MacroInvocation::new(
+            &value[1..value.len() - 1],
+            group,
+            self.span(start),
+        ))
+    }
+
+    // A bracket, the token trees up to the matching bracket, and that
+    // bracket. The current token must open a group.
+    fn parse_delimited(&mut self) -> ParseResult<Delimited<'a>> {
+        let start = self.start();
+        let open = self.advance();
+        let delimiter = Delimiter::opened_by(open.kind()).expect("an opening bracket");
+        let mut trees = vec![];
+
+        while !self.check(delimiter.closing()) {
+            if self.is_at_end() {
+                return Err(ParseError::new(
+                    format!("Unclosed `{}`", delimiter.open()),
+                    open.position(),
+                ));
+            }
+
+            trees.push(self.parse_token_tree()?);
+        }
+
+        self.advance();
+
+        let position = self.span(start);
+
+        Ok(Delimited::new(
+            delimiter,
+            trees,
+            &self.input[position.start..position.end],
+            position,
+        ))
+    }
+
+    fn parse_token_tree(&mut self) -> ParseResult<TokenTree<'a>> {
+        let Some(token) = self.peek().copied() else {
+            return self.error("a token");
+        };
+
+        if Delimiter::opened_by(token.kind()).is_some() {
+            return Ok(TokenTree::Delimited(self.parse_delimited()?));
+        }
+
+        if matches!(
+            token.kind(),
+            TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace
+        ) {
+            return Err(ParseError::new(
+                format!("Unmatched `{}`", token.value()),
+                token.position(),
+            ));
+        }
+
+        Ok(TokenTree::Token(self.advance()))
+    }
+
+    // Types
+
+    // `allow_arrow` is false where a following `->` belongs to something
+    // else, like the body of a lambda with an annotated return type.
+    fn parse_type_annotation(&mut self, allow_arrow: bool) -> ParseResult<TypeAnnotation<'a>> {
+        let start = self.start();
+        let annotated = self.parse_type(allow_arrow)?;
+
+        Ok(TypeAnnotation {
+            annotated,
+            position: self.span(start),
+        })
+    }
+
+    // <A>(A, B) -> C, A -> B, or a union of intersections. Arrows are the
+    // loosest and right associative, so `A -> B | C` returns `B | C`.
+    fn parse_type(&mut self, allow_arrow: bool) -> ParseResult<Type<'a>> {
+        if allow_arrow && self.check(TokenKind::LessThan) {
+            let mut type_parameters = vec![];
+
+            // Function types only keep the names of their type parameters.
+            for parameter in self.parse_type_parameters()? {
+                if parameter.extends.is_some() {
+                    return Err(ParseError::new(
+                        "Type parameters of function types can't have bounds",
+                        parameter.position,
+                    ));
+                }
+
+                type_parameters.push(Type::Reference(parameter.name, vec![]));
+            }
+
+            let arguments = self.parse_parenthesised_types()?;
+
+            self.expect(TokenKind::RightArrow, "`->`")?;
+
+            let returned = self.parse_type(true)?;
+
+            return Ok(Type::Function(
+                type_parameters,
+                arguments,
+                vec![],
+                Box::new(returned),
+            ));
+        }
+
+        // A parenthesised list directly followed by `->` is a parameter list,
+        // rather than a tuple passed as the only argument.
+        if allow_arrow && self.check(TokenKind::LeftParen) {
+            let checkpoint = self.checkpoint();
+
+            if let Ok(arguments) = self.parse_parenthesised_types() {
+                if self.eat(TokenKind::RightArrow).is_some() {
+                    let returned = self.parse_type(true)?;
+
+                    return Ok(Type::Function(
+                        vec![],
+                        arguments,
+                        vec![],
+                        Box::new(returned),
+                    ));
+                }
+            }
+
+            self.restore(checkpoint);
+        }
+
+        let parameter = self.parse_union_type()?;
+
+        if allow_arrow && self.eat(TokenKind::RightArrow).is_some() {
+            let returned = self.parse_type(true)?;
+
+            return Ok(Type::Function(
+                vec![],
+                vec![parameter],
+                vec![],
+                Box::new(returned),
+            ));
+        }
+
+        Ok(parameter)
+    }
+
+    fn parse_parenthesised_types(&mut self) -> ParseResult<Vec<Type<'a>>> {
+        let mut types = vec![];
+
+        self.expect(TokenKind::LeftParen, "`(`")?;
+
+        while !self.check(TokenKind::RightParen) && !self.is_at_end() {
+            types.push(self.parse_type(true)?);
+
+            if self.eat(TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+
+        self.expect(TokenKind::RightParen, "`)`")?;
+
+        Ok(types)
+    }
+
+    fn parse_union_type(&mut self) -> ParseResult<Type<'a>> {
+        self.eat(TokenKind::Or);
+
+        let mut members = vec![self.parse_intersection_type()?];
+
+        while self.eat(TokenKind::Or).is_some() {
+            members.push(self.parse_intersection_type()?);
+        }
+
+        Ok(match members.len() {
+            1 => members.remove(0),
+            _ => Type::Union(members),
+        })
+    }
+
+    fn parse_intersection_type(&mut self) -> ParseResult<Type<'a>> {
+        let mut members = vec![self.parse_primary_type()?];
+
+        while self.eat(TokenKind::And).is_some() {
+            members.push(self.parse_primary_type()?);
+        }
+
+        Ok(match members.len() {
+            1 => members.remove(0),
+            _ => Type::Intersection(members),
+        })
+    }
+
+    fn parse_primary_type(&mut self) -> ParseResult<Type<'a>> {
+        let Some(token) = self.peek().copied() else {
+            return self.error("a type");
+        };
+
+        match token.kind() {
+            TokenKind::Identifier => self.parse_named_type(),
+            TokenKind::MacroIdentifier => Ok(Type::Macro(self.parse_macro_invocation()?)),
+            TokenKind::Underscore => {
+                self.advance();
+
+                Ok(Type::Infer)
+            }
+            TokenKind::RegexpLiteral => {
+                self.advance();
+
+                Ok(Type::Regexp(token.value()))
+            }
+            // (), (A), or a tuple (A, B, ...C)
+            TokenKind::LeftParen => {
+                self.advance();
+
+                let mut members = vec![];
+                let mut trailing_comma = false;
+
+                while !self.check(TokenKind::RightParen) && !self.is_at_end() {
+                    members.push(if self.eat(TokenKind::DotDotDot).is_some() {
+                        TupleData::Spread(Box::new(self.parse_type(true)?))
+                    } else {
+                        TupleData::Member(Box::new(self.parse_type(true)?))
+                    });
+
+                    trailing_comma = self.eat(TokenKind::Comma).is_some();
+
+                    if !trailing_comma {
+                        break;
+                    }
+                }
+
+                self.expect(TokenKind::RightParen, "`)`")?;
+
+                match members.as_slice() {
+                    [] => Ok(Type::Unit),
+                    [TupleData::Member(member)] if !trailing_comma => Ok(member.as_ref().clone()),
+                    _ => Ok(Type::Tuple(members)),
+                }
+            }
+            TokenKind::LeftBracket => {
+                self.advance();
+
+                let member = self.parse_type(true)?;
+
+                self.expect(TokenKind::RightBracket, "`]`")?;
+
+                Ok(Type::Array(Box::new(member)))
+            }
+            // { name: A, ...Rest }
+            TokenKind::LeftBrace => {
+                self.advance();
+
+                let mut members = vec![];
+
+                while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+                    if self.eat(TokenKind::DotDotDot).is_some() {
+                        members.push(StructData::Spread(Box::new(self.parse_type(true)?)));
+                    } else {
+                        let name = self.expect(TokenKind::Identifier, "a field name")?.value();
+
+                        self.expect(TokenKind::Colon, "`:`")?;
+                        members.push(StructData::Member(name, Box::new(self.parse_type(true)?)));
+                    }
+
+                    self.end_list_item(TokenKind::RightBrace)?;
+                }
+
+                self.expect(TokenKind::RightBrace, "`}`")?;
+
+                Ok(Type::Struct(members))
+            }
+            _ => self.error("a type"),
+        }
+    }
+
+    // Built-in types, or a reference to a named type like `Effect<Console, Unit>`
+    // or `CalcError.CannotDivideByZero`.
+    fn parse_named_type(&mut self) -> ParseResult<Type<'a>> {
+        let start = self.start();
+
+        self.expect(TokenKind::Identifier, "a type name")?;
+
+        while self.check(TokenKind::Dot) && self.peek_kind_at(1) == Some(TokenKind::Identifier) {
+            self.advance();
+            self.advance();
+        }
+
+        let name = &self.input[start..self.span(start).end];
+        let mut arguments = self
+            .parse_type_arguments()?
+            .into_iter()
+            .map(|argument| argument.annotated)
+            .collect::<Vec<_>>();
+        let arity_error = |expected: usize| {
+            ParseError::new(
+                format!("`{}` expects {} type argument(s)", name, expected),
+                self.span(start),
+            )
+        };
+
+        match (name, arguments.len()) {
+            ("Unit", 0) => Ok(Type::Unit),
+            ("Never", 0) => Ok(Type::Never),
+            ("Unknown", 0) => Ok(Type::Unknown),
+            ("Boolean", 0) => Ok(Type::Boolean),
+            ("Int", 0) => Ok(Type::Int),
+            ("Float", 0) => Ok(Type::Float),
+            ("String", 0) => Ok(Type::String),
+            ("Option", 1) => Ok(Type::Option(Box::new(arguments.remove(0)))),
+            ("Option", _) => Err(arity_error(1)),
+            ("Either", 2) => {
+                let right = arguments.remove(1);
+                let left = arguments.remove(0);
+
+                Ok(Type::Either(Box::new(left), Box::new(right)))
+            }
+            ("Either", _) => Err(arity_error(2)),
+            ("Unit" | "Never" | "Unknown" | "Boolean" | "Int" | "Float" | "String", _) => {
+                Err(arity_error(0))
+            }
+            _ => Ok(Type::Reference(name, arguments)),
+        }
+    }
+
+    // `>>` and `>>>` close several type argument lists at once, so they are
+    // split in place and closed one `>` at a time.
+    fn check_closing_angle(&self) -> bool {
+        matches!(
+            self.peek_kind(),
+            Some(TokenKind::GreaterThan)
+                | Some(TokenKind::GreaterThanGreaterThan)
+                | Some(TokenKind::GreaterThanGreaterThanGreaterThan)
+        )
+    }
+
+    fn expect_closing_angle(&mut self) -> ParseResult<()> {
+        let Some(token) = self.peek().copied() else {
+            return self.error("`>`");
+        };
+        let remainder = match token.kind() {
+            TokenKind::GreaterThan => {
+                self.advance();
+
+                return Ok(());
+            }
+            TokenKind::GreaterThanGreaterThan => TokenKind::GreaterThan,
+            TokenKind::GreaterThanGreaterThanGreaterThan => TokenKind::GreaterThanGreaterThan,
+            _ => return self.error("`>`"),
+        };
+        let position = token.position();
+
+        self.tokens[self.index] = Token::new(
+            remainder,
+            &token.value()[1..],
+            Position::new(position.start + 1, position.end),
+        );
+        self.line_breaks[self.index] = false;
+
+        Ok(())
+    }
+
+    fn parse_block_body(&mut self) -> ParseResult<(Vec<NodeId>, Position)> {
+        let start = self.start();
+        let mut body = vec![];
+
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+        self.enter_scope();
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            body.push(self.parse_statement()?);
+            self.end_statement(Some(TokenKind::RightBrace))?;
+        }
+
+        self.exit_scope();
+        self.expect(TokenKind::RightBrace, "`}`")?;
+
+        Ok((body, self.span(start)))
+    }
+
+    // Expressions
+
+    pub fn parse_expression(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let expression = self.parse_with()?;
+
+        // Reassigning a mutable variable is an expression, as in
+        // `resume(is_shutdown = true)`.
+        match &self.arena[expression] {
+            AST::Identifier(target)
+                if self.check(TokenKind::Equal) && self.is_mutable(target.value) =>
+            {
+                let target = target.clone();
+
+                self.discard(expression);
+                self.advance();
+
+                let value = self.parse_expression()?;
+
+                Ok(self.alloc(AST::assignment(target, value, self.span(start))))
+            }
+            _ => Ok(expression),
+        }
+    }
+
+    // `with` binds looser than every binary operator, so that
+    // `main() with a ++ b` provides both `a` and `b` to `main()`.
+    fn parse_with(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let mut body = self.parse_binary(0)?;
+
+        while self.eat(TokenKind::With).is_some() {
+            let handlers = self.parse_binary(0)?;
+
+            body = self.alloc(AST::with(body, handlers, self.span(start)));
+        }
+
+        Ok(body)
+    }
+
+    fn parse_binary(&mut self, min_precedence: u8) -> ParseResult<NodeId> {
+        let start = self.start();
+        let mut left = self.parse_unary()?;
+
+        while let Some((operator, precedence, right_associative)) = self.peek_binary_operator() {
+            // A `-` starting a new line begins a new statement, it doesn't
+            // subtract from the previous one.
+            if precedence < min_precedence
+                || (self.check(TokenKind::Minus) && self.line_break_before())
+            {
+                break;
+            }
+
+            self.advance();
+
+            let right = self.parse_binary(if right_associative {
+                precedence
+            } else {
+                precedence + 1
+            })?;
+
+            left = self.alloc(AST::binary(operator, left, right, self.span(start)));
+        }
+
+        Ok(left)
+    }
+
+    fn peek_binary_operator(&self) -> Option<(BinaryOperator, u8, bool)> {
+        let operator = match self.peek_kind()? {
+            TokenKind::OrOr => (BinaryOperator::Or, 1, false),
+            TokenKind::AndAnd => (BinaryOperator::And, 2, false),
+            TokenKind::EqualEqual => (BinaryOperator::Equal, 3, false),
+            TokenKind::BangEqual => (BinaryOperator::NotEqual, 3, false),
+            TokenKind::LessThan => (BinaryOperator::LessThan, 4, false),
+            TokenKind::LessThanEqual => (BinaryOperator::LessThanEqual, 4, false),
+            TokenKind::GreaterThan => (BinaryOperator::GreaterThan, 4, false),
+            TokenKind::GreaterThanEqual => (BinaryOperator::GreaterThanEqual, 4, false),
+            TokenKind::PlusPlus => (BinaryOperator::Concat, 5, true),
+            TokenKind::Plus => (BinaryOperator::Add, 6, false),
+            TokenKind::Minus => (BinaryOperator::Subtract, 6, false),
+            TokenKind::Star => (BinaryOperator::Multiply, 7, false),
+            TokenKind::Slash => (BinaryOperator::Divide, 7, false),
+            TokenKind::Percent => (BinaryOperator::Remainder, 7, false),
+            TokenKind::StarStar => (BinaryOperator::Exponent, 8, true),
+            _ => return None,
+        };
+
+        Some(operator)
+    }
+
+    fn parse_unary(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let operator = match self.peek_kind() {
+            Some(TokenKind::Minus) => UnaryOperator::Negate,
+            Some(TokenKind::Bang) => UnaryOperator::Not,
+            _ => return self.parse_postfix(),
+        };
+
+        self.advance();
+
+        let operand = self.parse_unary()?;
+
+        Ok(self.alloc(AST::unary(operator, operand, self.span(start))))
+    }
+
+    fn parse_postfix(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let mut expression = self.parse_primary()?;
+
+        loop {
+            match self.peek_kind() {
+                // Calls must open on the same line as the callee, otherwise
+                // `a\n(b)` would be read as `a(b)`.
+                Some(TokenKind::LeftParen) if !self.line_break_before() => {
+                    let arguments = self.parse_arguments()?;
+
+                    expression = self.alloc(AST::call(expression, arguments, self.span(start)));
+                }
+                Some(TokenKind::Dot) => {
+                    self.advance();
+
+                    let token = self.expect(TokenKind::Identifier, "a member name")?;
+                    let property = Identifier {
+                        value: token.value(),
+                        position: token.position(),
+                    };
+
+                    expression =
+                        self.alloc(AST::member_access(expression, property, self.span(start)));
+                }
+                _ => return Ok(expression),
+            }
+        }
+    }
+
+    fn parse_arguments(&mut self) -> ParseResult<Vec<NodeId>> {
+        let mut arguments = vec![];
+
+        self.expect(TokenKind::LeftParen, "`(`")?;
+
+        while !self.check(TokenKind::RightParen) && !self.is_at_end() {
+            arguments.push(self.parse_expression()?);
+
+            if self.eat(TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+
+        self.expect(TokenKind::RightParen, "`)`")?;
+
+        Ok(arguments)
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<NodeId> {
+        let Some(token) = self.peek().copied() else {
+            return self.error("an expression");
+        };
+
+        match token.kind() {
+            TokenKind::BooleanLiteral => {
+                self.advance();
+
+                Ok(self.alloc(AST::boolean_literal(
+                    token.value() == "true",
+                    token.position(),
+                )))
+            }
+            TokenKind::NumberLiteral => {
+                self.advance();
+
+                if token.value().contains('.') {
+                    let value = token
+                        .value()
+                        .parse()
+                        .map_err(|_| ParseError::new("Invalid number literal", token.position()))?;
+
+                    Ok(self.alloc(AST::number_literal(value, token.position())))
+                } else {
+                    let value = token.value().parse().map_err(|_| {
+                        ParseError::new("Integer literal out of range", token.position())
+                    })?;
+
+                    Ok(self.alloc(AST::integer_literal(value, token.position())))
+                }
+            }
+            TokenKind::StringLiteral => {
+                self.advance();
+
+                let value = token.value();
+
+                Ok(self.alloc(AST::string_literal(
+                    &value[1..value.len() - 1],
+                    token.position(),
+                )))
+            }
+            TokenKind::RegexpLiteral => {
+                self.advance();
+
+                Ok(self.alloc(AST::regexp_literal(token.value(), token.position())))
+            }
+            TokenKind::Identifier | TokenKind::Underscore => {
+                self.advance();
+
+                Ok(self.alloc(AST::identifier(token.value(), token.position())))
+            }
+            TokenKind::TemplateLiteral => self.parse_template_literal(),
+            TokenKind::LeftParen | TokenKind::LessThan if self.is_lambda_ahead() => {
+                self.parse_lambda()
+            }
+            TokenKind::LeftParen => self.parse_parenthesised(),
+            TokenKind::LeftBracket => self.parse_array(),
+            TokenKind::LeftBrace if self.is_record_ahead() => self.parse_record(),
+            TokenKind::LeftBrace => {
+                let (body, position) = self.parse_block_body()?;
+
+                Ok(self.alloc(AST::block(body, position)))
+            }
+            TokenKind::Match => self.parse_match(),
+            TokenKind::Handler => self.parse_handler(),
+            TokenKind::MacroIdentifier => self.parse_macro_node(),
+            _ => self.error("an expression"),
+        }
+    }
+
+    // `fib(${n}) = ${fib(n)}` is split into the text around each `${...}`,
+    // and every interpolated expression is parsed from its own tokens.
+    fn parse_template_literal(&mut self) -> ParseResult<NodeId> {
+        let token = self.expect(TokenKind::TemplateLiteral, "a template literal")?;
+        let value = token.value();
+        let offset = token.position().start;
+        let interpolations = interpolations(value).map_err(|index| {
+            ParseError::new(
+                "Unterminated `${` in template literal",
+                Position::new(offset + index, offset + value.len()),
+            )
+        })?;
+        let mut template = vec![];
+        let mut values = vec![];
+        let mut text_start = 1;
+
+        for (start, end) in interpolations {
+            template.push(&value[text_start..start - 2]);
+            values.push(self.parse_interpolation(offset + start, offset + end)?);
+            text_start = end + 1;
+        }
+
+        template.push(&value[text_start..value.len() - 1]);
+
+        Ok(self.alloc(AST::template_literal(template, values, token.position())))
+    }
+
+    // Parses input[start..end] as a single expression, in place of the
+    // current token stream.
+    fn parse_interpolation(&mut self, start: usize, end: usize) -> ParseResult<NodeId> {
+        let (tokens, line_breaks, _) = tokenize(&self.input[..end], start)?;
+        let tokens = std::mem::replace(&mut self.tokens, tokens);
+        let line_breaks = std::mem::replace(&mut self.line_breaks, line_breaks);
+        let index = std::mem::replace(&mut self.index, 0);
+        let reach = self.reach.replace(0);
+        let expression = match self.parse_expression() {
+            Ok(_) if !self.is_at_end() => self.error("`}`"),
+            result => result,
+        };
+
+        self.tokens = tokens;
+        self.line_breaks = line_breaks;
+        self.index = index;
+        self.reach.set(reach);
+
+        expression
+    }
+
+    // (), (a), or a tuple (a, b, ...rest)
+    fn parse_parenthesised(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let mut elements = vec![];
+        let mut trailing_comma = false;
+
+        self.expect(TokenKind::LeftParen, "`(`")?;
+
+        while !self.check(TokenKind::RightParen) && !self.is_at_end() {
+            elements.push(self.parse_element()?);
+            trailing_comma = self.eat(TokenKind::Comma).is_some();
+
+            if !trailing_comma {
+                break;
+            }
+        }
+
+        self.expect(TokenKind::RightParen, "`)`")?;
+
+        match elements.pop() {
+            Some(Element::Member(expression)) if elements.is_empty() && !trailing_comma => {
+                Ok(expression)
+            }
+            Some(element) => {
+                elements.push(element);
+
+                Ok(self.alloc(AST::tuple(elements, self.span(start))))
+            }
+            None => Ok(self.alloc(AST::tuple(elements, self.span(start)))),
+        }
+    }
+
+    // [a, b, ...rest]
+    fn parse_array(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let mut elements = vec![];
+
+        self.expect(TokenKind::LeftBracket, "`[`")?;
+
+        while !self.check(TokenKind::RightBracket) && !self.is_at_end() {
+            elements.push(self.parse_element()?);
+            self.end_list_item(TokenKind::RightBracket)?;
+        }
+
+        self.expect(TokenKind::RightBracket, "`]`")?;
+
+        Ok(self.alloc(AST::array(elements, self.span(start))))
+    }
+
+    fn parse_element(&mut self) -> ParseResult<Element> {
+        if self.eat(TokenKind::DotDotDot).is_some() {
+            Ok(Element::Spread(self.parse_expression()?))
+        } else {
+            Ok(Element::Member(self.parse_expression()?))
+        }
+    }
+
+    // { name: value, shorthand, ...spread }
+    fn parse_record(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+        let mut members = vec![];
+
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            if self.eat(TokenKind::DotDotDot).is_some() {
+                members.push(RecordMember::Spread(self.parse_expression()?));
+            } else {
+                let token = self.expect(TokenKind::Identifier, "a field name")?;
+                let name = Identifier {
+                    value: token.value(),
+                    position: token.position(),
+                };
+
+                if self.eat(TokenKind::Colon).is_some() {
+                    members.push(RecordMember::Field(name, self.parse_expression()?));
+                } else {
+                    members.push(RecordMember::Shorthand(name));
+                }
+            }
+
+            self.end_list_item(TokenKind::RightBrace)?;
+        }
+
+        self.expect(TokenKind::RightBrace, "`}`")?;
+
+        Ok(self.alloc(AST::record(members, self.span(start))))
+    }
+
+    // A `{` in expression position starts a record when it is empty, or
+    // when its first member is a spread, `name:` or `name,`. Anything
+    // else, including `{ name }`, is a block.
+    fn is_record_ahead(&self) -> bool {
+        self.check(TokenKind::LeftBrace)
+            && matches!(
+                (self.peek_kind_at(1), self.peek_kind_at(2)),
+                (Some(TokenKind::RightBrace), _)
+                    | (Some(TokenKind::DotDotDot), _)
+                    | (
+                        Some(TokenKind::Identifier),
+                        Some(TokenKind::Colon) | Some(TokenKind::Comma)
+                    )
+            )
+    }
+
+    // <A>(a: A): A -> a
+    fn parse_lambda(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.enter_scope();
+
+        let type_parameters = self.parse_type_parameters()?;
+        let parameters = self.parse_parameters()?;
+        let return_type = if self.eat(TokenKind::Colon).is_some() {
+            Some(self.parse_type_annotation(false)?)
+        } else {
+            None
+        };
+
+        self.expect(TokenKind::RightArrow, "`->`")?;
+
+        let body = if self.check(TokenKind::LeftBrace) && !self.is_record_ahead() {
+            self.parse_block_body()?.0
+        } else {
+            vec![self.parse_expression()?]
+        };
+
+        self.exit_scope();
+
+        Ok(self.alloc(AST::lambda(
+            type_parameters,
+            parameters,
+            return_type,
+            body,
+            self.span(start),
+        )))
+    }
+
+    // A parenthesised list is a lambda's parameters when the closing `)` is
+    // followed by `->` or a return type. Explicit type parameters always
+    // start a lambda in expression position.
+    fn is_lambda_ahead(&self) -> bool {
+        if !self.allow_lambdas {
+            return false;
+        }
+
+        if self.check(TokenKind::LessThan) {
+            return true;
+        }
+
+        let mut depth = 0usize;
+
+        for (offset, token) in self.tokens[self.index..].iter().enumerate() {
+            match token.kind() {
+                TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return matches!(
+                            self.peek_kind_at(offset + 1),
+                            Some(TokenKind::RightArrow) | Some(TokenKind::Colon)
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.look(self.tokens.len());
+
+        false
+    }
+
+    fn parse_match(&mut self) -> ParseResult<NodeId> {
+        let start = self.start();
+
+        self.expect(TokenKind::Match, "`match`")?;
+
+        let value = self.parse_expression()?;
+        let mut cases = vec![];
+
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            let case_start = self.start();
+            let allow_lambdas = std::mem::replace(&mut self.allow_lambdas, false);
+            let test = self.parse_expression();
+
+            self.allow_lambdas = allow_lambdas;
+
+            let test = test?;
+
+            self.expect(TokenKind::RightArrow, "`->`")?;
+            self.enter_scope();
+
+            let test = match self.to_pattern(test) {
+                Some(pattern) => {
+                    self.discard(test);
+                    self.declare_pattern(&pattern, false);
+
+                    MatchTest::Pattern(pattern)
+                }
+                None => MatchTest::Predicate(test),
+            };
+            let body = self.parse_expression()?;
+
+            self.exit_scope();
+
+            cases.push(MatchCase {
+                test,
+                body,
+                position: self.span(case_start),
+            });
+
+            self.end_list_item(TokenKind::RightBrace)?;
+        }
+
+        self.expect(TokenKind::RightBrace, "`}`")?;
+
+        Ok(self.alloc(AST::match_(value, cases, self.span(start))))
+    }
+
+    // Scopes
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(vec![]);
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &'a str, mutable: bool) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name, mutable));
+        }
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern<'a>, mutable: bool) {
+        let mut names = vec![];
+
+        pattern_names(pattern, &mut names);
+
+        for name in names {
+            self.declare(name, mutable);
+        }
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            index: self.index,
+            depth: self.scopes.len(),
+            nodes: self.arena.len(),
+        }
+    }
+
+    // Rewinds a speculative parse, dropping any nodes it allocated.
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        self.index = checkpoint.index;
+        self.scopes.truncate(checkpoint.depth);
+        self.arena.truncate(checkpoint.nodes);
+    }
+
+    fn alloc(&mut self, node: AST<'a>) -> NodeId {
+        self.arena.alloc(node)
+    }
+
+    // Drops the nodes of an expression that was reinterpreted as a pattern
+    // or an assignment target. It must be the last thing allocated.
+    fn discard(&mut self, expression: NodeId) {
+        fn first(arena: &Arena<'_>, id: NodeId) -> NodeId {
+            arena[id]
+                .children()
+                .into_iter()
+                .map(|child| first(arena, child))
+                .fold(id, NodeId::min)
+        }
+
+        debug_assert_eq!(expression.index() + 1, self.arena.len());
+
+        self.arena.truncate(first(&self.arena, expression).index());
+    }
+
+    fn is_mutable(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(bound, _)| *bound == name)
+            .is_some_and(|(_, mutable)| *mutable)
+    }
+
+    // Token helpers
+
+    fn is_at_end(&self) -> bool {
+        self.look(self.index);
+        self.index >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.look(self.index);
+        self.tokens.get(self.index)
+    }
+
+    fn peek_kind(&self) -> Option<TokenKind> {
+        self.peek_kind_at(0)
+    }
+
+    fn peek_kind_at(&self, offset: usize) -> Option<TokenKind> {
+        self.look(self.index + offset);
+        self.tokens
+            .get(self.index + offset)
+            .map(|token| token.kind())
+    }
+
+    fn check(&self, kind: TokenKind) -> bool {
+        self.peek_kind() == Some(kind)
+    }
+
+    // Contextual keywords, like `as`, are lexed as identifiers.
+    fn eat_contextual(&mut self, keyword: &str) -> bool {
+        let found = self
+            .peek()
+            .is_some_and(|token| token.kind() == TokenKind::Identifier && token.value() == keyword);
+
+        if found {
+            self.advance();
+        }
+
+        found
+    }
+
+    fn expect_contextual(&mut self, keyword: &str) -> ParseResult<()> {
+        if self.eat_contextual(keyword) {
+            Ok(())
+        } else {
+            self.error(&format!("`{}`", keyword))
+        }
+    }
+
+    fn line_break_before(&self) -> bool {
+        self.look(self.index);
+        self.line_breaks.get(self.index).copied().unwrap_or(true)
+    }
+
+    fn look(&self, index: usize) {
+        self.reach.set(self.reach.get().max(index));
+    }
+
+    fn advance(&mut self) -> Token<'a> {
+        let token = self.tokens[self.index];
+
+        self.index += 1;
+
+        token
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> Option<Token<'a>> {
+        if self.check(kind) {
+            Some(self.advance())
+        } else {
+            None
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> ParseResult<Token<'a>> {
+        match self.eat(kind) {
+            Some(token) => Ok(token),
+            None => self.error(expected),
+        }
+    }
+
+    // Statements end at a line break, a `;` or the closing delimiter.
+    fn end_statement(&mut self, closing: Option<TokenKind>) -> ParseResult<()> {
+        if self.eat(TokenKind::Semicolon).is_some()
+            || self.is_at_end()
+            || self.line_break_before()
+            || closing.is_some_and(|closing| self.check(closing))
+        {
+            Ok(())
+        } else {
+            self.error("a new line")
+        }
+    }
+
+    // Skips a statement that didn't parse: at least one token, and then up
+    // to a `;` or a line break outside of any brackets.
+    fn skip_statement(&mut self) {
+        let mut depth = 0usize;
+
+        loop {
+            match self.advance().kind() {
+                TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                    depth = depth.saturating_sub(1)
+                }
+                TokenKind::Semicolon if depth == 0 => return,
+                _ => {}
+            }
+
+            if self.is_at_end() || depth == 0 && self.line_break_before() {
+                return;
+            }
+        }
+    }
+
+    // List items are separated by `,`, or by line breaks.
+    fn end_list_item(&mut self, closing: TokenKind) -> ParseResult<()> {
+        if self.eat(TokenKind::Comma).is_some() || self.check(closing) || self.line_break_before() {
+            Ok(())
+        } else {
+            self.error("`,`")
+        }
+    }
+
+    // The doc comment in front of the declaration at the current token, or
+    // in front of the `export` that precedes it.
+    fn leading_docs(&self) -> Option<Docs> {
+        let exported = match self.index.checked_sub(1).map(|index| &self.tokens[index]) {
+            Some(token) if token.kind() == TokenKind::Export => Some(token.position().start),
+            _ => None,
+        };
+
+        self.doc_comments
+            .get(&self.start())
+            .or_else(|| self.doc_comments.get(&exported?))
+            .map(Docs::parse)
+    }
+
+    fn start(&self) -> usize {
+        self.peek()
+            .map(|token| token.position().start)
+            .unwrap_or(self.input.len())
+    }
+
+    // The position from `start` to the end of the last consumed token.
+    fn span(&self, start: usize) -> Position {
+        let end = match self.index {
+            0 => start,
+            index => self.tokens[index - 1].position().end,
+        };
+
+        Position::new(start, end.max(start))
+    }
+
+    fn error<T>(&self, expected: &str) -> ParseResult<T> {
+        let message = match self.peek() {
+            Some(token) => format!("Expected {}, found `{}`", expected, token.value()),
+            None => format!("Expected {}, found end of input", expected),
+        };
+        let position = self
+            .peek()
+            .map(|token| token.position())
+            .unwrap_or(Position::new(self.input.len(), self.input.len()));
+
+        Err(ParseError::new(message, position))
+    }
+}
+
+type Tokens<'a> = (Vec<Token<'a>>, Vec<bool>, HashMap<usize, Token<'a>>);
+
+// How many tokens past the end of a statement parse_statement_at reads.
+const LOOKAHEAD: usize = 8;
+
+// The tokens of input[start..], minus whitespace and comments, along with
+// whether each one follows a line break, and the doc comments that precede
+// them.
+fn tokenize(input: &str, start: usize) -> ParseResult<Tokens<'_>> {
+    tokenize_until(input, start, usize::MAX).map(|(tokens, _)| tokens)
+}
+
+// Like tokenize, but stops LOOKAHEAD tokens after `until`, and says whether
+// it stopped before the end of the input.
+fn tokenize_until(input: &str, start: usize, until: usize) -> ParseResult<(Tokens<'_>, bool)> {
+    let (tokens, errors) = scan(input, start, until);
+
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(tokens),
+    }
+}
+
+// Like tokenize_until, but skips each character no token matches, with an
+// error for it, rather than stopping at the first.
+fn scan(input: &str, start: usize, until: usize) -> ((Tokens<'_>, bool), Vec<ParseError>) {
+    let mut tokenizer = Tokenizer::asura();
+    tokenizer.init_at(input, start);
+
+    let mut tokens = vec![];
+    let mut line_breaks = vec![];
+    let mut doc_comments = HashMap::new();
+    let mut errors = vec![];
+    let mut line_break = false;
+    let mut doc_comment = None;
+    let mut end = start;
+    let mut past = 0;
+
+    loop {
+        for token in &mut tokenizer {
+            if past == LOOKAHEAD {
+                return (((tokens, line_breaks, doc_comments), true), errors);
+            }
+
+            end = token.position().end;
+
+            match token.kind() {
+                TokenKind::WhiteSpace | TokenKind::Comment | TokenKind::DocComment => {
+                    line_break = line_break || token.value().contains('\n');
+
+                    if token.kind() == TokenKind::DocComment {
+                        doc_comment = Some(token);
+                    }
+                }
+                _ => {
+                    if let Some(comment) = doc_comment.take() {
+                        doc_comments.insert(token.position().start, comment);
+                    }
+
+                    if token.position().start >= until {
+                        past += 1;
+                    }
+
+                    tokens.push(token);
+                    line_breaks.push(line_break);
+                    line_break = false;
+                }
+            }
+        }
+
+        let Some(character) = input[end..].chars().next() else {
+            return (((tokens, line_breaks, doc_comments), false), errors);
+        };
+        let next = end + character.len_utf8();
+
+        errors.push(ParseError::new(
+            "Unexpected character",
+            Position::new(end, next),
+        ));
+        tokenizer.init_at(input, next);
+        end = next;
+    }
+}
+
+// Match cases are parsed as expressions and then reinterpreted as patterns.
+// Capitalised names are constructors, `_` is a wildcard and any other name
+// is a binding. Anything else, e.g. `n < 2`, is a predicate.
+fn to_pattern<'a>(arena: &Arena<'a>, id: NodeId) -> Option<Pattern<'a>> {
+    let expression = &arena[id];
+
+    match expression {
+        AST::Identifier(identifier) if identifier.value == "_" => {
+            Some(Pattern::Wildcard(identifier.position))
+        }
+        AST::Identifier(identifier) if !is_constructor_name(identifier.value) => {
+            Some(Pattern::Identifier(identifier.clone()))
+        }
+        AST::Identifier(_) | AST::MemberAccess(_) => {
+            let (qualifier, name) = to_constructor_path(arena, expression)?;
+
+            Some(Pattern::Constructor(ConstructorPattern {
+                qualifier,
+                name,
+                arguments: vec![],
+                position: position_of_path(expression)?,
+            }))
+        }
+        AST::BooleanLiteral(_)
+        | AST::IntegerLiteral(_)
+        | AST::NumberLiteral(_)
+        | AST::RegexpLiteral(_)
+        | AST::StringLiteral(_) => Some(Pattern::Literal(Box::new(expression.clone()))),
+        AST::Call(call) => {
+            let (qualifier, name) = to_constructor_path(arena, &arena[call.callee])?;
+            let arguments = call
+                .arguments
+                .iter()
+                .map(|&argument| to_pattern(arena, argument))
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(Pattern::Constructor(ConstructorPattern {
+                qualifier,
+                name,
+                arguments,
+                position: call.position,
+            }))
+        }
+        AST::Record(record) => {
+            let mut fields = vec![];
+            let mut rest = None;
+
+            for (index, member) in record.members.iter().enumerate() {
+                match member {
+                    RecordMember::Field(name, value) => fields.push(FieldPattern {
+                        name: name.clone(),
+                        pattern: to_pattern(arena, *value)?,
+                    }),
+                    RecordMember::Shorthand(name) => fields.push(FieldPattern {
+                        name: name.clone(),
+                        pattern: Pattern::Identifier(name.clone()),
+                    }),
+                    RecordMember::Spread(value) if index == record.members.len() - 1 => {
+                        rest = Some(Box::new(to_pattern(arena, *value)?));
+                    }
+                    RecordMember::Spread(_) => return None,
+                }
+            }
+
+            Some(Pattern::Record(RecordPattern {
+                fields,
+                rest,
+                position: record.position,
+            }))
+        }
+        AST::Array(array) => {
+            let mut elements = vec![];
+            let mut rest = None;
+
+            for (index, element) in array.elements.iter().enumerate() {
+                match element {
+                    Element::Member(value) => elements.push(to_pattern(arena, *value)?),
+                    Element::Spread(value) if index == array.elements.len() - 1 => {
+                        rest = Some(Box::new(to_pattern(arena, *value)?));
+                    }
+                    Element::Spread(_) => return None,
+                }
+            }
+
+            Some(Pattern::Array(ArrayPattern {
+                elements,
+                rest,
+                position: array.position,
+            }))
+        }
+        AST::Tuple(tuple) => {
+            let elements = tuple
+                .elements
+                .iter()
+                .map(|element| match element {
+                    Element::Member(value) => to_pattern(arena, *value),
+                    Element::Spread(_) => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(Pattern::Tuple(TuplePattern {
+                elements,
+                position: tuple.position,
+            }))
+        }
+        _ => None,
+    }
+}
+
+fn is_exportable(declaration: &AST<'_>) -> bool {
+    matches!(
+        declaration,
+        AST::FunctionDeclaration(_)
+            | AST::TypeDeclaration(_)
+            | AST::TypeAlias(_)
+            | AST::StructDeclaration(_)
+            | AST::EffectDeclaration(_)
+            | AST::TypeclassDeclaration(_)
+            | AST::MacroDeclaration(_)
+            | AST::LetBinding(_)
+    )
+}
+
+// `Left` or `Either.Left`, split into the qualifying names and the
+// constructor's own name, which must be capitalised.
+fn to_constructor_path<'a>(
+    arena: &Arena<'a>,
+    expression: &AST<'a>,
+) -> Option<(Vec<&'a str>, &'a str)> {
+    match expression {
+        AST::Identifier(identifier) if is_constructor_name(identifier.value) => {
+            Some((vec![], identifier.value))
+        }
+        AST::MemberAccess(member) if is_constructor_name(member.property.value) => {
+            let (mut qualifier, name) = match &arena[member.object] {
+                AST::Identifier(identifier) => (vec![], identifier.value),
+                object => to_constructor_path(arena, object)?,
+            };
+
+            qualifier.push(name);
+
+            Some((qualifier, member.property.value))
+        }
+        _ => None,
+    }
+}
+
+fn position_of_path(expression: &AST<'_>) -> Option<Position> {
+    match expression {
+        AST::Identifier(identifier) => Some(identifier.position),
+        AST::MemberAccess(member) => Some(member.position),
+        _ => None,
+    }
+}
+
+// Where each value interpolated into a template literal starts and ends,
+// relative to the start of the literal, or Err with where the first `${`
+// that isn't closed starts.
+pub(crate) fn interpolations(value: &str) -> Result<Vec<(usize, usize)>, usize> {
+    let bytes = value.as_bytes();
+    let end = value.len() - 1;
+    let mut interpolations = vec![];
+    let mut index = 1;
+
+    while index < end {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b'$' if bytes[index + 1] == b'{' => {
+                let start = index + 2;
+                let close = find_closing_brace(&value[..end], start).ok_or(index)?;
+
+                interpolations.push((start, close));
+                index = close + 1;
+            }
+            _ => index += 1,
+        }
+    }
+
+    Ok(interpolations)
+}
+
+// The index of the `}` closing an interpolation whose body starts at
+// `start`, skipping over nested braces and string literals.
+fn find_closing_brace(text: &str, start: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut index = start;
+
+    while index < bytes.len() {
+        match (quote, bytes[index]) {
+            (Some(_), b'\\') => index += 1,
+            (Some(q), byte) if byte == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(bytes[index]),
+            (None, b'{') => depth += 1,
+            (None, b'}') if depth == 0 => return Some(index),
+            (None, b'}') => depth -= 1,
+            (None, _) => {}
+        }
+
+        index += 1;
+    }
+
+    None
+}
+
+fn is_constructor_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+}
@@ -0,0 +1,119 @@
+use crate::parsing::{arena::*, ast::*, docs::*, visit::*};
+
+// Counts the nodes reachable from a module's body.
+#[derive(Debug, Default)]
+pub struct NodeCounter {
+    pub count: usize,
+}
+
+impl NodeCounter {
+    pub fn count(file: &SourceFile) -> usize {
+        let mut counter = NodeCounter::default();
+        counter.visit_source_file(file);
+
+        counter.count
+    }
+}
+
+impl<'a> Visitor<'a> for NodeCounter {
+    fn visit_node(&mut self, arena: &Arena<'a>, id: NodeId) {
+        self.count += 1;
+        walk_node(self, arena, id)
+    }
+}
+
+// Gathers every identifier in a module in source order, including binding
+// names in patterns, member properties and assignment targets.
+#[derive(Debug, Default)]
+pub struct IdentifierCollector<'a> {
+    pub identifiers: Vec<Identifier<'a>>,
+}
+
+impl<'a> IdentifierCollector<'a> {
+    pub fn collect(file: &SourceFile<'a>) -> Vec<Identifier<'a>> {
+        let mut collector = IdentifierCollector::default();
+        collector.visit_source_file(file);
+
+        collector.identifiers
+    }
+}
+
+impl<'a> Visitor<'a> for IdentifierCollector<'a> {
+    fn visit_identifier(&mut self, _arena: &Arena<'a>, identifier: &Identifier<'a>) {
+        self.identifiers.push(identifier.clone());
+    }
+}
+
+// Checks the @param tags of every doc comment in a module against the
+// declaration they document: parameters for functions, type parameters for
+// types and effects.
+#[derive(Debug, Default)]
+pub struct DocChecker {
+    pub warnings: Vec<DocWarning>,
+}
+
+impl DocChecker {
+    pub fn check(file: &SourceFile) -> Vec<DocWarning> {
+        let mut checker = DocChecker::default();
+        checker.visit_source_file(file);
+
+        checker.warnings
+    }
+
+    fn check_type_parameters(
+        &mut self,
+        docs: Option<&Docs>,
+        name: &str,
+        type_parameters: &[TypeParameter],
+    ) {
+        if let Some(docs) = docs {
+            let names: Vec<_> = type_parameters.iter().map(TypeParameter::name).collect();
+            let kind = format!("a type parameter of `{}`", name);
+
+            self.warnings.extend(docs.check_params(&names, &kind));
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for DocChecker {
+    fn visit_function_declaration(
+        &mut self,
+        arena: &Arena<'a>,
+        function: &FunctionDeclaration<'a>,
+    ) {
+        if let Some(docs) = function.docs() {
+            let names: Vec<_> = function
+                .parameters
+                .iter()
+                .map(FunctionParameter::name)
+                .collect();
+            let kind = format!("a parameter of `{}`", function.name);
+
+            self.warnings.extend(docs.check_params(&names, &kind));
+        }
+
+        walk_function_declaration(self, arena, function)
+    }
+
+    fn visit_type_declaration(&mut self, arena: &Arena<'a>, declaration: &TypeDeclaration<'a>) {
+        self.check_type_parameters(
+            declaration.docs(),
+            declaration.name,
+            &declaration.type_parameters,
+        );
+
+        walk_type_declaration(self, arena, declaration)
+    }
+
+    fn visit_type_alias(&mut self, arena: &Arena<'a>, alias: &TypeAlias<'a>) {
+        self.check_type_parameters(alias.docs(), alias.name, &alias.type_parameters);
+
+        walk_type_alias(self, arena, alias)
+    }
+
+    fn visit_effect_declaration(&mut self, arena: &Arena<'a>, effect: &EffectDeclaration<'a>) {
+        self.check_type_parameters(effect.docs(), effect.name, &effect.type_parameters);
+
+        walk_effect_declaration(self, arena, effect)
+    }
+}
@@ -0,0 +1,1269 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::parsing::{arena::*, ast::*, docs::Docs, types::*};
+
+// Prints a module back to Asura source. The output is laid out from scratch,
+// so comments and the original formatting are lost, but parsing it yields
+// the same tree up to positions. Parentheses are only added where the parser
+// would otherwise group things differently.
+impl<'a> SourceFile<'a> {
+    pub fn to_source(&self) -> String {
+        let mut printer = Printer::new(&self.arena);
+
+        for (index, &id) in self.body.iter().enumerate() {
+            if index > 0 {
+                let previous = &self.arena[self.body[index - 1]];
+
+                printer.write(
+                    if is_declaration(previous) || is_declaration(&self.arena[id]) {
+                        "\n\n"
+                    } else {
+                        "\n"
+                    },
+                );
+            }
+
+            printer.statement(id);
+        }
+
+        if !self.body.is_empty() {
+            printer.write("\n");
+        }
+
+        printer.output
+    }
+}
+
+impl<'a> Display for SourceFile<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_source())
+    }
+}
+
+impl<'a> AST<'a> {
+    // Nodes only hold the ids of their children, so printing one needs the
+    // arena they live in.
+    pub fn to_source(&self, arena: &Arena<'a>) -> String {
+        let mut printer = Printer::new(arena);
+        printer.node(self, LOWEST);
+
+        printer.output
+    }
+
+    pub fn display<'s>(&'s self, arena: &'s Arena<'a>) -> DisplayNode<'s, 'a> {
+        DisplayNode { node: self, arena }
+    }
+}
+
+// Formats a node with the arena its children live in, see AST::display.
+pub struct DisplayNode<'s, 'a> {
+    node: &'s AST<'a>,
+    arena: &'s Arena<'a>,
+}
+
+impl<'s, 'a> Display for DisplayNode<'s, 'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.node.to_source(self.arena))
+    }
+}
+
+impl<'a> Pattern<'a> {
+    // Patterns own the literals in them, so they print without an arena.
+    pub fn to_source(&self) -> String {
+        let arena = Arena::new();
+        let mut printer = Printer::new(&arena);
+        printer.pattern(self);
+
+        printer.output
+    }
+}
+
+impl<'a> Type<'a> {
+    pub fn to_source(&self) -> String {
+        let mut output = String::new();
+        write_type(&mut output, self, FUNCTION_TYPE);
+
+        output
+    }
+}
+
+impl<'a> Display for Type<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_source())
+    }
+}
+
+// How tightly an expression binds, from assignments and lambdas, whose
+// right-hand side extends as far as it can, up to literals. Binary
+// operators sit between WITH and UNARY, one above their parser precedence.
+const LOWEST: u8 = 0;
+const WITH: u8 = 1;
+const UNARY: u8 = 10;
+const POSTFIX: u8 = 11;
+const PRIMARY: u8 = 12;
+
+// The same for types: function types are the loosest, then unions and
+// intersections.
+const FUNCTION_TYPE: u8 = 0;
+const UNION_TYPE: u8 = 1;
+const INTERSECTION_TYPE: u8 = 2;
+const PRIMARY_TYPE: u8 = 3;
+
+const INDENT: &str = "  ";
+
+struct Printer<'s, 'a> {
+    arena: &'s Arena<'a>,
+    output: String,
+    indent: usize,
+}
+
+impl<'s, 'a> Printer<'s, 'a> {
+    fn new(arena: &'s Arena<'a>) -> Printer<'s, 'a> {
+        Printer {
+            arena,
+            output: String::new(),
+            indent: 0,
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn new_line(&mut self) {
+        self.write("\n");
+
+        for _ in 0..self.indent {
+            self.output.push_str(INDENT);
+        }
+    }
+
+    // Doc comments are printed as written, on the lines above the
+    // declaration they document.
+    fn statement(&mut self, id: NodeId) {
+        let arena = self.arena;
+        let declaration = match &arena[id] {
+            AST::ExportDeclaration(ExportDeclaration {
+                kind: ExportKind::Declaration(declaration),
+                ..
+            }) => *declaration,
+            _ => id,
+        };
+
+        if let Some(docs) = docs(&arena[declaration]) {
+            self.write(docs.text());
+            self.new_line();
+        }
+
+        self.expression(id, LOWEST)
+    }
+
+    // { statement \n statement }
+    fn body(&mut self, body: &[NodeId]) {
+        self.write("{");
+        self.indent += 1;
+
+        for &id in body {
+            self.new_line();
+            self.statement(id);
+        }
+
+        self.indent -= 1;
+
+        if !body.is_empty() {
+            self.new_line();
+        }
+
+        self.write("}");
+    }
+
+    fn expression(&mut self, id: NodeId, min_precedence: u8) {
+        let arena = self.arena;
+
+        self.node(&arena[id], min_precedence)
+    }
+
+    fn node(&mut self, node: &AST<'a>, min_precedence: u8) {
+        let parenthesised = precedence(node) < min_precedence;
+
+        if parenthesised {
+            self.write("(");
+        }
+
+        match node {
+            AST::BooleanLiteral(literal) => {
+                self.write(if literal.value { "true" } else { "false" })
+            }
+            AST::IntegerLiteral(literal) => self.write(&literal.value.to_string()),
+            // Debug keeps the `.0` of whole numbers, which would otherwise
+            // read back as integers.
+            AST::NumberLiteral(literal) => self.write(&format!("{:?}", literal.value)),
+            AST::RegexpLiteral(literal) => self.write(literal.value),
+            AST::StringLiteral(literal) => self.write(&quoted(literal.value)),
+            AST::TemplateLiteral(literal) => {
+                self.write("`");
+
+                for (index, text) in literal.template.iter().enumerate() {
+                    self.write(text);
+
+                    if let Some(&value) = literal.values.get(index) {
+                        self.write("${");
+                        self.expression(value, LOWEST);
+                        self.write("}");
+                    }
+                }
+
+                self.write("`");
+            }
+            AST::Identifier(identifier) => self.write(identifier.value),
+            AST::Record(record) => self.record(record),
+            AST::Array(array) => {
+                self.write("[");
+                self.elements(&array.elements);
+                self.write("]");
+            }
+            AST::Tuple(tuple) => {
+                self.write("(");
+                self.elements(&tuple.elements);
+
+                if let [Element::Member(_)] = tuple.elements.as_slice() {
+                    self.write(",");
+                }
+
+                self.write(")");
+            }
+            AST::Call(call) => {
+                self.callee(call.callee);
+                self.write("(");
+                self.list(&call.arguments, |printer, &argument| {
+                    printer.expression(argument, LOWEST)
+                });
+                self.write(")");
+            }
+            AST::MemberAccess(member) => {
+                self.callee(member.object);
+                self.write(".");
+                self.write(member.property.value);
+            }
+            AST::Binary(binary) => {
+                let (operator, precedence, right_associative) = binary_operator(binary.operator);
+                let (left, right) = if right_associative {
+                    (precedence + 1, precedence)
+                } else {
+                    (precedence, precedence + 1)
+                };
+
+                self.expression(binary.left, left);
+                self.write(" ");
+                self.write(operator);
+                self.write(" ");
+                self.expression(binary.right, right);
+            }
+            AST::Unary(unary) => {
+                self.write(match unary.operator {
+                    UnaryOperator::Negate => "-",
+                    UnaryOperator::Not => "!",
+                });
+
+                // `-(-a)` rather than `--a`
+                let operand = &self.arena[unary.operand];
+                let min_precedence = match operand {
+                    AST::Unary(_) => PRIMARY,
+                    _ => UNARY,
+                };
+
+                self.node(operand, min_precedence);
+            }
+            AST::Block(block) => self.body(&block.body),
+            AST::With(with) => {
+                self.expression(with.body, WITH);
+                self.write(" with ");
+                self.expression(with.handlers, WITH + 1);
+            }
+            AST::Lambda(lambda) => self.lambda(lambda),
+            AST::LetBinding(binding) => {
+                self.pattern(&binding.pattern);
+                self.write(if binding.mutable { " := " } else { " = " });
+                self.expression(binding.value, LOWEST);
+            }
+            AST::Assignment(assignment) => {
+                self.write(assignment.target.value);
+                self.write(" = ");
+                self.expression(assignment.value, LOWEST);
+            }
+            AST::If(if_) => self.if_(if_),
+            AST::While(while_) => {
+                self.write("while ");
+
+                if let Some(pattern) = &while_.pattern {
+                    self.pattern(pattern);
+                    self.write(" = ");
+                }
+
+                self.expression(while_.condition, WITH);
+                self.write(" ");
+                self.body(&while_.body);
+            }
+            AST::ForOf(for_of) => {
+                self.write("for ");
+                self.pattern(&for_of.pattern);
+                self.write(" of ");
+                self.expression(for_of.iterable, WITH);
+                self.write(" ");
+                self.body(&for_of.body);
+            }
+            AST::Return(Return { value, .. }) | AST::Yield(Yield { value, .. }) => {
+                self.write(match node {
+                    AST::Return(_) => "return",
+                    _ => "yield",
+                });
+
+                if let Some(value) = value {
+                    self.write(" ");
+                    self.expression(*value, LOWEST);
+                }
+            }
+            AST::ImportDeclaration(import) => self.import(import),
+            AST::ExportDeclaration(export) => self.export(export),
+            AST::Match(match_) => self.match_(match_),
+            AST::FunctionDeclaration(function) => self.function(
+                function.name,
+                &function.type_parameters,
+                &function.parameters,
+                &function.return_type,
+                Some(&function.body),
+            ),
+            AST::TypeDeclaration(declaration) => {
+                self.write("type ");
+                self.write(declaration.name);
+                self.type_parameters(&declaration.type_parameters);
+                self.write(" =");
+
+                for (index, constructor) in declaration.type_constructors.iter().enumerate() {
+                    self.write(if index == 0 { " " } else { " | " });
+                    self.type_constructor(constructor);
+                }
+            }
+            AST::TypeAlias(alias) => match alias.branded() {
+                Some(branded) => {
+                    self.write("brand ");
+                    self.write(alias.name);
+                    self.type_parameters(&alias.type_parameters);
+                    self.write(" = ");
+                    write_type(&mut self.output, &branded, FUNCTION_TYPE);
+                }
+                None => {
+                    self.write("alias ");
+                    self.write(alias.name);
+                    self.type_parameters(&alias.type_parameters);
+                    self.write(" = ");
+                    self.annotation(&alias.aliased, FUNCTION_TYPE);
+                }
+            },
+            AST::StructDeclaration(declaration) => {
+                self.write("struct ");
+                self.write(declaration.name);
+                self.type_parameters(&declaration.type_parameters);
+                self.write(" ");
+                write_struct_type(&mut self.output, &declaration.fields);
+            }
+            AST::EffectDeclaration(effect) => self.effect(effect),
+            AST::ImplBlock(block) => {
+                self.write("impl");
+                self.type_parameters(&block.type_parameters);
+                self.write(" for ");
+                self.write(block.effect);
+                self.type_arguments(&block.type_arguments);
+                self.write(" ");
+                self.members(&[], &block.members);
+            }
+            AST::TypeclassDeclaration(typeclass) => self.typeclass(typeclass),
+            AST::InstanceDeclaration(instance) => {
+                self.write("impl");
+                self.type_parameters(&instance.type_parameters);
+                self.write(" ");
+                self.write(instance.typeclass);
+                self.type_arguments(&instance.type_arguments);
+                self.write(" for ");
+                self.annotation(&instance.target, UNION_TYPE);
+                self.write(" ");
+                self.members(&instance.associated_types, &instance.members);
+            }
+            AST::HandlerDeclaration(handler) => self.handler(handler),
+            // Token trees are printed as written, since how they're laid
+            // out may matter to the macro.
+            AST::MacroDeclaration(declaration) => {
+                self.write("macro ");
+                self.write(declaration.name);
+                self.parameters(&declaration.parameters);
+                self.write(" ");
+                self.write(declaration.body.text);
+            }
+            AST::MacroInvocation(invocation) => {
+                write_macro_invocation(&mut self.output, invocation)
+            }
+        }
+
+        if parenthesised {
+            self.write(")");
+        }
+    }
+
+    // The object of a call or member access. Number literals are wrapped so
+    // that `(1).a` isn't read as the start of a number.
+    fn callee(&mut self, id: NodeId) {
+        match &self.arena[id] {
+            AST::IntegerLiteral(_) | AST::NumberLiteral(_) => {
+                self.write("(");
+                self.expression(id, LOWEST);
+                self.write(")");
+            }
+            _ => self.expression(id, POSTFIX),
+        }
+    }
+
+    fn list<T>(&mut self, items: &[T], mut print: impl FnMut(&mut Self, &T)) {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                self.write(", ");
+            }
+
+            print(self, item);
+        }
+    }
+
+    fn elements(&mut self, elements: &[Element]) {
+        self.list(elements, |printer, element| match element {
+            Element::Member(value) => printer.expression(*value, LOWEST),
+            Element::Spread(value) => {
+                printer.write("...");
+                printer.expression(*value, LOWEST);
+            }
+        });
+    }
+
+    // `{ a }` is a block, so a record with a single shorthand member keeps
+    // a trailing comma: `{ a, }`.
+    fn record(&mut self, record: &Record<'a>) {
+        if record.members.is_empty() {
+            return self.write("{}");
+        }
+
+        self.write("{ ");
+        self.list(&record.members, |printer, member| match member {
+            RecordMember::Field(name, value) => {
+                printer.write(name.value);
+                printer.write(": ");
+                printer.expression(*value, LOWEST);
+            }
+            RecordMember::Shorthand(name) => printer.write(name.value),
+            RecordMember::Spread(value) => {
+                printer.write("...");
+                printer.expression(*value, LOWEST);
+            }
+        });
+
+        if let [RecordMember::Shorthand(_)] = record.members.as_slice() {
+            self.write(",");
+        }
+
+        self.write(" }");
+    }
+
+    // A lambda whose body is a single expression is printed without braces.
+    fn lambda(&mut self, lambda: &Lambda<'a>) {
+        self.type_parameters(&lambda.type_parameters);
+        self.parameters(&lambda.parameters);
+
+        if let Some(return_type) = &lambda.return_type {
+            self.write(": ");
+            self.annotation(return_type, UNION_TYPE);
+        }
+
+        self.write(" -> ");
+
+        match lambda.body.as_slice() {
+            [id] if is_expression(&self.arena[*id]) => self.expression(*id, LOWEST),
+            body => self.body(body),
+        }
+    }
+
+    fn if_(&mut self, if_: &If) {
+        self.write("if ");
+        self.expression(if_.condition, WITH);
+        self.write(" ");
+        self.body(&if_.consequent);
+
+        if let Some(alternative) = if_.alternative {
+            match &self.arena[alternative] {
+                AST::If(alternative) => {
+                    self.write(" else ");
+                    self.if_(alternative);
+                }
+                AST::Block(block) => {
+                    self.write(" else ");
+                    self.body(&block.body);
+                }
+                _ => {
+                    self.write(" else {");
+                    self.indent += 1;
+                    self.new_line();
+                    self.statement(alternative);
+                    self.indent -= 1;
+                    self.new_line();
+                    self.write("}");
+                }
+            }
+        }
+    }
+
+    fn match_(&mut self, match_: &Match<'a>) {
+        self.write("match ");
+        self.expression(match_.value, LOWEST);
+        self.write(" {");
+        self.indent += 1;
+
+        for case in &match_.cases {
+            self.new_line();
+
+            match &case.test {
+                MatchTest::Pattern(pattern) => self.pattern(pattern),
+                MatchTest::Predicate(predicate) => self.expression(*predicate, LOWEST),
+            }
+
+            self.write(" -> ");
+            self.expression(case.body, LOWEST);
+            self.write(",");
+        }
+
+        self.indent -= 1;
+
+        if !match_.cases.is_empty() {
+            self.new_line();
+        }
+
+        self.write("}");
+    }
+
+    fn pattern(&mut self, pattern: &Pattern<'a>) {
+        match pattern {
+            Pattern::Wildcard(_) => self.write("_"),
+            Pattern::Identifier(identifier) => self.write(identifier.value),
+            Pattern::Literal(literal) => self.node(literal, LOWEST),
+            Pattern::Constructor(constructor) => {
+                for qualifier in &constructor.qualifier {
+                    self.write(qualifier);
+                    self.write(".");
+                }
+
+                self.write(constructor.name);
+
+                if !constructor.arguments.is_empty() {
+                    self.write("(");
+                    self.list(&constructor.arguments, Self::pattern);
+                    self.write(")");
+                }
+            }
+            Pattern::Record(record) => {
+                if record.fields.is_empty() && record.rest.is_none() {
+                    return self.write("{}");
+                }
+
+                self.write("{ ");
+                self.list(&record.fields, |printer, field| {
+                    printer.write(field.name.value);
+
+                    match &field.pattern {
+                        Pattern::Identifier(identifier) if identifier.value == field.name.value => {
+                        }
+                        pattern => {
+                            printer.write(": ");
+                            printer.pattern(pattern);
+                        }
+                    }
+                });
+
+                if let Some(rest) = &record.rest {
+                    if !record.fields.is_empty() {
+                        self.write(", ");
+                    }
+
+                    self.write("...");
+                    self.pattern(rest);
+                } else if let [field] = record.fields.as_slice() {
+                    if matches!(&field.pattern, Pattern::Identifier(identifier) if identifier.value == field.name.value)
+                    {
+                        self.write(",");
+                    }
+                }
+
+                self.write(" }");
+            }
+            Pattern::Array(array) => {
+                self.write("[");
+                self.list(&array.elements, Self::pattern);
+
+                if let Some(rest) = &array.rest {
+                    if !array.elements.is_empty() {
+                        self.write(", ");
+                    }
+
+                    self.write("...");
+                    self.pattern(rest);
+                }
+
+                self.write("]");
+            }
+            Pattern::Tuple(tuple) => {
+                self.write("(");
+                self.list(&tuple.elements, Self::pattern);
+
+                if tuple.elements.len() == 1 {
+                    self.write(",");
+                }
+
+                self.write(")");
+            }
+        }
+    }
+
+    fn import(&mut self, import: &ImportDeclaration<'a>) {
+        self.write("import ");
+
+        if import.type_only {
+            self.write("type ");
+        }
+
+        if let Some(namespace) = import.namespace {
+            self.write("* as ");
+            self.write(namespace);
+        } else {
+            if let Some(default) = import.default {
+                self.write(default);
+            }
+
+            if import.default.is_some() && !import.named.is_empty() {
+                self.write(", ");
+            }
+
+            if import.default.is_none() || !import.named.is_empty() {
+                self.named_bindings(&import.named);
+            }
+        }
+
+        self.write(" from ");
+        self.specifier(import.specifier);
+    }
+
+    fn export(&mut self, export: &ExportDeclaration<'a>) {
+        self.write("export ");
+
+        match &export.kind {
+            ExportKind::Declaration(declaration) => self.expression(*declaration, LOWEST),
+            ExportKind::Named { bindings, from } => {
+                self.named_bindings(bindings);
+
+                if let Some(from) = from {
+                    self.write(" from ");
+                    self.specifier(from);
+                }
+            }
+            ExportKind::All { alias, from } => {
+                self.write("*");
+
+                if let Some(alias) = alias {
+                    self.write(" as ");
+                    self.write(alias);
+                }
+
+                self.write(" from ");
+                self.specifier(from);
+            }
+        }
+    }
+
+    fn named_bindings(&mut self, bindings: &[NamedBinding<'a>]) {
+        if bindings.is_empty() {
+            return self.write("{}");
+        }
+
+        self.write("{ ");
+        self.list(bindings, |printer, binding| {
+            printer.write(binding.name);
+
+            if let Some(alias) = binding.alias {
+                printer.write(" as ");
+                printer.write(alias);
+            }
+        });
+        self.write(" }");
+    }
+
+    fn specifier(&mut self, specifier: &str) {
+        self.write("'");
+        self.write(specifier);
+        self.write("'");
+    }
+
+    fn function(
+        &mut self,
+        name: &str,
+        type_parameters: &[TypeParameter<'a>],
+        parameters: &[FunctionParameter<'a>],
+        return_type: &Option<TypeAnnotation<'a>>,
+        body: Option<&[NodeId]>,
+    ) {
+        self.write("fun ");
+        self.write(name);
+        self.type_parameters(type_parameters);
+        self.parameters(parameters);
+
+        if let Some(return_type) = return_type {
+            self.write(": ");
+            self.annotation(return_type, FUNCTION_TYPE);
+        }
+
+        if let Some(body) = body {
+            self.write(" ");
+            self.body(body);
+        }
+    }
+
+    fn parameters(&mut self, parameters: &[FunctionParameter<'a>]) {
+        self.write("(");
+        self.list(parameters, |printer, parameter| {
+            printer.write(parameter.name);
+
+            if let Some(annotation) = &parameter.annotation {
+                printer.write(": ");
+                printer.annotation(annotation, FUNCTION_TYPE);
+            }
+        });
+        self.write(")");
+    }
+
+    fn type_parameters(&mut self, type_parameters: &[TypeParameter<'a>]) {
+        if type_parameters.is_empty() {
+            return;
+        }
+
+        self.write("<");
+        self.list(type_parameters, |printer, parameter| {
+            printer.write(parameter.name);
+
+            if let Some(extends) = &parameter.extends {
+                printer.write(": ");
+                write_type(&mut printer.output, extends, FUNCTION_TYPE);
+            }
+        });
+        self.write(">");
+    }
+
+    fn type_arguments(&mut self, type_arguments: &[TypeAnnotation<'a>]) {
+        if type_arguments.is_empty() {
+            return;
+        }
+
+        self.write("<");
+        self.list(type_arguments, |printer, argument| {
+            printer.annotation(argument, FUNCTION_TYPE)
+        });
+        self.write(">");
+    }
+
+    fn annotation(&mut self, annotation: &TypeAnnotation<'a>, min_precedence: u8) {
+        write_type(&mut self.output, &annotation.annotated, min_precedence);
+    }
+
+    // Constructors' return types stop short of unions, as `|` starts the
+    // next constructor.
+    fn type_constructor(&mut self, constructor: &TypeConstructor<'a>) {
+        self.write(constructor.name);
+        self.type_parameters(&constructor.type_parameters);
+
+        if !constructor.parameters.is_empty() {
+            self.write("(");
+            self.list(&constructor.parameters, |printer, parameter| {
+                if let Some(name) = parameter.name {
+                    printer.write(name);
+                    printer.write(": ");
+                }
+
+                printer.annotation(&parameter.annotated, FUNCTION_TYPE);
+            });
+            self.write(")");
+        }
+
+        if let Some(return_type) = &constructor.return_type {
+            self.write(": ");
+            self.annotation(return_type, INTERSECTION_TYPE);
+        }
+    }
+
+    fn effect(&mut self, effect: &EffectDeclaration<'a>) {
+        self.write("effect ");
+        self.write(effect.name);
+        self.type_parameters(&effect.type_parameters);
+
+        if let [operation @ EffectOperation { name: None, .. }] = effect.operations.as_slice() {
+            return self.effect_operation(operation);
+        }
+
+        self.write(" {");
+        self.indent += 1;
+
+        for operation in &effect.operations {
+            self.new_line();
+
+            if let Some(name) = operation.name {
+                self.write(name);
+            }
+
+            self.type_parameters(&operation.type_parameters);
+            self.effect_operation(operation);
+        }
+
+        self.indent -= 1;
+
+        if !effect.operations.is_empty() {
+            self.new_line();
+        }
+
+        self.write("}");
+    }
+
+    fn effect_operation(&mut self, operation: &EffectOperation<'a>) {
+        self.write("(");
+        self.list(&operation.parameters, |printer, parameter| {
+            printer.annotation(parameter, FUNCTION_TYPE)
+        });
+        self.write("): ");
+        self.annotation(&operation.return_type, FUNCTION_TYPE);
+    }
+
+    fn typeclass(&mut self, typeclass: &TypeclassDeclaration<'a>) {
+        self.write("typeclass ");
+        self.write(typeclass.name);
+        self.type_parameters(&typeclass.type_parameters);
+
+        if !typeclass.superclasses.is_empty() {
+            self.write(": ");
+            self.list(&typeclass.superclasses, |printer, superclass| {
+                printer.annotation(superclass, UNION_TYPE)
+            });
+        }
+
+        self.write(" {");
+        self.indent += 1;
+
+        for associated in &typeclass.associated_types {
+            self.new_line();
+            self.associated_type(associated);
+        }
+
+        for method in &typeclass.methods {
+            self.new_line();
+            self.function(
+                method.name,
+                &method.type_parameters,
+                &method.parameters,
+                &method.return_type,
+                method.default.as_deref(),
+            );
+        }
+
+        self.indent -= 1;
+
+        if !typeclass.associated_types.is_empty() || !typeclass.methods.is_empty() {
+            self.new_line();
+        }
+
+        self.write("}");
+    }
+
+    fn associated_type(&mut self, associated: &AssociatedType<'a>) {
+        self.write("type ");
+        self.write(associated.name);
+
+        if let Some(bound) = &associated.bound {
+            self.write(": ");
+            self.annotation(bound, FUNCTION_TYPE);
+        }
+
+        if let Some(default) = &associated.default {
+            self.write(" = ");
+            self.annotation(default, FUNCTION_TYPE);
+        }
+    }
+
+    // The `{ ... }` of an impl block or an instance.
+    fn members(&mut self, associated_types: &[AssociatedType<'a>], members: &[NodeId]) {
+        self.write("{");
+        self.indent += 1;
+
+        for associated in associated_types {
+            self.new_line();
+            self.associated_type(associated);
+        }
+
+        for &member in members {
+            self.new_line();
+            self.statement(member);
+        }
+
+        self.indent -= 1;
+
+        if !associated_types.is_empty() || !members.is_empty() {
+            self.new_line();
+        }
+
+        self.write("}");
+    }
+
+    fn handler(&mut self, handler: &HandlerDeclaration<'a>) {
+        self.write("handler");
+
+        if let Some(name) = handler.name {
+            self.write(" ");
+            self.write(name);
+        }
+
+        self.parameters(&handler.parameters);
+        self.write(" {");
+        self.indent += 1;
+
+        for &id in &handler.body {
+            self.new_line();
+            self.statement(id);
+        }
+
+        if !handler.operations.is_empty() {
+            self.new_line();
+            self.write("return {");
+            self.indent += 1;
+
+            for operation in &handler.operations {
+                self.new_line();
+                self.write(operation.name);
+                self.parameters(&operation.parameters);
+                self.write(" ");
+                self.body(&operation.body);
+            }
+
+            self.indent -= 1;
+            self.new_line();
+            self.write("}");
+        }
+
+        self.indent -= 1;
+
+        if !handler.body.is_empty() || !handler.operations.is_empty() {
+            self.new_line();
+        }
+
+        self.write("}");
+
+        if let Some(clause) = &handler.return_clause {
+            self.write(" {");
+            self.indent += 1;
+            self.new_line();
+            self.write("return");
+            self.parameters(std::slice::from_ref(&clause.parameter));
+            self.write(" ");
+            self.body(&clause.body);
+            self.indent -= 1;
+            self.new_line();
+            self.write("}");
+        }
+    }
+}
+
+fn precedence(node: &AST<'_>) -> u8 {
+    match node {
+        AST::Assignment(_) | AST::Lambda(_) => LOWEST,
+        AST::With(_) => WITH,
+        AST::Binary(binary) => binary_operator(binary.operator).1,
+        AST::Unary(_) => UNARY,
+        AST::Call(_) | AST::MemberAccess(_) => POSTFIX,
+        _ => PRIMARY,
+    }
+}
+
+// The operator's text, its precedence and whether it is right associative.
+fn binary_operator(operator: BinaryOperator) -> (&'static str, u8, bool) {
+    let (text, precedence, right_associative) = match operator {
+        BinaryOperator::Or => ("||", 1, false),
+        BinaryOperator::And => ("&&", 2, false),
+        BinaryOperator::Equal => ("==", 3, false),
+        BinaryOperator::NotEqual => ("!=", 3, false),
+        BinaryOperator::LessThan => ("<", 4, false),
+        BinaryOperator::LessThanEqual => ("<=", 4, false),
+        BinaryOperator::GreaterThan => (">", 4, false),
+        BinaryOperator::GreaterThanEqual => (">=", 4, false),
+        BinaryOperator::Concat => ("++", 5, true),
+        BinaryOperator::Add => ("+", 6, false),
+        BinaryOperator::Subtract => ("-", 6, false),
+        BinaryOperator::Multiply => ("*", 7, false),
+        BinaryOperator::Divide => ("/", 7, false),
+        BinaryOperator::Remainder => ("%", 7, false),
+        BinaryOperator::Exponent => ("**", 8, true),
+    };
+
+    (text, precedence + WITH, right_associative)
+}
+
+// Whether a node can stand alone as a lambda's body, i.e. it is parsed as
+// an expression rather than a statement.
+// String values keep their escapes, so they print as they were written. A
+// value with an unescaped `"` can only have come from single quotes; any
+// unescaped quote of the other kind is escaped, which only happens to
+// values that weren't parsed.
+fn quoted(value: &str) -> String {
+    let quote = if unescaped(value, '"') && !unescaped(value, '\'') {
+        '\''
+    } else {
+        '"'
+    };
+
+    let mut output = String::from(quote);
+    let mut escaped = false;
+
+    for char in value.chars() {
+        if char == quote && !escaped {
+            output.push('\\');
+        }
+
+        escaped = char == '\\' && !escaped;
+        output.push(char);
+    }
+
+    output.push(quote);
+    output
+}
+
+fn unescaped(value: &str, quote: char) -> bool {
+    let mut escaped = false;
+
+    for char in value.chars() {
+        if char == quote && !escaped {
+            return true;
+        }
+
+        escaped = char == '\\' && !escaped;
+    }
+
+    false
+}
+
+fn is_expression(node: &AST<'_>) -> bool {
+    !matches!(
+        node,
+        AST::LetBinding(_)
+            | AST::If(_)
+            | AST::While(_)
+            | AST::ForOf(_)
+            | AST::Return(_)
+            | AST::Yield(_)
+            | AST::Block(_)
+            | AST::ImportDeclaration(_)
+            | AST::ExportDeclaration(_)
+    ) && !is_declaration(node)
+}
+
+fn docs<'n>(node: &'n AST<'_>) -> Option<&'n Docs> {
+    match node {
+        AST::FunctionDeclaration(function) => function.docs(),
+        AST::TypeDeclaration(declaration) => declaration.docs(),
+        AST::TypeAlias(alias) => alias.docs(),
+        AST::EffectDeclaration(effect) => effect.docs(),
+        _ => None,
+    }
+}
+
+fn is_declaration(node: &AST<'_>) -> bool {
+    matches!(
+        node,
+        AST::FunctionDeclaration(_)
+            | AST::TypeDeclaration(_)
+            | AST::TypeAlias(_)
+            | AST::StructDeclaration(_)
+            | AST::EffectDeclaration(_)
+            | AST::ImplBlock(_)
+            | AST::TypeclassDeclaration(_)
+            | AST::InstanceDeclaration(_)
+            | AST::ExportDeclaration(_)
+            | AST::MacroDeclaration(_)
+    )
+}
+
+fn type_precedence(type_: &Type<'_>) -> u8 {
+    match type_ {
+        Type::Function(..) => FUNCTION_TYPE,
+        Type::Union(_) => UNION_TYPE,
+        Type::Intersection(_) => INTERSECTION_TYPE,
+        _ => PRIMARY_TYPE,
+    }
+}
+
+fn write_type(output: &mut String, type_: &Type<'_>, min_precedence: u8) {
+    let parenthesised = type_precedence(type_) < min_precedence;
+
+    if parenthesised {
+        output.push('(');
+    }
+
+    match type_ {
+        Type::Unit => output.push_str("Unit"),
+        Type::Never => output.push_str("Never"),
+        Type::Unknown => output.push_str("Unknown"),
+        Type::Boolean => output.push_str("Boolean"),
+        Type::Int => output.push_str("Int"),
+        Type::Float => output.push_str("Float"),
+        Type::String => output.push_str("String"),
+        Type::Infer => output.push('_'),
+        Type::Variable(index) => {
+            output.push('?');
+            output.push_str(&index.to_string());
+        }
+        Type::Any => output.push_str("Any"),
+        Type::Macro(invocation) => write_macro_invocation(output, invocation),
+        Type::Regexp(regexp) => output.push_str(regexp),
+        Type::Brand(name) | Type::Alias(name, _) => output.push_str(name),
+        Type::Template(types) => {
+            output.push('`');
+
+            for type_ in types {
+                output.push_str("${");
+                write_type(output, type_, FUNCTION_TYPE);
+                output.push('}');
+            }
+
+            output.push('`');
+        }
+        Type::Option(inner) => write_reference(output, "Option", [inner.as_ref()]),
+        Type::Either(left, right) => {
+            write_reference(output, "Either", [left.as_ref(), right.as_ref()])
+        }
+        Type::Reference(name, arguments) => write_reference(output, name, arguments),
+        Type::Array(inner) => {
+            output.push('[');
+            write_type(output, inner, FUNCTION_TYPE);
+            output.push(']');
+        }
+        Type::Tuple(members) => {
+            output.push('(');
+
+            for (index, member) in members.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+
+                match member {
+                    TupleData::Member(type_) => write_type(output, type_, FUNCTION_TYPE),
+                    TupleData::Spread(type_) => {
+                        output.push_str("...");
+                        write_type(output, type_, FUNCTION_TYPE);
+                    }
+                }
+            }
+
+            if let [TupleData::Member(_)] = members.as_slice() {
+                output.push(',');
+            }
+
+            output.push(')');
+        }
+        Type::Struct(members) => write_struct_type(output, members),
+        // Effects have no syntax of their own yet, so only the signature is
+        // printed.
+        Type::Function(type_parameters, arguments, _, returned) => {
+            if !type_parameters.is_empty() {
+                output.push('<');
+                write_types(output, type_parameters, ", ", FUNCTION_TYPE);
+                output.push('>');
+            }
+
+            output.push('(');
+            write_types(output, arguments, ", ", FUNCTION_TYPE);
+            output.push_str(") -> ");
+            write_type(output, returned, FUNCTION_TYPE);
+        }
+        Type::Union(members) => write_types(output, members, " | ", INTERSECTION_TYPE),
+        Type::Intersection(members) => write_types(output, members, " & ", PRIMARY_TYPE),
+    }
+
+    if parenthesised {
+        output.push(')');
+    }
+}
+
+fn write_macro_invocation(output: &mut String, invocation: &MacroInvocation<'_>) {
+    output.push('@');
+    output.push_str(invocation.name);
+    output.push('!');
+    output.push_str(invocation.group.text);
+}
+
+fn write_types(output: &mut String, types: &[Type<'_>], separator: &str, min_precedence: u8) {
+    for (index, type_) in types.iter().enumerate() {
+        if index > 0 {
+            output.push_str(separator);
+        }
+
+        write_type(output, type_, min_precedence);
+    }
+}
+
+fn write_reference<'t, 'a: 't>(
+    output: &mut String,
+    name: &str,
+    arguments: impl IntoIterator<Item = &'t Type<'a>>,
+) {
+    output.push_str(name);
+
+    let mut arguments = arguments.into_iter().peekable();
+
+    if arguments.peek().is_none() {
+        return;
+    }
+
+    output.push('<');
+
+    for (index, argument) in arguments.enumerate() {
+        if index > 0 {
+            output.push_str(", ");
+        }
+
+        write_type(output, argument, FUNCTION_TYPE);
+    }
+
+    output.push('>');
+}
+
+fn write_struct_type(output: &mut String, members: &[StructData<'_>]) {
+    if members.is_empty() {
+        return output.push_str("{}");
+    }
+
+    output.push_str("{ ");
+
+    for (index, member) in members.iter().enumerate() {
+        if index > 0 {
+            output.push_str(", ");
+        }
+
+        match member {
+            StructData::Member(name, type_) => {
+                output.push_str(name);
+                output.push_str(": ");
+                write_type(output, type_, FUNCTION_TYPE);
+            }
+            StructData::Spread(type_) => {
+                output.push_str("...");
+                write_type(output, type_, FUNCTION_TYPE);
+            }
+        }
+    }
+
+    output.push_str(" }");
+}
@@ -0,0 +1,1284 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    lexing::{Position, Spanned},
+    parsing::{arena::*, ast::*, types::*, visit::*},
+};
+
+// An index into Resolution::declarations.
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash, PartialOrd, Ord)]
+pub struct DeclarationId(pub(crate) usize);
+
+impl DeclarationId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DeclarationKind {
+    Import,
+    Function,
+    Parameter,
+    Variable { mutable: bool },
+    TypeParameter,
+    Type,
+    Constructor,
+    Alias,
+    Struct,
+    Effect,
+    Operation,
+    Typeclass,
+    Method, // Of a typeclass
+    AssociatedType,
+    Handler,
+    Macro,
+    Resume,
+}
+
+impl DeclarationKind {
+    // Whether the declaration can be named in a type.
+    pub fn is_type(self) -> bool {
+        matches!(
+            self,
+            DeclarationKind::Import
+                | DeclarationKind::TypeParameter
+                | DeclarationKind::Type
+                | DeclarationKind::Alias
+                | DeclarationKind::Struct
+                | DeclarationKind::Effect
+                | DeclarationKind::Typeclass
+                | DeclarationKind::AssociatedType
+        )
+    }
+
+    // Bindings are reported when they are never used or shadow something.
+    fn is_binding(self) -> bool {
+        matches!(
+            self,
+            DeclarationKind::Import
+                | DeclarationKind::Parameter
+                | DeclarationKind::Variable { .. }
+                | DeclarationKind::TypeParameter
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Declaration<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) kind: DeclarationKind,
+    pub(crate) position: Option<Position>, // None for built-ins
+    pub(crate) uses: Vec<Position>,        // Where it is read, in source order
+}
+
+impl<'a> Declaration<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn kind(&self) -> DeclarationKind {
+        self.kind
+    }
+
+    pub fn position(&self) -> Option<Position> {
+        self.position
+    }
+
+    pub fn uses(&self) -> &[Position] {
+        &self.uses
+    }
+
+    pub fn is_builtin(&self) -> bool {
+        self.position.is_none()
+    }
+}
+
+// Where a name without a node of its own is bound or matched: a parameter
+// or pattern of a node, the pattern of a match arm, or a handler's clause.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Site {
+    Node(NodeId),
+    Case(NodeId, usize),   // An arm of a match, by index
+    Clause(NodeId, usize), // An operation clause of a handler, by index
+    Return(NodeId),        // The return clause of a handler
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NameDiagnosticKind {
+    Unresolved, // An error, the name refers to nothing
+    Shadowed,   // A warning, a binding hides another one with the same name
+    Unused,     // A warning, a binding is never read
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct NameDiagnostic {
+    pub kind: NameDiagnosticKind,
+    pub message: String,
+    pub position: Position,
+}
+
+#[derive(Debug, Default)]
+pub struct Resolution<'a> {
+    pub(crate) declarations: Vec<Declaration<'a>>,
+    pub(crate) references: HashMap<NodeId, DeclarationId>,
+    pub(crate) definitions: HashMap<NodeId, DeclarationId>,
+    pub(crate) nodes: HashMap<DeclarationId, NodeId>, // The other way around, and imports
+    pub(crate) members: HashMap<DeclarationId, Vec<DeclarationId>>, // Constructors of types, operations and impl members of effects
+    pub(crate) aliases: HashMap<DeclarationId, DeclarationId>,      // What an alias refers to
+    pub(crate) values: HashMap<DeclarationId, NodeId>, // What `name = value` binds a variable to
+    pub(crate) names: HashMap<(Site, &'a str), DeclarationId>,
+    pub(crate) diagnostics: Vec<NameDiagnostic>,
+}
+
+impl<'a> Resolution<'a> {
+    pub fn declarations(&self) -> &[Declaration<'a>] {
+        &self.declarations
+    }
+
+    pub fn declaration(&self, id: DeclarationId) -> &Declaration<'a> {
+        &self.declarations[id.0]
+    }
+
+    // What an Identifier, an Assignment, or a MemberAccess naming a
+    // constructor, operation or impl member refers to.
+    pub fn reference(&self, node: NodeId) -> Option<DeclarationId> {
+        self.references.get(&node).copied()
+    }
+
+    // What a declaration node, like a function or an effect, declares.
+    pub fn definition(&self, node: NodeId) -> Option<DeclarationId> {
+        self.definitions.get(&node).copied()
+    }
+
+    // What a parameter, a name in a pattern, `resume` or the `_` of a
+    // wildcard arm declares, or what a constructor in a pattern or a
+    // shorthand record member like `{ name }` refers to.
+    pub fn name(&self, site: Site, name: &'a str) -> Option<DeclarationId> {
+        self.names.get(&(site, name)).copied()
+    }
+
+    // The declaration node that declares a declaration, or the import
+    // declaration of an import.
+    pub fn node(&self, id: DeclarationId) -> Option<NodeId> {
+        self.nodes.get(&id).copied()
+    }
+
+    // What an immutable variable bound by `name = value` was bound to.
+    pub fn value(&self, id: DeclarationId) -> Option<NodeId> {
+        self.values.get(&id).copied()
+    }
+
+    // Follows aliases like `alias IntQueue = Queue<Int>` to what they alias.
+    pub fn follow_aliases(&self, mut id: DeclarationId) -> DeclarationId {
+        let mut seen = HashSet::new();
+
+        while let Some(&target) = self.aliases.get(&id) {
+            if !seen.insert(id) {
+                break;
+            }
+
+            id = target;
+        }
+
+        id
+    }
+
+    // A constructor of a type, or an operation or impl member of an effect,
+    // through aliases: the `dequeue` of `IntQueue`.
+    pub fn member(&self, owner: DeclarationId, name: &str) -> Option<DeclarationId> {
+        let members = self.members.get(&self.follow_aliases(owner))?;
+
+        members
+            .iter()
+            .copied()
+            .find(|&member| self.declaration(member).name == name)
+    }
+
+    // In source order.
+    pub fn diagnostics(&self) -> &[NameDiagnostic] {
+        &self.diagnostics
+    }
+}
+
+const BUILTIN_TYPES: [&str; 8] = [
+    "Unit", "Never", "Unknown", "Boolean", "Int", "Float", "String", "Effect",
+];
+const BUILTIN_CONSTRUCTORS: [(&str, [&str; 2]); 2] =
+    [("Option", ["Some", "None"]), ("Either", ["Left", "Right"])];
+const BUILTIN_FUNCTIONS: [&str; 2] = ["assert_equal", "print"];
+
+// Links every name in a module to what declares it. Scopes are opened for
+// the module, functions, lambdas, handlers and their clauses, match arms and
+// every other braced body, and names are looked up from the innermost one
+// out, ending in a prelude of built-ins.
+//
+// Declarations (functions, types, effects, typeclasses, named handlers,
+// macros and imports) are visible throughout the body they are declared in,
+// so they can be used before they are declared and call each other.
+// Bindings (variables, parameters, type parameters and the names bound by
+// patterns) are only visible after they are made.
+//
+// Names that resolve to nothing are reported as errors. Bindings that are
+// never read, or that hide an earlier declaration that isn't a built-in,
+// are reported as warnings, unless their name starts with `_`.
+//
+// Besides plain names, the resolver links:
+//
+// - Types and type parameters in annotations, e.g. `Effect<Console, Unit>`.
+// - Constructors, bare like `Left(a)` or through their type like
+//   `Either.Left(a)`, and operations and `impl for` members of effects like
+//   `Queue.dequeue()`, also through aliases like `IntQueue.dequeue()`.
+// - `resume`, which every operation clause of a handler declares, along with
+//   the body of a handler without clauses, which handles the effect's only
+//   operation.
+// - `_` in a match arm whose pattern is `_`, which is the matched value.
+#[derive(Debug, Default)]
+pub struct Resolver<'a> {
+    resolution: Resolution<'a>,
+    scopes: Vec<Vec<(&'a str, DeclarationId)>>,
+    site: Option<Site>,           // Where parameters and patterns are being bound
+    annotation: Option<Position>, // The annotation whose types are being resolved
+    type_bindings: Vec<&'a str>,  // Type parameters of function types, like `<A>(A) -> A`
+}
+
+impl<'a> Resolver<'a> {
+    pub fn resolve(file: &SourceFile<'a>) -> Resolution<'a> {
+        let mut resolver = Resolver::default();
+
+        resolver.declare_prelude();
+        resolver.visit_source_file(file);
+        resolver.report_unused();
+        resolver
+            .resolution
+            .diagnostics
+            .sort_by_key(|diagnostic| diagnostic.position.start);
+
+        resolver.resolution
+    }
+
+    fn declare_prelude(&mut self) {
+        self.scopes.push(vec![]);
+
+        for name in BUILTIN_TYPES {
+            self.declare(name, DeclarationKind::Type, None);
+        }
+
+        for (type_name, constructors) in BUILTIN_CONSTRUCTORS {
+            let id = self.declare(type_name, DeclarationKind::Type, None);
+
+            for constructor in constructors {
+                let constructor = self.declare(constructor, DeclarationKind::Constructor, None);
+
+                self.resolution
+                    .members
+                    .entry(id)
+                    .or_default()
+                    .push(constructor);
+            }
+        }
+
+        for name in BUILTIN_FUNCTIONS {
+            self.declare(name, DeclarationKind::Function, None);
+        }
+    }
+
+    // Scopes
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(vec![]);
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    // A declaration that isn't in any scope, like an effect's operation.
+    fn add(
+        &mut self,
+        name: &'a str,
+        kind: DeclarationKind,
+        position: Option<Position>,
+    ) -> DeclarationId {
+        let id = DeclarationId(self.resolution.declarations.len());
+
+        self.resolution.declarations.push(Declaration {
+            name,
+            kind,
+            position,
+            uses: vec![],
+        });
+
+        id
+    }
+
+    fn declare(
+        &mut self,
+        name: &'a str,
+        kind: DeclarationKind,
+        position: Option<Position>,
+    ) -> DeclarationId {
+        let id = self.add(name, kind, position);
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name, id));
+        }
+
+        id
+    }
+
+    // Declares a binding, warning when it hides another declaration that
+    // isn't a built-in.
+    fn bind(&mut self, name: &'a str, kind: DeclarationKind, position: Position) -> DeclarationId {
+        if !name.starts_with('_') {
+            if let Some(shadowed) = self.lookup(name, false) {
+                if !self.resolution.declaration(shadowed).is_builtin() {
+                    self.report(
+                        NameDiagnosticKind::Shadowed,
+                        format!("`{}` shadows an earlier `{}`", name, name),
+                        position,
+                    );
+                }
+            }
+        }
+
+        self.declare(name, kind, Some(position))
+    }
+
+    fn name_at_site(&mut self, name: &'a str, id: DeclarationId) {
+        if let Some(site) = self.site {
+            self.resolution.names.insert((site, name), id);
+        }
+    }
+
+    fn lookup(&self, name: &str, is_type: bool) -> Option<DeclarationId> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|&&(declared, id)| {
+                declared == name && (!is_type || self.resolution.declaration(id).kind.is_type())
+            })
+            .map(|&(_, id)| id)
+    }
+
+    // Looks up the name before a `.`, which names a type or effect before a
+    // value declared in the same scope: with `type Wrap<A> = Wrap(A)`,
+    // `Wrap.Wrap` is the constructor of the type, not a member of the
+    // constructor. A value declared in an inner scope still shadows a type.
+    fn lookup_receiver(&self, name: &str) -> Option<DeclarationId> {
+        self.scopes.iter().rev().find_map(|scope| {
+            let declared = scope
+                .iter()
+                .rev()
+                .filter(|&&(declared, _)| declared == name);
+            let is_type =
+                |&&(_, id): &&(&str, DeclarationId)| self.resolution.declaration(id).kind.is_type();
+
+            declared
+                .clone()
+                .find(is_type)
+                .or_else(|| declared.clone().next())
+                .map(|&(_, id)| id)
+        })
+    }
+
+    fn use_declaration(&mut self, id: DeclarationId, position: Position) {
+        self.resolution.declarations[id.0].uses.push(position);
+    }
+
+    // Looks up a name read at `position`, reporting it when nothing
+    // declares it.
+    fn resolve_name(&mut self, name: &'a str, position: Position) -> Option<DeclarationId> {
+        match self.lookup(name, false) {
+            Some(id) => {
+                self.use_declaration(id, position);
+
+                Some(id)
+            }
+            None => {
+                self.report(
+                    NameDiagnosticKind::Unresolved,
+                    format!("`{}` is not defined", name),
+                    position,
+                );
+
+                None
+            }
+        }
+    }
+
+    // A member named through a type or an effect, like `Either.Left` or
+    // `IntQueue.dequeue`. Returns None without reporting anything when
+    // `owner` has no members, e.g. when it is a variable or an import.
+    fn resolve_member(
+        &mut self,
+        owner: DeclarationId,
+        name: &'a str,
+        position: Position,
+    ) -> Option<DeclarationId> {
+        let declaration = self
+            .resolution
+            .declaration(self.resolution.follow_aliases(owner));
+        let what = match declaration.kind {
+            DeclarationKind::Type => "constructor",
+            DeclarationKind::Effect => "operation or member",
+            _ => return None,
+        };
+        let member = self.resolution.member(owner, name);
+
+        match member {
+            Some(member) => self.use_declaration(member, position),
+            None => {
+                let message = format!("`{}` has no {} `{}`", declaration.name, what, name);
+
+                self.report(NameDiagnosticKind::Unresolved, message, position);
+            }
+        }
+
+        member
+    }
+
+    fn report(&mut self, kind: NameDiagnosticKind, message: String, position: Position) {
+        self.resolution.diagnostics.push(NameDiagnostic {
+            kind,
+            message,
+            position,
+        });
+    }
+
+    fn report_unused(&mut self) {
+        for declaration in &self.resolution.declarations {
+            let Some(position) = declaration.position else {
+                continue;
+            };
+
+            if declaration.kind.is_binding()
+                && declaration.uses.is_empty()
+                && !declaration.name.starts_with('_')
+            {
+                self.resolution.diagnostics.push(NameDiagnostic {
+                    kind: NameDiagnosticKind::Unused,
+                    message: format!("`{}` is never used", declaration.name),
+                    position,
+                });
+            }
+        }
+    }
+
+    // Declarations
+
+    // Declares the declarations of a body up front, so that they are
+    // visible throughout it.
+    fn hoist(&mut self, arena: &Arena<'a>, body: &[NodeId]) {
+        for &id in body {
+            self.hoist_declaration(arena, id);
+        }
+
+        // Aliases and impl members may name what is declared after them.
+        for &id in body {
+            self.link_alias(arena, id);
+        }
+
+        for &id in body {
+            if let AST::ImplBlock(block) = &arena[id] {
+                self.hoist_impl_members(arena, block);
+            }
+        }
+    }
+
+    fn hoist_declaration(&mut self, arena: &Arena<'a>, id: NodeId) {
+        let position = Some(arena[id].position());
+        let declared = match &arena[id] {
+            AST::ExportDeclaration(export) => {
+                if let ExportKind::Declaration(declaration) = export.kind {
+                    self.hoist_declaration(arena, declaration);
+                }
+
+                return;
+            }
+            AST::ImportDeclaration(import) => {
+                for local in import.default.into_iter().chain(import.namespace) {
+                    let local = self.declare(local, DeclarationKind::Import, position);
+
+                    self.resolution.nodes.insert(local, id);
+                }
+
+                for binding in &import.named {
+                    let local = binding.alias.unwrap_or(binding.name);
+                    let local =
+                        self.declare(local, DeclarationKind::Import, Some(binding.position));
+
+                    self.resolution.nodes.insert(local, id);
+                }
+
+                return;
+            }
+            AST::FunctionDeclaration(function) => {
+                self.declare(function.name, DeclarationKind::Function, position)
+            }
+            AST::TypeDeclaration(declaration) => {
+                let type_id = self.declare(declaration.name, DeclarationKind::Type, position);
+
+                for constructor in &declaration.type_constructors {
+                    let constructor = self.declare(
+                        constructor.name,
+                        DeclarationKind::Constructor,
+                        Some(constructor.position),
+                    );
+
+                    self.resolution
+                        .members
+                        .entry(type_id)
+                        .or_default()
+                        .push(constructor);
+                }
+
+                type_id
+            }
+            AST::TypeAlias(alias) => self.declare(alias.name, DeclarationKind::Alias, position),
+            AST::StructDeclaration(declaration) => {
+                self.declare(declaration.name, DeclarationKind::Struct, position)
+            }
+            AST::EffectDeclaration(effect) => {
+                let effect_id = self.declare(effect.name, DeclarationKind::Effect, position);
+
+                for operation in &effect.operations {
+                    if let Some(name) = operation.name {
+                        let operation =
+                            self.add(name, DeclarationKind::Operation, Some(operation.position));
+
+                        self.resolution
+                            .members
+                            .entry(effect_id)
+                            .or_default()
+                            .push(operation);
+                    }
+                }
+
+                effect_id
+            }
+            AST::TypeclassDeclaration(typeclass) => {
+                let typeclass_id =
+                    self.declare(typeclass.name, DeclarationKind::Typeclass, position);
+
+                for method in &typeclass.methods {
+                    let method =
+                        self.declare(method.name, DeclarationKind::Method, Some(method.position));
+
+                    self.resolution
+                        .members
+                        .entry(typeclass_id)
+                        .or_default()
+                        .push(method);
+                }
+
+                typeclass_id
+            }
+            AST::HandlerDeclaration(HandlerDeclaration {
+                name: Some(name), ..
+            }) => self.declare(name, DeclarationKind::Handler, position),
+            AST::MacroDeclaration(declaration) => {
+                self.declare(declaration.name, DeclarationKind::Macro, position)
+            }
+            _ => return,
+        };
+
+        self.resolution.definitions.insert(id, declared);
+        self.resolution.nodes.insert(declared, id);
+    }
+
+    fn link_alias(&mut self, arena: &Arena<'a>, id: NodeId) {
+        match &arena[id] {
+            AST::ExportDeclaration(export) => {
+                if let ExportKind::Declaration(declaration) = export.kind {
+                    self.link_alias(arena, declaration);
+                }
+            }
+            AST::TypeAlias(alias) => {
+                let Type::Reference(target, _) = &alias.aliased.annotated else {
+                    return;
+                };
+
+                if let (Some(alias), Some(target)) =
+                    (self.resolution.definition(id), self.lookup(target, true))
+                {
+                    self.resolution.aliases.insert(alias, target);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Members of `impl for` blocks are reached through their effect, like
+    // `Queue.unbounded()`, so they aren't declared in the enclosing scope.
+    fn hoist_impl_members(&mut self, arena: &Arena<'a>, block: &ImplBlock<'a>) {
+        let effect = self
+            .lookup(block.effect, true)
+            .map(|effect| self.resolution.follow_aliases(effect));
+
+        for &member in &block.members {
+            let (name, kind) = match &arena[member] {
+                AST::FunctionDeclaration(function) => (function.name, DeclarationKind::Function),
+                AST::HandlerDeclaration(HandlerDeclaration {
+                    name: Some(name), ..
+                }) => (*name, DeclarationKind::Handler),
+                _ => continue,
+            };
+            let id = self.add(name, kind, Some(arena[member].position()));
+
+            self.resolution.definitions.insert(member, id);
+            self.resolution.nodes.insert(id, member);
+
+            if let Some(effect) = effect {
+                self.resolution.members.entry(effect).or_default().push(id);
+            }
+        }
+    }
+
+    // Brings hoisted declarations from outside the current scope into it,
+    // like the members of an impl block.
+    fn declare_defined(&mut self, arena: &Arena<'a>, members: &[NodeId]) {
+        for &member in members {
+            if let Some(id) = self.resolution.definition(member) {
+                let name = self.resolution.declaration(id).name;
+
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.push((name, id));
+                }
+            } else {
+                self.hoist_declaration(arena, member);
+            }
+        }
+    }
+
+    // A braced body of statements, in a scope of its own.
+    fn visit_body(&mut self, arena: &Arena<'a>, body: &[NodeId]) {
+        self.enter_scope();
+        self.hoist(arena, body);
+
+        for &id in body {
+            self.visit_node(arena, id);
+        }
+
+        self.exit_scope();
+    }
+
+    fn bind_type_parameters(&mut self, arena: &Arena<'a>, parameters: &[TypeParameter<'a>]) {
+        for parameter in parameters {
+            if let Some(extends) = &parameter.extends {
+                self.annotation = Some(parameter.position);
+                self.visit_type(arena, extends);
+            }
+
+            self.bind(
+                parameter.name,
+                DeclarationKind::TypeParameter,
+                parameter.position,
+            );
+        }
+    }
+
+    // Parameters are bound after their annotation is resolved, so only
+    // parameters with a body to use them in are bound at all.
+    fn bind_parameters(&mut self, arena: &Arena<'a>, parameters: &[FunctionParameter<'a>]) {
+        for parameter in parameters {
+            self.visit_function_parameter(arena, parameter);
+
+            let id = self.bind(
+                parameter.name,
+                DeclarationKind::Parameter,
+                parameter.position,
+            );
+
+            self.name_at_site(parameter.name, id);
+        }
+    }
+
+    fn bind_pattern(&mut self, pattern: &Pattern<'a>, mutable: bool) {
+        match pattern {
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+            Pattern::Identifier(identifier) => {
+                // `None` matches the constructor rather than binding a name.
+                let constructor = identifier
+                    .value
+                    .starts_with(char::is_uppercase)
+                    .then(|| self.lookup(identifier.value, false))
+                    .flatten()
+                    .filter(|&id| {
+                        self.resolution.declaration(id).kind == DeclarationKind::Constructor
+                    });
+
+                let id = match constructor {
+                    Some(id) => {
+                        self.use_declaration(id, identifier.position);
+
+                        id
+                    }
+                    None => self.bind(
+                        identifier.value,
+                        DeclarationKind::Variable { mutable },
+                        identifier.position,
+                    ),
+                };
+
+                self.name_at_site(identifier.value, id);
+            }
+            Pattern::Constructor(constructor) => {
+                if let Some(id) = self.resolve_constructor(constructor) {
+                    self.name_at_site(constructor.name, id);
+                }
+
+                for argument in &constructor.arguments {
+                    self.bind_pattern(argument, mutable);
+                }
+            }
+            Pattern::Record(record) => {
+                for field in &record.fields {
+                    self.bind_pattern(&field.pattern, mutable);
+                }
+
+                if let Some(rest) = &record.rest {
+                    self.bind_pattern(rest, mutable);
+                }
+            }
+            Pattern::Array(array) => {
+                for element in &array.elements {
+                    self.bind_pattern(element, mutable);
+                }
+
+                if let Some(rest) = &array.rest {
+                    self.bind_pattern(rest, mutable);
+                }
+            }
+            Pattern::Tuple(tuple) => {
+                for element in &tuple.elements {
+                    self.bind_pattern(element, mutable);
+                }
+            }
+        }
+    }
+
+    fn resolve_constructor(
+        &mut self,
+        constructor: &ConstructorPattern<'a>,
+    ) -> Option<DeclarationId> {
+        let position = constructor.position;
+
+        match constructor.qualifier.first() {
+            Some(&owner) => {
+                let owner = self.resolve_name(owner, position)?;
+
+                self.resolve_member(owner, constructor.name, position)
+            }
+            None => {
+                let id = self.resolve_name(constructor.name, position)?;
+
+                if self.resolution.declaration(id).kind != DeclarationKind::Constructor {
+                    self.report(
+                        NameDiagnosticKind::Unresolved,
+                        format!("`{}` is not a constructor", constructor.name),
+                        position,
+                    );
+
+                    return None;
+                }
+
+                Some(id)
+            }
+        }
+    }
+
+    // A type named in an annotation, like `Queue<A>` or
+    // `CalcError.CannotDivideByZero`.
+    fn resolve_type_name(&mut self, name: &'a str) {
+        let position = self.annotation.unwrap_or(Position::new(0, 0));
+        let mut segments = name.split('.');
+        let head = segments.next().unwrap_or(name);
+
+        if self.type_bindings.contains(&head) {
+            return;
+        }
+
+        let Some(id) = self.lookup(head, true) else {
+            let message = match self.lookup(head, false) {
+                Some(_) => format!("`{}` is not a type", head),
+                None => format!("`{}` is not defined", head),
+            };
+
+            return self.report(NameDiagnosticKind::Unresolved, message, position);
+        };
+
+        self.use_declaration(id, position);
+
+        if let Some(member) = segments.next() {
+            self.resolve_member(id, member, position);
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for Resolver<'a> {
+    fn visit_source_file(&mut self, file: &SourceFile<'a>) {
+        self.enter_scope();
+        self.hoist(&file.arena, &file.body);
+        walk_source_file(self, file);
+        self.exit_scope();
+    }
+
+    fn visit_node(&mut self, arena: &Arena<'a>, id: NodeId) {
+        let site = self.site.replace(Site::Node(id));
+
+        match &arena[id] {
+            AST::Identifier(identifier) => {
+                if let Some(declaration) = self.resolve_name(identifier.value, identifier.position)
+                {
+                    self.resolution.references.insert(id, declaration);
+                }
+            }
+            AST::MemberAccess(member) => {
+                let owner = match &arena[member.object] {
+                    AST::Identifier(object) => self
+                        .lookup_receiver(object.value)
+                        .map(|owner| (owner, object.position)),
+                    _ => None,
+                };
+
+                match owner {
+                    Some((owner, position)) => {
+                        self.use_declaration(owner, position);
+                        self.resolution.references.insert(member.object, owner);
+                    }
+                    None => self.visit_node(arena, member.object),
+                }
+
+                if let Some(&owner) = self.resolution.references.get(&member.object) {
+                    let property = &member.property;
+
+                    if let Some(declaration) =
+                        self.resolve_member(owner, property.value, property.position)
+                    {
+                        self.resolution.references.insert(id, declaration);
+                    }
+                }
+            }
+            AST::Assignment(assignment) => {
+                self.visit_node(arena, assignment.value);
+
+                let target = &assignment.target;
+
+                match self.lookup(target.value, false) {
+                    Some(declaration) => {
+                        self.resolution.references.insert(id, declaration);
+                    }
+                    None => self.report(
+                        NameDiagnosticKind::Unresolved,
+                        format!("`{}` is not defined", target.value),
+                        target.position,
+                    ),
+                }
+            }
+            _ => walk_node(self, arena, id),
+        }
+
+        self.site = site;
+    }
+
+    fn visit_record_member(&mut self, arena: &Arena<'a>, member: &RecordMember<'a>) {
+        match member {
+            RecordMember::Shorthand(name) => {
+                if let Some(id) = self.resolve_name(name.value, name.position) {
+                    self.name_at_site(name.value, id);
+                }
+            }
+            RecordMember::Field(_, value) | RecordMember::Spread(value) => {
+                self.visit_node(arena, *value)
+            }
+        }
+    }
+
+    fn visit_block(&mut self, arena: &Arena<'a>, block: &Block) {
+        self.visit_body(arena, &block.body);
+    }
+
+    fn visit_lambda(&mut self, arena: &Arena<'a>, lambda: &Lambda<'a>) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &lambda.type_parameters);
+        self.bind_parameters(arena, &lambda.parameters);
+
+        if let Some(return_type) = &lambda.return_type {
+            self.visit_type_annotation(arena, return_type);
+        }
+
+        self.visit_body(arena, &lambda.body);
+        self.exit_scope();
+    }
+
+    fn visit_let_binding(&mut self, arena: &Arena<'a>, binding: &LetBinding<'a>) {
+        self.visit_node(arena, binding.value);
+        self.bind_pattern(&binding.pattern, binding.mutable);
+
+        // Kept so that member calls on the variable can see what it holds.
+        if let (Pattern::Identifier(identifier), false) = (&binding.pattern, binding.mutable) {
+            let bound = self.lookup(identifier.value, false).filter(|&id| {
+                self.resolution.declaration(id).kind == DeclarationKind::Variable { mutable: false }
+            });
+
+            if let Some(bound) = bound {
+                self.resolution.values.insert(bound, binding.value);
+            }
+        }
+    }
+
+    fn visit_if(&mut self, arena: &Arena<'a>, if_: &If) {
+        self.visit_node(arena, if_.condition);
+        self.visit_body(arena, &if_.consequent);
+
+        if let Some(alternative) = if_.alternative {
+            self.visit_node(arena, alternative);
+        }
+    }
+
+    fn visit_while(&mut self, arena: &Arena<'a>, while_: &While<'a>) {
+        self.visit_node(arena, while_.condition);
+        self.enter_scope();
+
+        if let Some(pattern) = &while_.pattern {
+            self.bind_pattern(pattern, false);
+        }
+
+        self.visit_body(arena, &while_.body);
+        self.exit_scope();
+    }
+
+    fn visit_for_of(&mut self, arena: &Arena<'a>, for_of: &ForOf<'a>) {
+        self.visit_node(arena, for_of.iterable);
+        self.enter_scope();
+        self.bind_pattern(&for_of.pattern, false);
+        self.visit_body(arena, &for_of.body);
+        self.exit_scope();
+    }
+
+    fn visit_match(&mut self, arena: &Arena<'a>, match_: &Match<'a>) {
+        let Some(Site::Node(node)) = self.site else {
+            return walk_match(self, arena, match_);
+        };
+
+        self.visit_node(arena, match_.value);
+
+        for (index, case) in match_.cases.iter().enumerate() {
+            self.site = Some(Site::Case(node, index));
+            self.visit_match_case(arena, case);
+        }
+
+        self.site = Some(Site::Node(node));
+    }
+
+    fn visit_match_case(&mut self, arena: &Arena<'a>, case: &MatchCase<'a>) {
+        self.enter_scope();
+
+        match &case.test {
+            MatchTest::Pattern(Pattern::Wildcard(position)) => {
+                let id = self.declare(
+                    "_",
+                    DeclarationKind::Variable { mutable: false },
+                    Some(*position),
+                );
+
+                self.name_at_site("_", id);
+            }
+            MatchTest::Pattern(pattern) => self.bind_pattern(pattern, false),
+            MatchTest::Predicate(predicate) => self.visit_node(arena, *predicate),
+        }
+
+        self.visit_node(arena, case.body);
+        self.exit_scope();
+    }
+
+    fn visit_function_declaration(
+        &mut self,
+        arena: &Arena<'a>,
+        function: &FunctionDeclaration<'a>,
+    ) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &function.type_parameters);
+        self.bind_parameters(arena, &function.parameters);
+
+        if let Some(return_type) = &function.return_type {
+            self.visit_type_annotation(arena, return_type);
+        }
+
+        self.visit_body(arena, &function.body);
+        self.exit_scope();
+    }
+
+    fn visit_type_declaration(&mut self, arena: &Arena<'a>, declaration: &TypeDeclaration<'a>) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &declaration.type_parameters);
+
+        for constructor in &declaration.type_constructors {
+            self.visit_type_constructor(arena, constructor);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_type_constructor(&mut self, arena: &Arena<'a>, constructor: &TypeConstructor<'a>) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &constructor.type_parameters);
+
+        for parameter in &constructor.parameters {
+            self.visit_labeled_type_annotation(arena, parameter);
+        }
+
+        if let Some(return_type) = &constructor.return_type {
+            self.visit_type_annotation(arena, return_type);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_type_alias(&mut self, arena: &Arena<'a>, alias: &TypeAlias<'a>) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &alias.type_parameters);
+        self.visit_type_annotation(arena, &alias.aliased);
+        self.exit_scope();
+    }
+
+    fn visit_struct_declaration(&mut self, arena: &Arena<'a>, declaration: &StructDeclaration<'a>) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &declaration.type_parameters);
+
+        for field in &declaration.fields {
+            self.annotation = Some(declaration.position);
+            self.visit_struct_data(arena, field);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_effect_declaration(&mut self, arena: &Arena<'a>, effect: &EffectDeclaration<'a>) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &effect.type_parameters);
+
+        for operation in &effect.operations {
+            self.visit_effect_operation(arena, operation);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_effect_operation(&mut self, arena: &Arena<'a>, operation: &EffectOperation<'a>) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &operation.type_parameters);
+
+        for parameter in &operation.parameters {
+            self.visit_type_annotation(arena, parameter);
+        }
+
+        self.visit_type_annotation(arena, &operation.return_type);
+        self.exit_scope();
+    }
+
+    fn visit_impl_block(&mut self, arena: &Arena<'a>, block: &ImplBlock<'a>) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &block.type_parameters);
+        self.annotation = Some(block.position);
+        self.resolve_type_name(block.effect);
+
+        for argument in &block.type_arguments {
+            self.visit_type_annotation(arena, argument);
+        }
+
+        self.declare_defined(arena, &block.members);
+
+        for &member in &block.members {
+            self.visit_node(arena, member);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_typeclass_declaration(
+        &mut self,
+        arena: &Arena<'a>,
+        typeclass: &TypeclassDeclaration<'a>,
+    ) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &typeclass.type_parameters);
+
+        for superclass in &typeclass.superclasses {
+            self.visit_type_annotation(arena, superclass);
+        }
+
+        for associated in &typeclass.associated_types {
+            self.visit_associated_type(arena, associated);
+        }
+
+        for method in &typeclass.methods {
+            self.visit_typeclass_method(arena, method);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_typeclass_method(&mut self, arena: &Arena<'a>, method: &TypeclassMethod<'a>) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &method.type_parameters);
+
+        match &method.default {
+            Some(_) => self.bind_parameters(arena, &method.parameters),
+            None => {
+                for parameter in &method.parameters {
+                    self.visit_function_parameter(arena, parameter);
+                }
+            }
+        }
+
+        if let Some(return_type) = &method.return_type {
+            self.visit_type_annotation(arena, return_type);
+        }
+
+        if let Some(default) = &method.default {
+            self.visit_body(arena, default);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_associated_type(&mut self, arena: &Arena<'a>, associated: &AssociatedType<'a>) {
+        walk_associated_type(self, arena, associated);
+        self.declare(
+            associated.name,
+            DeclarationKind::AssociatedType,
+            Some(associated.position),
+        );
+    }
+
+    fn visit_instance_declaration(
+        &mut self,
+        arena: &Arena<'a>,
+        instance: &InstanceDeclaration<'a>,
+    ) {
+        self.enter_scope();
+        self.bind_type_parameters(arena, &instance.type_parameters);
+        self.annotation = Some(instance.position);
+        self.resolve_type_name(instance.typeclass);
+
+        for argument in &instance.type_arguments {
+            self.visit_type_annotation(arena, argument);
+        }
+
+        self.visit_type_annotation(arena, &instance.target);
+
+        for associated in &instance.associated_types {
+            self.visit_associated_type(arena, associated);
+        }
+
+        self.declare_defined(arena, &instance.members);
+
+        for &member in &instance.members {
+            self.visit_node(arena, member);
+        }
+
+        self.exit_scope();
+    }
+
+    fn visit_handler_declaration(&mut self, arena: &Arena<'a>, handler: &HandlerDeclaration<'a>) {
+        let site = self.site;
+
+        self.enter_scope();
+        self.bind_parameters(arena, &handler.parameters);
+
+        // Without clauses, the body handles the effect's only operation.
+        if handler.operations.is_empty() {
+            let id = self.declare("resume", DeclarationKind::Resume, Some(handler.position));
+
+            self.name_at_site("resume", id);
+        }
+
+        // The handler's state is visible in its clauses.
+        self.enter_scope();
+        self.hoist(arena, &handler.body);
+
+        for &id in &handler.body {
+            self.visit_node(arena, id);
+        }
+
+        for (index, operation) in handler.operations.iter().enumerate() {
+            if let Some(Site::Node(node)) = site {
+                self.site = Some(Site::Clause(node, index));
+            }
+
+            self.visit_operation_clause(arena, operation);
+        }
+
+        self.exit_scope();
+        self.exit_scope();
+
+        if let Some(clause) = &handler.return_clause {
+            if let Some(Site::Node(node)) = site {
+                self.site = Some(Site::Return(node));
+            }
+
+            self.visit_return_clause(arena, clause);
+        }
+
+        self.site = site;
+    }
+
+    fn visit_operation_clause(&mut self, arena: &Arena<'a>, clause: &OperationClause<'a>) {
+        self.enter_scope();
+
+        let id = self.declare("resume", DeclarationKind::Resume, Some(clause.position));
+
+        self.name_at_site("resume", id);
+        self.bind_parameters(arena, &clause.parameters);
+        self.visit_body(arena, &clause.body);
+        self.exit_scope();
+    }
+
+    fn visit_return_clause(&mut self, arena: &Arena<'a>, clause: &ReturnClause<'a>) {
+        self.enter_scope();
+        self.bind_parameters(arena, std::slice::from_ref(&clause.parameter));
+        self.visit_body(arena, &clause.body);
+        self.exit_scope();
+    }
+
+    // Macro bodies are token trees, so their parameters can't be resolved.
+    fn visit_macro_declaration(&mut self, arena: &Arena<'a>, declaration: &MacroDeclaration<'a>) {
+        for parameter in &declaration.parameters {
+            self.visit_function_parameter(arena, parameter);
+        }
+    }
+
+    fn visit_type_annotation(&mut self, arena: &Arena<'a>, annotation: &TypeAnnotation<'a>) {
+        self.annotation = Some(annotation.position);
+        self.visit_type(arena, &annotation.annotated);
+    }
+
+    fn visit_type(&mut self, arena: &Arena<'a>, type_: &Type<'a>) {
+        match type_ {
+            Type::Reference(name, _) => {
+                self.resolve_type_name(name);
+                walk_type(self, arena, type_);
+            }
+            Type::Function(type_parameters, arguments, effects, returned) => {
+                let bound = self.type_bindings.len();
+
+                for parameter in type_parameters {
+                    if let Type::Reference(name, _) = parameter {
+                        self.type_bindings.push(name);
+                    }
+                }
+
+                for type_ in arguments.iter().chain(effects) {
+                    self.visit_type(arena, type_);
+                }
+
+                self.visit_type(arena, returned);
+                self.type_bindings.truncate(bound);
+            }
+            _ => walk_type(self, arena, type_),
+        }
+    }
+}
@@ -1,8 +1,14 @@
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::MacroInvocation;
+
 // TODO: Refinment types?
 // TODO: Mapped types?
 // TODO: Constraints?
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum Type<'a> {
     Unit,    // Nothing
     Never,   // Bottom
@@ -20,25 +26,35 @@ pub enum Type<'a> {
     Tuple(Vec<TupleData<'a>>),
     Struct(Vec<StructData<'a>>),
     Function(
-        /*input type params*/ Vec<Type<'a>>,
-        /*Argument types*/ Vec<Type<'a>>,
-        /*Effect Types*/ Vec<Type<'a>>,
-        /*Return Types*/ Box<Type<'a>>,
+        /*input type params*/
+        Vec<Type<'a>>,
+        /*Argument types*/
+        Vec<Type<'a>>,
+        /*Effect Types*/
+        Vec<Type<'a>>,
+        /*Return Types*/
+        Box<Type<'a>>,
     ),
     Alias(&'a str, Box<Type<'a>>), // To preserve the name of the type in hovers
+    Reference(&'a str, Vec<Type<'a>>), // A named type and its type arguments, e.g. Queue<Int>
     Union(Vec<Type<'a>>),
     Intersection(Vec<Type<'a>>),
     Infer,
+    Variable(usize), // A type being inferred by the checker, printed as `?0`
+    Any,             // What the checker doesn't check, e.g. imports, which fits anywhere
+    Macro(MacroInvocation<'a>), // Expands to a type
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum TupleData<'a> {
     Member(Box<Type<'a>>),
-    Spread(Box<TupleData<'a>>),
+    Spread(Box<Type<'a>>),
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum StructData<'a> {
     Member(&'a str, Box<Type<'a>>),
-    Spread(Box<StructData<'a>>),
+    Spread(Box<Type<'a>>),
 }
@@ -0,0 +1,918 @@
+use crate::parsing::{arena::*, ast::*, types::*};
+
+// Walks a module without changing it. Every method defaults to the matching
+// walk function below, which visits the node's children in source order, so
+// a pass only overrides the nodes it cares about and calls back into the
+// walk function to keep descending.
+pub trait Visitor<'a> {
+    fn visit_source_file(&mut self, file: &SourceFile<'a>) {
+        walk_source_file(self, file)
+    }
+
+    fn visit_node(&mut self, arena: &Arena<'a>, id: NodeId) {
+        walk_node(self, arena, id)
+    }
+
+    fn visit_boolean_literal(&mut self, _arena: &Arena<'a>, _literal: &BooleanLiteral) {}
+
+    fn visit_integer_literal(&mut self, _arena: &Arena<'a>, _literal: &IntegerLiteral) {}
+
+    fn visit_number_literal(&mut self, _arena: &Arena<'a>, _literal: &NumberLiteral) {}
+
+    fn visit_regexp_literal(&mut self, _arena: &Arena<'a>, _literal: &RegexpLiteral<'a>) {}
+
+    fn visit_string_literal(&mut self, _arena: &Arena<'a>, _literal: &StringLiteral<'a>) {}
+
+    fn visit_template_literal(&mut self, arena: &Arena<'a>, literal: &TemplateLiteral<'a>) {
+        walk_template_literal(self, arena, literal)
+    }
+
+    fn visit_identifier(&mut self, _arena: &Arena<'a>, _identifier: &Identifier<'a>) {}
+
+    fn visit_record(&mut self, arena: &Arena<'a>, record: &Record<'a>) {
+        walk_record(self, arena, record)
+    }
+
+    fn visit_record_member(&mut self, arena: &Arena<'a>, member: &RecordMember<'a>) {
+        walk_record_member(self, arena, member)
+    }
+
+    fn visit_array(&mut self, arena: &Arena<'a>, array: &Array) {
+        walk_array(self, arena, array)
+    }
+
+    fn visit_tuple(&mut self, arena: &Arena<'a>, tuple: &Tuple) {
+        walk_tuple(self, arena, tuple)
+    }
+
+    fn visit_element(&mut self, arena: &Arena<'a>, element: &Element) {
+        walk_element(self, arena, element)
+    }
+
+    fn visit_call(&mut self, arena: &Arena<'a>, call: &Call) {
+        walk_call(self, arena, call)
+    }
+
+    fn visit_member_access(&mut self, arena: &Arena<'a>, member: &MemberAccess<'a>) {
+        walk_member_access(self, arena, member)
+    }
+
+    fn visit_binary(&mut self, arena: &Arena<'a>, binary: &Binary) {
+        walk_binary(self, arena, binary)
+    }
+
+    fn visit_unary(&mut self, arena: &Arena<'a>, unary: &Unary) {
+        walk_unary(self, arena, unary)
+    }
+
+    fn visit_block(&mut self, arena: &Arena<'a>, block: &Block) {
+        walk_block(self, arena, block)
+    }
+
+    fn visit_with(&mut self, arena: &Arena<'a>, with: &With) {
+        walk_with(self, arena, with)
+    }
+
+    fn visit_lambda(&mut self, arena: &Arena<'a>, lambda: &Lambda<'a>) {
+        walk_lambda(self, arena, lambda)
+    }
+
+    fn visit_let_binding(&mut self, arena: &Arena<'a>, binding: &LetBinding<'a>) {
+        walk_let_binding(self, arena, binding)
+    }
+
+    fn visit_assignment(&mut self, arena: &Arena<'a>, assignment: &Assignment<'a>) {
+        walk_assignment(self, arena, assignment)
+    }
+
+    fn visit_if(&mut self, arena: &Arena<'a>, if_: &If) {
+        walk_if(self, arena, if_)
+    }
+
+    fn visit_while(&mut self, arena: &Arena<'a>, while_: &While<'a>) {
+        walk_while(self, arena, while_)
+    }
+
+    fn visit_for_of(&mut self, arena: &Arena<'a>, for_of: &ForOf<'a>) {
+        walk_for_of(self, arena, for_of)
+    }
+
+    fn visit_return(&mut self, arena: &Arena<'a>, return_: &Return) {
+        walk_return(self, arena, return_)
+    }
+
+    fn visit_yield(&mut self, arena: &Arena<'a>, yield_: &Yield) {
+        walk_yield(self, arena, yield_)
+    }
+
+    fn visit_import_declaration(&mut self, _arena: &Arena<'a>, _import: &ImportDeclaration<'a>) {}
+
+    fn visit_export_declaration(&mut self, arena: &Arena<'a>, export: &ExportDeclaration<'a>) {
+        walk_export_declaration(self, arena, export)
+    }
+
+    fn visit_match(&mut self, arena: &Arena<'a>, match_: &Match<'a>) {
+        walk_match(self, arena, match_)
+    }
+
+    fn visit_match_case(&mut self, arena: &Arena<'a>, case: &MatchCase<'a>) {
+        walk_match_case(self, arena, case)
+    }
+
+    fn visit_pattern(&mut self, arena: &Arena<'a>, pattern: &Pattern<'a>) {
+        walk_pattern(self, arena, pattern)
+    }
+
+    fn visit_function_declaration(
+        &mut self,
+        arena: &Arena<'a>,
+        function: &FunctionDeclaration<'a>,
+    ) {
+        walk_function_declaration(self, arena, function)
+    }
+
+    fn visit_function_parameter(&mut self, arena: &Arena<'a>, parameter: &FunctionParameter<'a>) {
+        walk_function_parameter(self, arena, parameter)
+    }
+
+    fn visit_type_parameter(&mut self, arena: &Arena<'a>, parameter: &TypeParameter<'a>) {
+        walk_type_parameter(self, arena, parameter)
+    }
+
+    fn visit_type_declaration(&mut self, arena: &Arena<'a>, declaration: &TypeDeclaration<'a>) {
+        walk_type_declaration(self, arena, declaration)
+    }
+
+    fn visit_type_constructor(&mut self, arena: &Arena<'a>, constructor: &TypeConstructor<'a>) {
+        walk_type_constructor(self, arena, constructor)
+    }
+
+    fn visit_type_alias(&mut self, arena: &Arena<'a>, alias: &TypeAlias<'a>) {
+        walk_type_alias(self, arena, alias)
+    }
+
+    fn visit_struct_declaration(&mut self, arena: &Arena<'a>, declaration: &StructDeclaration<'a>) {
+        walk_struct_declaration(self, arena, declaration)
+    }
+
+    fn visit_effect_declaration(&mut self, arena: &Arena<'a>, effect: &EffectDeclaration<'a>) {
+        walk_effect_declaration(self, arena, effect)
+    }
+
+    fn visit_effect_operation(&mut self, arena: &Arena<'a>, operation: &EffectOperation<'a>) {
+        walk_effect_operation(self, arena, operation)
+    }
+
+    fn visit_impl_block(&mut self, arena: &Arena<'a>, block: &ImplBlock<'a>) {
+        walk_impl_block(self, arena, block)
+    }
+
+    fn visit_typeclass_declaration(
+        &mut self,
+        arena: &Arena<'a>,
+        typeclass: &TypeclassDeclaration<'a>,
+    ) {
+        walk_typeclass_declaration(self, arena, typeclass)
+    }
+
+    fn visit_typeclass_method(&mut self, arena: &Arena<'a>, method: &TypeclassMethod<'a>) {
+        walk_typeclass_method(self, arena, method)
+    }
+
+    fn visit_associated_type(&mut self, arena: &Arena<'a>, associated: &AssociatedType<'a>) {
+        walk_associated_type(self, arena, associated)
+    }
+
+    fn visit_instance_declaration(
+        &mut self,
+        arena: &Arena<'a>,
+        instance: &InstanceDeclaration<'a>,
+    ) {
+        walk_instance_declaration(self, arena, instance)
+    }
+
+    fn visit_handler_declaration(&mut self, arena: &Arena<'a>, handler: &HandlerDeclaration<'a>) {
+        walk_handler_declaration(self, arena, handler)
+    }
+
+    fn visit_operation_clause(&mut self, arena: &Arena<'a>, clause: &OperationClause<'a>) {
+        walk_operation_clause(self, arena, clause)
+    }
+
+    fn visit_return_clause(&mut self, arena: &Arena<'a>, clause: &ReturnClause<'a>) {
+        walk_return_clause(self, arena, clause)
+    }
+
+    fn visit_macro_declaration(&mut self, arena: &Arena<'a>, declaration: &MacroDeclaration<'a>) {
+        walk_macro_declaration(self, arena, declaration)
+    }
+
+    fn visit_macro_invocation(&mut self, _arena: &Arena<'a>, _invocation: &MacroInvocation<'a>) {}
+
+    fn visit_type_annotation(&mut self, arena: &Arena<'a>, annotation: &TypeAnnotation<'a>) {
+        walk_type_annotation(self, arena, annotation)
+    }
+
+    fn visit_labeled_type_annotation(
+        &mut self,
+        arena: &Arena<'a>,
+        annotation: &LabeledTypeAnnotation<'a>,
+    ) {
+        walk_labeled_type_annotation(self, arena, annotation)
+    }
+
+    fn visit_type(&mut self, arena: &Arena<'a>, type_: &Type<'a>) {
+        walk_type(self, arena, type_)
+    }
+
+    fn visit_tuple_data(&mut self, arena: &Arena<'a>, data: &TupleData<'a>) {
+        walk_tuple_data(self, arena, data)
+    }
+
+    fn visit_struct_data(&mut self, arena: &Arena<'a>, data: &StructData<'a>) {
+        walk_struct_data(self, arena, data)
+    }
+}
+
+pub fn walk_source_file<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, file: &SourceFile<'a>) {
+    for &id in &file.body {
+        visitor.visit_node(&file.arena, id);
+    }
+}
+
+pub fn walk_node<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, id: NodeId) {
+    walk_ast(visitor, arena, &arena[id])
+}
+
+// Dispatches to the method for the node's variant. Used for nodes in the
+// arena and for the literals owned by patterns.
+pub fn walk_ast<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, node: &AST<'a>) {
+    match node {
+        AST::BooleanLiteral(literal) => visitor.visit_boolean_literal(arena, literal),
+        AST::IntegerLiteral(literal) => visitor.visit_integer_literal(arena, literal),
+        AST::NumberLiteral(literal) => visitor.visit_number_literal(arena, literal),
+        AST::RegexpLiteral(literal) => visitor.visit_regexp_literal(arena, literal),
+        AST::StringLiteral(literal) => visitor.visit_string_literal(arena, literal),
+        AST::TemplateLiteral(literal) => visitor.visit_template_literal(arena, literal),
+        AST::Identifier(identifier) => visitor.visit_identifier(arena, identifier),
+        AST::Record(record) => visitor.visit_record(arena, record),
+        AST::Array(array) => visitor.visit_array(arena, array),
+        AST::Tuple(tuple) => visitor.visit_tuple(arena, tuple),
+        AST::Call(call) => visitor.visit_call(arena, call),
+        AST::MemberAccess(member) => visitor.visit_member_access(arena, member),
+        AST::Binary(binary) => visitor.visit_binary(arena, binary),
+        AST::Unary(unary) => visitor.visit_unary(arena, unary),
+        AST::Block(block) => visitor.visit_block(arena, block),
+        AST::With(with) => visitor.visit_with(arena, with),
+        AST::Lambda(lambda) => visitor.visit_lambda(arena, lambda),
+        AST::LetBinding(binding) => visitor.visit_let_binding(arena, binding),
+        AST::Assignment(assignment) => visitor.visit_assignment(arena, assignment),
+        AST::If(if_) => visitor.visit_if(arena, if_),
+        AST::While(while_) => visitor.visit_while(arena, while_),
+        AST::ForOf(for_of) => visitor.visit_for_of(arena, for_of),
+        AST::Return(return_) => visitor.visit_return(arena, return_),
+        AST::Yield(yield_) => visitor.visit_yield(arena, yield_),
+        AST::ImportDeclaration(import) => visitor.visit_import_declaration(arena, import),
+        AST::ExportDeclaration(export) => visitor.visit_export_declaration(arena, export),
+        AST::Match(match_) => visitor.visit_match(arena, match_),
+        AST::FunctionDeclaration(function) => visitor.visit_function_declaration(arena, function),
+        AST::TypeDeclaration(declaration) => visitor.visit_type_declaration(arena, declaration),
+        AST::TypeAlias(alias) => visitor.visit_type_alias(arena, alias),
+        AST::StructDeclaration(declaration) => visitor.visit_struct_declaration(arena, declaration),
+        AST::EffectDeclaration(effect) => visitor.visit_effect_declaration(arena, effect),
+        AST::ImplBlock(block) => visitor.visit_impl_block(arena, block),
+        AST::TypeclassDeclaration(typeclass) => {
+            visitor.visit_typeclass_declaration(arena, typeclass)
+        }
+        AST::InstanceDeclaration(instance) => visitor.visit_instance_declaration(arena, instance),
+        AST::HandlerDeclaration(handler) => visitor.visit_handler_declaration(arena, handler),
+        AST::MacroDeclaration(declaration) => visitor.visit_macro_declaration(arena, declaration),
+        AST::MacroInvocation(invocation) => visitor.visit_macro_invocation(arena, invocation),
+    }
+}
+
+fn walk_nodes<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, ids: &[NodeId]) {
+    for &id in ids {
+        visitor.visit_node(arena, id);
+    }
+}
+
+pub fn walk_template_literal<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    literal: &TemplateLiteral<'a>,
+) {
+    walk_nodes(visitor, arena, &literal.values);
+}
+
+pub fn walk_record<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    record: &Record<'a>,
+) {
+    for member in &record.members {
+        visitor.visit_record_member(arena, member);
+    }
+}
+
+pub fn walk_record_member<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    member: &RecordMember<'a>,
+) {
+    match member {
+        RecordMember::Field(name, value) => {
+            visitor.visit_identifier(arena, name);
+            visitor.visit_node(arena, *value);
+        }
+        RecordMember::Shorthand(name) => visitor.visit_identifier(arena, name),
+        RecordMember::Spread(value) => visitor.visit_node(arena, *value),
+    }
+}
+
+pub fn walk_array<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, array: &Array) {
+    for element in &array.elements {
+        visitor.visit_element(arena, element);
+    }
+}
+
+pub fn walk_tuple<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, tuple: &Tuple) {
+    for element in &tuple.elements {
+        visitor.visit_element(arena, element);
+    }
+}
+
+pub fn walk_element<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    element: &Element,
+) {
+    match element {
+        Element::Member(value) | Element::Spread(value) => visitor.visit_node(arena, *value),
+    }
+}
+
+pub fn walk_call<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, call: &Call) {
+    visitor.visit_node(arena, call.callee);
+    walk_nodes(visitor, arena, &call.arguments);
+}
+
+pub fn walk_member_access<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    member: &MemberAccess<'a>,
+) {
+    visitor.visit_node(arena, member.object);
+    visitor.visit_identifier(arena, &member.property);
+}
+
+pub fn walk_binary<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    binary: &Binary,
+) {
+    visitor.visit_node(arena, binary.left);
+    visitor.visit_node(arena, binary.right);
+}
+
+pub fn walk_unary<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, unary: &Unary) {
+    visitor.visit_node(arena, unary.operand);
+}
+
+pub fn walk_block<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, block: &Block) {
+    walk_nodes(visitor, arena, &block.body);
+}
+
+pub fn walk_with<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, with: &With) {
+    visitor.visit_node(arena, with.body);
+    visitor.visit_node(arena, with.handlers);
+}
+
+pub fn walk_lambda<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    lambda: &Lambda<'a>,
+) {
+    for parameter in &lambda.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for parameter in &lambda.parameters {
+        visitor.visit_function_parameter(arena, parameter);
+    }
+
+    if let Some(return_type) = &lambda.return_type {
+        visitor.visit_type_annotation(arena, return_type);
+    }
+
+    walk_nodes(visitor, arena, &lambda.body);
+}
+
+pub fn walk_let_binding<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    binding: &LetBinding<'a>,
+) {
+    visitor.visit_pattern(arena, &binding.pattern);
+    visitor.visit_node(arena, binding.value);
+}
+
+pub fn walk_assignment<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    assignment: &Assignment<'a>,
+) {
+    visitor.visit_identifier(arena, &assignment.target);
+    visitor.visit_node(arena, assignment.value);
+}
+
+pub fn walk_if<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, if_: &If) {
+    visitor.visit_node(arena, if_.condition);
+    walk_nodes(visitor, arena, &if_.consequent);
+
+    if let Some(alternative) = if_.alternative {
+        visitor.visit_node(arena, alternative);
+    }
+}
+
+pub fn walk_while<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    while_: &While<'a>,
+) {
+    if let Some(pattern) = &while_.pattern {
+        visitor.visit_pattern(arena, pattern);
+    }
+
+    visitor.visit_node(arena, while_.condition);
+    walk_nodes(visitor, arena, &while_.body);
+}
+
+pub fn walk_for_of<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    for_of: &ForOf<'a>,
+) {
+    visitor.visit_pattern(arena, &for_of.pattern);
+    visitor.visit_node(arena, for_of.iterable);
+    walk_nodes(visitor, arena, &for_of.body);
+}
+
+pub fn walk_return<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    return_: &Return,
+) {
+    if let Some(value) = return_.value {
+        visitor.visit_node(arena, value);
+    }
+}
+
+pub fn walk_yield<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arena: &Arena<'a>, yield_: &Yield) {
+    if let Some(value) = yield_.value {
+        visitor.visit_node(arena, value);
+    }
+}
+
+pub fn walk_export_declaration<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    export: &ExportDeclaration<'a>,
+) {
+    if let ExportKind::Declaration(declaration) = export.kind {
+        visitor.visit_node(arena, declaration);
+    }
+}
+
+pub fn walk_match<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    match_: &Match<'a>,
+) {
+    visitor.visit_node(arena, match_.value);
+
+    for case in &match_.cases {
+        visitor.visit_match_case(arena, case);
+    }
+}
+
+pub fn walk_match_case<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    case: &MatchCase<'a>,
+) {
+    match &case.test {
+        MatchTest::Pattern(pattern) => visitor.visit_pattern(arena, pattern),
+        MatchTest::Predicate(predicate) => visitor.visit_node(arena, *predicate),
+    }
+
+    visitor.visit_node(arena, case.body);
+}
+
+pub fn walk_pattern<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    pattern: &Pattern<'a>,
+) {
+    match pattern {
+        Pattern::Wildcard(_) => {}
+        Pattern::Identifier(identifier) => visitor.visit_identifier(arena, identifier),
+        Pattern::Literal(literal) => walk_ast(visitor, arena, literal),
+        Pattern::Constructor(constructor) => {
+            for argument in &constructor.arguments {
+                visitor.visit_pattern(arena, argument);
+            }
+        }
+        Pattern::Record(record) => {
+            for field in &record.fields {
+                visitor.visit_identifier(arena, &field.name);
+                visitor.visit_pattern(arena, &field.pattern);
+            }
+
+            if let Some(rest) = &record.rest {
+                visitor.visit_pattern(arena, rest);
+            }
+        }
+        Pattern::Array(array) => {
+            for element in &array.elements {
+                visitor.visit_pattern(arena, element);
+            }
+
+            if let Some(rest) = &array.rest {
+                visitor.visit_pattern(arena, rest);
+            }
+        }
+        Pattern::Tuple(tuple) => {
+            for element in &tuple.elements {
+                visitor.visit_pattern(arena, element);
+            }
+        }
+    }
+}
+
+pub fn walk_function_declaration<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    function: &FunctionDeclaration<'a>,
+) {
+    for parameter in &function.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for parameter in &function.parameters {
+        visitor.visit_function_parameter(arena, parameter);
+    }
+
+    if let Some(return_type) = &function.return_type {
+        visitor.visit_type_annotation(arena, return_type);
+    }
+
+    walk_nodes(visitor, arena, &function.body);
+}
+
+pub fn walk_function_parameter<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    parameter: &FunctionParameter<'a>,
+) {
+    if let Some(annotation) = &parameter.annotation {
+        visitor.visit_type_annotation(arena, annotation);
+    }
+}
+
+pub fn walk_type_parameter<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    parameter: &TypeParameter<'a>,
+) {
+    if let Some(extends) = &parameter.extends {
+        visitor.visit_type(arena, extends);
+    }
+}
+
+pub fn walk_type_declaration<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    declaration: &TypeDeclaration<'a>,
+) {
+    for parameter in &declaration.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for constructor in &declaration.type_constructors {
+        visitor.visit_type_constructor(arena, constructor);
+    }
+}
+
+pub fn walk_type_constructor<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    constructor: &TypeConstructor<'a>,
+) {
+    for parameter in &constructor.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for parameter in &constructor.parameters {
+        visitor.visit_labeled_type_annotation(arena, parameter);
+    }
+
+    if let Some(return_type) = &constructor.return_type {
+        visitor.visit_type_annotation(arena, return_type);
+    }
+}
+
+pub fn walk_type_alias<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    alias: &TypeAlias<'a>,
+) {
+    for parameter in &alias.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    visitor.visit_type_annotation(arena, &alias.aliased);
+}
+
+pub fn walk_struct_declaration<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    declaration: &StructDeclaration<'a>,
+) {
+    for parameter in &declaration.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for field in &declaration.fields {
+        visitor.visit_struct_data(arena, field);
+    }
+}
+
+pub fn walk_effect_declaration<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    effect: &EffectDeclaration<'a>,
+) {
+    for parameter in &effect.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for operation in &effect.operations {
+        visitor.visit_effect_operation(arena, operation);
+    }
+}
+
+pub fn walk_effect_operation<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    operation: &EffectOperation<'a>,
+) {
+    for parameter in &operation.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for parameter in &operation.parameters {
+        visitor.visit_type_annotation(arena, parameter);
+    }
+
+    visitor.visit_type_annotation(arena, &operation.return_type);
+}
+
+pub fn walk_impl_block<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    block: &ImplBlock<'a>,
+) {
+    for parameter in &block.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for argument in &block.type_arguments {
+        visitor.visit_type_annotation(arena, argument);
+    }
+
+    walk_nodes(visitor, arena, &block.members);
+}
+
+pub fn walk_typeclass_declaration<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    typeclass: &TypeclassDeclaration<'a>,
+) {
+    for parameter in &typeclass.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for superclass in &typeclass.superclasses {
+        visitor.visit_type_annotation(arena, superclass);
+    }
+
+    for associated in &typeclass.associated_types {
+        visitor.visit_associated_type(arena, associated);
+    }
+
+    for method in &typeclass.methods {
+        visitor.visit_typeclass_method(arena, method);
+    }
+}
+
+pub fn walk_typeclass_method<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    method: &TypeclassMethod<'a>,
+) {
+    for parameter in &method.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for parameter in &method.parameters {
+        visitor.visit_function_parameter(arena, parameter);
+    }
+
+    if let Some(return_type) = &method.return_type {
+        visitor.visit_type_annotation(arena, return_type);
+    }
+
+    if let Some(default) = &method.default {
+        walk_nodes(visitor, arena, default);
+    }
+}
+
+pub fn walk_associated_type<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    associated: &AssociatedType<'a>,
+) {
+    if let Some(bound) = &associated.bound {
+        visitor.visit_type_annotation(arena, bound);
+    }
+
+    if let Some(default) = &associated.default {
+        visitor.visit_type_annotation(arena, default);
+    }
+}
+
+pub fn walk_instance_declaration<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    instance: &InstanceDeclaration<'a>,
+) {
+    for parameter in &instance.type_parameters {
+        visitor.visit_type_parameter(arena, parameter);
+    }
+
+    for argument in &instance.type_arguments {
+        visitor.visit_type_annotation(arena, argument);
+    }
+
+    visitor.visit_type_annotation(arena, &instance.target);
+
+    for associated in &instance.associated_types {
+        visitor.visit_associated_type(arena, associated);
+    }
+
+    walk_nodes(visitor, arena, &instance.members);
+}
+
+pub fn walk_handler_declaration<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    handler: &HandlerDeclaration<'a>,
+) {
+    for parameter in &handler.parameters {
+        visitor.visit_function_parameter(arena, parameter);
+    }
+
+    walk_nodes(visitor, arena, &handler.body);
+
+    for operation in &handler.operations {
+        visitor.visit_operation_clause(arena, operation);
+    }
+
+    if let Some(clause) = &handler.return_clause {
+        visitor.visit_return_clause(arena, clause);
+    }
+}
+
+pub fn walk_operation_clause<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    clause: &OperationClause<'a>,
+) {
+    for parameter in &clause.parameters {
+        visitor.visit_function_parameter(arena, parameter);
+    }
+
+    walk_nodes(visitor, arena, &clause.body);
+}
+
+pub fn walk_return_clause<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    clause: &ReturnClause<'a>,
+) {
+    visitor.visit_function_parameter(arena, &clause.parameter);
+    walk_nodes(visitor, arena, &clause.body);
+}
+
+pub fn walk_macro_declaration<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    declaration: &MacroDeclaration<'a>,
+) {
+    for parameter in &declaration.parameters {
+        visitor.visit_function_parameter(arena, parameter);
+    }
+}
+
+pub fn walk_type_annotation<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    annotation: &TypeAnnotation<'a>,
+) {
+    visitor.visit_type(arena, &annotation.annotated);
+}
+
+pub fn walk_labeled_type_annotation<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    annotation: &LabeledTypeAnnotation<'a>,
+) {
+    visitor.visit_type_annotation(arena, &annotation.annotated);
+}
+
+pub fn walk_type<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    type_: &Type<'a>,
+) {
+    match type_ {
+        Type::Unit
+        | Type::Never
+        | Type::Unknown
+        | Type::Boolean
+        | Type::Int
+        | Type::Float
+        | Type::String
+        | Type::Regexp(_)
+        | Type::Brand(_)
+        | Type::Infer
+        | Type::Variable(_)
+        | Type::Any => {}
+        Type::Option(inner) | Type::Array(inner) | Type::Alias(_, inner) => {
+            visitor.visit_type(arena, inner)
+        }
+        Type::Either(left, right) => {
+            visitor.visit_type(arena, left);
+            visitor.visit_type(arena, right);
+        }
+        Type::Template(types)
+        | Type::Reference(_, types)
+        | Type::Union(types)
+        | Type::Intersection(types) => {
+            for type_ in types {
+                visitor.visit_type(arena, type_);
+            }
+        }
+        Type::Tuple(members) => {
+            for member in members {
+                visitor.visit_tuple_data(arena, member);
+            }
+        }
+        Type::Struct(members) => {
+            for member in members {
+                visitor.visit_struct_data(arena, member);
+            }
+        }
+        Type::Function(type_parameters, arguments, effects, return_type) => {
+            for type_ in type_parameters.iter().chain(arguments).chain(effects) {
+                visitor.visit_type(arena, type_);
+            }
+
+            visitor.visit_type(arena, return_type);
+        }
+        Type::Macro(invocation) => visitor.visit_macro_invocation(arena, invocation),
+    }
+}
+
+pub fn walk_tuple_data<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    data: &TupleData<'a>,
+) {
+    match data {
+        TupleData::Member(type_) | TupleData::Spread(type_) => visitor.visit_type(arena, type_),
+    }
+}
+
+pub fn walk_struct_data<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena<'a>,
+    data: &StructData<'a>,
+) {
+    match data {
+        StructData::Member(_, type_) | StructData::Spread(type_) => {
+            visitor.visit_type(arena, type_)
+        }
+    }
+}
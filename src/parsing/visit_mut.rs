@@ -0,0 +1,1058 @@
+use crate::parsing::{arena::*, ast::*, types::*};
+
+// Walks a module, rewriting it in place. Each node is taken out of the arena
+// while it is being visited, so methods can allocate new nodes and point
+// the node at them, and its children are linked back to it afterwards.
+// Use Fold to replace a node with one of a different kind.
+pub trait VisitorMut<'a> {
+    fn visit_source_file_mut(&mut self, file: &mut SourceFile<'a>) {
+        walk_source_file_mut(self, file)
+    }
+
+    fn visit_node_mut(&mut self, arena: &mut Arena<'a>, id: NodeId) {
+        walk_node_mut(self, arena, id)
+    }
+
+    fn visit_boolean_literal_mut(&mut self, _arena: &mut Arena<'a>, _literal: &mut BooleanLiteral) {
+    }
+
+    fn visit_integer_literal_mut(&mut self, _arena: &mut Arena<'a>, _literal: &mut IntegerLiteral) {
+    }
+
+    fn visit_number_literal_mut(&mut self, _arena: &mut Arena<'a>, _literal: &mut NumberLiteral) {}
+
+    fn visit_regexp_literal_mut(
+        &mut self,
+        _arena: &mut Arena<'a>,
+        _literal: &mut RegexpLiteral<'a>,
+    ) {
+    }
+
+    fn visit_string_literal_mut(
+        &mut self,
+        _arena: &mut Arena<'a>,
+        _literal: &mut StringLiteral<'a>,
+    ) {
+    }
+
+    fn visit_template_literal_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        literal: &mut TemplateLiteral<'a>,
+    ) {
+        walk_template_literal_mut(self, arena, literal)
+    }
+
+    fn visit_identifier_mut(&mut self, _arena: &mut Arena<'a>, _identifier: &mut Identifier<'a>) {}
+
+    fn visit_record_mut(&mut self, arena: &mut Arena<'a>, record: &mut Record<'a>) {
+        walk_record_mut(self, arena, record)
+    }
+
+    fn visit_record_member_mut(&mut self, arena: &mut Arena<'a>, member: &mut RecordMember<'a>) {
+        walk_record_member_mut(self, arena, member)
+    }
+
+    fn visit_array_mut(&mut self, arena: &mut Arena<'a>, array: &mut Array) {
+        walk_array_mut(self, arena, array)
+    }
+
+    fn visit_tuple_mut(&mut self, arena: &mut Arena<'a>, tuple: &mut Tuple) {
+        walk_tuple_mut(self, arena, tuple)
+    }
+
+    fn visit_element_mut(&mut self, arena: &mut Arena<'a>, element: &mut Element) {
+        walk_element_mut(self, arena, element)
+    }
+
+    fn visit_call_mut(&mut self, arena: &mut Arena<'a>, call: &mut Call) {
+        walk_call_mut(self, arena, call)
+    }
+
+    fn visit_member_access_mut(&mut self, arena: &mut Arena<'a>, member: &mut MemberAccess<'a>) {
+        walk_member_access_mut(self, arena, member)
+    }
+
+    fn visit_binary_mut(&mut self, arena: &mut Arena<'a>, binary: &mut Binary) {
+        walk_binary_mut(self, arena, binary)
+    }
+
+    fn visit_unary_mut(&mut self, arena: &mut Arena<'a>, unary: &mut Unary) {
+        walk_unary_mut(self, arena, unary)
+    }
+
+    fn visit_block_mut(&mut self, arena: &mut Arena<'a>, block: &mut Block) {
+        walk_block_mut(self, arena, block)
+    }
+
+    fn visit_with_mut(&mut self, arena: &mut Arena<'a>, with: &mut With) {
+        walk_with_mut(self, arena, with)
+    }
+
+    fn visit_lambda_mut(&mut self, arena: &mut Arena<'a>, lambda: &mut Lambda<'a>) {
+        walk_lambda_mut(self, arena, lambda)
+    }
+
+    fn visit_let_binding_mut(&mut self, arena: &mut Arena<'a>, binding: &mut LetBinding<'a>) {
+        walk_let_binding_mut(self, arena, binding)
+    }
+
+    fn visit_assignment_mut(&mut self, arena: &mut Arena<'a>, assignment: &mut Assignment<'a>) {
+        walk_assignment_mut(self, arena, assignment)
+    }
+
+    fn visit_if_mut(&mut self, arena: &mut Arena<'a>, if_: &mut If) {
+        walk_if_mut(self, arena, if_)
+    }
+
+    fn visit_while_mut(&mut self, arena: &mut Arena<'a>, while_: &mut While<'a>) {
+        walk_while_mut(self, arena, while_)
+    }
+
+    fn visit_for_of_mut(&mut self, arena: &mut Arena<'a>, for_of: &mut ForOf<'a>) {
+        walk_for_of_mut(self, arena, for_of)
+    }
+
+    fn visit_return_mut(&mut self, arena: &mut Arena<'a>, return_: &mut Return) {
+        walk_return_mut(self, arena, return_)
+    }
+
+    fn visit_yield_mut(&mut self, arena: &mut Arena<'a>, yield_: &mut Yield) {
+        walk_yield_mut(self, arena, yield_)
+    }
+
+    fn visit_import_declaration_mut(
+        &mut self,
+        _arena: &mut Arena<'a>,
+        _import: &mut ImportDeclaration<'a>,
+    ) {
+    }
+
+    fn visit_export_declaration_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        export: &mut ExportDeclaration<'a>,
+    ) {
+        walk_export_declaration_mut(self, arena, export)
+    }
+
+    fn visit_match_mut(&mut self, arena: &mut Arena<'a>, match_: &mut Match<'a>) {
+        walk_match_mut(self, arena, match_)
+    }
+
+    fn visit_match_case_mut(&mut self, arena: &mut Arena<'a>, case: &mut MatchCase<'a>) {
+        walk_match_case_mut(self, arena, case)
+    }
+
+    fn visit_pattern_mut(&mut self, arena: &mut Arena<'a>, pattern: &mut Pattern<'a>) {
+        walk_pattern_mut(self, arena, pattern)
+    }
+
+    fn visit_function_declaration_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        function: &mut FunctionDeclaration<'a>,
+    ) {
+        walk_function_declaration_mut(self, arena, function)
+    }
+
+    fn visit_function_parameter_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        parameter: &mut FunctionParameter<'a>,
+    ) {
+        walk_function_parameter_mut(self, arena, parameter)
+    }
+
+    fn visit_type_parameter_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        parameter: &mut TypeParameter<'a>,
+    ) {
+        walk_type_parameter_mut(self, arena, parameter)
+    }
+
+    fn visit_type_declaration_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        declaration: &mut TypeDeclaration<'a>,
+    ) {
+        walk_type_declaration_mut(self, arena, declaration)
+    }
+
+    fn visit_type_constructor_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        constructor: &mut TypeConstructor<'a>,
+    ) {
+        walk_type_constructor_mut(self, arena, constructor)
+    }
+
+    fn visit_type_alias_mut(&mut self, arena: &mut Arena<'a>, alias: &mut TypeAlias<'a>) {
+        walk_type_alias_mut(self, arena, alias)
+    }
+
+    fn visit_struct_declaration_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        declaration: &mut StructDeclaration<'a>,
+    ) {
+        walk_struct_declaration_mut(self, arena, declaration)
+    }
+
+    fn visit_effect_declaration_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        effect: &mut EffectDeclaration<'a>,
+    ) {
+        walk_effect_declaration_mut(self, arena, effect)
+    }
+
+    fn visit_effect_operation_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        operation: &mut EffectOperation<'a>,
+    ) {
+        walk_effect_operation_mut(self, arena, operation)
+    }
+
+    fn visit_impl_block_mut(&mut self, arena: &mut Arena<'a>, block: &mut ImplBlock<'a>) {
+        walk_impl_block_mut(self, arena, block)
+    }
+
+    fn visit_typeclass_declaration_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        typeclass: &mut TypeclassDeclaration<'a>,
+    ) {
+        walk_typeclass_declaration_mut(self, arena, typeclass)
+    }
+
+    fn visit_typeclass_method_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        method: &mut TypeclassMethod<'a>,
+    ) {
+        walk_typeclass_method_mut(self, arena, method)
+    }
+
+    fn visit_associated_type_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        associated: &mut AssociatedType<'a>,
+    ) {
+        walk_associated_type_mut(self, arena, associated)
+    }
+
+    fn visit_instance_declaration_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        instance: &mut InstanceDeclaration<'a>,
+    ) {
+        walk_instance_declaration_mut(self, arena, instance)
+    }
+
+    fn visit_handler_declaration_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        handler: &mut HandlerDeclaration<'a>,
+    ) {
+        walk_handler_declaration_mut(self, arena, handler)
+    }
+
+    fn visit_operation_clause_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        clause: &mut OperationClause<'a>,
+    ) {
+        walk_operation_clause_mut(self, arena, clause)
+    }
+
+    fn visit_return_clause_mut(&mut self, arena: &mut Arena<'a>, clause: &mut ReturnClause<'a>) {
+        walk_return_clause_mut(self, arena, clause)
+    }
+
+    fn visit_macro_declaration_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        declaration: &mut MacroDeclaration<'a>,
+    ) {
+        walk_macro_declaration_mut(self, arena, declaration)
+    }
+
+    fn visit_macro_invocation_mut(
+        &mut self,
+        _arena: &mut Arena<'a>,
+        _invocation: &mut MacroInvocation<'a>,
+    ) {
+    }
+
+    fn visit_type_annotation_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        annotation: &mut TypeAnnotation<'a>,
+    ) {
+        walk_type_annotation_mut(self, arena, annotation)
+    }
+
+    fn visit_labeled_type_annotation_mut(
+        &mut self,
+        arena: &mut Arena<'a>,
+        annotation: &mut LabeledTypeAnnotation<'a>,
+    ) {
+        walk_labeled_type_annotation_mut(self, arena, annotation)
+    }
+
+    fn visit_type_mut(&mut self, arena: &mut Arena<'a>, type_: &mut Type<'a>) {
+        walk_type_mut(self, arena, type_)
+    }
+
+    fn visit_tuple_data_mut(&mut self, arena: &mut Arena<'a>, data: &mut TupleData<'a>) {
+        walk_tuple_data_mut(self, arena, data)
+    }
+
+    fn visit_struct_data_mut(&mut self, arena: &mut Arena<'a>, data: &mut StructData<'a>) {
+        walk_struct_data_mut(self, arena, data)
+    }
+}
+
+pub fn walk_source_file_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    file: &mut SourceFile<'a>,
+) {
+    for &id in &file.body {
+        visitor.visit_node_mut(&mut file.arena, id);
+    }
+}
+
+pub fn walk_node_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    id: NodeId,
+) {
+    let mut node = arena.take(id);
+
+    walk_ast_mut(visitor, arena, &mut node);
+    arena.replace(id, node);
+}
+
+// Dispatches to the method for the node's variant. Used for nodes in the
+// arena and for the literals owned by patterns.
+pub fn walk_ast_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    node: &mut AST<'a>,
+) {
+    match node {
+        AST::BooleanLiteral(literal) => visitor.visit_boolean_literal_mut(arena, literal),
+        AST::IntegerLiteral(literal) => visitor.visit_integer_literal_mut(arena, literal),
+        AST::NumberLiteral(literal) => visitor.visit_number_literal_mut(arena, literal),
+        AST::RegexpLiteral(literal) => visitor.visit_regexp_literal_mut(arena, literal),
+        AST::StringLiteral(literal) => visitor.visit_string_literal_mut(arena, literal),
+        AST::TemplateLiteral(literal) => visitor.visit_template_literal_mut(arena, literal),
+        AST::Identifier(identifier) => visitor.visit_identifier_mut(arena, identifier),
+        AST::Record(record) => visitor.visit_record_mut(arena, record),
+        AST::Array(array) => visitor.visit_array_mut(arena, array),
+        AST::Tuple(tuple) => visitor.visit_tuple_mut(arena, tuple),
+        AST::Call(call) => visitor.visit_call_mut(arena, call),
+        AST::MemberAccess(member) => visitor.visit_member_access_mut(arena, member),
+        AST::Binary(binary) => visitor.visit_binary_mut(arena, binary),
+        AST::Unary(unary) => visitor.visit_unary_mut(arena, unary),
+        AST::Block(block) => visitor.visit_block_mut(arena, block),
+        AST::With(with) => visitor.visit_with_mut(arena, with),
+        AST::Lambda(lambda) => visitor.visit_lambda_mut(arena, lambda),
+        AST::LetBinding(binding) => visitor.visit_let_binding_mut(arena, binding),
+        AST::Assignment(assignment) => visitor.visit_assignment_mut(arena, assignment),
+        AST::If(if_) => visitor.visit_if_mut(arena, if_),
+        AST::While(while_) => visitor.visit_while_mut(arena, while_),
+        AST::ForOf(for_of) => visitor.visit_for_of_mut(arena, for_of),
+        AST::Return(return_) => visitor.visit_return_mut(arena, return_),
+        AST::Yield(yield_) => visitor.visit_yield_mut(arena, yield_),
+        AST::ImportDeclaration(import) => visitor.visit_import_declaration_mut(arena, import),
+        AST::ExportDeclaration(export) => visitor.visit_export_declaration_mut(arena, export),
+        AST::Match(match_) => visitor.visit_match_mut(arena, match_),
+        AST::FunctionDeclaration(function) => {
+            visitor.visit_function_declaration_mut(arena, function)
+        }
+        AST::TypeDeclaration(declaration) => visitor.visit_type_declaration_mut(arena, declaration),
+        AST::TypeAlias(alias) => visitor.visit_type_alias_mut(arena, alias),
+        AST::StructDeclaration(declaration) => {
+            visitor.visit_struct_declaration_mut(arena, declaration)
+        }
+        AST::EffectDeclaration(effect) => visitor.visit_effect_declaration_mut(arena, effect),
+        AST::ImplBlock(block) => visitor.visit_impl_block_mut(arena, block),
+        AST::TypeclassDeclaration(typeclass) => {
+            visitor.visit_typeclass_declaration_mut(arena, typeclass)
+        }
+        AST::InstanceDeclaration(instance) => {
+            visitor.visit_instance_declaration_mut(arena, instance)
+        }
+        AST::HandlerDeclaration(handler) => visitor.visit_handler_declaration_mut(arena, handler),
+        AST::MacroDeclaration(declaration) => {
+            visitor.visit_macro_declaration_mut(arena, declaration)
+        }
+        AST::MacroInvocation(invocation) => visitor.visit_macro_invocation_mut(arena, invocation),
+    }
+}
+
+fn walk_nodes_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    ids: &[NodeId],
+) {
+    for &id in ids {
+        visitor.visit_node_mut(arena, id);
+    }
+}
+
+pub fn walk_template_literal_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    literal: &mut TemplateLiteral<'a>,
+) {
+    walk_nodes_mut(visitor, arena, &literal.values);
+}
+
+pub fn walk_record_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    record: &mut Record<'a>,
+) {
+    for member in &mut record.members {
+        visitor.visit_record_member_mut(arena, member);
+    }
+}
+
+pub fn walk_record_member_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    member: &mut RecordMember<'a>,
+) {
+    match member {
+        RecordMember::Field(name, value) => {
+            visitor.visit_identifier_mut(arena, name);
+            visitor.visit_node_mut(arena, *value);
+        }
+        RecordMember::Shorthand(name) => visitor.visit_identifier_mut(arena, name),
+        RecordMember::Spread(value) => visitor.visit_node_mut(arena, *value),
+    }
+}
+
+pub fn walk_array_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    array: &mut Array,
+) {
+    for element in &mut array.elements {
+        visitor.visit_element_mut(arena, element);
+    }
+}
+
+pub fn walk_tuple_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    tuple: &mut Tuple,
+) {
+    for element in &mut tuple.elements {
+        visitor.visit_element_mut(arena, element);
+    }
+}
+
+pub fn walk_element_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    element: &mut Element,
+) {
+    match element {
+        Element::Member(value) | Element::Spread(value) => visitor.visit_node_mut(arena, *value),
+    }
+}
+
+pub fn walk_call_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    call: &mut Call,
+) {
+    visitor.visit_node_mut(arena, call.callee);
+    walk_nodes_mut(visitor, arena, &call.arguments);
+}
+
+pub fn walk_member_access_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    member: &mut MemberAccess<'a>,
+) {
+    visitor.visit_node_mut(arena, member.object);
+    visitor.visit_identifier_mut(arena, &mut member.property);
+}
+
+pub fn walk_binary_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    binary: &mut Binary,
+) {
+    visitor.visit_node_mut(arena, binary.left);
+    visitor.visit_node_mut(arena, binary.right);
+}
+
+pub fn walk_unary_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    unary: &mut Unary,
+) {
+    visitor.visit_node_mut(arena, unary.operand);
+}
+
+pub fn walk_block_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    block: &mut Block,
+) {
+    walk_nodes_mut(visitor, arena, &block.body);
+}
+
+pub fn walk_with_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    with: &mut With,
+) {
+    visitor.visit_node_mut(arena, with.body);
+    visitor.visit_node_mut(arena, with.handlers);
+}
+
+pub fn walk_lambda_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    lambda: &mut Lambda<'a>,
+) {
+    for parameter in &mut lambda.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for parameter in &mut lambda.parameters {
+        visitor.visit_function_parameter_mut(arena, parameter);
+    }
+
+    if let Some(return_type) = &mut lambda.return_type {
+        visitor.visit_type_annotation_mut(arena, return_type);
+    }
+
+    walk_nodes_mut(visitor, arena, &lambda.body);
+}
+
+pub fn walk_let_binding_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    binding: &mut LetBinding<'a>,
+) {
+    visitor.visit_pattern_mut(arena, &mut binding.pattern);
+    visitor.visit_node_mut(arena, binding.value);
+}
+
+pub fn walk_assignment_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    assignment: &mut Assignment<'a>,
+) {
+    visitor.visit_identifier_mut(arena, &mut assignment.target);
+    visitor.visit_node_mut(arena, assignment.value);
+}
+
+pub fn walk_if_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    if_: &mut If,
+) {
+    visitor.visit_node_mut(arena, if_.condition);
+    walk_nodes_mut(visitor, arena, &if_.consequent);
+
+    if let Some(alternative) = if_.alternative {
+        visitor.visit_node_mut(arena, alternative);
+    }
+}
+
+pub fn walk_while_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    while_: &mut While<'a>,
+) {
+    if let Some(pattern) = &mut while_.pattern {
+        visitor.visit_pattern_mut(arena, pattern);
+    }
+
+    visitor.visit_node_mut(arena, while_.condition);
+    walk_nodes_mut(visitor, arena, &while_.body);
+}
+
+pub fn walk_for_of_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    for_of: &mut ForOf<'a>,
+) {
+    visitor.visit_pattern_mut(arena, &mut for_of.pattern);
+    visitor.visit_node_mut(arena, for_of.iterable);
+    walk_nodes_mut(visitor, arena, &for_of.body);
+}
+
+pub fn walk_return_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    return_: &mut Return,
+) {
+    if let Some(value) = return_.value {
+        visitor.visit_node_mut(arena, value);
+    }
+}
+
+pub fn walk_yield_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    yield_: &mut Yield,
+) {
+    if let Some(value) = yield_.value {
+        visitor.visit_node_mut(arena, value);
+    }
+}
+
+pub fn walk_export_declaration_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    export: &mut ExportDeclaration<'a>,
+) {
+    if let ExportKind::Declaration(declaration) = export.kind {
+        visitor.visit_node_mut(arena, declaration);
+    }
+}
+
+pub fn walk_match_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    match_: &mut Match<'a>,
+) {
+    visitor.visit_node_mut(arena, match_.value);
+
+    for case in &mut match_.cases {
+        visitor.visit_match_case_mut(arena, case);
+    }
+}
+
+pub fn walk_match_case_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    case: &mut MatchCase<'a>,
+) {
+    match &mut case.test {
+        MatchTest::Pattern(pattern) => visitor.visit_pattern_mut(arena, pattern),
+        MatchTest::Predicate(predicate) => visitor.visit_node_mut(arena, *predicate),
+    }
+
+    visitor.visit_node_mut(arena, case.body);
+}
+
+pub fn walk_pattern_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    pattern: &mut Pattern<'a>,
+) {
+    match pattern {
+        Pattern::Wildcard(_) => {}
+        Pattern::Identifier(identifier) => visitor.visit_identifier_mut(arena, identifier),
+        Pattern::Literal(literal) => walk_ast_mut(visitor, arena, literal),
+        Pattern::Constructor(constructor) => {
+            for argument in &mut constructor.arguments {
+                visitor.visit_pattern_mut(arena, argument);
+            }
+        }
+        Pattern::Record(record) => {
+            for field in &mut record.fields {
+                visitor.visit_identifier_mut(arena, &mut field.name);
+                visitor.visit_pattern_mut(arena, &mut field.pattern);
+            }
+
+            if let Some(rest) = &mut record.rest {
+                visitor.visit_pattern_mut(arena, rest);
+            }
+        }
+        Pattern::Array(array) => {
+            for element in &mut array.elements {
+                visitor.visit_pattern_mut(arena, element);
+            }
+
+            if let Some(rest) = &mut array.rest {
+                visitor.visit_pattern_mut(arena, rest);
+            }
+        }
+        Pattern::Tuple(tuple) => {
+            for element in &mut tuple.elements {
+                visitor.visit_pattern_mut(arena, element);
+            }
+        }
+    }
+}
+
+pub fn walk_function_declaration_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    function: &mut FunctionDeclaration<'a>,
+) {
+    for parameter in &mut function.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for parameter in &mut function.parameters {
+        visitor.visit_function_parameter_mut(arena, parameter);
+    }
+
+    if let Some(return_type) = &mut function.return_type {
+        visitor.visit_type_annotation_mut(arena, return_type);
+    }
+
+    walk_nodes_mut(visitor, arena, &function.body);
+}
+
+pub fn walk_function_parameter_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    parameter: &mut FunctionParameter<'a>,
+) {
+    if let Some(annotation) = &mut parameter.annotation {
+        visitor.visit_type_annotation_mut(arena, annotation);
+    }
+}
+
+pub fn walk_type_parameter_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    parameter: &mut TypeParameter<'a>,
+) {
+    if let Some(extends) = &mut parameter.extends {
+        visitor.visit_type_mut(arena, extends);
+    }
+}
+
+pub fn walk_type_declaration_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    declaration: &mut TypeDeclaration<'a>,
+) {
+    for parameter in &mut declaration.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for constructor in &mut declaration.type_constructors {
+        visitor.visit_type_constructor_mut(arena, constructor);
+    }
+}
+
+pub fn walk_type_constructor_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    constructor: &mut TypeConstructor<'a>,
+) {
+    for parameter in &mut constructor.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for parameter in &mut constructor.parameters {
+        visitor.visit_labeled_type_annotation_mut(arena, parameter);
+    }
+
+    if let Some(return_type) = &mut constructor.return_type {
+        visitor.visit_type_annotation_mut(arena, return_type);
+    }
+}
+
+pub fn walk_type_alias_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    alias: &mut TypeAlias<'a>,
+) {
+    for parameter in &mut alias.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    visitor.visit_type_annotation_mut(arena, &mut alias.aliased);
+}
+
+pub fn walk_struct_declaration_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    declaration: &mut StructDeclaration<'a>,
+) {
+    for parameter in &mut declaration.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for field in &mut declaration.fields {
+        visitor.visit_struct_data_mut(arena, field);
+    }
+}
+
+pub fn walk_effect_declaration_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    effect: &mut EffectDeclaration<'a>,
+) {
+    for parameter in &mut effect.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for operation in &mut effect.operations {
+        visitor.visit_effect_operation_mut(arena, operation);
+    }
+}
+
+pub fn walk_effect_operation_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    operation: &mut EffectOperation<'a>,
+) {
+    for parameter in &mut operation.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for parameter in &mut operation.parameters {
+        visitor.visit_type_annotation_mut(arena, parameter);
+    }
+
+    visitor.visit_type_annotation_mut(arena, &mut operation.return_type);
+}
+
+pub fn walk_impl_block_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    block: &mut ImplBlock<'a>,
+) {
+    for parameter in &mut block.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for argument in &mut block.type_arguments {
+        visitor.visit_type_annotation_mut(arena, argument);
+    }
+
+    walk_nodes_mut(visitor, arena, &block.members);
+}
+
+pub fn walk_typeclass_declaration_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    typeclass: &mut TypeclassDeclaration<'a>,
+) {
+    for parameter in &mut typeclass.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for superclass in &mut typeclass.superclasses {
+        visitor.visit_type_annotation_mut(arena, superclass);
+    }
+
+    for associated in &mut typeclass.associated_types {
+        visitor.visit_associated_type_mut(arena, associated);
+    }
+
+    for method in &mut typeclass.methods {
+        visitor.visit_typeclass_method_mut(arena, method);
+    }
+}
+
+pub fn walk_typeclass_method_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    method: &mut TypeclassMethod<'a>,
+) {
+    for parameter in &mut method.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for parameter in &mut method.parameters {
+        visitor.visit_function_parameter_mut(arena, parameter);
+    }
+
+    if let Some(return_type) = &mut method.return_type {
+        visitor.visit_type_annotation_mut(arena, return_type);
+    }
+
+    if let Some(default) = &mut method.default {
+        walk_nodes_mut(visitor, arena, default);
+    }
+}
+
+pub fn walk_associated_type_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    associated: &mut AssociatedType<'a>,
+) {
+    if let Some(bound) = &mut associated.bound {
+        visitor.visit_type_annotation_mut(arena, bound);
+    }
+
+    if let Some(default) = &mut associated.default {
+        visitor.visit_type_annotation_mut(arena, default);
+    }
+}
+
+pub fn walk_instance_declaration_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    instance: &mut InstanceDeclaration<'a>,
+) {
+    for parameter in &mut instance.type_parameters {
+        visitor.visit_type_parameter_mut(arena, parameter);
+    }
+
+    for argument in &mut instance.type_arguments {
+        visitor.visit_type_annotation_mut(arena, argument);
+    }
+
+    visitor.visit_type_annotation_mut(arena, &mut instance.target);
+
+    for associated in &mut instance.associated_types {
+        visitor.visit_associated_type_mut(arena, associated);
+    }
+
+    walk_nodes_mut(visitor, arena, &instance.members);
+}
+
+pub fn walk_handler_declaration_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    handler: &mut HandlerDeclaration<'a>,
+) {
+    for parameter in &mut handler.parameters {
+        visitor.visit_function_parameter_mut(arena, parameter);
+    }
+
+    walk_nodes_mut(visitor, arena, &handler.body);
+
+    for operation in &mut handler.operations {
+        visitor.visit_operation_clause_mut(arena, operation);
+    }
+
+    if let Some(clause) = &mut handler.return_clause {
+        visitor.visit_return_clause_mut(arena, clause);
+    }
+}
+
+pub fn walk_operation_clause_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    clause: &mut OperationClause<'a>,
+) {
+    for parameter in &mut clause.parameters {
+        visitor.visit_function_parameter_mut(arena, parameter);
+    }
+
+    walk_nodes_mut(visitor, arena, &clause.body);
+}
+
+pub fn walk_return_clause_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    clause: &mut ReturnClause<'a>,
+) {
+    visitor.visit_function_parameter_mut(arena, &mut clause.parameter);
+    walk_nodes_mut(visitor, arena, &clause.body);
+}
+
+pub fn walk_macro_declaration_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    declaration: &mut MacroDeclaration<'a>,
+) {
+    for parameter in &mut declaration.parameters {
+        visitor.visit_function_parameter_mut(arena, parameter);
+    }
+}
+
+pub fn walk_type_annotation_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    annotation: &mut TypeAnnotation<'a>,
+) {
+    visitor.visit_type_mut(arena, &mut annotation.annotated);
+}
+
+pub fn walk_labeled_type_annotation_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    annotation: &mut LabeledTypeAnnotation<'a>,
+) {
+    visitor.visit_type_annotation_mut(arena, &mut annotation.annotated);
+}
+
+pub fn walk_type_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    type_: &mut Type<'a>,
+) {
+    match type_ {
+        Type::Unit
+        | Type::Never
+        | Type::Unknown
+        | Type::Boolean
+        | Type::Int
+        | Type::Float
+        | Type::String
+        | Type::Regexp(_)
+        | Type::Brand(_)
+        | Type::Infer
+        | Type::Variable(_)
+        | Type::Any => {}
+        Type::Option(inner) | Type::Array(inner) | Type::Alias(_, inner) => {
+            visitor.visit_type_mut(arena, inner)
+        }
+        Type::Either(left, right) => {
+            visitor.visit_type_mut(arena, left);
+            visitor.visit_type_mut(arena, right);
+        }
+        Type::Template(types)
+        | Type::Reference(_, types)
+        | Type::Union(types)
+        | Type::Intersection(types) => {
+            for type_ in types {
+                visitor.visit_type_mut(arena, type_);
+            }
+        }
+        Type::Tuple(members) => {
+            for member in members {
+                visitor.visit_tuple_data_mut(arena, member);
+            }
+        }
+        Type::Struct(members) => {
+            for member in members {
+                visitor.visit_struct_data_mut(arena, member);
+            }
+        }
+        Type::Function(type_parameters, arguments, effects, return_type) => {
+            for type_ in type_parameters.iter_mut().chain(arguments).chain(effects) {
+                visitor.visit_type_mut(arena, type_);
+            }
+
+            visitor.visit_type_mut(arena, return_type);
+        }
+        Type::Macro(invocation) => visitor.visit_macro_invocation_mut(arena, invocation),
+    }
+}
+
+pub fn walk_tuple_data_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    data: &mut TupleData<'a>,
+) {
+    match data {
+        TupleData::Member(type_) | TupleData::Spread(type_) => visitor.visit_type_mut(arena, type_),
+    }
+}
+
+pub fn walk_struct_data_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena<'a>,
+    data: &mut StructData<'a>,
+) {
+    match data {
+        StructData::Member(_, type_) | StructData::Spread(type_) => {
+            visitor.visit_type_mut(arena, type_)
+        }
+    }
+}
@@ -0,0 +1,685 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+// Tokens, nodes and types borrow their text from the source they were parsed
+// from, so they can be serialized but not deserialized: a JSON string with
+// escapes in it has to be unescaped into a new one, and a reader has no
+// input to borrow from at all. Each of them has an owned mirror here, with
+// the same serialized form, that deserializes like any other value and lends
+// out the borrowing tree it stands for.
+//
+//     let file: OwnedSourceFile = serde_json::from_str(&json)?;
+//     let resolution = Resolver::resolve(&file.lend());
+pub trait Lend<'a> {
+    type Lent;
+
+    fn lend(&'a self) -> Self::Lent;
+}
+
+impl<'a> Lend<'a> for String {
+    type Lent = &'a str;
+
+    fn lend(&'a self) -> &'a str {
+        self
+    }
+}
+
+impl<'a, T: Lend<'a>> Lend<'a> for Vec<T> {
+    type Lent = Vec<T::Lent>;
+
+    fn lend(&'a self) -> Vec<T::Lent> {
+        self.iter().map(Lend::lend).collect()
+    }
+}
+
+impl<'a, T: Lend<'a>> Lend<'a> for Option<T> {
+    type Lent = Option<T::Lent>;
+
+    fn lend(&'a self) -> Option<T::Lent> {
+        self.as_ref().map(Lend::lend)
+    }
+}
+
+impl<'a, T: Lend<'a>> Lend<'a> for Box<T> {
+    type Lent = Box<T::Lent>;
+
+    fn lend(&'a self) -> Box<T::Lent> {
+        Box::new((**self).lend())
+    }
+}
+
+// Values that don't borrow anything lend a copy of themselves.
+macro_rules! lend_clones {
+    ($($type:ty),* $(,)?) => {
+        $(
+            impl<'a> Lend<'a> for $type {
+                type Lent = $type;
+
+                fn lend(&'a self) -> $type {
+                    self.clone()
+                }
+            }
+        )*
+    };
+}
+
+lend_clones!(
+    bool,
+    usize,
+    Position,
+    NodeId,
+    TokenKind,
+    Delimiter,
+    Docs,
+    BooleanLiteral,
+    IntegerLiteral,
+    NumberLiteral,
+    Array,
+    Tuple,
+    Call,
+    Binary,
+    Unary,
+    Block,
+    With,
+    If,
+    Return,
+    Yield,
+);
+
+// Declares the owned mirror of each borrowing struct or enum, with the same
+// fields, and lends it out field by field.
+macro_rules! owned {
+    () => {};
+    (
+        struct $name:ident => $owned:ident { $($field:ident: $type:ty),* $(,)? }
+        $($rest:tt)*
+    ) => {
+        #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+        pub struct $owned {
+            $(pub(crate) $field: $type,)*
+        }
+
+        impl<'a> Lend<'a> for $owned {
+            type Lent = $name<'a>;
+
+            fn lend(&'a self) -> $name<'a> {
+                $name {
+                    $($field: self.$field.lend(),)*
+                }
+            }
+        }
+
+        owned! { $($rest)* }
+    };
+    (
+        enum $name:ident => $owned:ident {
+            $($variant:ident $(($($value:ident: $type:ty),* $(,)?))?),* $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+        pub enum $owned {
+            $($variant $(($($type),*))?,)*
+        }
+
+        impl<'a> Lend<'a> for $owned {
+            type Lent = $name<'a>;
+
+            fn lend(&'a self) -> $name<'a> {
+                match self {
+                    $($owned::$variant $(($($value),*))? => {
+                        $name::$variant $(($($value.lend()),*))?
+                    })*
+                }
+            }
+        }
+
+        owned! { $($rest)* }
+    };
+}
+
+owned! {
+    enum AST => OwnedAST {
+        BooleanLiteral(value: BooleanLiteral),
+        IntegerLiteral(value: IntegerLiteral),
+        NumberLiteral(value: NumberLiteral),
+        RegexpLiteral(value: OwnedRegexpLiteral),
+        StringLiteral(value: OwnedStringLiteral),
+        TemplateLiteral(value: OwnedTemplateLiteral),
+        Identifier(value: OwnedIdentifier),
+        Record(value: OwnedRecord),
+        Array(value: Array),
+        Tuple(value: Tuple),
+        Call(value: Call),
+        MemberAccess(value: OwnedMemberAccess),
+        Binary(value: Binary),
+        Unary(value: Unary),
+        Block(value: Block),
+        With(value: With),
+        Lambda(value: OwnedLambda),
+        LetBinding(value: OwnedLetBinding),
+        Assignment(value: OwnedAssignment),
+        If(value: If),
+        While(value: OwnedWhile),
+        ForOf(value: OwnedForOf),
+        Return(value: Return),
+        Yield(value: Yield),
+        ImportDeclaration(value: OwnedImportDeclaration),
+        ExportDeclaration(value: OwnedExportDeclaration),
+        Match(value: OwnedMatch),
+        FunctionDeclaration(value: OwnedFunctionDeclaration),
+        TypeDeclaration(value: OwnedTypeDeclaration),
+        TypeAlias(value: OwnedTypeAlias),
+        StructDeclaration(value: OwnedStructDeclaration),
+        EffectDeclaration(value: OwnedEffectDeclaration),
+        ImplBlock(value: OwnedImplBlock),
+        TypeclassDeclaration(value: OwnedTypeclassDeclaration),
+        InstanceDeclaration(value: OwnedInstanceDeclaration),
+        HandlerDeclaration(value: OwnedHandlerDeclaration),
+        MacroDeclaration(value: OwnedMacroDeclaration),
+        MacroInvocation(value: OwnedMacroInvocation),
+    }
+
+    struct RegexpLiteral => OwnedRegexpLiteral {
+        value: String,
+        position: Position,
+    }
+
+    struct StringLiteral => OwnedStringLiteral {
+        value: String,
+        position: Position,
+    }
+
+    struct TemplateLiteral => OwnedTemplateLiteral {
+        template: Vec<String>,
+        values: Vec<NodeId>,
+        position: Position,
+    }
+
+    struct Identifier => OwnedIdentifier {
+        value: String,
+        position: Position,
+    }
+
+    struct Record => OwnedRecord {
+        members: Vec<OwnedRecordMember>,
+        position: Position,
+    }
+
+    enum RecordMember => OwnedRecordMember {
+        Field(name: OwnedIdentifier, value: NodeId),
+        Shorthand(value: OwnedIdentifier),
+        Spread(value: NodeId),
+    }
+
+    struct ImportDeclaration => OwnedImportDeclaration {
+        default: Option<String>,
+        namespace: Option<String>,
+        named: Vec<OwnedNamedBinding>,
+        type_only: bool,
+        specifier: String,
+        position: Position,
+    }
+
+    struct NamedBinding => OwnedNamedBinding {
+        name: String,
+        alias: Option<String>,
+        position: Position,
+    }
+
+    struct ExportDeclaration => OwnedExportDeclaration {
+        kind: OwnedExportKind,
+        position: Position,
+    }
+
+    struct MemberAccess => OwnedMemberAccess {
+        object: NodeId,
+        property: OwnedIdentifier,
+        position: Position,
+    }
+
+    struct LetBinding => OwnedLetBinding {
+        pattern: OwnedPattern,
+        mutable: bool,
+        value: NodeId,
+        position: Position,
+    }
+
+    struct Assignment => OwnedAssignment {
+        target: OwnedIdentifier,
+        value: NodeId,
+        position: Position,
+    }
+
+    struct While => OwnedWhile {
+        pattern: Option<OwnedPattern>,
+        condition: NodeId,
+        body: Vec<NodeId>,
+        position: Position,
+    }
+
+    struct ForOf => OwnedForOf {
+        pattern: OwnedPattern,
+        iterable: NodeId,
+        body: Vec<NodeId>,
+        position: Position,
+    }
+
+    struct Match => OwnedMatch {
+        value: NodeId,
+        cases: Vec<OwnedMatchCase>,
+        position: Position,
+    }
+
+    struct MatchCase => OwnedMatchCase {
+        test: OwnedMatchTest,
+        body: NodeId,
+        position: Position,
+    }
+
+    enum MatchTest => OwnedMatchTest {
+        Pattern(value: OwnedPattern),
+        Predicate(value: NodeId),
+    }
+
+    enum Pattern => OwnedPattern {
+        Wildcard(value: Position),
+        Identifier(value: OwnedIdentifier),
+        Literal(value: Box<OwnedAST>),
+        Constructor(value: OwnedConstructorPattern),
+        Record(value: OwnedRecordPattern),
+        Array(value: OwnedArrayPattern),
+        Tuple(value: OwnedTuplePattern),
+    }
+
+    struct ConstructorPattern => OwnedConstructorPattern {
+        qualifier: Vec<String>,
+        name: String,
+        arguments: Vec<OwnedPattern>,
+        position: Position,
+    }
+
+    struct RecordPattern => OwnedRecordPattern {
+        fields: Vec<OwnedFieldPattern>,
+        rest: Option<Box<OwnedPattern>>,
+        position: Position,
+    }
+
+    struct FieldPattern => OwnedFieldPattern {
+        name: OwnedIdentifier,
+        pattern: OwnedPattern,
+    }
+
+    struct ArrayPattern => OwnedArrayPattern {
+        elements: Vec<OwnedPattern>,
+        rest: Option<Box<OwnedPattern>>,
+        position: Position,
+    }
+
+    struct TuplePattern => OwnedTuplePattern {
+        elements: Vec<OwnedPattern>,
+        position: Position,
+    }
+
+    struct FunctionDeclaration => OwnedFunctionDeclaration {
+        name: String,
+        type_parameters: Vec<OwnedTypeParameter>,
+        parameters: Vec<OwnedFunctionParameter>,
+        return_type: Option<OwnedTypeAnnotation>,
+        body: Vec<NodeId>,
+        docs: Option<Docs>,
+        position: Position,
+    }
+
+    struct Lambda => OwnedLambda {
+        type_parameters: Vec<OwnedTypeParameter>,
+        parameters: Vec<OwnedFunctionParameter>,
+        return_type: Option<OwnedTypeAnnotation>,
+        body: Vec<NodeId>,
+        position: Position,
+    }
+
+    struct FunctionParameter => OwnedFunctionParameter {
+        name: String,
+        annotation: Option<OwnedTypeAnnotation>,
+        position: Position,
+    }
+
+    struct TypeParameter => OwnedTypeParameter {
+        name: String,
+        extends: Option<OwnedType>,
+        position: Position,
+    }
+
+    struct TypeDeclaration => OwnedTypeDeclaration {
+        name: String,
+        type_parameters: Vec<OwnedTypeParameter>,
+        type_constructors: Vec<OwnedTypeConstructor>,
+        docs: Option<Docs>,
+        position: Position,
+    }
+
+    struct StructDeclaration => OwnedStructDeclaration {
+        name: String,
+        type_parameters: Vec<OwnedTypeParameter>,
+        fields: Vec<OwnedStructData>,
+        position: Position,
+    }
+
+    struct TypeConstructor => OwnedTypeConstructor {
+        name: String,
+        type_parameters: Vec<OwnedTypeParameter>,
+        parameters: Vec<OwnedLabeledTypeAnnotation>,
+        return_type: Option<OwnedTypeAnnotation>,
+        position: Position,
+    }
+
+    struct TypeAnnotation => OwnedTypeAnnotation {
+        annotated: OwnedType,
+        position: Position,
+    }
+
+    struct LabeledTypeAnnotation => OwnedLabeledTypeAnnotation {
+        name: Option<String>,
+        annotated: OwnedTypeAnnotation,
+        position: Position,
+    }
+
+    struct EffectDeclaration => OwnedEffectDeclaration {
+        name: String,
+        type_parameters: Vec<OwnedTypeParameter>,
+        operations: Vec<OwnedEffectOperation>,
+        docs: Option<Docs>,
+        position: Position,
+    }
+
+    struct EffectOperation => OwnedEffectOperation {
+        name: Option<String>,
+        type_parameters: Vec<OwnedTypeParameter>,
+        parameters: Vec<OwnedTypeAnnotation>,
+        return_type: OwnedTypeAnnotation,
+        position: Position,
+    }
+
+    struct ImplBlock => OwnedImplBlock {
+        type_parameters: Vec<OwnedTypeParameter>,
+        effect: String,
+        type_arguments: Vec<OwnedTypeAnnotation>,
+        members: Vec<NodeId>,
+        position: Position,
+    }
+
+    struct TypeclassDeclaration => OwnedTypeclassDeclaration {
+        name: String,
+        type_parameters: Vec<OwnedTypeParameter>,
+        superclasses: Vec<OwnedTypeAnnotation>,
+        associated_types: Vec<OwnedAssociatedType>,
+        methods: Vec<OwnedTypeclassMethod>,
+        position: Position,
+    }
+
+    struct TypeclassMethod => OwnedTypeclassMethod {
+        name: String,
+        type_parameters: Vec<OwnedTypeParameter>,
+        parameters: Vec<OwnedFunctionParameter>,
+        return_type: Option<OwnedTypeAnnotation>,
+        default: Option<Vec<NodeId>>,
+        position: Position,
+    }
+
+    struct AssociatedType => OwnedAssociatedType {
+        name: String,
+        bound: Option<OwnedTypeAnnotation>,
+        default: Option<OwnedTypeAnnotation>,
+        position: Position,
+    }
+
+    struct InstanceDeclaration => OwnedInstanceDeclaration {
+        type_parameters: Vec<OwnedTypeParameter>,
+        typeclass: String,
+        type_arguments: Vec<OwnedTypeAnnotation>,
+        target: OwnedTypeAnnotation,
+        associated_types: Vec<OwnedAssociatedType>,
+        members: Vec<NodeId>,
+        position: Position,
+    }
+
+    struct HandlerDeclaration => OwnedHandlerDeclaration {
+        name: Option<String>,
+        parameters: Vec<OwnedFunctionParameter>,
+        body: Vec<NodeId>,
+        operations: Vec<OwnedOperationClause>,
+        return_clause: Option<OwnedReturnClause>,
+        position: Position,
+    }
+
+    struct OperationClause => OwnedOperationClause {
+        name: String,
+        parameters: Vec<OwnedFunctionParameter>,
+        body: Vec<NodeId>,
+        position: Position,
+    }
+
+    struct ReturnClause => OwnedReturnClause {
+        parameter: OwnedFunctionParameter,
+        body: Vec<NodeId>,
+        position: Position,
+    }
+
+    struct TypeAlias => OwnedTypeAlias {
+        name: String,
+        type_parameters: Vec<OwnedTypeParameter>,
+        aliased: OwnedTypeAnnotation,
+        docs: Option<Docs>,
+        position: Position,
+    }
+
+    struct MacroDeclaration => OwnedMacroDeclaration {
+        name: String,
+        parameters: Vec<OwnedFunctionParameter>,
+        body: OwnedDelimited,
+        position: Position,
+    }
+
+    struct MacroInvocation => OwnedMacroInvocation {
+        name: String,
+        group: OwnedDelimited,
+        position: Position,
+    }
+
+    enum TokenTree => OwnedTokenTree {
+        Token(value: OwnedToken),
+        Delimited(value: OwnedDelimited),
+    }
+
+    struct Delimited => OwnedDelimited {
+        delimiter: Delimiter,
+        trees: Vec<OwnedTokenTree>,
+        text: String,
+        position: Position,
+    }
+
+    enum Type => OwnedType {
+        Unit,
+        Never,
+        Unknown,
+        Boolean,
+        Int,
+        Float,
+        String,
+        Regexp(value: String),
+        Template(value: Vec<OwnedType>),
+        Brand(value: String),
+        Option(value: Box<OwnedType>),
+        Either(left: Box<OwnedType>, right: Box<OwnedType>),
+        Array(value: Box<OwnedType>),
+        Tuple(value: Vec<OwnedTupleData>),
+        Struct(value: Vec<OwnedStructData>),
+        Function(
+            type_parameters: Vec<OwnedType>,
+            parameters: Vec<OwnedType>,
+            effects: Vec<OwnedType>,
+            returned: Box<OwnedType>
+        ),
+        Alias(name: String, aliased: Box<OwnedType>),
+        Reference(name: String, arguments: Vec<OwnedType>),
+        Union(value: Vec<OwnedType>),
+        Intersection(value: Vec<OwnedType>),
+        Infer,
+        Variable(value: usize),
+        Any,
+        Macro(value: OwnedMacroInvocation),
+    }
+
+    enum TupleData => OwnedTupleData {
+        Member(value: Box<OwnedType>),
+        Spread(value: Box<OwnedType>),
+    }
+
+    enum StructData => OwnedStructData {
+        Member(name: String, type_: Box<OwnedType>),
+        Spread(value: Box<OwnedType>),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum OwnedExportKind {
+    Declaration(NodeId),
+    Named {
+        bindings: Vec<OwnedNamedBinding>,
+        from: Option<String>,
+    },
+    All {
+        alias: Option<String>,
+        from: String,
+    },
+}
+
+impl<'a> Lend<'a> for OwnedExportKind {
+    type Lent = ExportKind<'a>;
+
+    fn lend(&'a self) -> ExportKind<'a> {
+        match self {
+            OwnedExportKind::Declaration(id) => ExportKind::Declaration(*id),
+            OwnedExportKind::Named { bindings, from } => ExportKind::Named {
+                bindings: bindings.lend(),
+                from: from.lend(),
+            },
+            OwnedExportKind::All { alias, from } => ExportKind::All {
+                alias: alias.lend(),
+                from: from.lend(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct OwnedToken {
+    kind: TokenKind,
+    value: String,
+    position: Position,
+}
+
+impl<'a> Lend<'a> for OwnedToken {
+    type Lent = Token<'a>;
+
+    fn lend(&'a self) -> Token<'a> {
+        Token::new(self.kind, &self.value, self.position)
+    }
+}
+
+// Nodes are allocated again when lent out, which links them to their
+// parents, so they must only refer to nodes before them, as in a parsed
+// arena. That is checked while deserializing, since lending can't fail.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedArena")]
+pub struct OwnedArena {
+    nodes: Vec<OwnedAST>,
+}
+
+#[derive(Deserialize)]
+struct UncheckedArena {
+    nodes: Vec<OwnedAST>,
+}
+
+impl TryFrom<UncheckedArena> for OwnedArena {
+    type Error = String;
+
+    fn try_from(arena: UncheckedArena) -> Result<OwnedArena, String> {
+        for (index, node) in arena.nodes.iter().enumerate() {
+            if let Some(child) = node
+                .lend()
+                .children()
+                .into_iter()
+                .find(|child| child.index() >= index)
+            {
+                return Err(format!(
+                    "Node {} refers to node {}, which doesn't precede it",
+                    index,
+                    child.index()
+                ));
+            }
+        }
+
+        Ok(OwnedArena { nodes: arena.nodes })
+    }
+}
+
+impl<'a> Lend<'a> for OwnedArena {
+    type Lent = Arena<'a>;
+
+    fn lend(&'a self) -> Arena<'a> {
+        let mut arena = Arena::new();
+
+        for node in &self.nodes {
+            arena.alloc(node.lend());
+        }
+
+        arena
+    }
+}
+
+// Imports and exports aren't serialized; they are collected again from the
+// body when lent out.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedSourceFile")]
+pub struct OwnedSourceFile {
+    arena: OwnedArena,
+    body: Vec<NodeId>,
+}
+
+#[derive(Deserialize)]
+struct UncheckedSourceFile {
+    arena: OwnedArena,
+    body: Vec<NodeId>,
+}
+
+impl TryFrom<UncheckedSourceFile> for OwnedSourceFile {
+    type Error = String;
+
+    fn try_from(file: UncheckedSourceFile) -> Result<OwnedSourceFile, String> {
+        let len = file.arena.nodes.len();
+
+        if let Some(id) = file.body.iter().find(|id| id.index() >= len) {
+            return Err(format!(
+                "The body refers to node {}, but there are only {}",
+                id.index(),
+                len
+            ));
+        }
+
+        Ok(OwnedSourceFile {
+            arena: file.arena,
+            body: file.body,
+        })
+    }
+}
+
+impl<'a> Lend<'a> for OwnedSourceFile {
+    type Lent = SourceFile<'a>;
+
+    fn lend(&'a self) -> SourceFile<'a> {
+        SourceFile::new(self.arena.lend(), self.body.clone())
+    }
+}
@@ -0,0 +1,248 @@
+use std::{cmp::Reverse, rc::Rc};
+
+use crate::{
+    parsing::{parser::interpolations, visit::*},
+    syntax::{GreenElement, GreenNode, GreenToken, SyntaxKind},
+    *,
+};
+
+// Builds the green tree of a module from every token of its source, trivia
+// included, nested according to the positions of the nodes the parser
+// produced for it, see Parser::parse_module_leniently. Trivia ends up in the
+// innermost node that spans it, so whitespace between two statements belongs
+// to the module or block around them rather than to either statement. What
+// the parser skipped ends up in Error nodes, and template literals are split
+// around the tokens of the values interpolated into them.
+//
+// Every character of `input` is covered by a token, so the tree prints back
+// to it exactly whether or not it parses. Returns the parse errors too.
+pub fn build_green_tree(input: &str) -> (Rc<GreenNode>, Vec<ParseError>) {
+    let (file, skipped) = Parser::parse_module_leniently(input);
+    let mut spans = Spans::default();
+    spans.visit_source_file(&file);
+
+    for (_, position) in &skipped {
+        spans.push(SyntaxKind::Error, *position);
+    }
+
+    // Outer nodes before the nodes they contain, and parents before children
+    // that span the same text.
+    spans
+        .spans
+        .sort_by_key(|span| (span.start, Reverse(span.end)));
+
+    let mut spans = spans.spans.into_iter().peekable();
+    let mut stack = vec![Builder::new(SyntaxKind::Module, input.len())];
+    let mut tokens = vec![];
+
+    tokens_of(input, 0, input.len(), &mut tokens);
+
+    for token in tokens {
+        let start = token.position().start;
+
+        while stack.len() > 1 && stack.last().unwrap().end <= start {
+            close(&mut stack);
+        }
+
+        while let Some(span) = spans.next_if(|span| span.start <= start) {
+            let parent_end = stack.last().unwrap().end;
+
+            // Spans that cross the end of the node around them can't be
+            // represented and are left out.
+            if span.start == start && span.end > start && span.end <= parent_end {
+                stack.push(Builder::new(span.kind, span.end));
+            }
+        }
+
+        let token = GreenToken::new(token.kind(), token.value());
+
+        stack
+            .last_mut()
+            .unwrap()
+            .children
+            .push(GreenElement::Token(Rc::new(token)));
+    }
+
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+
+    let root = stack.pop().unwrap();
+    let errors = skipped.into_iter().map(|(error, _)| error).collect();
+
+    (Rc::new(GreenNode::new(root.kind, root.children)), errors)
+}
+
+// Adds the tokens of input[start..end] to `tokens`, trivia included. A
+// character no token matches becomes an Unknown token, and a template
+// literal becomes the text before, between and after its interpolations,
+// with the tokens of each interpolated value in between.
+fn tokens_of<'a>(input: &'a str, start: usize, end: usize, tokens: &mut Vec<Token<'a>>) {
+    let mut tokenizer = Tokenizer::asura();
+    let mut position = start;
+
+    tokenizer.init_at(&input[..end], start);
+
+    loop {
+        for token in &mut tokenizer {
+            position = token.position().end;
+
+            match token.kind() {
+                TokenKind::TemplateLiteral => split_template(input, token, tokens),
+                _ => tokens.push(token),
+            }
+        }
+
+        let Some(character) = input[position..end].chars().next() else {
+            return;
+        };
+        let next = position + character.len_utf8();
+
+        tokens.push(Token::new(
+            TokenKind::Unknown,
+            &input[position..next],
+            Position::new(position, next),
+        ));
+        tokenizer.init_at(&input[..end], next);
+        position = next;
+    }
+}
+
+fn split_template<'a>(input: &'a str, token: Token<'a>, tokens: &mut Vec<Token<'a>>) {
+    // An unterminated interpolation is left in the literal, like the parser
+    // does before rejecting it.
+    let Ok(interpolations) = interpolations(token.value()) else {
+        tokens.push(token);
+        return;
+    };
+    let offset = token.position().start;
+    let mut text_start = offset;
+
+    for (start, end) in interpolations {
+        tokens.push(template_text(input, text_start, offset + start));
+        tokens_of(input, offset + start, offset + end, tokens);
+        text_start = offset + end;
+    }
+
+    tokens.push(template_text(input, text_start, token.position().end));
+}
+
+fn template_text(input: &str, start: usize, end: usize) -> Token<'_> {
+    Token::new(
+        TokenKind::TemplateLiteral,
+        &input[start..end],
+        Position::new(start, end),
+    )
+}
+
+struct Builder {
+    kind: SyntaxKind,
+    end: usize,
+    children: Vec<GreenElement>,
+}
+
+impl Builder {
+    fn new(kind: SyntaxKind, end: usize) -> Builder {
+        Builder {
+            kind,
+            end,
+            children: vec![],
+        }
+    }
+}
+
+fn close(stack: &mut Vec<Builder>) {
+    let builder = stack.pop().unwrap();
+    let node = GreenNode::new(builder.kind, builder.children);
+
+    stack
+        .last_mut()
+        .unwrap()
+        .children
+        .push(GreenElement::Node(Rc::new(node)));
+}
+
+struct Span {
+    kind: SyntaxKind,
+    start: usize,
+    end: usize,
+}
+
+// Collects the position of every node, and of every part of a node that has
+// one, in pre-order.
+#[derive(Default)]
+struct Spans {
+    spans: Vec<Span>,
+}
+
+impl Spans {
+    fn push(&mut self, kind: SyntaxKind, position: Position) {
+        self.spans.push(Span {
+            kind,
+            start: position.start,
+            end: position.end,
+        });
+    }
+}
+
+impl<'a> Visitor<'a> for Spans {
+    fn visit_node(&mut self, arena: &Arena<'a>, id: NodeId) {
+        self.push(SyntaxKind::of(&arena[id]), arena[id].position());
+        walk_node(self, arena, id)
+    }
+
+    fn visit_match_case(&mut self, arena: &Arena<'a>, case: &MatchCase<'a>) {
+        self.push(SyntaxKind::MatchCase, case.position());
+        walk_match_case(self, arena, case)
+    }
+
+    fn visit_pattern(&mut self, arena: &Arena<'a>, pattern: &Pattern<'a>) {
+        self.push(SyntaxKind::Pattern, pattern.position());
+        walk_pattern(self, arena, pattern)
+    }
+
+    fn visit_function_parameter(&mut self, arena: &Arena<'a>, parameter: &FunctionParameter<'a>) {
+        self.push(SyntaxKind::FunctionParameter, parameter.position());
+        walk_function_parameter(self, arena, parameter)
+    }
+
+    fn visit_type_parameter(&mut self, arena: &Arena<'a>, parameter: &TypeParameter<'a>) {
+        self.push(SyntaxKind::TypeParameter, parameter.position());
+        walk_type_parameter(self, arena, parameter)
+    }
+
+    fn visit_type_annotation(&mut self, arena: &Arena<'a>, annotation: &TypeAnnotation<'a>) {
+        self.push(SyntaxKind::TypeAnnotation, annotation.position());
+        walk_type_annotation(self, arena, annotation)
+    }
+
+    fn visit_type_constructor(&mut self, arena: &Arena<'a>, constructor: &TypeConstructor<'a>) {
+        self.push(SyntaxKind::TypeConstructor, constructor.position());
+        walk_type_constructor(self, arena, constructor)
+    }
+
+    fn visit_effect_operation(&mut self, arena: &Arena<'a>, operation: &EffectOperation<'a>) {
+        self.push(SyntaxKind::EffectOperation, operation.position());
+        walk_effect_operation(self, arena, operation)
+    }
+
+    fn visit_typeclass_method(&mut self, arena: &Arena<'a>, method: &TypeclassMethod<'a>) {
+        self.push(SyntaxKind::TypeclassMethod, method.position());
+        walk_typeclass_method(self, arena, method)
+    }
+
+    fn visit_associated_type(&mut self, arena: &Arena<'a>, associated: &AssociatedType<'a>) {
+        self.push(SyntaxKind::AssociatedType, associated.position());
+        walk_associated_type(self, arena, associated)
+    }
+
+    fn visit_operation_clause(&mut self, arena: &Arena<'a>, clause: &OperationClause<'a>) {
+        self.push(SyntaxKind::OperationClause, clause.position());
+        walk_operation_clause(self, arena, clause)
+    }
+
+    fn visit_return_clause(&mut self, arena: &Arena<'a>, clause: &ReturnClause<'a>) {
+        self.push(SyntaxKind::ReturnClause, clause.position());
+        walk_return_clause(self, arena, clause)
+    }
+}
@@ -0,0 +1,79 @@
+use std::rc::Rc;
+
+use crate::{syntax::SyntaxKind, TokenKind};
+
+// The green tree is the immutable half of the concrete syntax tree. Nodes
+// only know their kind, their width and their children, not where they are,
+// so an unchanged subtree can be shared between versions of a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    width: usize,
+    children: Vec<GreenElement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenToken {
+    kind: TokenKind,
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let width = children.iter().map(GreenElement::width).sum();
+
+        GreenNode {
+            kind,
+            width,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+impl GreenToken {
+    pub fn new(kind: TokenKind, text: &str) -> GreenToken {
+        GreenToken {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    pub fn width(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl GreenElement {
+    pub fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width(),
+            GreenElement::Token(token) => token.width(),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::AST;
+
+// The kinds of node in the concrete syntax tree: the module itself, one for
+// each kind of AST node, one for each part of a node that has a position of
+// its own, e.g. a function's parameters, and one for what didn't parse.
+// Tokens keep their TokenKind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Module,
+
+    // Nodes
+    BooleanLiteral,
+    IntegerLiteral,
+    NumberLiteral,
+    RegexpLiteral,
+    StringLiteral,
+    TemplateLiteral,
+    Identifier,
+    Record,
+    Array,
+    Tuple,
+    Call,
+    MemberAccess,
+    Binary,
+    Unary,
+    Block,
+    With,
+    Lambda,
+    LetBinding,
+    Assignment,
+    If,
+    While,
+    ForOf,
+    Return,
+    Yield,
+    ImportDeclaration,
+    ExportDeclaration,
+    Match,
+    FunctionDeclaration,
+    TypeDeclaration,
+    TypeAlias,
+    StructDeclaration,
+    EffectDeclaration,
+    ImplBlock,
+    TypeclassDeclaration,
+    InstanceDeclaration,
+    HandlerDeclaration,
+    MacroDeclaration,
+    MacroInvocation,
+
+    // Parts of nodes
+    MatchCase,
+    Pattern,
+    FunctionParameter,
+    TypeParameter,
+    TypeAnnotation,
+    TypeConstructor,
+    EffectOperation,
+    TypeclassMethod,
+    AssociatedType,
+    OperationClause,
+    ReturnClause,
+
+    // A statement that didn't parse, or a character no token matches
+    Error,
+}
+
+impl SyntaxKind {
+    pub fn of(node: &AST<'_>) -> SyntaxKind {
+        match node {
+            AST::BooleanLiteral(_) => SyntaxKind::BooleanLiteral,
+            AST::IntegerLiteral(_) => SyntaxKind::IntegerLiteral,
+            AST::NumberLiteral(_) => SyntaxKind::NumberLiteral,
+            AST::RegexpLiteral(_) => SyntaxKind::RegexpLiteral,
+            AST::StringLiteral(_) => SyntaxKind::StringLiteral,
+            AST::TemplateLiteral(_) => SyntaxKind::TemplateLiteral,
+            AST::Identifier(_) => SyntaxKind::Identifier,
+            AST::Record(_) => SyntaxKind::Record,
+            AST::Array(_) => SyntaxKind::Array,
+            AST::Tuple(_) => SyntaxKind::Tuple,
+            AST::Call(_) => SyntaxKind::Call,
+            AST::MemberAccess(_) => SyntaxKind::MemberAccess,
+            AST::Binary(_) => SyntaxKind::Binary,
+            AST::Unary(_) => SyntaxKind::Unary,
+            AST::Block(_) => SyntaxKind::Block,
+            AST::With(_) => SyntaxKind::With,
+            AST::Lambda(_) => SyntaxKind::Lambda,
+            AST::LetBinding(_) => SyntaxKind::LetBinding,
+            AST::Assignment(_) => SyntaxKind::Assignment,
+            AST::If(_) => SyntaxKind::If,
+            AST::While(_) => SyntaxKind::While,
+            AST::ForOf(_) => SyntaxKind::ForOf,
+            AST::Return(_) => SyntaxKind::Return,
+            AST::Yield(_) => SyntaxKind::Yield,
+            AST::ImportDeclaration(_) => SyntaxKind::ImportDeclaration,
+            AST::ExportDeclaration(_) => SyntaxKind::ExportDeclaration,
+            AST::Match(_) => SyntaxKind::Match,
+            AST::FunctionDeclaration(_) => SyntaxKind::FunctionDeclaration,
+            AST::TypeDeclaration(_) => SyntaxKind::TypeDeclaration,
+            AST::TypeAlias(_) => SyntaxKind::TypeAlias,
+            AST::StructDeclaration(_) => SyntaxKind::StructDeclaration,
+            AST::EffectDeclaration(_) => SyntaxKind::EffectDeclaration,
+            AST::ImplBlock(_) => SyntaxKind::ImplBlock,
+            AST::TypeclassDeclaration(_) => SyntaxKind::TypeclassDeclaration,
+            AST::InstanceDeclaration(_) => SyntaxKind::InstanceDeclaration,
+            AST::HandlerDeclaration(_) => SyntaxKind::HandlerDeclaration,
+            AST::MacroDeclaration(_) => SyntaxKind::MacroDeclaration,
+            AST::MacroInvocation(_) => SyntaxKind::MacroInvocation,
+        }
+    }
+}
@@ -0,0 +1,11 @@
+pub use build::*;
+pub use green::*;
+pub use kind::*;
+pub use red::*;
+pub use views::SyntaxView;
+
+pub mod build;
+pub mod green;
+pub mod kind;
+pub mod red;
+pub mod views;
@@ -0,0 +1,254 @@
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    rc::Rc,
+};
+
+use crate::{
+    syntax::{GreenElement, GreenNode, GreenToken, SyntaxKind},
+    Position, Spanned, TokenKind,
+};
+
+// The red tree is a view of the green tree that knows where each node is:
+// its parent and its offset in the file. Red nodes are created on demand
+// while walking down from the root and are cheap to clone.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    offset: usize,
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> SyntaxNode {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(self.parent(), SyntaxNode::parent)
+    }
+
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+
+        self.0
+            .green
+            .children()
+            .iter()
+            .map(|child| {
+                let element = match child {
+                    GreenElement::Node(green) => {
+                        SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                            green: green.clone(),
+                            parent: Some(self.clone()),
+                            offset,
+                        })))
+                    }
+                    GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                        green: green.clone(),
+                        parent: self.clone(),
+                        offset,
+                    }),
+                };
+
+                offset += child.width();
+                element
+            })
+            .collect()
+    }
+
+    pub fn children(&self) -> Vec<SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(SyntaxElement::into_node)
+            .collect()
+    }
+
+    // The tokens directly beneath this node, trivia included.
+    pub fn child_tokens(&self) -> Vec<SyntaxToken> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(SyntaxElement::into_token)
+            .collect()
+    }
+
+    // This node and every node beneath it, in source order.
+    pub fn descendants(&self) -> Vec<SyntaxNode> {
+        let mut nodes = vec![self.clone()];
+
+        for child in self.children() {
+            nodes.extend(child.descendants());
+        }
+
+        nodes
+    }
+
+    // Every token beneath this node, in source order.
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        self.children_with_tokens()
+            .into_iter()
+            .flat_map(|child| match child {
+                SyntaxElement::Node(node) => node.tokens(),
+                SyntaxElement::Token(token) => vec![token],
+            })
+            .collect()
+    }
+
+    // The token that contains the given offset, for mapping a cursor back to
+    // the tree.
+    pub fn token_at(&self, offset: usize) -> Option<SyntaxToken> {
+        for child in self.children_with_tokens() {
+            let position = child.position();
+
+            if position.start <= offset && offset < position.end {
+                return match child {
+                    SyntaxElement::Node(node) => node.token_at(offset),
+                    SyntaxElement::Token(token) => Some(token),
+                };
+            }
+        }
+
+        None
+    }
+
+    pub fn text(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Spanned for SyntaxNode {
+    fn position(&self) -> Position {
+        Position::new(self.0.offset, self.0.offset + self.0.green.width())
+    }
+}
+
+// Two red nodes are the same node when they view the same green node at the
+// same place.
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &SyntaxNode) -> bool {
+        Rc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+// Prints the text the node was built from, trivia included.
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for token in self.tokens() {
+            f.write_str(token.text())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let position = self.position();
+
+        write!(f, "{:?}@{}..{}", self.kind(), position.start, position.end)
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> TokenKind {
+        self.green.kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    // Whitespace and comments, which the parser skips over.
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self.kind(),
+            TokenKind::WhiteSpace | TokenKind::Comment | TokenKind::DocComment
+        )
+    }
+}
+
+impl Spanned for SyntaxToken {
+    fn position(&self) -> Position {
+        Position::new(self.offset, self.offset + self.green.width())
+    }
+}
+
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &SyntaxToken) -> bool {
+        Rc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+
+impl Debug for SyntaxToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let position = self.position();
+
+        write!(
+            f,
+            "{:?}@{}..{} {:?}",
+            self.kind(),
+            position.start,
+            position.end,
+            self.text()
+        )
+    }
+}
+
+impl SyntaxElement {
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token),
+        }
+    }
+}
+
+impl Spanned for SyntaxElement {
+    fn position(&self) -> Position {
+        match self {
+            SyntaxElement::Node(node) => node.position(),
+            SyntaxElement::Token(token) => token.position(),
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use crate::{
+    syntax::{SyntaxKind, SyntaxNode, SyntaxToken},
+    Position, Spanned, TokenKind,
+};
+
+// Typed views of concrete syntax nodes, one per SyntaxKind. A view is just
+// a SyntaxNode known to be of a certain kind, with accessors for the parts
+// that kind of node is made of. Children that can be any expression are
+// returned as plain SyntaxNodes.
+pub trait SyntaxView: Sized {
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+macro_rules! views {
+    ($($kind:ident),* $(,)?) => {
+        $(
+            #[derive(Debug, Clone, PartialEq)]
+            pub struct $kind(SyntaxNode);
+
+            impl SyntaxView for $kind {
+                fn cast(node: SyntaxNode) -> Option<$kind> {
+                    (node.kind() == SyntaxKind::$kind).then_some($kind(node))
+                }
+
+                fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+
+            impl Spanned for $kind {
+                fn position(&self) -> Position {
+                    self.0.position()
+                }
+            }
+        )*
+    };
+}
+
+views!(
+    Module,
+    BooleanLiteral,
+    IntegerLiteral,
+    NumberLiteral,
+    RegexpLiteral,
+    StringLiteral,
+    TemplateLiteral,
+    Identifier,
+    Record,
+    Array,
+    Tuple,
+    Call,
+    MemberAccess,
+    Binary,
+    Unary,
+    Block,
+    With,
+    Lambda,
+    LetBinding,
+    Assignment,
+    If,
+    While,
+    ForOf,
+    Return,
+    Yield,
+    ImportDeclaration,
+    ExportDeclaration,
+    Match,
+    FunctionDeclaration,
+    TypeDeclaration,
+    TypeAlias,
+    StructDeclaration,
+    EffectDeclaration,
+    ImplBlock,
+    TypeclassDeclaration,
+    InstanceDeclaration,
+    HandlerDeclaration,
+    MacroDeclaration,
+    MacroInvocation,
+    MatchCase,
+    Pattern,
+    FunctionParameter,
+    TypeParameter,
+    TypeAnnotation,
+    TypeConstructor,
+    EffectOperation,
+    TypeclassMethod,
+    AssociatedType,
+    OperationClause,
+    ReturnClause,
+);
+
+impl Module {
+    pub fn statements(&self) -> Vec<SyntaxNode> {
+        self.0.children()
+    }
+}
+
+impl Identifier {
+    pub fn name(&self) -> String {
+        self.0.text()
+    }
+}
+
+impl Call {
+    pub fn callee(&self) -> Option<SyntaxNode> {
+        self.0.children().into_iter().next()
+    }
+
+    pub fn arguments(&self) -> Vec<SyntaxNode> {
+        self.0.children().into_iter().skip(1).collect()
+    }
+}
+
+impl MemberAccess {
+    pub fn object(&self) -> Option<SyntaxNode> {
+        self.0.children().into_iter().next()
+    }
+
+    pub fn property(&self) -> Option<SyntaxToken> {
+        last_token(&self.0, TokenKind::Identifier)
+    }
+}
+
+impl Binary {
+    pub fn left(&self) -> Option<SyntaxNode> {
+        self.0.children().into_iter().next()
+    }
+
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .find(|token| !token.is_trivia())
+    }
+
+    pub fn right(&self) -> Option<SyntaxNode> {
+        self.0.children().into_iter().nth(1)
+    }
+}
+
+impl Block {
+    pub fn statements(&self) -> Vec<SyntaxNode> {
+        self.0.children()
+    }
+}
+
+impl LetBinding {
+    pub fn pattern(&self) -> Option<Pattern> {
+        children(&self.0).next()
+    }
+
+    pub fn value(&self) -> Option<SyntaxNode> {
+        self.0.children().into_iter().nth(1)
+    }
+}
+
+impl Lambda {
+    pub fn type_parameters(&self) -> Vec<TypeParameter> {
+        children(&self.0).collect()
+    }
+
+    pub fn parameters(&self) -> Vec<FunctionParameter> {
+        children(&self.0).collect()
+    }
+
+    pub fn return_type(&self) -> Option<TypeAnnotation> {
+        children(&self.0).next()
+    }
+
+    pub fn body(&self) -> Vec<SyntaxNode> {
+        body(&self.0)
+    }
+}
+
+impl Match {
+    pub fn value(&self) -> Option<SyntaxNode> {
+        self.0.children().into_iter().next()
+    }
+
+    pub fn cases(&self) -> Vec<MatchCase> {
+        children(&self.0).collect()
+    }
+}
+
+impl MatchCase {
+    // A Pattern, or the expression of a predicate like `n < 2`.
+    pub fn test(&self) -> Option<SyntaxNode> {
+        self.0.children().into_iter().next()
+    }
+
+    pub fn body(&self) -> Option<SyntaxNode> {
+        self.0.children().into_iter().nth(1)
+    }
+}
+
+impl FunctionDeclaration {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        first_token(&self.0, TokenKind::Identifier)
+    }
+
+    pub fn type_parameters(&self) -> Vec<TypeParameter> {
+        children(&self.0).collect()
+    }
+
+    pub fn parameters(&self) -> Vec<FunctionParameter> {
+        children(&self.0).collect()
+    }
+
+    pub fn return_type(&self) -> Option<TypeAnnotation> {
+        children(&self.0).next()
+    }
+
+    pub fn body(&self) -> Vec<SyntaxNode> {
+        body(&self.0)
+    }
+}
+
+impl FunctionParameter {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        first_token(&self.0, TokenKind::Identifier)
+    }
+
+    pub fn annotation(&self) -> Option<TypeAnnotation> {
+        children(&self.0).next()
+    }
+}
+
+impl TypeParameter {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        first_token(&self.0, TokenKind::Identifier)
+    }
+}
+
+impl TypeDeclaration {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        first_token(&self.0, TokenKind::Identifier)
+    }
+
+    pub fn constructors(&self) -> Vec<TypeConstructor> {
+        children(&self.0).collect()
+    }
+}
+
+impl TypeConstructor {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        first_token(&self.0, TokenKind::Identifier)
+    }
+}
+
+impl EffectDeclaration {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        first_token(&self.0, TokenKind::Identifier)
+    }
+
+    pub fn operations(&self) -> Vec<EffectOperation> {
+        children(&self.0).collect()
+    }
+}
+
+impl HandlerDeclaration {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        first_token(&self.0, TokenKind::Identifier)
+    }
+
+    pub fn operations(&self) -> Vec<OperationClause> {
+        children(&self.0).collect()
+    }
+
+    pub fn return_clause(&self) -> Option<ReturnClause> {
+        children(&self.0).next()
+    }
+}
+
+impl MacroDeclaration {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        first_token(&self.0, TokenKind::Identifier)
+    }
+
+    pub fn parameters(&self) -> Vec<FunctionParameter> {
+        children(&self.0).collect()
+    }
+}
+
+impl MacroInvocation {
+    // The `@name!` token.
+    pub fn name(&self) -> Option<SyntaxToken> {
+        first_token(&self.0, TokenKind::MacroIdentifier)
+    }
+}
+
+fn children<T: SyntaxView>(node: &SyntaxNode) -> impl Iterator<Item = T> {
+    node.children().into_iter().filter_map(T::cast)
+}
+
+fn first_token(node: &SyntaxNode, kind: TokenKind) -> Option<SyntaxToken> {
+    node.child_tokens()
+        .into_iter()
+        .find(|token| token.kind() == kind)
+}
+
+fn last_token(node: &SyntaxNode, kind: TokenKind) -> Option<SyntaxToken> {
+    node.child_tokens()
+        .into_iter()
+        .rev()
+        .find(|token| token.kind() == kind)
+}
+
+// The statements of a function or lambda, i.e. the children that aren't
+// part of its signature.
+fn body(node: &SyntaxNode) -> Vec<SyntaxNode> {
+    node.children()
+        .into_iter()
+        .filter(|child| {
+            !matches!(
+                child.kind(),
+                SyntaxKind::TypeParameter
+                    | SyntaxKind::FunctionParameter
+                    | SyntaxKind::TypeAnnotation
+            )
+        })
+        .collect()
+}